@@ -5,10 +5,10 @@
 use chrono::offset::Utc;
 use chrono::DateTime;
 use serde::{Deserialize, Serialize};
-use std::cmp::Ordering;
 use std::collections::HashMap;
+use std::path::PathBuf;
 
-#[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "lowercase")]
 /// Defines how a property should be controlled
 pub enum ControlMode {
@@ -16,10 +16,22 @@ pub enum ControlMode {
     Set,
     /// The value should be interpolated to be reached upon the desired time
     Interpolate,
+    /// The value should be interpolated along a cubic Bézier easing curve
+    /// to be reached upon the desired time, as with CSS' `cubic-bezier()`
+    ///
+    /// The curve goes from (0, 0) to (1, 1), time on the x axis and value
+    /// on the y axis, and its control points are expressed as fractions of
+    /// the interpolation duration and value range, in [0, 1].
+    BezierCubic {
+        cp1_time_frac: f64,
+        cp1_value_frac: f64,
+        cp2_time_frac: f64,
+        cp2_value_frac: f64,
+    },
 }
 
 /// A property control point
-#[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "lowercase")]
 pub struct ControlPoint {
     /// The identifier of the control point, unique per property
@@ -33,18 +45,6 @@ pub struct ControlPoint {
     pub mode: ControlMode,
 }
 
-impl Ord for ControlPoint {
-    fn cmp(&self, other: &Self) -> Ordering {
-        self.time.cmp(&other.time)
-    }
-}
-
-impl PartialOrd for ControlPoint {
-    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        Some(self.cmp(other))
-    }
-}
-
 /// Command variants
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
@@ -58,6 +58,33 @@ pub enum Command {
         /// URI to play back
         uri: String,
     },
+    /// Create a source capturing a display, on Linux and macOS
+    ///
+    /// The source only produces video, links from it only connect its
+    /// video. No slot properties are available.
+    CreateScreenCapture {
+        /// Unique identifier of the source
+        id: String,
+        /// Initial configuration of the source
+        ///
+        /// Available settings:
+        ///
+        /// * display-id, i64, 0 -> 4294967295, default 0: the display to
+        ///   capture, the X screen number, the PipeWire node under Wayland,
+        ///   or the index of the display on macOS. It must be set under
+        ///   Wayland, where PipeWire node ids are allocated dynamically
+        /// * region, object with x, y, width and height members, default
+        ///   null: the part of the display to capture, in display pixels,
+        ///   the whole display when null
+        /// * framerate, String, strictly positive fraction, default "30/1"
+        /// * forward-mode, String, one of manual, auto, keyframe, default
+        ///   "manual": when captured frames start being forwarded to the
+        ///   consumers of the source, manual once the source is started, auto
+        ///   as soon as the first frame is captured, possibly before a
+        ///   scheduled start, and keyframe from the first keyframe captured.
+        ///   Raw frames are all keyframes, keyframe then behaves as auto
+        config: Option<HashMap<String, serde_json::Value>>,
+    },
     /// Create a destination
     ///
     /// No slot properties are available.
@@ -66,12 +93,153 @@ pub enum Command {
         id: String,
         /// Type of the destination
         family: DestinationFamily,
+        /// Initial configuration of the destination
+        ///
+        /// Available settings, only applying to the families that encode
+        /// their input (Rtmp, LocalFile and Dash):
+        ///
+        /// * video-bitrate (kbit/s), i32, 0 -> 2048000, default 0: the
+        ///   target bitrate of the video encoder, 0 for the encoder default
+        /// * video-max-bitrate (kbit/s), i32, 0 -> 2048000, default 0: the
+        ///   bitrate the video encoder must not exceed, 0 for no cap. A warning
+        ///   is emitted when the encoder is capped for sustained periods
+        ///
+        /// Available settings for the Pcm family:
+        ///
+        /// * audio-format, String, one of S16LE, S24LE, S32LE, F32LE, default
+        ///   "S16LE": the format of the output samples
+        /// * audio-sample-rate, i32, 0 -> 2147483647, default 0: the sample
+        ///   rate of the output, 0 for that of the producer
+        ///
+        /// Available settings for the Jack family:
+        ///
+        /// * jack-port-names, String, default "": comma-separated names of
+        ///   the ports to connect to with [`JackConnect::Manual`]
+        ///
+        /// Available settings for the Dash family:
+        ///
+        /// * base-url, String, default "": the base URL of the segments,
+        ///   advertised in the manifest
+        /// * target-duration (seconds), i32, 1 -> 3600, default 15: the
+        ///   duration of the segments
+        /// * minimum-update-period (ms), i32, 0 -> 2147483647, default 0: how
+        ///   often clients should refresh the manifest, 0 for unspecified
+        /// * use-segment-list, bool, default false: whether the manifest lists
+        ///   the segments rather than describing them with a template
+        /// * upload-command, String, default "": a shell command run for each
+        ///   written segment, then for the updated manifest, with the path of
+        ///   the file as first argument (`$1`), eg to upload it to S3 or GCS
+        ///
+        /// Additionally, `cascade-stop`, bool, default true, defines whether
+        /// the destination stops along with the nodes upstream of it, see
+        /// [`Command::Remove`]
+        #[serde(default)]
+        config: Option<HashMap<String, serde_json::Value>>,
     },
     /// Create a mixer
     ///
     /// Available slot properties are all those exposed by audiomixer
     /// and compositor pads, accessible through a `audio::` or
     /// `video::` prefix respectively, eg `video::width`.
+    ///
+    /// This includes the blending mode of a slot, `video::operator`, one of
+    /// "source", "over" or "add", default "over", which can be changed at
+    /// any time with control points in mode Set, eg "add" for additive
+    /// overlays.
+    ///
+    /// Additionally, `video::pts-offset-ns` and `audio::pts-offset-ns`, i64,
+    /// controllable, offset the timestamps of the slot streams, in
+    /// nanoseconds, to correct drift in either direction.
+    ///
+    /// The `appsrc` elements feeding the slots can be configured through
+    /// `video::is-live`, `video::format` and `video::do-timestamp`, and
+    /// their `audio::` counterparts. The defaults, is-live true, format
+    /// "time" and do-timestamp false, suit live producers. compositor and
+    /// audiomixer only accept "time" as the format, any other is rejected.
+    /// do-timestamp only affects buffers that reach the slot without a
+    /// timestamp, which are then timestamped with the running time of the
+    /// mixer upon reception. Buffers forwarded by producers usually carry
+    /// timestamps, which do-timestamp leaves untouched, use
+    /// `pts-offset-ns` to realign them instead. As the base plate of the
+    /// mixer is always live, is-live false is not recommended: such a slot
+    /// does not take part in latency reporting.
+    ///
+    /// Slots can also be configured, at connection time only, with:
+    ///
+    /// * `label`, String: a human-readable name for the slot, reported in
+    ///   the mixer info for display purposes only
+    /// * `watchdog-timeout-ms`, u32, default 0: when positive, the slot is
+    ///   flushed, as with [`Command::FlushSlot`], once its input has not
+    ///   provided any data for that long while the mixer is started, and
+    ///   every timeout thereafter until it recovers. Listeners are notified
+    ///   with a warning each time. Frozen slots are not flushed
+    /// * `reconnect-on-eos`, bool, default false: whether the slot is
+    ///   reconnected to its producers, with its initial configuration,
+    ///   once its input has reached EOS. The slot is reconnected once the
+    ///   producers output data again, eg after a flush, so that they don't
+    ///   immediately send EOS to it again. Sources never send EOS to their
+    ///   slots, they are disconnected when the source stops instead
+    /// * `reconnect-delay-ms`, u32, default 0: how long to wait at least
+    ///   after EOS before reconnecting
+    /// * `latency-ms`, u32: when set, the latency the slot inputs are
+    ///   configured with, instead of the latency reported downstream of the
+    ///   producers, eg to run a slot tighter than the other consumers of
+    ///   the same producers
+    /// * `video::base-plate-timeout-ms`, i32, default 0: when positive,
+    ///   overrides the fallback-timeout mixer setting for this slot. Once no
+    ///   slot provides video anymore, the mixer falls back to its base plate
+    ///   after the largest timeout among the slots that last provided video
+    /// * `priority`, u8, default 0: once a slot with a non-zero priority has
+    ///   received video, the mixer doesn't start falling back to its base
+    ///   plate until the slot provided no video for its fallback timeout,
+    ///   eg a main camera momentarily providing no video. Slots with
+    ///   priority 0, eg lower thirds, do not hold off the base plate
+    /// * `video::late-start-mode`, String, one of mute, freeze, blank,
+    ///   default mute: how a slot connected before the mixer started is
+    ///   shown until it receives video. With mute, the mixer falls back to
+    ///   its base plate as usual. With freeze, a black placeholder is shown
+    ///   in place of the slot, covering the whole output when the slot is not
+    ///   resized. With blank, the slot is transparent. Both freeze and blank
+    ///   hold off the base plate until the slot receives video
+    /// * `video::border-radius`, u32, default 0: the radius of the rounded
+    ///   corners of the video of the slot, in pixels. Requires the
+    ///   `roundedcorners` element, corners are left square when it is not
+    ///   available
+    /// * `audio::channel-map`, String or array of arrays of f64: how the
+    ///   channels of the input are mixed into the two channels of the mixer,
+    ///   either `left` or `right` to only use that channel of a stereo input,
+    ///   or a matrix with one row per output channel and one column per
+    ///   input channel, eg `[[0.5, 0.5], [0.5, 0.5]]`. The matrix must match
+    ///   the number of channels of the input. Defaults to the automatic
+    ///   downmix of `audioconvert`
+    /// * `audio::allow-format-change`, bool, default false: whether the
+    ///   audio conversion of the slot renegotiates when the format of its
+    ///   input changes mid-stream, eg when a live encoder is reconfigured.
+    ///   `audio::channel-map` is only applied while it matches the number
+    ///   of channels of the input
+    /// * `audio::compressor-threshold-db`, f64, default 0: when negative, the
+    ///   audio of the slot goes through a soft-knee compressor, which
+    ///   compresses samples above that level
+    /// * `audio::compressor-ratio`, f64, default 1, controllable: the
+    ///   compression ratio, only available when the compressor is enabled
+    /// * `video::denoise`, bool, default false: whether the video of the slot
+    ///   goes through a `hqdn3d` denoiser. That element is not part of the
+    ///   GStreamer plugin sets, the video is left as is when it is not
+    ///   available
+    /// * `video::denoise-spatial`, f64, controllable: the luma spatial
+    ///   strength of the denoiser, only available when it is enabled
+    /// * `video::denoise-temporal`, f64, controllable: the luma temporal
+    ///   strength of the denoiser, only available when it is enabled
+    /// * `video::label-text`, String, default "", controllable: a label drawn
+    ///   over the video of the slot, also see [`Command::SetSlotLabel`].
+    ///   Requires the `textoverlay` element, the label keys are ignored
+    ///   when it is not available
+    /// * `video::label-position`, String, one of top-left, top-right,
+    ///   bottom-left, bottom-right, center, default top-left
+    /// * `video::label-font`, String, default "Sans 16", controllable: a
+    ///   Pango font description
+    /// * `video::label-color`, String, default 0xFFFFFFFF: the ARGB color
+    ///   of the label
     CreateMixer {
         /// Unique identifier of the mixer
         id: String,
@@ -82,11 +250,167 @@ pub enum Command {
         /// * width, i32, 1 -> 2147483647, default 1920, controllable
         /// * height, i32, 1 -> 2147483647, default 1920, controllable
         /// * sample-rate, i32, 1 -> 2147483647, default 48000
+        /// * output-audio-bits, String, one of 16, 24, 32, default "16": the bit
+        ///   depth of the output audio samples, 24-bit samples are carried in
+        ///   32 bits (S24_32LE)
+        /// * output-channel-mode, String, one of stereo, mono, 5.1, pass-through,
+        ///   default "stereo": the channel layout of the output audio. Mono
+        ///   outputs are mixed in stereo and downmixed. 5.1 outputs are mixed
+        ///   in 5.1, stereo inputs then only feed the front left and right
+        ///   channels, as do the rows of their `audio::channel-map`. With
+        ///   pass-through, no layout is enforced: the mix takes the layout of
+        ///   the earliest connected slot whose audio was negotiated when the
+        ///   mixer starts, or stereo if there is none, and the other slots are
+        ///   converted to it. The layout is not renegotiated afterwards: as
+        ///   slots connected to a mixer that is not started yet usually only
+        ///   negotiate once it starts, the mix is then effectively stereo
+        /// * pixel-aspect-ratio, String, strictly positive fraction, default "1/1":
+        ///   the pixel aspect ratio of the output. Slots with
+        ///   `video::sizing-policy=keep-aspect-ratio` preserve their display
+        ///   aspect ratio, as compositor takes both their pixel aspect ratio
+        ///   and that of the output into account. `video::width` and
+        ///   `video::height` are expressed in output pixels.
+        /// * format, String, one of AYUV, VUYA, BGRA, ARGB, RGBA, ABGR, Y444, Y42B,
+        ///   YUY2, UYVY, YVYU, I420, YV12, NV12, NV21, Y41B, RGB, BGR, xRGB, xBGR,
+        ///   RGBx, BGRx, default "AYUV": the raw video format composited in, before
+        ///   encoding by the consumers. Outputs that don't need an alpha channel
+        ///   can save bandwidth and conversions by selecting the format of their
+        ///   encoder, eg I420 or NV12. A transparent compositor-background
+        ///   requires a format with an alpha channel, such as AYUV or BGRA
+        /// * colorimetry, String, one of bt601, bt709, smpte240m, sRGB, bt2020,
+        ///   bt2100-pq, bt2100-hlg, default "bt601": the colorimetry of the output,
+        ///   bt709 is usually expected for HD. The video of each slot is converted
+        ///   from its own colorimetry, as announced by its caps, to that of the
+        ///   output by compositor, and the output is converted again to enforce
+        ///   the matrix of that colorimetry
+        /// * chroma-site, String, one of jpeg, mpeg2, dv, alt-line, cosited,
+        ///   default "jpeg": the chroma siting of the output
+        /// * compositor-background, String, one of black, white, transparent,
+        ///   checker, default "black": what compositor fills the output with
+        ///   where no slot is displayed
         /// * fallback-image, String, default ""
+        /// * fallback-slideshow, String, default "": a directory, or a path
+        ///   whose file name may contain `*` and `?` wildcards, listing
+        ///   images to cycle through in path order while the base plate is
+        ///   shown. All images must share the same format. Mutually
+        ///   exclusive with fallback-image
+        /// * fallback-slideshow-interval (ms), i32, 1 -> 2147483647, default
+        ///   5000: for how long each slideshow image is shown
         /// * fallback-timeout (ms), i32, 1 -> 2147483647, default 500, controllable
+        /// * idle-graphic, String, default "": path to an image shown along with
+        ///   the base plate, above it but below the slots, eg a "technical
+        ///   difficulties" graphic over a fallback image or slideshow
+        /// * watermark-image, String, default "": path to an image overlaid on
+        ///   top of the output, no watermark is overlaid when empty
+        /// * watermark-x, i32, default 0, controllable: horizontal position of
+        ///   the watermark, in output pixels
+        /// * watermark-y, i32, default 0, controllable: vertical position of
+        ///   the watermark, in output pixels
+        /// * watermark-alpha, f64, 0.0 -> 1.0, default 1.0, controllable: opacity
+        ///   of the watermark
+        /// * watermark-scale, f64, 0.01 -> 100.0, default 1.0: scaling factor
+        ///   applied to the size of the watermark image
+        /// * audio-eq-low-gain-db, f64, -24.0 -> 12.0, default 0.0, controllable:
+        ///   gain of the low shelf band of the output equalizer
+        /// * audio-eq-mid-gain-db, f64, -24.0 -> 12.0, default 0.0, controllable:
+        ///   gain of the peak band of the output equalizer, centered between
+        ///   the low and high frequencies
+        /// * audio-eq-high-gain-db, f64, -24.0 -> 12.0, default 0.0, controllable:
+        ///   gain of the high shelf band of the output equalizer
+        /// * audio-eq-low-freq-hz, f64, 20.0 -> 20000.0, default 100.0: cutoff
+        ///   frequency of the low shelf band
+        /// * audio-eq-high-freq-hz, f64, 20.0 -> 20000.0, default 11000.0: cutoff
+        ///   frequency of the high shelf band
+        ///
+        ///   The equalizer is only part of the pipeline when one of the gains
+        ///   is non-zero upon starting the mixer, the gains can only be
+        ///   controlled in that case.
+        /// * audio-base-signal, String, one of silence, tone, pink-noise, default
+        ///   "silence": what the mixer outputs once no slot has had audio for
+        ///   fallback-timeout, tone being a 1 kHz sine, for lining up
+        ///   equipment downstream
+        /// * audio-base-level-db, f64, -60 -> 0, default -18: the level of the
+        ///   tone or pink noise, in dBFS
+        /// * enable-audio-monitor, bool, default false: whether the mixer should
+        ///   additionally output a low bitrate, mono audio feed for monitoring,
+        ///   connected with [`ConnectMedia::AudioMonitor`]
+        /// * audio-monitor-sample-rate, i32, 1 -> 2147483647, default 8000: the
+        ///   sample rate of the audio monitor feed
+        /// * video-ignore-inactive-pads, bool, default true: whether compositor
+        ///   ignores the slots that have not received any video yet. When false,
+        ///   compositor waits for every slot, and as long as one of them has
+        ///   not received data each output frame is only produced once the
+        ///   latency of the mixer has fully elapsed
+        /// * audio-ignore-inactive-pads, bool, default true: the same for
+        ///   audiomixer. false is the correct mode for synchronized multi-track
+        ///   recording, where no track should be mixed before the others, at
+        ///   the cost of the same added delay while a slot is inactive
+        /// * use-ptp, bool, default false: whether the mixer pipeline should
+        ///   be clocked by a PTP (IEEE 1588) clock instead of the system clock.
+        ///   The mixer only starts once the clock has synchronized, and errors
+        ///   out if it doesn't within 10 seconds
+        /// * ptp-domain, i32, 0 -> 255, default 0: the PTP domain to synchronize
+        ///   to when use-ptp is true
+        /// * program-number, i32, 1 -> 65535, default 1: the MPEG-TS program
+        ///   number downstream muxers should assign to the mixer output
+        /// * stream-id, String, default "": an identifier for the mixer output
+        ///   streams, for downstream muxers
+        /// * control-timing, one of wall-clock, pts, default wall-clock: what
+        ///   control points are evaluated against. With wall-clock, each output
+        ///   frame evaluates them at the current time. With pts, the first output
+        ///   frame is mapped to the current time and later frames to that time
+        ///   plus their PTS difference, for frame-accurate animations: control
+        ///   points are still authored in wall-clock terms, but drift between
+        ///   the wall clock and the pipeline clock is no longer followed
+        /// * net-clock-address, String, default "": when set, the mixer pipeline
+        ///   is clocked by a network clock, synchronized to the
+        ///   `GstNetTimeProvider` at that address, waited for as with use-ptp.
+        ///   Mutually exclusive with use-ptp
+        /// * net-clock-port, i32, 1 -> 65535, default 8554: the port of the
+        ///   network time provider
+        ///
+        ///   With either clock, the times of control points are evaluated
+        ///   against that clock rather than against the system clock, for
+        ///   them to be applied in sync across machines.
+        /// * output-pts-offset-ns, i64, -3600000000000 -> 3600000000000, default 0,
+        ///   controllable: offset applied to the running time of the mixer output,
+        ///   for aligning the timestamps of multiple mixers
+        /// * output-qos, bool, default false: whether the outputs of the mixer
+        ///   drop buffers that are late against the clock, rather than
+        ///   accumulating delay when the mixer can't keep up
+        /// * output-max-lateness-ms, i32, -1 -> 2147483647, default -1: how late
+        ///   output buffers can be before they are dropped, -1 for unlimited
+        /// * consumer-removal-timeout (ms), i32, 0 -> 2147483647, default 0: for
+        ///   how long a consumer of the mixer, eg a destination, may fail to
+        ///   accept output samples before it is disconnected, 0 to never
+        ///   disconnect it
+        ///
+        /// Additionally, `layouts` defines named layouts that can be applied
+        /// at once with [`Command::ApplyLayout`]. It maps layout names to
+        /// slot ids to slot config keys and values, eg
+        /// `{"pip": {"cam": {"video::width": 1920}, "guest": {"video::xpos": 1440}}}`
+        ///
+        /// Keys prefixed with `compositor::` or `audiomixer::` set arbitrary
+        /// properties of the respective GStreamer elements, eg
+        /// `compositor::latency`. They are validated upon creation, and
+        /// applied once when the mixer starts, ahead of the settings above,
+        /// which take precedence where they overlap (`background`,
+        /// `start-time-selection`, `ignore-inactive-pads`). Properties such
+        /// as `start-time-selection`, `start-time` and `latency` are only
+        /// taken into account at start, these keys are thus not applied
+        /// live: the controllable settings above cover what can safely
+        /// change while mixing.
+        ///
+        /// `cascade-stop`, bool, default true, defines whether the mixer
+        /// stops along with the nodes upstream of it, see [`Command::Remove`]
         config: Option<HashMap<String, serde_json::Value>>,
     },
     /// Connect a producer with a consumer
+    ///
+    /// A producer can be connected several times to the same mixer, with
+    /// different link ids, eg to show a camera both full screen and as a
+    /// cropped inset. Each link gets its own slot, configured and
+    /// controlled independently.
     Connect {
         /// Unique identifier of the created connection
         link_id: String,
@@ -99,6 +423,21 @@ pub enum Command {
         /// Check out the documentation for the node creation function
         /// for more information on the available consumer slot config keys.
         config: Option<HashMap<String, serde_json::Value>>,
+        /// Which media types of the producer to connect
+        ///
+        /// Only mixers support connecting a single media type, the
+        /// slot then has no properties for the other one. Screen captures
+        /// only have video, which is all that is connected from them.
+        #[serde(default)]
+        media: ConnectMedia,
+        /// What to do when a link with the same id already exists
+        #[serde(default)]
+        on_duplicate: DuplicateLinkPolicy,
+        /// The id of an output added to the producer with
+        /// [`Command::AddOutputScale`], to connect instead of its main
+        /// output. Such outputs only have video
+        #[serde(default)]
+        output_id: Option<String>,
     },
     /// Schedule any node for starting, possibly immediately
     Start {
@@ -109,6 +448,59 @@ pub enum Command {
         /// When to stop the node. Never if None
         end_time: Option<DateTime<Utc>>,
     },
+    /// Group existing nodes, to start and stop them together
+    ///
+    /// Nodes leave their groups once removed, groups are removed with
+    /// their last node.
+    CreateGroup {
+        /// Unique identifier of the group
+        id: String,
+        /// Identifiers of existing nodes, each listed once
+        node_ids: Vec<String>,
+    },
+    /// Schedule all the nodes of a group for starting
+    ///
+    /// Every node of the group is sent the same schedule at once. A
+    /// cue_time in the near future, rather than None, ensures all nodes
+    /// start at exactly the same time.
+    StartGroup {
+        /// Identifier of an existing group
+        id: String,
+        /// When to start the nodes. Immediate if None
+        cue_time: Option<DateTime<Utc>>,
+        /// When to stop the nodes. Never if None
+        end_time: Option<DateTime<Utc>>,
+    },
+    /// Stop and remove all the nodes of a group, as with
+    /// [`Command::Remove`]
+    StopGroup {
+        /// Identifier of an existing group
+        id: String,
+    },
+    /// Override the level of the traces emitted by a node
+    ///
+    /// The level applies to the traces emitted from the spans of the
+    /// node, which record its id, other traces are filtered according
+    /// to the global level.
+    SetNodeLogLevel {
+        /// Identifier of an existing node, only made of ASCII
+        /// alphanumerics, `-` and `_`
+        id: String,
+        /// One of off, error, warn, info, debug, trace, None resets the
+        /// node to the global level
+        level: Option<String>,
+    },
+    /// Set when a node starts forwarding its output to its consumers
+    ///
+    /// Applies to the video and audio outputs of sources, screen
+    /// captures and mixers, and only has an effect until the node
+    /// starts forwarding them.
+    SetForwardMode {
+        /// Identifier of an existing producer node
+        id: String,
+        /// The new mode
+        mode: ForwardMode,
+    },
     /// Reschedule any node
     Reschedule {
         /// Identifier of an existing node
@@ -119,20 +511,58 @@ pub enum Command {
         end_time: Option<DateTime<Utc>>,
     },
     /// Remove a node
+    ///
+    /// Mixers send EOS through their pipeline before stopping, so that
+    /// the consumers of their outputs can finalize, eg recordings.
     Remove {
         /// Identifier of an existing node
         id: String,
+        /// Whether the node should stop immediately, without draining its
+        /// pipeline
+        #[serde(default)]
+        kill: bool,
+        /// Whether the nodes downstream of this node should be stopped as
+        /// well, once it has stopped, unless they opted out with their
+        /// `cascade-stop` setting. Each level of the topology is stopped
+        /// once the previous one has, which is incompatible with `kill`.
+        #[serde(default)]
+        cascade: bool,
     },
     /// Remove a connection between two nodes
     Disconnect {
         /// Identifier of an existing connection
         link_id: String,
     },
+    /// Flush the data queued for a connection, and request a keyframe
+    /// from its producer, to recover a stalled input in place
+    ///
+    /// Only mixers support flushing their slots. Control points keep
+    /// animating the slot properties from the current time.
+    FlushSlot {
+        /// Identifier of an existing connection
+        link_id: String,
+    },
+    /// Hold the video of a connection on its current frame, without
+    /// disconnecting it, or resume it
+    ///
+    /// Only mixers support freezing their slots. A frozen slot keeps
+    /// presenting frames at the rate of its input, and thus does not
+    /// trigger the base plate fallback unless its input stalls. Audio
+    /// is not affected.
+    FreezeSlot {
+        /// Identifier of an existing connection
+        link_id: String,
+        /// Whether to freeze or resume the connection
+        frozen: bool,
+    },
     /// Retrieve the info of one or all nodes
     GetInfo {
         /// The id of an existing node, or None, in which case the info
         /// of all nodes in the system will be gathered
         id: Option<String>,
+        /// When id is None, only gather the info of the nodes of that type
+        #[serde(default)]
+        node_type: Option<NodeType>,
     },
     /// Control a property on a node or slot
     AddControlPoint {
@@ -152,6 +582,360 @@ pub enum Command {
         /// Name of the controlled property
         property: String,
     },
+    /// Schedule the application of a preset on a mixer
+    ///
+    /// A control point with mode Set is added at cue_time for each
+    /// property of the preset, replacing any control point previously
+    /// scheduled at that time for the same property. The control points
+    /// are identified as `preset-<cue_time>`, with cue_time in RFC 3339
+    /// format.
+    ApplyPreset {
+        /// Identifier of an existing mixer
+        id: String,
+        /// The preset to apply
+        preset: MixerPreset,
+        /// When to apply the preset
+        cue_time: DateTime<Utc>,
+    },
+    /// Schedule the application of a layout defined in the `layouts`
+    /// configuration of a mixer
+    ///
+    /// This works as [`Command::ApplyPreset`], with the following
+    /// differences: the slots that the layout does not map are hidden by
+    /// setting their `video::alpha` to 0.0, the slots that it maps are
+    /// shown with `video::alpha` 1.0 unless it specifies otherwise, and
+    /// the control points are identified as `layout-<cue_time>`. The slots
+    /// that the layout maps but that are not connected are skipped, with
+    /// a warning.
+    ///
+    /// When transition_ms is set, numeric properties are interpolated
+    /// from their value at cue_time to that of the layout over the
+    /// duration of the transition: the control points at the end of the
+    /// transition are identified as `layout-<cue_time>-end`. Other
+    /// properties are set at cue_time.
+    ApplyLayout {
+        /// Identifier of an existing mixer
+        id: String,
+        /// The name of the layout to apply
+        layout: String,
+        /// When to apply the layout
+        cue_time: DateTime<Utc>,
+        /// Duration of the transition to the layout, in milliseconds
+        transition_ms: Option<u32>,
+    },
+    /// Write the next frame of the video output of a mixer to a file, on
+    /// the server side
+    ///
+    /// The mixer must be started.
+    CaptureFrame {
+        /// Identifier of an existing mixer
+        id: String,
+        /// Path of the file to write, on the server
+        path: PathBuf,
+        /// The format of the image
+        format: ImageFileFormat,
+    },
+    /// Update the background of the video output of a mixer, see the
+    /// `compositor-background` setting
+    ///
+    /// Applies from the next output frame, without restarting the mixer.
+    SetCompositorBackground {
+        /// Identifier of an existing mixer
+        id: String,
+        /// One of none, black, white, transparent or checker
+        background: String,
+    },
+    /// Add a video output with a different resolution and framerate to
+    /// a mixer
+    ///
+    /// The output can then be connected to consumers by setting its id
+    /// as the `output_id` of [`Command::Connect`].
+    AddOutputScale {
+        /// Identifier of an existing mixer
+        id: String,
+        /// Unique identifier of the output for the mixer
+        output_id: String,
+        /// Width of the output
+        width: u32,
+        /// Height of the output
+        height: u32,
+        /// Framerate of the output, a strictly positive fraction, eg "30/1"
+        framerate: String,
+    },
+    /// Remove an output added with [`Command::AddOutputScale`], the links
+    /// it feeds are disconnected
+    RemoveOutput {
+        /// Identifier of an existing mixer
+        id: String,
+        /// Identifier of an existing output of the mixer
+        output_id: String,
+    },
+    /// Update a setting of a running mixer immediately, eg
+    /// `audio-eq-low-gain-db`
+    UpdateMixerSetting {
+        /// Identifier of an existing mixer
+        id: String,
+        /// Name of the setting
+        name: String,
+        /// The new value of the setting
+        value: serde_json::Value,
+    },
+    /// Retrieve the control points of a slot of a mixer, by property
+    GetSlotControlPoints {
+        /// Identifier of an existing mixer
+        id: String,
+        /// Identifier of an existing slot of the mixer
+        slot_id: String,
+    },
+    /// Remove all the pending control points of a property of a mixer
+    /// slot, the property keeps its current value
+    ClearSlotControlPoints {
+        /// Identifier of an existing mixer
+        id: String,
+        /// Identifier of an existing slot of the mixer
+        slot_id: String,
+        /// The slot config key of the property, eg `video::xpos`
+        property: String,
+    },
+    /// Set the additional latency of the `compositor` of a mixer, while
+    /// it is running
+    SetCompositorLatency {
+        /// Identifier of an existing mixer
+        id: String,
+        /// The additional latency, in nanoseconds
+        latency_ns: u64,
+    },
+    /// Set the additional latency of the `audiomixer` of a mixer, while
+    /// it is running
+    SetAudiomixerLatency {
+        /// Identifier of an existing mixer
+        id: String,
+        /// The additional latency, in nanoseconds
+        latency_ns: u64,
+    },
+    /// Describe the elements of the audio path of a mixer, from its slot
+    /// inputs to its outputs, see [`CommandResult::PipelineDescription`]
+    GetAudioPipelineDescription {
+        /// Identifier of an existing mixer
+        id: String,
+    },
+    /// Describe the elements of the video path of a mixer, from its slot
+    /// inputs to its outputs, see [`CommandResult::PipelineDescription`]
+    GetVideoPipelineDescription {
+        /// Identifier of an existing mixer
+        id: String,
+    },
+    /// Resize the video output of a mixer, scaling the position and size
+    /// of its slots along
+    ///
+    /// Slots without an explicit size keep the size of their input. While
+    /// mixing, the new size and layout are applied together with the next
+    /// frame.
+    ResizeMixerOutput {
+        /// Identifier of an existing mixer
+        id: String,
+        /// The new width of the output, strictly positive
+        width: i32,
+        /// The new height of the output, strictly positive
+        height: i32,
+    },
+    /// Capture the next video frame and audio buffer output by a running
+    /// mixer at the same time, see [`CommandResult::Snapshot`]
+    TakeMixerSnapshot {
+        /// Identifier of an existing mixer
+        id: String,
+    },
+    /// Update whether a mixer ignores the slots that have not received
+    /// any data yet, see the `video-ignore-inactive-pads` and
+    /// `audio-ignore-inactive-pads` mixer settings
+    ///
+    /// Applies immediately, including while mixing.
+    SetMixerIgnoreInactivePads {
+        /// Identifier of an existing mixer
+        id: String,
+        /// The new value for the video mixer, unchanged if None
+        #[serde(default)]
+        video: Option<bool>,
+        /// The new value for the audio mixer, unchanged if None
+        #[serde(default)]
+        audio: Option<bool>,
+    },
+    /// Set the text of the label drawn over the video of a mixer slot,
+    /// see `video::label-text`
+    SetSlotLabel {
+        /// Identifier of an existing mixer
+        id: String,
+        /// Identifier of an existing slot of the mixer
+        slot_id: String,
+        /// The text of the label, empty to hide it
+        text: String,
+    },
+    /// Stack the slots of a mixer in the given order, in one go
+    ///
+    /// The listed slots are stacked bottom to top, above the base plate
+    /// and below the watermark, by reassigning the `video::zorder` of
+    /// all the slots. Slots that are not listed are stacked above the
+    /// listed ones, keeping their current relative order. Slots without
+    /// video are ignored. A `video::zorder` under control keeps
+    /// following its control points.
+    SetSlotOrder {
+        /// Identifier of an existing mixer
+        id: String,
+        /// Identifiers of existing slots of the mixer, bottom to top
+        order: Vec<String>,
+    },
+    /// Validate mixer settings and a slot configuration without applying
+    /// them
+    ///
+    /// Errors are reported one per line, all settings and slot config
+    /// keys are checked. The slot configuration is checked as for a slot
+    /// with both audio and video.
+    ValidateMixerConfig {
+        /// Identifier of an existing mixer
+        id: String,
+        /// Mixer settings, as in [`Command::CreateMixer`]
+        config: Option<HashMap<String, serde_json::Value>>,
+        /// A slot configuration, as in [`Command::Connect`]
+        slot_config: Option<HashMap<String, serde_json::Value>>,
+    },
+    /// Set a key-value pair of metadata on the output streams of a mixer
+    ///
+    /// The metadata is sent as a tag event to the consumers of the
+    /// mixer: keys naming a GStreamer string tag, eg `title`, are sent
+    /// as that tag, others as an extended comment, `key=value`.
+    /// Consumers connected later only receive the metadata once it is
+    /// next set.
+    SetStreamMetadata {
+        /// Identifier of an existing mixer
+        id: String,
+        /// The metadata key
+        key: String,
+        /// The metadata value
+        value: String,
+    },
+    /// Dump the graph of a mixer pipeline to a dot file, for debugging
+    ///
+    /// The file is written to the directory pointed at by the
+    /// `GST_DEBUG_DUMP_DOT_DIR` environment variable of the server, which
+    /// must be set, and is named `<timestamp>-dump-mixer-<id>.dot`.
+    DumpPipeline {
+        /// Identifier of an existing mixer
+        id: String,
+    },
+}
+
+/// The media types of a producer to connect to a consumer
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ConnectMedia {
+    /// Both audio and video, this is the default
+    Both,
+    /// Video only
+    Video,
+    /// Audio only
+    Audio,
+    /// The audio monitor feed of a mixer, see `enable-audio-monitor` in
+    /// [`Command::CreateMixer`]
+    AudioMonitor,
+}
+
+impl Default for ConnectMedia {
+    fn default() -> Self {
+        ConnectMedia::Both
+    }
+}
+
+/// The image formats a frame can be captured as, see
+/// [`Command::CaptureFrame`]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ImageFileFormat {
+    Jpeg,
+    Png,
+}
+
+/// The type of a node, see [`Command::GetInfo`]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum NodeType {
+    /// Sources, including screen captures
+    Source,
+    Destination,
+    Mixer,
+}
+
+/// When a node starts forwarding its output to its consumers, see
+/// [`Command::SetForwardMode`]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ForwardMode {
+    /// The output is discarded until the node decides it is relevant,
+    /// eg once a source has prerolled or a mixer has started. This is
+    /// the default.
+    Manual,
+    /// The output is forwarded from its first sample on, without
+    /// waiting for the node.
+    Auto,
+    /// The output is discarded until its first keyframe, which is
+    /// requested from upstream as soon as a delta unit is discarded.
+    ///
+    /// This is useful for live sources that need to preroll, in order not
+    /// to forward partial frames at startup. As the consumers receive a
+    /// keyframe first, they don't request another one for their first
+    /// buffer, unlike in the other modes. Raw streams are forwarded from
+    /// their first sample, as they only consist of keyframes.
+    Keyframe,
+}
+
+impl Default for ForwardMode {
+    fn default() -> Self {
+        ForwardMode::Manual
+    }
+}
+
+/// What to do when connecting a link whose id is already in use
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum DuplicateLinkPolicy {
+    /// Refuse the connection, the existing link is kept. This is the
+    /// default
+    Reject,
+    /// Disconnect the existing link and connect the new one in its place.
+    ///
+    /// Only possible when both links have the same consumer. On a mixer,
+    /// the new slot takes the position, size and stacking order of the
+    /// slot it replaces, unless its configuration sets them. The base
+    /// plate is not shown in the meantime as long as the new producer
+    /// provides video within `fallback-timeout`, or the
+    /// `video::base-plate-timeout-ms` of the slot; the area of the slot
+    /// is empty until then.
+    ///
+    /// If the replacement fails, the command errors out and neither
+    /// link is kept.
+    Replace,
+    /// Refuse the connection as with `Reject`, and also send a warning
+    /// to the listeners of the consumer, for clients treating duplicates
+    /// as a bookkeeping error
+    Warn,
+}
+
+impl Default for DuplicateLinkPolicy {
+    fn default() -> Self {
+        DuplicateLinkPolicy::Reject
+    }
+}
+
+/// A set of mixer settings and slot configurations, all applied at once
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub struct MixerPreset {
+    /// Configuration of the mixer slots, slot id -> slot config key -> value,
+    /// eg `video::xpos`
+    #[serde(default)]
+    pub slot_configs: HashMap<String, HashMap<String, serde_json::Value>>,
+    /// Settings of the mixer, setting name -> value
+    #[serde(default)]
+    pub mixer_settings: HashMap<String, serde_json::Value>,
 }
 
 /// Messages sent from the controller to the server.
@@ -201,13 +985,64 @@ pub enum DestinationFamily {
     },
     /// Play on local devices
     LocalPlayback,
+    /// Write the audio stream, uncompressed and interleaved, to a local
+    /// file or a TCP socket. The video stream is ignored.
+    Pcm {
+        /// Where to write the audio stream:
+        ///
+        /// * `tcp://<host>:<port>`: raw PCM, sent to a TCP server
+        /// * `/path/to/audio.wav`: a WAV file, its header is finalized
+        ///   once the destination has stopped
+        /// * any other path: a raw PCM file
+        location: String,
+    },
+    /// Play the audio stream through a JACK server, as a JACK client.
+    /// The video stream is ignored.
+    Jack {
+        /// The name of the JACK client
+        client_name: String,
+        /// The name of the JACK server, the default server if None
+        server_name: Option<String>,
+        /// How the ports of the client should be connected
+        #[serde(default)]
+        connect: JackConnect,
+    },
+    /// Stream as live MPEG-DASH, an H264 stream and an AAC stream in
+    /// fragmented MP4 segments, written next to the manifest.
+    ///
+    /// The `base-url`, `target-duration`, `minimum-update-period`,
+    /// `use-segment-list` and `upload-command` settings of the
+    /// destination further control the output.
+    Dash {
+        /// The path of the manifest, eg `/path/to/dash.mpd`
+        manifest_location: String,
+    },
+}
+
+/// How the ports of a [`DestinationFamily::Jack`] client are connected
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum JackConnect {
+    /// Connect to the physical output ports, this is the default
+    Auto,
+    /// Leave the ports unconnected, eg for a patchbay to connect them
+    None,
+    /// Connect to the ports listed in the `jack-port-names` setting
+    Manual,
+}
+
+impl Default for JackConnect {
+    fn default() -> Self {
+        JackConnect::Auto
+    }
 }
 
 /// Source-specific information
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub struct SourceInfo {
-    /// The URI played back by the source
+    /// The URI played back by the source, `screen://<display-id>` for
+    /// screen captures
     pub uri: String,
     /// The identifiers of the consumers of the source
     pub consumer_slot_ids: Vec<String>,
@@ -217,6 +1052,12 @@ pub struct SourceInfo {
     pub end_time: Option<DateTime<Utc>>,
     /// The state of the source
     pub state: State,
+    /// Whether the video producer of the source forwards its samples to
+    /// its consumers, rather than discarding them
+    pub video_forwarding: bool,
+    /// Whether the audio producer of the source forwards its samples to
+    /// its consumers, rather than discarding them
+    pub audio_forwarding: bool,
 }
 
 /// Destination-specific information
@@ -233,6 +1074,31 @@ pub struct DestinationInfo {
     pub end_time: Option<DateTime<Utc>>,
     /// The state of the destination
     pub state: State,
+    /// All the destination settings
+    pub settings: HashMap<String, serde_json::Value>,
+    /// The bitrate output by the video encoder over the last second, in
+    /// bits per second, if any
+    pub video_bitrate: Option<u64>,
+    /// The number of segments written so far, for destinations writing
+    /// out segments
+    pub segment_count: Option<u64>,
+}
+
+/// Statistics about one of the input streams of a mixer slot
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub struct MixerSlotInputInfo {
+    /// The total number of buffers received
+    pub n_buffers: u64,
+    /// The number of buffers received per second of stream time, measured
+    /// over the last full second, eg the observed framerate for video
+    pub buffer_rate: f64,
+    /// The stream time gap between the last two buffers received, in
+    /// nanoseconds
+    pub last_gap_ns: Option<u64>,
+    /// The largest stream time gap observed between two consecutive
+    /// buffers, in nanoseconds
+    pub max_gap_ns: Option<u64>,
 }
 
 /// Mixer-slot-specific information
@@ -241,6 +1107,28 @@ pub struct DestinationInfo {
 pub struct MixerSlotInfo {
     /// The volume of the slot
     pub volume: f64,
+    /// The human-readable name of the slot, if any
+    pub label: Option<String>,
+    /// The sample rate of the audio input of the slot, once known. When it
+    /// matches the sample rate of the mixer, the audio of the slot is not
+    /// resampled
+    pub native_sample_rate: Option<i32>,
+    /// The number of video buffers dropped before reaching the slot,
+    /// because it did not consume them fast enough
+    pub video_buffers_dropped: u64,
+    /// The number of audio buffers dropped before reaching the slot,
+    /// because it did not consume them fast enough
+    pub audio_buffers_dropped: u64,
+    /// The PTS of the last video buffer selected for mixing, in
+    /// nanoseconds, 0 until one was selected
+    pub last_video_pts_ns: u64,
+    /// The PTS of the last audio buffer selected for mixing, in
+    /// nanoseconds, 0 until one was selected
+    pub last_audio_pts_ns: u64,
+    /// Statistics about the video input of the slot
+    pub video_input: MixerSlotInputInfo,
+    /// Statistics about the audio input of the slot
+    pub audio_input: MixerSlotInputInfo,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -265,6 +1153,51 @@ pub struct MixerInfo {
     pub slot_settings: HashMap<String, HashMap<String, serde_json::Value>>,
     /// All controllers active on the mixer's input slots
     pub slot_control_points: HashMap<String, HashMap<String, Vec<ControlPoint>>>,
+    /// A summary of each controller active on the mixer settings
+    pub control_point_summaries: HashMap<String, ControlPointSummary>,
+    /// A summary of each controller active on the mixer's input slots
+    pub slot_control_point_summaries: HashMap<String, HashMap<String, ControlPointSummary>>,
+    /// Whether the mixer is currently displaying its base plate
+    pub base_plate_active: bool,
+    /// How late the mixer produced its last video frame relative to
+    /// real time, in nanoseconds. This includes the latency of the
+    /// mixer, an approximation of its processing load
+    pub processing_lag_ns: Option<u64>,
+    /// Whether the mixer lags behind real time by more than its latency
+    /// plus 100 milliseconds, eg because the machine is oversubscribed.
+    /// Listeners are warned whenever that becomes the case
+    pub falling_behind: bool,
+    /// Whether the video producer of the mixer forwards its samples to
+    /// its consumers, rather than discarding them
+    pub video_forwarding: bool,
+    /// Whether the audio producer of the mixer forwards its samples to
+    /// its consumers, rather than discarding them
+    pub audio_forwarding: bool,
+    /// The MPEG-TS program number of the mixer output
+    pub program_number: u32,
+    /// The identifier of the mixer output streams
+    pub stream_id: String,
+    /// The metadata set on the mixer output streams
+    pub stream_metadata: HashMap<String, String>,
+    /// The caps negotiated on the mixer video output, which may differ
+    /// from those requested through the settings after a renegotiation.
+    /// None until the mixer has started
+    pub video_caps: Option<String>,
+    /// The caps negotiated on the mixer audio output, None until the
+    /// mixer has started
+    pub audio_caps: Option<String>,
+}
+
+/// A summary of the control points pending on a property or setting
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ControlPointSummary {
+    /// How many control points are pending
+    pub count: usize,
+    /// When the next control point is scheduled, if any
+    pub next_time: Option<DateTime<Utc>>,
+    /// The value of the last control point reached by the controller,
+    /// if any
+    pub last_value: Option<serde_json::Value>,
 }
 
 /// Info variants
@@ -281,6 +1214,54 @@ pub enum NodeInfo {
 #[serde(rename_all = "lowercase")]
 pub struct Info {
     pub nodes: HashMap<String, NodeInfo>,
+    /// When getting the info of all nodes, the nodes that failed to
+    /// report it, eg because they did not reply in time, id -> reason
+    #[serde(default)]
+    pub errors: HashMap<String, String>,
+}
+
+/// Describes an element of the pipeline of a mixer
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ElementInfo {
+    /// The name of the element
+    pub name: String,
+    /// The name of the type of the element, eg `GstAudioMixer`
+    pub type_name: String,
+    /// The current state of the element, eg `playing`
+    pub state: String,
+    /// The current values of the readable properties of the element
+    pub properties: HashMap<String, serde_json::Value>,
+}
+
+/// A video frame and an audio buffer output by a mixer at the same time
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MixerSnapshot {
+    /// The video frame, encoded as JPEG
+    pub video_jpeg: Vec<u8>,
+    /// The audio samples, interleaved
+    pub audio_pcm: Vec<f32>,
+    /// The width of the video frame
+    pub video_width: u32,
+    /// The height of the video frame
+    pub video_height: u32,
+    /// The sample rate of the audio samples
+    pub audio_sample_rate: u32,
+    /// The number of audio channels
+    pub audio_channels: u32,
+    /// The timestamp of the video frame, in nanoseconds
+    pub timestamp_ns: u64,
+}
+
+/// Sent for every node at the heartbeat interval of the server, as long
+/// as the node processes messages
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct NodeHeartbeat {
+    /// Identifier of the node
+    pub id: String,
+    /// The current state of the node
+    pub state: State,
+    /// Time elapsed since the node was created, in milliseconds
+    pub uptime_ms: u64,
 }
 
 /// Messages sent from the the server to the controller.
@@ -293,6 +1274,15 @@ pub enum CommandResult {
     Success,
     /// Information about one or all nodes
     Info(Info),
+    /// The control points of a mixer slot, by property
+    ControlPoints(HashMap<String, Vec<ControlPoint>>),
+    /// The elements of the audio or video path of a mixer
+    PipelineDescription(Vec<ElementInfo>),
+    /// A snapshot of the output of a mixer
+    Snapshot(MixerSnapshot),
+    /// A node is alive, sent periodically to all the controllers
+    /// without a command id
+    Heartbeat(NodeHeartbeat),
 }
 
 /// Messages sent from the the server to the controller.
@@ -304,3 +1294,17 @@ pub struct ServerMessage {
     /// The command result
     pub result: CommandResult,
 }
+
+/// Audio level measurements of a mixer output, pushed to the clients
+/// connected to `/ws/audio/{node_id}` as they get measured.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct AudioLevelEvent {
+    /// Identifier of the mixer
+    pub node_id: String,
+    /// The RMS level in dB, one value per channel
+    pub rms_db: Vec<f64>,
+    /// The peak level in dB, one value per channel
+    pub peak_db: Vec<f64>,
+    /// The timestamp of the measured buffer, in nanoseconds
+    pub timestamp_ns: u64,
+}
@@ -173,6 +173,38 @@ impl Controller {
                                 println!("Received info:");
                                 println!("{}", serde_json::to_string_pretty(&info).unwrap());
                             }
+                            CommandResult::ControlPoints(control_points) => {
+                                println!("Received control points:");
+                                println!(
+                                    "{}",
+                                    serde_json::to_string_pretty(&control_points).unwrap()
+                                );
+                            }
+                            CommandResult::PipelineDescription(elements) => {
+                                println!("Received pipeline description:");
+                                println!("{}", serde_json::to_string_pretty(&elements).unwrap());
+                            }
+                            CommandResult::Snapshot(snapshot) => {
+                                println!("Received snapshot at {} ns:", snapshot.timestamp_ns);
+                                println!(
+                                    "{}x{} JPEG of {} bytes",
+                                    snapshot.video_width,
+                                    snapshot.video_height,
+                                    snapshot.video_jpeg.len()
+                                );
+                                println!(
+                                    "{} audio samples, {} channels at {} Hz",
+                                    snapshot.audio_pcm.len(),
+                                    snapshot.audio_channels,
+                                    snapshot.audio_sample_rate
+                                );
+                            }
+                            CommandResult::Heartbeat(heartbeat) => {
+                                debug!(
+                                    "Node {} is alive, {:?} for {} ms",
+                                    heartbeat.id, heartbeat.state, heartbeat.uptime_ms
+                                );
+                            }
                         }
 
                         let exit_on_response_id = exit_on_response_id_clone.lock().unwrap();
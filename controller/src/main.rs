@@ -8,7 +8,10 @@ use std::path::PathBuf;
 mod controller;
 use controller::Controller;
 
-use auteur_controlling::controller::{Command, ControlMode, ControlPoint, DestinationFamily};
+use auteur_controlling::controller::{
+    Command, ConnectMedia, ControlMode, ControlPoint, DestinationFamily, DuplicateLinkPolicy,
+    ForwardMode, ImageFileFormat, JackConnect, MixerPreset, NodeType,
+};
 
 #[derive(Clap, Debug)]
 #[clap(author = "Mathieu Duponchelle <mathieu@centricular.com>")]
@@ -32,12 +35,18 @@ enum SubCommand {
         #[clap(subcommand)]
         subcmd: NodeSubCommand,
     },
+    /// Control mixers
+    Mixer {
+        #[clap(subcommand)]
+        subcmd: MixerSubCommand,
+    },
 }
 
 #[derive(Clap, Debug)]
 enum ArgControlMode {
     Interpolate,
     Set,
+    BezierCubic,
 }
 
 impl From<ArgControlMode> for ControlMode {
@@ -45,6 +54,13 @@ impl From<ArgControlMode> for ControlMode {
         match other {
             ArgControlMode::Interpolate => ControlMode::Interpolate,
             ArgControlMode::Set => ControlMode::Set,
+            // CSS' ease, when no curve is specified
+            ArgControlMode::BezierCubic => ControlMode::BezierCubic {
+                cp1_time_frac: 0.25,
+                cp1_value_frac: 0.1,
+                cp2_time_frac: 0.25,
+                cp2_value_frac: 1.0,
+            },
         }
     }
 }
@@ -54,6 +70,109 @@ impl From<ControlMode> for ArgControlMode {
         match other {
             ControlMode::Interpolate => ArgControlMode::Interpolate,
             ControlMode::Set => ArgControlMode::Set,
+            ControlMode::BezierCubic { .. } => ArgControlMode::BezierCubic,
+        }
+    }
+}
+
+#[derive(Clap, Debug)]
+enum ArgConnectMedia {
+    Both,
+    Video,
+    Audio,
+    AudioMonitor,
+}
+
+impl From<ArgConnectMedia> for ConnectMedia {
+    fn from(other: ArgConnectMedia) -> ConnectMedia {
+        match other {
+            ArgConnectMedia::Both => ConnectMedia::Both,
+            ArgConnectMedia::Video => ConnectMedia::Video,
+            ArgConnectMedia::Audio => ConnectMedia::Audio,
+            ArgConnectMedia::AudioMonitor => ConnectMedia::AudioMonitor,
+        }
+    }
+}
+
+#[derive(Clap, Debug)]
+enum ArgDuplicateLinkPolicy {
+    Reject,
+    Replace,
+    Warn,
+}
+
+impl From<ArgDuplicateLinkPolicy> for DuplicateLinkPolicy {
+    fn from(other: ArgDuplicateLinkPolicy) -> DuplicateLinkPolicy {
+        match other {
+            ArgDuplicateLinkPolicy::Reject => DuplicateLinkPolicy::Reject,
+            ArgDuplicateLinkPolicy::Replace => DuplicateLinkPolicy::Replace,
+            ArgDuplicateLinkPolicy::Warn => DuplicateLinkPolicy::Warn,
+        }
+    }
+}
+
+#[derive(Clap, Debug)]
+enum ArgForwardMode {
+    Manual,
+    Auto,
+    Keyframe,
+}
+
+impl From<ArgForwardMode> for ForwardMode {
+    fn from(other: ArgForwardMode) -> ForwardMode {
+        match other {
+            ArgForwardMode::Manual => ForwardMode::Manual,
+            ArgForwardMode::Auto => ForwardMode::Auto,
+            ArgForwardMode::Keyframe => ForwardMode::Keyframe,
+        }
+    }
+}
+
+#[derive(Clap, Debug)]
+enum ArgNodeType {
+    Source,
+    Destination,
+    Mixer,
+}
+
+impl From<ArgNodeType> for NodeType {
+    fn from(other: ArgNodeType) -> NodeType {
+        match other {
+            ArgNodeType::Source => NodeType::Source,
+            ArgNodeType::Destination => NodeType::Destination,
+            ArgNodeType::Mixer => NodeType::Mixer,
+        }
+    }
+}
+
+#[derive(Clap, Debug)]
+enum ArgImageFileFormat {
+    Jpeg,
+    Png,
+}
+
+impl From<ArgImageFileFormat> for ImageFileFormat {
+    fn from(other: ArgImageFileFormat) -> ImageFileFormat {
+        match other {
+            ArgImageFileFormat::Jpeg => ImageFileFormat::Jpeg,
+            ArgImageFileFormat::Png => ImageFileFormat::Png,
+        }
+    }
+}
+
+#[derive(Clap, Debug)]
+enum ArgJackConnect {
+    Auto,
+    None,
+    Manual,
+}
+
+impl From<ArgJackConnect> for JackConnect {
+    fn from(other: ArgJackConnect) -> JackConnect {
+        match other {
+            ArgJackConnect::Auto => JackConnect::Auto,
+            ArgJackConnect::None => JackConnect::None,
+            ArgJackConnect::Manual => JackConnect::Manual,
         }
     }
 }
@@ -73,6 +192,28 @@ where
     Ok((s[..pos].parse()?, res))
 }
 
+/// Parse the control points of a bezier easing curve
+fn parse_bezier(
+    s: &str,
+) -> Result<(f64, f64, f64, f64), Box<dyn std::error::Error + Send + Sync + 'static>> {
+    let coords = s
+        .split(',')
+        .map(|coord| coord.trim().parse::<f64>())
+        .collect::<Result<Vec<f64>, _>>()?;
+
+    match coords[..] {
+        [x1, y1, x2, y2] => Ok((x1, y1, x2, y2)),
+        _ => Err(format!("invalid bezier curve `{}`, expected x1,y1,x2,y2", s).into()),
+    }
+}
+
+/// Parse a JSON mixer preset
+fn parse_preset(
+    s: &str,
+) -> Result<MixerPreset, Box<dyn std::error::Error + Send + Sync + 'static>> {
+    Ok(serde_json::from_str(s)?)
+}
+
 /// Create and connect nodes
 #[derive(Clap, Debug)]
 enum NodeSubCommand {
@@ -92,12 +233,35 @@ enum NodeSubCommand {
         /// Initial configuration of the consumer slot
         #[clap(parse(try_from_str = parse_config))]
         config: Vec<(String, serde_json::Value)>,
+        /// Which media types to connect
+        #[clap(long, arg_enum, default_value = "both")]
+        media: ArgConnectMedia,
+        /// What to do when a link with the same id already exists
+        #[clap(long, arg_enum, default_value = "reject")]
+        on_duplicate: ArgDuplicateLinkPolicy,
+        /// The id of a scaled output of the producer to connect, instead
+        /// of its main output
+        #[clap(long)]
+        output_id: Option<String>,
     },
     /// Remove an existing link
     Disconnect {
         /// The id of the link
         link_id: String,
     },
+    /// Flush an existing link, to recover a stalled input
+    FlushSlot {
+        /// The id of the link
+        link_id: String,
+    },
+    /// Hold the video of an existing link on its current frame
+    FreezeSlot {
+        /// The id of the link
+        link_id: String,
+        /// Resume the link instead
+        #[clap(long)]
+        unfreeze: bool,
+    },
     /// Cue a node for playback
     Start {
         /// The id of an existing node
@@ -109,6 +273,46 @@ enum NodeSubCommand {
         #[clap(long)]
         end_time: Option<DateTime<Utc>>,
     },
+    /// Group existing nodes, to start and stop them together
+    CreateGroup {
+        /// The id of the group
+        id: String,
+        /// The ids of existing nodes
+        #[clap(required = true)]
+        node_ids: Vec<String>,
+    },
+    /// Cue all the nodes of a group for playback
+    StartGroup {
+        /// The id of an existing group
+        id: String,
+        /// When to cue the nodes, None is immediate
+        #[clap(long)]
+        cue_time: Option<DateTime<Utc>>,
+        /// When to stop the nodes, None is never
+        #[clap(long)]
+        end_time: Option<DateTime<Utc>>,
+    },
+    /// Stop and remove all the nodes of a group
+    StopGroup {
+        /// The id of an existing group
+        id: String,
+    },
+    /// Override the level of the traces emitted by a node
+    SetLogLevel {
+        /// The id of a node
+        id: String,
+        /// One of off, error, warn, info, debug, trace, the global level
+        /// is restored when not specified
+        level: Option<String>,
+    },
+    /// Set when a producer node starts forwarding its output
+    SetForwardMode {
+        /// The id of an existing source, screen capture or mixer
+        id: String,
+        /// When to start forwarding
+        #[clap(arg_enum)]
+        mode: ArgForwardMode,
+    },
     /// Reschedule any node
     Reschedule {
         /// The id of an existing node
@@ -124,11 +328,20 @@ enum NodeSubCommand {
     Remove {
         /// The id of the node
         id: String,
+        /// Stop the node immediately, without draining its pipeline
+        #[clap(long)]
+        kill: bool,
+        /// Also stop the nodes downstream of the node, once it has stopped
+        #[clap(long)]
+        cascade: bool,
     },
     /// Retrieve the info of all nodes or a specific node
     GetInfo {
         /// The id of the node, if not specified, all nodes
         id: Option<String>,
+        /// When no id is specified, only the nodes of that type
+        #[clap(long, arg_enum)]
+        node_type: Option<ArgNodeType>,
     },
     /// Control properties over time for a node or slot
     AddControlPoint {
@@ -145,6 +358,11 @@ enum NodeSubCommand {
         /// How to apply the control point
         #[clap(arg_enum)]
         mode: ArgControlMode,
+        /// The cubic bezier curve of the bezier-cubic mode, as x1,y1,x2,y2
+        /// in [0, 1], eg 0.42,0,0.58,1 for ease-in-out, CSS' ease
+        /// (0.25,0.1,0.25,1) by default
+        #[clap(long, parse(try_from_str = parse_bezier))]
+        bezier: Option<(f64, f64, f64, f64)>,
     },
     /// Remove a previously set control point
     RemoveControlPoint {
@@ -167,6 +385,14 @@ enum CreateNodeSubCommand {
         /// The URI of the source
         uri: String,
     },
+    /// Create a new source capturing a display
+    ScreenCapture {
+        /// Unique identifier for the source
+        id: String,
+        /// Initial configuration of the source
+        #[clap(parse(try_from_str = parse_config))]
+        config: Vec<(String, serde_json::Value)>,
+    },
     /// Create a new destination
     Destination {
         #[clap(subcommand)]
@@ -191,6 +417,9 @@ enum CreateDestinationSubCommand {
         id: String,
         /// RTMP URI
         uri: String,
+        /// Initial configuration of the destination
+        #[clap(parse(try_from_str = parse_config))]
+        config: Vec<(String, serde_json::Value)>,
     },
     /// Create a new local file destination
     LocalFile {
@@ -203,11 +432,51 @@ enum CreateDestinationSubCommand {
         /// files. milliseconds
         #[clap(long)]
         max_size_time: Option<u32>,
+        /// Initial configuration of the destination
+        #[clap(parse(try_from_str = parse_config))]
+        config: Vec<(String, serde_json::Value)>,
     },
     LocalPlayback {
         /// Unique identifier for the destination
         id: String,
     },
+    /// Create a new uncompressed audio destination
+    Pcm {
+        /// Unique identifier for the destination
+        id: String,
+        /// tcp://host:port, a path ending in .wav for a WAV file, or
+        /// any other path for a raw PCM file
+        location: String,
+        /// Initial configuration of the destination
+        #[clap(parse(try_from_str = parse_config))]
+        config: Vec<(String, serde_json::Value)>,
+    },
+    /// Create a new JACK audio destination
+    Jack {
+        /// Unique identifier for the destination
+        id: String,
+        /// The name of the JACK client
+        client_name: String,
+        /// The name of the JACK server, the default server if not set
+        #[clap(long)]
+        server_name: Option<String>,
+        /// How to connect the ports of the client
+        #[clap(long, arg_enum, default_value = "auto")]
+        connect: ArgJackConnect,
+        /// Initial configuration of the destination
+        #[clap(parse(try_from_str = parse_config))]
+        config: Vec<(String, serde_json::Value)>,
+    },
+    /// Create a new MPEG-DASH destination
+    Dash {
+        /// Unique identifier for the destination
+        id: String,
+        /// The path of the manifest, segments are written next to it
+        manifest_location: String,
+        /// Initial configuration of the destination
+        #[clap(parse(try_from_str = parse_config))]
+        config: Vec<(String, serde_json::Value)>,
+    },
 }
 
 /// Source-specific commands
@@ -220,7 +489,188 @@ enum DestinationSubCommand {}
 
 /// Mixer-specific commands
 #[derive(Clap, Debug)]
-enum MixerSubCommand {}
+enum MixerSubCommand {
+    /// Schedule the application of a preset
+    ApplyPreset {
+        /// The id of an existing mixer
+        id: String,
+        /// When to apply the preset
+        cue_time: DateTime<Utc>,
+        /// The preset, as JSON, eg
+        /// '{"slot_configs": {"slot-0": {"video::xpos": 0}}, "mixer_settings": {"width": 1280}}'
+        #[clap(parse(try_from_str = parse_preset))]
+        preset: MixerPreset,
+    },
+    /// Schedule the application of a layout defined in the mixer config
+    ApplyLayout {
+        /// The id of an existing mixer
+        id: String,
+        /// The name of the layout
+        layout: String,
+        /// When to apply the layout
+        cue_time: DateTime<Utc>,
+        /// Duration of the transition to the layout, in milliseconds
+        #[clap(long)]
+        transition_ms: Option<u32>,
+    },
+    /// Write the next frame of the mixer video output to a file, on the
+    /// server side
+    CaptureFrame {
+        /// The id of an existing mixer
+        id: String,
+        /// The path of the file to write, on the server
+        path: PathBuf,
+        /// The format of the image
+        #[clap(long, arg_enum, default_value = "png")]
+        format: ArgImageFileFormat,
+    },
+    /// Update the background of the mixer video output
+    SetCompositorBackground {
+        /// The id of an existing mixer
+        id: String,
+        /// One of none, black, white, transparent or checker
+        background: String,
+    },
+    /// Add a video output with a different resolution and framerate,
+    /// connected with `node connect --output-id`
+    AddOutputScale {
+        /// The id of an existing mixer
+        id: String,
+        /// The id of the output
+        output_id: String,
+        /// The width of the output
+        width: u32,
+        /// The height of the output
+        height: u32,
+        /// The framerate of the output
+        #[clap(long, default_value = "30/1")]
+        framerate: String,
+    },
+    /// Remove a scaled output, disconnecting the links it feeds
+    RemoveOutput {
+        /// The id of an existing mixer
+        id: String,
+        /// The id of an existing output of the mixer
+        output_id: String,
+    },
+    /// Update a setting of a running mixer immediately
+    UpdateSetting {
+        /// The id of an existing mixer
+        id: String,
+        /// Name of the setting, eg audio-eq-low-gain-db
+        name: String,
+        /// The new value of the setting
+        value: serde_json::Value,
+    },
+    /// Retrieve the control points of a slot of a mixer
+    GetSlotControlPoints {
+        /// The id of an existing mixer
+        id: String,
+        /// The id of an existing slot of the mixer
+        slot_id: String,
+    },
+    /// Remove all the pending control points of a property of a slot
+    ClearSlotControlPoints {
+        /// The id of an existing mixer
+        id: String,
+        /// The id of an existing slot of the mixer
+        slot_id: String,
+        /// The slot config key of the property, eg video::xpos
+        property: String,
+    },
+    /// Set the additional latency of the compositor of a running mixer
+    SetCompositorLatency {
+        /// The id of an existing mixer
+        id: String,
+        /// The additional latency, in nanoseconds
+        latency_ns: u64,
+    },
+    /// Set the additional latency of the audiomixer of a running mixer
+    SetAudiomixerLatency {
+        /// The id of an existing mixer
+        id: String,
+        /// The additional latency, in nanoseconds
+        latency_ns: u64,
+    },
+    /// Describe the elements of the audio path of a mixer
+    GetAudioPipelineDescription {
+        /// The id of an existing mixer
+        id: String,
+    },
+    /// Describe the elements of the video path of a mixer
+    GetVideoPipelineDescription {
+        /// The id of an existing mixer
+        id: String,
+    },
+    /// Resize the video output of a mixer along with the layout of its slots
+    ResizeOutput {
+        /// The id of an existing mixer
+        id: String,
+        /// The new width of the output
+        width: i32,
+        /// The new height of the output
+        height: i32,
+    },
+    /// Capture the video and audio output of a running mixer at the same time
+    TakeSnapshot {
+        /// The id of an existing mixer
+        id: String,
+    },
+    /// Update whether a mixer ignores the slots that have not received any data yet
+    SetIgnoreInactivePads {
+        /// The id of an existing mixer
+        id: String,
+        /// The new value for the video mixer, true or false
+        #[clap(long)]
+        video: Option<bool>,
+        /// The new value for the audio mixer, true or false
+        #[clap(long)]
+        audio: Option<bool>,
+    },
+    /// Set the text of the label drawn over the video of a mixer slot
+    SetSlotLabel {
+        /// The id of an existing mixer
+        id: String,
+        /// The id of an existing slot of the mixer
+        slot_id: String,
+        /// The text of the label, empty to hide it
+        text: String,
+    },
+    /// Stack the slots of a mixer in the given order, bottom to top
+    SetSlotOrder {
+        /// The id of an existing mixer
+        id: String,
+        /// The ids of existing slots of the mixer, bottom to top
+        order: Vec<String>,
+    },
+    /// Validate mixer settings and a slot configuration without applying
+    /// them
+    ValidateConfig {
+        /// The id of an existing mixer
+        id: String,
+        /// Mixer settings
+        #[clap(long, parse(try_from_str = parse_config))]
+        setting: Vec<(String, serde_json::Value)>,
+        /// Slot config keys
+        #[clap(long, parse(try_from_str = parse_config))]
+        slot_config: Vec<(String, serde_json::Value)>,
+    },
+    /// Set a key-value pair of metadata on the mixer output streams
+    SetStreamMetadata {
+        /// The id of an existing mixer
+        id: String,
+        /// The metadata key, eg title
+        key: String,
+        /// The metadata value
+        value: String,
+    },
+    /// Dump the graph of the mixer pipeline to a dot file, on the server
+    /// side. GST_DEBUG_DUMP_DOT_DIR must be set for the server
+    DumpPipeline {
+        /// The id of an existing mixer
+        id: String,
+    },
+}
 
 /// Client application entry point
 fn main() -> Result<(), Error> {
@@ -241,30 +691,73 @@ fn main() -> Result<(), Error> {
             SubCommand::Node { subcmd } => match subcmd {
                 NodeSubCommand::Create { subcmd } => match subcmd {
                     CreateNodeSubCommand::Source { id, uri } => Command::CreateSource { id, uri },
+                    CreateNodeSubCommand::ScreenCapture { id, config } => {
+                        Command::CreateScreenCapture {
+                            id,
+                            config: Some(config.into_iter().collect()),
+                        }
+                    }
                     CreateNodeSubCommand::Destination { subcmd } => match subcmd {
-                        CreateDestinationSubCommand::Rtmp { id, uri } => {
+                        CreateDestinationSubCommand::Rtmp { id, uri, config } => {
                             Command::CreateDestination {
                                 id,
                                 family: DestinationFamily::Rtmp { uri },
+                                config: Some(config.into_iter().collect()),
                             }
                         }
                         CreateDestinationSubCommand::LocalFile {
                             id,
                             base_name,
                             max_size_time,
+                            config,
                         } => Command::CreateDestination {
                             id,
                             family: DestinationFamily::LocalFile {
                                 base_name,
                                 max_size_time,
                             },
+                            config: Some(config.into_iter().collect()),
                         },
                         CreateDestinationSubCommand::LocalPlayback { id } => {
                             Command::CreateDestination {
                                 id,
                                 family: DestinationFamily::LocalPlayback,
+                                config: None,
                             }
                         }
+                        CreateDestinationSubCommand::Pcm {
+                            id,
+                            location,
+                            config,
+                        } => Command::CreateDestination {
+                            id,
+                            family: DestinationFamily::Pcm { location },
+                            config: Some(config.into_iter().collect()),
+                        },
+                        CreateDestinationSubCommand::Jack {
+                            id,
+                            client_name,
+                            server_name,
+                            connect,
+                            config,
+                        } => Command::CreateDestination {
+                            id,
+                            family: DestinationFamily::Jack {
+                                client_name,
+                                server_name,
+                                connect: connect.into(),
+                            },
+                            config: Some(config.into_iter().collect()),
+                        },
+                        CreateDestinationSubCommand::Dash {
+                            id,
+                            manifest_location,
+                            config,
+                        } => Command::CreateDestination {
+                            id,
+                            family: DestinationFamily::Dash { manifest_location },
+                            config: Some(config.into_iter().collect()),
+                        },
                     },
                     CreateNodeSubCommand::Mixer { id, config } => Command::CreateMixer {
                         id,
@@ -276,13 +769,24 @@ fn main() -> Result<(), Error> {
                     src_id,
                     sink_id,
                     config,
+                    media,
+                    on_duplicate,
+                    output_id,
                 } => Command::Connect {
                     link_id,
                     src_id,
                     sink_id,
                     config: Some(config.into_iter().collect()),
+                    media: media.into(),
+                    on_duplicate: on_duplicate.into(),
+                    output_id,
                 },
                 NodeSubCommand::Disconnect { link_id } => Command::Disconnect { link_id },
+                NodeSubCommand::FlushSlot { link_id } => Command::FlushSlot { link_id },
+                NodeSubCommand::FreezeSlot { link_id, unfreeze } => Command::FreezeSlot {
+                    link_id,
+                    frozen: !unfreeze,
+                },
                 NodeSubCommand::Start {
                     id,
                     cue_time,
@@ -292,6 +796,24 @@ fn main() -> Result<(), Error> {
                     cue_time,
                     end_time,
                 },
+                NodeSubCommand::CreateGroup { id, node_ids } => {
+                    Command::CreateGroup { id, node_ids }
+                }
+                NodeSubCommand::StartGroup {
+                    id,
+                    cue_time,
+                    end_time,
+                } => Command::StartGroup {
+                    id,
+                    cue_time,
+                    end_time,
+                },
+                NodeSubCommand::StopGroup { id } => Command::StopGroup { id },
+                NodeSubCommand::SetLogLevel { id, level } => Command::SetNodeLogLevel { id, level },
+                NodeSubCommand::SetForwardMode { id, mode } => Command::SetForwardMode {
+                    id,
+                    mode: mode.into(),
+                },
                 NodeSubCommand::Reschedule {
                     id,
                     cue_time,
@@ -301,8 +823,13 @@ fn main() -> Result<(), Error> {
                     cue_time,
                     end_time,
                 },
-                NodeSubCommand::Remove { id } => Command::Remove { id },
-                NodeSubCommand::GetInfo { id } => Command::GetInfo { id },
+                NodeSubCommand::Remove { id, kill, cascade } => {
+                    Command::Remove { id, kill, cascade }
+                }
+                NodeSubCommand::GetInfo { id, node_type } => Command::GetInfo {
+                    id,
+                    node_type: node_type.map(NodeType::from),
+                },
                 NodeSubCommand::AddControlPoint {
                     id,
                     controllee_id,
@@ -310,6 +837,7 @@ fn main() -> Result<(), Error> {
                     time,
                     value,
                     mode,
+                    bezier,
                 } => Command::AddControlPoint {
                     controllee_id,
                     property,
@@ -317,7 +845,17 @@ fn main() -> Result<(), Error> {
                         id,
                         time,
                         value,
-                        mode: mode.into(),
+                        mode: match (mode, bezier) {
+                            (ArgControlMode::BezierCubic, Some((x1, y1, x2, y2))) => {
+                                ControlMode::BezierCubic {
+                                    cp1_time_frac: x1,
+                                    cp1_value_frac: y1,
+                                    cp2_time_frac: x2,
+                                    cp2_value_frac: y2,
+                                }
+                            }
+                            (mode, _) => mode.into(),
+                        },
                     },
                 },
                 NodeSubCommand::RemoveControlPoint {
@@ -330,6 +868,103 @@ fn main() -> Result<(), Error> {
                     property,
                 },
             },
+            SubCommand::Mixer { subcmd } => match subcmd {
+                MixerSubCommand::ApplyPreset {
+                    id,
+                    cue_time,
+                    preset,
+                } => Command::ApplyPreset {
+                    id,
+                    preset,
+                    cue_time,
+                },
+                MixerSubCommand::ApplyLayout {
+                    id,
+                    layout,
+                    cue_time,
+                    transition_ms,
+                } => Command::ApplyLayout {
+                    id,
+                    layout,
+                    cue_time,
+                    transition_ms,
+                },
+                MixerSubCommand::CaptureFrame { id, path, format } => Command::CaptureFrame {
+                    id,
+                    path,
+                    format: format.into(),
+                },
+                MixerSubCommand::SetCompositorBackground { id, background } => {
+                    Command::SetCompositorBackground { id, background }
+                }
+                MixerSubCommand::AddOutputScale {
+                    id,
+                    output_id,
+                    width,
+                    height,
+                    framerate,
+                } => Command::AddOutputScale {
+                    id,
+                    output_id,
+                    width,
+                    height,
+                    framerate,
+                },
+                MixerSubCommand::RemoveOutput { id, output_id } => {
+                    Command::RemoveOutput { id, output_id }
+                }
+                MixerSubCommand::UpdateSetting { id, name, value } => {
+                    Command::UpdateMixerSetting { id, name, value }
+                }
+                MixerSubCommand::GetSlotControlPoints { id, slot_id } => {
+                    Command::GetSlotControlPoints { id, slot_id }
+                }
+                MixerSubCommand::ClearSlotControlPoints {
+                    id,
+                    slot_id,
+                    property,
+                } => Command::ClearSlotControlPoints {
+                    id,
+                    slot_id,
+                    property,
+                },
+                MixerSubCommand::SetCompositorLatency { id, latency_ns } => {
+                    Command::SetCompositorLatency { id, latency_ns }
+                }
+                MixerSubCommand::SetAudiomixerLatency { id, latency_ns } => {
+                    Command::SetAudiomixerLatency { id, latency_ns }
+                }
+                MixerSubCommand::GetAudioPipelineDescription { id } => {
+                    Command::GetAudioPipelineDescription { id }
+                }
+                MixerSubCommand::GetVideoPipelineDescription { id } => {
+                    Command::GetVideoPipelineDescription { id }
+                }
+                MixerSubCommand::ResizeOutput { id, width, height } => {
+                    Command::ResizeMixerOutput { id, width, height }
+                }
+                MixerSubCommand::TakeSnapshot { id } => Command::TakeMixerSnapshot { id },
+                MixerSubCommand::SetIgnoreInactivePads { id, video, audio } => {
+                    Command::SetMixerIgnoreInactivePads { id, video, audio }
+                }
+                MixerSubCommand::SetSlotLabel { id, slot_id, text } => {
+                    Command::SetSlotLabel { id, slot_id, text }
+                }
+                MixerSubCommand::SetSlotOrder { id, order } => Command::SetSlotOrder { id, order },
+                MixerSubCommand::ValidateConfig {
+                    id,
+                    setting,
+                    slot_config,
+                } => Command::ValidateMixerConfig {
+                    id,
+                    config: Some(setting.into_iter().collect()),
+                    slot_config: Some(slot_config.into_iter().collect()),
+                },
+                MixerSubCommand::SetStreamMetadata { id, key, value } => {
+                    Command::SetStreamMetadata { id, key, value }
+                }
+                MixerSubCommand::DumpPipeline { id } => Command::DumpPipeline { id },
+            },
         };
 
         let (mut controller, join_handle) =
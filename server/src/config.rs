@@ -24,4 +24,12 @@ pub struct Config {
     /// Where logs should be stored
     #[structopt(long)]
     pub log_path: Option<PathBuf>,
+    /// Emit logs as structured JSON lines, including the fields of
+    /// the current span and of its parents
+    #[structopt(long)]
+    pub log_json: bool,
+    /// Interval in seconds at which nodes report they are alive to
+    /// status listeners, 0 to disable.
+    #[structopt(long, default_value = "0")]
+    pub heartbeat_interval: u64,
 }
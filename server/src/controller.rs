@@ -3,7 +3,7 @@
 //! Receives JSON messages through websockets, conforming with
 //! [`this protocol`](auteur_controlling::controller)
 
-use crate::node::{CommandMessage, NodeManager};
+use crate::node::{CommandMessage, NodeManager, NodeStatusMessage, RegisterListenerMessage};
 
 use anyhow::{format_err, Error};
 
@@ -13,7 +13,9 @@ use actix_web_actors::ws;
 
 use tracing::{debug, error, instrument, trace};
 
-use auteur_controlling::controller::{Command, CommandResult, ControllerMessage, ServerMessage};
+use auteur_controlling::controller::{
+    Command, CommandResult, ControllerMessage, NodeHeartbeat, ServerMessage,
+};
 
 /// Actor that represents an application controller.
 #[derive(Debug)]
@@ -104,6 +106,14 @@ impl Controller {
 
 impl Actor for Controller {
     type Context = ws::WebsocketContext<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        // Listen to node statuses, for forwarding heartbeats
+        NodeManager::from_registry().do_send(RegisterListenerMessage {
+            id: format!("controller-{}", uuid::Uuid::new_v4()),
+            recipient: ctx.address().downgrade().recipient(),
+        });
+    }
 }
 
 impl StreamHandler<Result<ws::Message, ws::ProtocolError>> for Controller {
@@ -172,3 +182,27 @@ impl Handler<ErrorMessage> for Controller {
         );
     }
 }
+
+impl Handler<NodeStatusMessage> for Controller {
+    type Result = ();
+
+    fn handle(
+        &mut self,
+        msg: NodeStatusMessage,
+        ctx: &mut ws::WebsocketContext<Self>,
+    ) -> Self::Result {
+        if let NodeStatusMessage::Heartbeat { id, state, uptime } = msg {
+            ctx.text(
+                serde_json::to_string(&ServerMessage {
+                    id: None,
+                    result: CommandResult::Heartbeat(NodeHeartbeat {
+                        id,
+                        state,
+                        uptime_ms: uptime.as_millis() as u64,
+                    }),
+                })
+                .expect("Failed to serialize heartbeat message"),
+            );
+        }
+    }
+}
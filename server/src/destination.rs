@@ -1,7 +1,7 @@
 //! A destination processing node.
 //!
-//! The actual destination depends on its family, for example RTMP or LocalFile
-//! are supported.
+//! The actual destination depends on its family, for example RTMP, LocalFile
+//! or DASH are supported.
 //!
 //! Destinations spend time in the [`stopping state`](State::Stopping)
 //! during which EOS will be propagated down their pipeline before actually
@@ -10,19 +10,31 @@
 use actix::prelude::*;
 use anyhow::{anyhow, Error};
 use gst::prelude::*;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{mpsc, Arc, Mutex, MutexGuard};
 use tracing::{debug, error, instrument, trace};
 
-use auteur_controlling::controller::{DestinationFamily, DestinationInfo, NodeInfo, State};
+use auteur_controlling::controller::{
+    DestinationFamily, DestinationInfo, JackConnect, NodeInfo, State,
+};
 
 use crate::node::{
-    AddControlPointMessage, ConsumerMessage, GetNodeInfoMessage, NodeManager, NodeStatusMessage,
-    RemoveControlPointMessage, ScheduleMessage, StartMessage, StopMessage, StoppedMessage,
+    AddControlPointMessage, ConsumerMessage, GetLatencyMessage, GetNodeInfoMessage,
+    GetStateMessage, NodeManager, NodeStatusMessage, RemoveControlPointMessage, ScheduleMessage,
+    StartMessage, StopMessage, StoppedMessage,
 };
 use crate::utils::{
-    make_element, ErrorMessage, PipelineManager, Schedulable, StateChangeResult, StateMachine,
-    StopManagerMessage, StreamProducer, WaitForEosMessage,
+    make_element, query_pipeline_latency, ErrorMessage, FragmentClosedMessage, PipelineLatency,
+    PipelineManager, Schedulable, Setting, SettingController, SettingSpec, StateChangeResult,
+    StateMachine, StopManagerMessage, StreamProducer, WaitForEosMessage,
 };
 
+/// How long the video encoder must be capped before we warn about it,
+/// in seconds
+const BITRATE_CAP_WARNING_SECS: u32 = 10;
+
 /// Represents the potential connection to a producer
 struct ConsumerSlot {
     /// Identifier of the slot
@@ -51,6 +63,17 @@ pub struct Destination {
     consumer_slot: Option<ConsumerSlot>,
     /// Our state machine
     state_machine: StateMachine,
+    /// Our settings
+    settings: HashMap<String, Arc<Mutex<Setting>>>,
+    /// The bitrate output by the video encoder over the last second
+    video_bitrate: Option<u64>,
+    /// For how many consecutive seconds the video encoder was capped
+    n_capped_seconds: u32,
+    /// How many segments were written, for the Dash family
+    segment_count: u64,
+    /// Feeds the thread running our `upload-command`, for the Dash
+    /// family, see [`Destination::spawn_uploader`]
+    upload_sender: Option<mpsc::Sender<Vec<String>>>,
 }
 
 impl Actor for Destination {
@@ -58,14 +81,20 @@ impl Actor for Destination {
 
     #[instrument(level = "debug", name = "starting", skip(self, ctx), fields(id = %self.id))]
     fn started(&mut self, ctx: &mut Self::Context) {
-        self.pipeline_manager = Some(
-            PipelineManager::new(
-                self.pipeline.clone(),
-                ctx.address().downgrade().recipient(),
-                &self.id,
-            )
-            .start(),
+        let mut pipeline_manager = PipelineManager::new(
+            self.pipeline.clone(),
+            ctx.address().downgrade().recipient(),
+            &self.id,
         );
+
+        if let DestinationFamily::Dash { .. } = self.family {
+            pipeline_manager =
+                pipeline_manager.forward_closed_fragments(ctx.address().downgrade().recipient());
+        }
+
+        self.pipeline_manager = Some(pipeline_manager.start());
+
+        self.start_heartbeat(ctx);
     }
 
     #[instrument(level = "debug", name = "stopping", skip(self, ctx), fields(id = %self.id))]
@@ -101,8 +130,158 @@ impl Actor for Destination {
 }
 
 impl Destination {
+    fn create_settings() -> HashMap<String, Arc<Mutex<Setting>>> {
+        let mut settings = HashMap::new();
+
+        settings.insert(
+            "video-bitrate".to_string(),
+            Arc::new(Mutex::new(Setting {
+                name: "video-bitrate".to_string(),
+                spec: SettingSpec::I32 {
+                    min: 0,
+                    max: 2048000,
+                    current: 0,
+                },
+                controllable: false,
+            })),
+        );
+
+        settings.insert(
+            "video-max-bitrate".to_string(),
+            Arc::new(Mutex::new(Setting {
+                name: "video-max-bitrate".to_string(),
+                spec: SettingSpec::I32 {
+                    min: 0,
+                    max: 2048000,
+                    current: 0,
+                },
+                controllable: false,
+            })),
+        );
+
+        settings.insert(
+            "audio-format".to_string(),
+            Arc::new(Mutex::new(Setting {
+                name: "audio-format".to_string(),
+                spec: SettingSpec::Enum {
+                    current: "S16LE".to_string(),
+                    choices: &["S16LE", "S24LE", "S32LE", "F32LE"],
+                },
+                controllable: false,
+            })),
+        );
+
+        settings.insert(
+            "audio-sample-rate".to_string(),
+            Arc::new(Mutex::new(Setting {
+                name: "audio-sample-rate".to_string(),
+                spec: SettingSpec::I32 {
+                    min: 0,
+                    max: 2147483647,
+                    current: 0,
+                },
+                controllable: false,
+            })),
+        );
+
+        settings.insert(
+            "jack-port-names".to_string(),
+            Arc::new(Mutex::new(Setting {
+                name: "jack-port-names".to_string(),
+                spec: SettingSpec::Str { current: "".into() },
+                controllable: false,
+            })),
+        );
+
+        settings.insert(
+            "base-url".to_string(),
+            Arc::new(Mutex::new(Setting {
+                name: "base-url".to_string(),
+                spec: SettingSpec::Str { current: "".into() },
+                controllable: false,
+            })),
+        );
+
+        settings.insert(
+            "target-duration".to_string(),
+            Arc::new(Mutex::new(Setting {
+                name: "target-duration".to_string(),
+                spec: SettingSpec::I32 {
+                    min: 1,
+                    max: 3600,
+                    current: 15,
+                },
+                controllable: false,
+            })),
+        );
+
+        settings.insert(
+            "minimum-update-period".to_string(),
+            Arc::new(Mutex::new(Setting {
+                name: "minimum-update-period".to_string(),
+                spec: SettingSpec::I32 {
+                    min: 0,
+                    max: 2147483647,
+                    current: 0,
+                },
+                controllable: false,
+            })),
+        );
+
+        settings.insert(
+            "use-segment-list".to_string(),
+            Arc::new(Mutex::new(Setting {
+                name: "use-segment-list".to_string(),
+                spec: SettingSpec::Bool { current: false },
+                controllable: false,
+            })),
+        );
+
+        settings.insert(
+            "upload-command".to_string(),
+            Arc::new(Mutex::new(Setting {
+                name: "upload-command".to_string(),
+                spec: SettingSpec::Str { current: "".into() },
+                controllable: false,
+            })),
+        );
+
+        settings
+    }
+
+    fn setting(&self, name: &str) -> Option<MutexGuard<Setting>> {
+        self.settings
+            .get(name)
+            .map(|setting| setting.lock().unwrap())
+    }
+
+    fn settings(&self) -> HashMap<String, serde_json::Value> {
+        self.settings
+            .iter()
+            .map(|(id, setting)| (id.clone(), setting.lock().unwrap().as_value()))
+            .collect()
+    }
+
     /// Create a destination
-    pub fn new(id: &str, family: &DestinationFamily) -> Self {
+    pub fn new(
+        id: &str,
+        family: &DestinationFamily,
+        config: Option<HashMap<String, serde_json::Value>>,
+    ) -> Result<Self, Error> {
+        let settings = Destination::create_settings();
+
+        if let Some(config) = config {
+            for (key, value) in config {
+                if let Some(setting) = settings.get(&key) {
+                    let mut setting = setting.lock().unwrap();
+                    SettingController::validate_value(&setting, &value)?;
+                    SettingController::set_from_value(&mut setting, &value);
+                } else {
+                    return Err(anyhow!("No setting with name {} on destinations", key));
+                }
+            }
+        }
+
         let video_appsrc =
             gst::ElementFactory::make("appsrc", Some(&format!("destination-video-appsrc-{}", id)))
                 .unwrap()
@@ -122,7 +301,7 @@ impl Destination {
 
         let pipeline = gst::Pipeline::new(None);
 
-        Self {
+        Ok(Self {
             id: id.to_string(),
             family: family.clone(),
             pipeline,
@@ -131,9 +310,95 @@ impl Destination {
             audio_appsrc,
             consumer_slot: None,
             state_machine: StateMachine::default(),
+            settings,
+            video_bitrate: None,
+            n_capped_seconds: 0,
+            segment_count: 0,
+            upload_sender: None,
+        })
+    }
+
+    /// Whether our family consumes the video stream of its producer
+    fn has_video(&self) -> bool {
+        !matches!(
+            self.family,
+            DestinationFamily::Pcm { .. } | DestinationFamily::Jack { .. }
+        )
+    }
+
+    /// Apply our bitrate settings to a video encoder, either `nvh264enc`
+    /// or `x264enc`, both expressing bitrates in kbit/s
+    fn configure_video_encoder(&self, venc: &gst::Element) {
+        let max_bitrate = self.setting("video-max-bitrate").unwrap().as_i32().unwrap() as u32;
+        let mut bitrate = self.setting("video-bitrate").unwrap().as_i32().unwrap() as u32;
+
+        if max_bitrate > 0 && (bitrate == 0 || bitrate > max_bitrate) {
+            bitrate = max_bitrate;
+        }
+
+        if bitrate > 0 {
+            venc.set_property("bitrate", &bitrate).unwrap();
+        }
+
+        if max_bitrate > 0 {
+            if venc.has_property("max-bitrate", None) {
+                venc.set_property("max-bitrate", &max_bitrate).unwrap();
+            } else if venc.has_property("option-string", None) {
+                // One second worth of VBV buffer
+                venc.set_property(
+                    "option-string",
+                    &format!("vbv-maxrate={}:vbv-bufsize={}", max_bitrate, max_bitrate),
+                )
+                .unwrap();
+            }
         }
     }
 
+    /// Measure the bitrate flowing out of the pad of a video encoder
+    /// every second, and warn when it is capped for sustained periods
+    fn monitor_video_bitrate(&mut self, ctx: &mut Context<Self>, pad: &gst::Pad) {
+        let n_bytes = Arc::new(AtomicU64::new(0));
+        let n_bytes_clone = n_bytes.clone();
+
+        pad.add_probe(gst::PadProbeType::BUFFER, move |_pad, info| {
+            if let Some(gst::PadProbeData::Buffer(ref buffer)) = info.data {
+                n_bytes_clone.fetch_add(buffer.size() as u64, Ordering::Relaxed);
+            }
+
+            gst::PadProbeReturn::Ok
+        });
+
+        ctx.run_interval(std::time::Duration::from_secs(1), move |s, _ctx| {
+            let bitrate = n_bytes.swap(0, Ordering::Relaxed) * 8;
+            let max_bitrate =
+                s.setting("video-max-bitrate").unwrap().as_i32().unwrap() as u64 * 1000;
+
+            s.video_bitrate = Some(bitrate);
+
+            trace!(id = %s.id, "video bitrate: {}", bitrate);
+
+            if max_bitrate == 0 {
+                return;
+            }
+
+            if bitrate * 100 >= max_bitrate * 95 {
+                s.n_capped_seconds += 1;
+
+                if s.n_capped_seconds == BITRATE_CAP_WARNING_SECS {
+                    NodeManager::from_registry().do_send(NodeStatusMessage::Warning {
+                        id: s.id.clone(),
+                        message: format!(
+                            "Video encoder capped at {} bit/s for {} seconds, quality may degrade",
+                            max_bitrate, BITRATE_CAP_WARNING_SECS
+                        ),
+                    });
+                }
+            } else {
+                s.n_capped_seconds = 0;
+            }
+        });
+    }
+
     /// RTMP family
     #[instrument(level = "debug", name = "streaming", skip(self, ctx), fields(id = %self.id))]
     fn start_rtmp_pipeline(
@@ -181,6 +446,9 @@ impl Destination {
             venc.set_property("zerolatency", &true).unwrap();
         }
 
+        self.configure_video_encoder(&venc);
+        self.monitor_video_bitrate(ctx, &venc.static_pad("src").unwrap());
+
         if venc.has_property("key-int-max", None) {
             venc.set_property("key-int-max", &30u32).unwrap();
         } else if venc.has_property("gop-size", None) {
@@ -237,9 +505,9 @@ impl Destination {
         if let Some(slot) = &self.consumer_slot {
             debug!("connecting to producers");
             slot.video_producer
-                .add_consumer(&self.video_appsrc, &slot.id);
+                .add_consumer(&self.video_appsrc, &slot.id)?;
             slot.audio_producer
-                .add_consumer(&self.audio_appsrc, &slot.id);
+                .add_consumer(&self.audio_appsrc, &slot.id)?;
         } else {
             debug!("started but not yet connected");
         }
@@ -301,6 +569,9 @@ impl Destination {
             &sink,
         ])?;
 
+        self.configure_video_encoder(&venc);
+        self.monitor_video_bitrate(ctx, &venc.static_pad("src").unwrap());
+
         if let Some(max_size_time) = max_size_time {
             sink.set_property("max-size-time", (max_size_time as u64) * gst::MSECOND)
                 .unwrap();
@@ -329,9 +600,9 @@ impl Destination {
         if let Some(slot) = &self.consumer_slot {
             debug!("connecting to producers");
             slot.video_producer
-                .add_consumer(&self.video_appsrc, &slot.id);
+                .add_consumer(&self.video_appsrc, &slot.id)?;
             slot.audio_producer
-                .add_consumer(&self.audio_appsrc, &slot.id);
+                .add_consumer(&self.audio_appsrc, &slot.id)?;
         } else {
             debug!("started but not yet connected");
         }
@@ -390,9 +661,312 @@ impl Destination {
         if let Some(slot) = &self.consumer_slot {
             debug!("connecting to producers");
             slot.video_producer
-                .add_consumer(&self.video_appsrc, &slot.id);
+                .add_consumer(&self.video_appsrc, &slot.id)?;
+            slot.audio_producer
+                .add_consumer(&self.audio_appsrc, &slot.id)?;
+        } else {
+            debug!("started but not yet connected");
+        }
+
+        let addr = ctx.address();
+        let id = self.id.clone();
+        self.pipeline.call_async(move |pipeline| {
+            if let Err(err) = pipeline.set_state(gst::State::Playing) {
+                let _ = addr.do_send(ErrorMessage(format!(
+                    "Failed to start destination {}: {}",
+                    id, err
+                )));
+            }
+        });
+
+        Ok(StateChangeResult::Success)
+    }
+
+    /// Pcm family
+    #[instrument(level = "debug", name = "writing PCM", skip(self, ctx), fields(id = %self.id))]
+    fn start_pcm_pipeline(
+        &mut self,
+        ctx: &mut Context<Self>,
+        location: &str,
+    ) -> Result<StateChangeResult, Error> {
+        let format = self
+            .setting("audio-format")
+            .unwrap()
+            .as_str()
+            .unwrap()
+            .to_string();
+        let sample_rate = self.setting("audio-sample-rate").unwrap().as_i32().unwrap();
+
+        let aqueue = make_element("queue", None)?;
+        let aconv = make_element("audioconvert", None)?;
+        let aresample = make_element("audioresample", None)?;
+        let capsfilter = make_element("capsfilter", None)?;
+
+        let mut caps = gst::Caps::builder("audio/x-raw")
+            .field("format", &format)
+            .field("layout", &"interleaved");
+        if sample_rate > 0 {
+            caps = caps.field("rate", &sample_rate);
+        }
+        capsfilter.set_property("caps", &caps.build()).unwrap();
+
+        let mut elements = vec![
+            self.audio_appsrc.clone().upcast::<gst::Element>(),
+            aqueue,
+            aconv,
+            aresample,
+            capsfilter,
+        ];
+
+        if let Some(address) = location.strip_prefix("tcp://") {
+            let (host, port) = address
+                .rsplit_once(':')
+                .and_then(|(host, port)| port.parse::<i32>().ok().map(|port| (host, port)))
+                .ok_or_else(|| anyhow!("Invalid TCP location {}", location))?;
+
+            let sink = make_element("tcpclientsink", None)?;
+            sink.set_property("host", host).unwrap();
+            sink.set_property("port", &port).unwrap();
+            elements.push(sink);
+        } else {
+            // wavenc rewrites its header upon EOS, which we wait for
+            // before stopping
+            if location.ends_with(".wav") {
+                elements.push(make_element("wavenc", None)?);
+            }
+
+            let sink = make_element("filesink", None)?;
+            sink.set_property("location", location).unwrap();
+            elements.push(sink);
+        }
+
+        self.pipeline
+            .add_many(&elements.iter().collect::<Vec<_>>())?;
+        gst::Element::link_many(&elements.iter().collect::<Vec<_>>())?;
+
+        if let Some(slot) = &self.consumer_slot {
+            debug!("connecting to producer");
+            slot.audio_producer
+                .add_consumer(&self.audio_appsrc, &slot.id)?;
+        } else {
+            debug!("started but not yet connected");
+        }
+
+        let addr = ctx.address();
+        let id = self.id.clone();
+        self.pipeline.call_async(move |pipeline| {
+            if let Err(err) = pipeline.set_state(gst::State::Playing) {
+                let _ = addr.do_send(ErrorMessage(format!(
+                    "Failed to start destination {}: {}",
+                    id, err
+                )));
+            }
+        });
+
+        Ok(StateChangeResult::Success)
+    }
+
+    /// Jack family
+    ///
+    /// Should the JACK server shut down, jackaudiosink posts an error
+    /// and we stop like for any other error.
+    #[instrument(level = "debug", name = "playing through JACK", skip(self, ctx), fields(id = %self.id))]
+    fn start_jack_pipeline(
+        &mut self,
+        ctx: &mut Context<Self>,
+        client_name: &str,
+        server_name: Option<&str>,
+        connect: JackConnect,
+    ) -> Result<StateChangeResult, Error> {
+        let aqueue = make_element("queue", None)?;
+        let aconv = make_element("audioconvert", None)?;
+        let aresample = make_element("audioresample", None)?;
+        let asink = make_element("jackaudiosink", None)?;
+
+        asink.set_property("client-name", client_name).unwrap();
+
+        if let Some(server_name) = server_name {
+            asink.set_property("server", server_name).unwrap();
+        }
+
+        match connect {
+            JackConnect::Auto => asink.set_property_from_str("connect", "auto"),
+            JackConnect::None => asink.set_property_from_str("connect", "none"),
+            JackConnect::Manual => {
+                let port_names = self
+                    .setting("jack-port-names")
+                    .unwrap()
+                    .as_str()
+                    .unwrap()
+                    .to_string();
+
+                if port_names.is_empty() {
+                    return Err(anyhow!(
+                        "jack-port-names must be set to connect ports manually"
+                    ));
+                }
+
+                asink.set_property_from_str("connect", "explicit");
+                asink.set_property("port-names", &port_names).unwrap();
+            }
+        }
+
+        let elements = [
+            self.audio_appsrc.upcast_ref::<gst::Element>(),
+            &aqueue,
+            &aconv,
+            &aresample,
+            &asink,
+        ];
+
+        self.pipeline.add_many(&elements)?;
+        gst::Element::link_many(&elements)?;
+
+        if let Some(slot) = &self.consumer_slot {
+            debug!("connecting to producer");
+            slot.audio_producer
+                .add_consumer(&self.audio_appsrc, &slot.id)?;
+        } else {
+            debug!("started but not yet connected");
+        }
+
+        let addr = ctx.address();
+        let id = self.id.clone();
+        self.pipeline.call_async(move |pipeline| {
+            if let Err(err) = pipeline.set_state(gst::State::Playing) {
+                let _ = addr.do_send(ErrorMessage(format!(
+                    "Failed to start destination {}: {}",
+                    id, err
+                )));
+            }
+        });
+
+        Ok(StateChangeResult::Success)
+    }
+
+    /// Dash family
+    #[instrument(level = "debug", name = "streaming DASH", skip(self, ctx), fields(id = %self.id))]
+    fn start_dash_pipeline(
+        &mut self,
+        ctx: &mut Context<Self>,
+        manifest_location: &str,
+    ) -> Result<StateChangeResult, Error> {
+        let manifest_path = Path::new(manifest_location);
+        let mpd_filename = manifest_path
+            .file_name()
+            .and_then(|filename| filename.to_str())
+            .ok_or_else(|| anyhow!("Invalid manifest location {}", manifest_location))?;
+        let mpd_root_path = manifest_path
+            .parent()
+            .and_then(|parent| parent.to_str())
+            .filter(|parent| !parent.is_empty())
+            .unwrap_or(".");
+
+        let vconv = make_element("videoconvert", None)?;
+        let venc = make_element("nvh264enc", None).unwrap_or(make_element("x264enc", None)?);
+        let vparse = make_element("h264parse", None)?;
+        let venc_queue = make_element("queue", None)?;
+
+        let aconv = make_element("audioconvert", None)?;
+        let aresample = make_element("audioresample", None)?;
+        let aenc = make_element("avenc_aac", None)?;
+        let aenc_queue = make_element("queue", None)?;
+
+        let sink = make_element("dashsink", None)?;
+
+        self.pipeline.add_many(&[
+            self.video_appsrc.upcast_ref(),
+            &vconv,
+            &venc,
+            &vparse,
+            &venc_queue,
+            self.audio_appsrc.upcast_ref(),
+            &aconv,
+            &aresample,
+            &aenc,
+            &aenc_queue,
+            &sink,
+        ])?;
+
+        self.configure_video_encoder(&venc);
+        self.monitor_video_bitrate(ctx, &venc.static_pad("src").unwrap());
+
+        // Segments can only start on keyframes
+        if venc.has_property("key-int-max", None) {
+            venc.set_property("key-int-max", &30u32).unwrap();
+        } else if venc.has_property("gop-size", None) {
+            venc.set_property("gop-size", &30i32).unwrap();
+        }
+
+        vparse.set_property("config-interval", &-1i32).unwrap();
+
+        let base_url = self
+            .setting("base-url")
+            .unwrap()
+            .as_str()
+            .unwrap()
+            .to_string();
+        let target_duration = self.setting("target-duration").unwrap().as_i32().unwrap() as u32;
+        let minimum_update_period = self
+            .setting("minimum-update-period")
+            .unwrap()
+            .as_i32()
+            .unwrap() as u64;
+        let use_segment_list = self.setting("use-segment-list").unwrap().as_bool().unwrap();
+
+        sink.set_property("mpd-root-path", mpd_root_path).unwrap();
+        sink.set_property("mpd-filename", mpd_filename).unwrap();
+        if !base_url.is_empty() {
+            sink.set_property("mpd-baseurl", &base_url).unwrap();
+        }
+        sink.set_property("target-duration", &target_duration)
+            .unwrap();
+        sink.set_property("minimum-update-period", &minimum_update_period)
+            .unwrap();
+        sink.set_property("use-segment-list", &use_segment_list)
+            .unwrap();
+        sink.set_property("dynamic", &true).unwrap();
+        sink.set_property_from_str("muxer", "mp4");
+
+        for queue in &[&venc_queue, &aenc_queue] {
+            queue
+                .set_properties(&[
+                    ("max-size-buffers", &0u32),
+                    ("max-size-bytes", &0u32),
+                    ("max-size-time", &(3 * gst::SECOND)),
+                ])
+                .unwrap();
+        }
+
+        gst::Element::link_many(&[
+            self.video_appsrc.upcast_ref(),
+            &vconv,
+            &venc,
+            &vparse,
+            &venc_queue,
+        ])?;
+
+        gst::Element::link_many(&[
+            self.audio_appsrc.upcast_ref(),
+            &aconv,
+            &aresample,
+            &aenc,
+            &aenc_queue,
+        ])?;
+
+        for (queue, template) in &[(&venc_queue, "video_%u"), (&aenc_queue, "audio_%u")] {
+            let sinkpad = sink
+                .request_pad_simple(template)
+                .ok_or_else(|| anyhow!("Failed to request {} pad from dashsink", template))?;
+            queue.static_pad("src").unwrap().link(&sinkpad)?;
+        }
+
+        if let Some(slot) = &self.consumer_slot {
+            debug!("connecting to producers");
+            slot.video_producer
+                .add_consumer(&self.video_appsrc, &slot.id)?;
             slot.audio_producer
-                .add_consumer(&self.audio_appsrc, &slot.id);
+                .add_consumer(&self.audio_appsrc, &slot.id)?;
         } else {
             debug!("started but not yet connected");
         }
@@ -411,6 +985,55 @@ impl Destination {
         Ok(StateChangeResult::Success)
     }
 
+    /// Spawn a thread running `upload_command` for batches of locations,
+    /// in the order they are sent
+    ///
+    /// Within a batch, the remaining locations aren't uploaded once a
+    /// command has failed, so that we don't upload a manifest referring
+    /// to a missing segment. The thread exits once the sender is dropped.
+    fn spawn_uploader(
+        id: &str,
+        upload_command: &str,
+    ) -> (mpsc::Sender<Vec<String>>, std::thread::JoinHandle<()>) {
+        let id = id.to_string();
+        let upload_command = upload_command.to_string();
+        let (sender, receiver) = mpsc::channel::<Vec<String>>();
+
+        let handle = std::thread::spawn(move || {
+            for locations in receiver {
+                for location in locations {
+                    trace!(id = %id, "uploading {}", location);
+
+                    let res = std::process::Command::new("sh")
+                        .arg("-c")
+                        .arg(&upload_command)
+                        .arg("sh")
+                        .arg(&location)
+                        .status();
+
+                    let message = match res {
+                        Ok(status) if status.success() => continue,
+                        Ok(status) => format!("Failed to upload {}: {}", location, status),
+                        Err(err) => {
+                            format!("Failed to run upload command for {}: {}", location, err)
+                        }
+                    };
+
+                    error!(id = %id, "{}", message);
+
+                    NodeManager::from_registry().do_send(NodeStatusMessage::Warning {
+                        id: id.clone(),
+                        message,
+                    });
+
+                    break;
+                }
+            }
+        });
+
+        (sender, handle)
+    }
+
     /// Implement Connect command
     #[instrument(level = "debug", name = "connecting", skip(self, video_producer, audio_producer), fields(id = %self.id))]
     fn connect(
@@ -425,8 +1048,13 @@ impl Destination {
 
         if self.state_machine.state == State::Started {
             debug!("destination {} connecting to producers", self.id);
-            video_producer.add_consumer(&self.video_appsrc, link_id);
-            audio_producer.add_consumer(&self.audio_appsrc, link_id);
+            if self.has_video() {
+                video_producer.add_consumer(&self.video_appsrc, link_id)?;
+            }
+            if let Err(err) = audio_producer.add_consumer(&self.audio_appsrc, link_id) {
+                video_producer.remove_consumer(link_id);
+                return Err(err);
+            }
         }
 
         self.consumer_slot = Some(ConsumerSlot {
@@ -531,6 +1159,15 @@ impl Schedulable<Self> for Destination {
                     max_size_time,
                 } => self.start_local_file_pipeline(ctx, &base_name, max_size_time),
                 DestinationFamily::LocalPlayback => self.start_local_playback_pipeline(ctx),
+                DestinationFamily::Pcm { location } => self.start_pcm_pipeline(ctx, &location),
+                DestinationFamily::Jack {
+                    client_name,
+                    server_name,
+                    connect,
+                } => self.start_jack_pipeline(ctx, &client_name, server_name.as_deref(), connect),
+                DestinationFamily::Dash { manifest_location } => {
+                    self.start_dash_pipeline(ctx, &manifest_location)
+                }
             },
             State::Started => Ok(StateChangeResult::Success),
             State::Stopping => {
@@ -552,8 +1189,23 @@ impl Handler<ConsumerMessage> for Destination {
                 link_id,
                 video_producer,
                 audio_producer,
+                replace,
                 ..
-            } => MessageResult(self.connect(&link_id, &video_producer, &audio_producer)),
+            } => MessageResult(match (video_producer, audio_producer) {
+                (Some(video_producer), Some(audio_producer)) => {
+                    let res = if replace {
+                        self.disconnect(&link_id)
+                    } else {
+                        Ok(())
+                    };
+
+                    res.and_then(|_| self.connect(&link_id, &video_producer, &audio_producer))
+                }
+                _ => Err(anyhow!(
+                    "destination {} must be connected to both audio and video",
+                    self.id
+                )),
+            }),
             ConsumerMessage::Disconnect { slot_id } => MessageResult(self.disconnect(&slot_id)),
             ConsumerMessage::AddControlPoint { .. } => {
                 MessageResult(Err(anyhow!("destination slot cannot be controlled")))
@@ -561,6 +1213,15 @@ impl Handler<ConsumerMessage> for Destination {
             ConsumerMessage::RemoveControlPoint { .. } => {
                 MessageResult(Err(anyhow!("destination slot cannot be controlled")))
             }
+            ConsumerMessage::FlushSlot { .. } => {
+                MessageResult(Err(anyhow!("destination slot cannot be flushed")))
+            }
+            ConsumerMessage::FreezeSlot { .. } => {
+                MessageResult(Err(anyhow!("destination slot cannot be frozen")))
+            }
+            ConsumerMessage::SetSlotOrder { .. } => {
+                MessageResult(Err(anyhow!("destination slots cannot be reordered")))
+            }
         }
     }
 }
@@ -611,6 +1272,57 @@ impl Handler<StopMessage> for Destination {
     }
 }
 
+impl Handler<FragmentClosedMessage> for Destination {
+    type Result = ();
+
+    fn handle(&mut self, msg: FragmentClosedMessage, _ctx: &mut Context<Self>) -> Self::Result {
+        // Audio fragments are closed along with the video ones
+        if msg.is_video {
+            self.segment_count += 1;
+        }
+
+        trace!(id = %self.id, "segment {} written to {}", self.segment_count, msg.location);
+
+        let upload_command = self
+            .setting("upload-command")
+            .unwrap()
+            .as_str()
+            .unwrap()
+            .to_string();
+
+        if upload_command.is_empty() {
+            return;
+        }
+
+        // dashsink has rewritten the manifest by the time the closed
+        // fragment gets reported
+        if let DestinationFamily::Dash { manifest_location } = &self.family {
+            let id = &self.id;
+            let sender = self
+                .upload_sender
+                .get_or_insert_with(|| Destination::spawn_uploader(id, &upload_command).0);
+
+            let _ = sender.send(vec![msg.location, manifest_location.clone()]);
+        }
+    }
+}
+
+impl Handler<GetLatencyMessage> for Destination {
+    type Result = ResponseFuture<Result<PipelineLatency, Error>>;
+
+    fn handle(&mut self, _msg: GetLatencyMessage, _ctx: &mut Context<Self>) -> Self::Result {
+        query_pipeline_latency(self.pipeline_manager.clone(), &self.id)
+    }
+}
+
+impl Handler<GetStateMessage> for Destination {
+    type Result = MessageResult<GetStateMessage>;
+
+    fn handle(&mut self, _msg: GetStateMessage, _ctx: &mut Context<Self>) -> Self::Result {
+        MessageResult(self.state_machine.state)
+    }
+}
+
 impl Handler<GetNodeInfoMessage> for Destination {
     type Result = Result<NodeInfo, Error>;
 
@@ -621,6 +1333,12 @@ impl Handler<GetNodeInfoMessage> for Destination {
             cue_time: self.state_machine.cue_time,
             end_time: self.state_machine.end_time,
             state: self.state_machine.state,
+            settings: self.settings(),
+            video_bitrate: self.video_bitrate,
+            segment_count: match self.family {
+                DestinationFamily::Dash { .. } => Some(self.segment_count),
+                _ => None,
+            },
         }))
     }
 }
@@ -629,6 +1347,7 @@ impl Handler<GetNodeInfoMessage> for Destination {
 mod tests {
     use super::*;
     use crate::utils::tests::*;
+    use auteur_controlling::controller::DuplicateLinkPolicy;
     use tempfile::tempdir;
     use test_env_log::test;
 
@@ -662,6 +1381,157 @@ mod tests {
             panic!("Wrong info type");
         }
     }
+
+    #[test]
+    fn test_dash_settings() {
+        gst::init().unwrap();
+
+        let family = DestinationFamily::Dash {
+            manifest_location: "/tmp/dash.mpd".to_string(),
+        };
+
+        let config = |key: &str, value: serde_json::Value| {
+            let mut config = HashMap::new();
+            config.insert(key.to_string(), value);
+            Destination::new("test-destination", &family, Some(config))
+        };
+
+        let destination = config("target-duration", serde_json::json!(4)).unwrap();
+        assert_eq!(destination.settings()["target-duration"], 4);
+        assert_eq!(destination.settings()["use-segment-list"], false);
+        assert_eq!(destination.segment_count, 0);
+
+        assert!(config("target-duration", serde_json::json!(0)).is_err());
+        assert!(config("minimum-update-period", serde_json::json!(-1)).is_err());
+        assert!(config("use-segment-list", serde_json::json!("yes")).is_err());
+        assert!(config(
+            "upload-command",
+            serde_json::json!("aws s3 cp $1 s3://bucket/")
+        )
+        .is_ok());
+    }
+
+    #[actix_rt::test]
+    #[test]
+    async fn test_dash_fragments() {
+        gst::init().unwrap();
+
+        let dir = tempdir().unwrap();
+        let log = dir.path().join("uploads.log");
+
+        let family = DestinationFamily::Dash {
+            manifest_location: "/tmp/dash.mpd".to_string(),
+        };
+
+        let mut config = HashMap::new();
+        config.insert(
+            "upload-command".to_string(),
+            serde_json::json!(format!("echo \"$1\" >> {}", log.display())),
+        );
+
+        let addr = Destination::new("test-destination", &family, Some(config))
+            .unwrap()
+            .start();
+
+        for (location, is_video) in &[
+            ("video_00001.mp4", true),
+            ("audio_00001.mp4", false),
+            ("video_00002.mp4", true),
+        ] {
+            addr.send(FragmentClosedMessage {
+                location: location.to_string(),
+                is_video: *is_video,
+            })
+            .await
+            .unwrap();
+        }
+
+        // One segment per video fragment
+        match addr.send(GetNodeInfoMessage).await.unwrap().unwrap() {
+            NodeInfo::Destination(info) => assert_eq!(info.segment_count, Some(2)),
+            _ => panic!("Wrong info type"),
+        }
+
+        let expected = "video_00001.mp4\n/tmp/dash.mpd\n\
+                        audio_00001.mp4\n/tmp/dash.mpd\n\
+                        video_00002.mp4\n/tmp/dash.mpd\n";
+
+        // Uploads happen in the background, in the order of the fragments
+        let mut uploads = String::new();
+        for _ in 0..50 {
+            uploads = std::fs::read_to_string(&log).unwrap_or_default();
+
+            if uploads.len() >= expected.len() {
+                break;
+            }
+
+            tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+        }
+
+        assert_eq!(uploads, expected);
+    }
+
+    #[actix_rt::test]
+    #[test]
+    async fn test_replace_link() {
+        gst::init().unwrap();
+        let dir = tempdir().unwrap();
+        let uri = asset_uri("ball.mp4");
+
+        create_source("test-source-0", &uri).await.unwrap();
+        create_source("test-source-1", &uri).await.unwrap();
+        create_local_destination(
+            "test-destination",
+            &format!("{}/video", dir.path().display()),
+            None,
+        )
+        .await
+        .unwrap();
+
+        connect("link-0", "test-source-0", "test-destination")
+            .await
+            .unwrap();
+
+        let consumer_slot_ids = |id: &'static str| async move {
+            match node_info_unchecked(id).await {
+                NodeInfo::Source(info) => info.consumer_slot_ids,
+                _ => unreachable!(),
+            }
+        };
+
+        // Refused by default, with or without a warning
+        for on_duplicate in &[DuplicateLinkPolicy::Reject, DuplicateLinkPolicy::Warn] {
+            assert!(connect_on_duplicate(
+                "link-0",
+                "test-source-1",
+                "test-destination",
+                *on_duplicate
+            )
+            .await
+            .is_err());
+        }
+
+        assert_eq!(consumer_slot_ids("test-source-0").await, vec!["link-0"]);
+        assert!(consumer_slot_ids("test-source-1").await.is_empty());
+
+        connect_on_duplicate(
+            "link-0",
+            "test-source-1",
+            "test-destination",
+            DuplicateLinkPolicy::Replace,
+        )
+        .await
+        .unwrap();
+
+        // The destination is now fed by the new producer only
+        assert!(consumer_slot_ids("test-source-0").await.is_empty());
+        assert_eq!(consumer_slot_ids("test-source-1").await, vec!["link-0"]);
+
+        match node_info_unchecked("test-destination").await {
+            NodeInfo::Destination(info) => assert_eq!(info.slot_id, Some("link-0".to_string())),
+            _ => unreachable!(),
+        }
+    }
 }
 
 impl Handler<AddControlPointMessage> for Destination {
@@ -29,15 +29,19 @@ mod controller;
 mod destination;
 mod mixer;
 mod node;
+mod plugins;
 mod server;
 mod source;
 mod utils;
+mod ws;
 
 use anyhow::{anyhow, Error};
 use structopt::StructOpt;
 
 use config::Config;
+use utils::LogFilter;
 
+use tracing::warn;
 use tracing_subscriber::prelude::*;
 
 use std::fs;
@@ -47,8 +51,10 @@ fn main() -> Result<(), Error> {
     let cfg = Config::from_args();
 
     tracing_log::LogTracer::init().expect("Failed to set logger");
-    let env_filter = tracing_subscriber::EnvFilter::try_from_env("AUTEUR_LOG")
-        .unwrap_or_else(|_| tracing_subscriber::EnvFilter::new("warn"));
+    let (log_filter, env_filter) = std::env::var("AUTEUR_LOG")
+        .map_err(Error::from)
+        .and_then(|directives| LogFilter::new(&directives))
+        .or_else(|_| LogFilter::new("warn"))?;
 
     let (writer, _guard) = {
         if let Some(ref path) = cfg.log_path {
@@ -79,15 +85,44 @@ fn main() -> Result<(), Error> {
         )
         .with_writer(writer);
 
-    let subscriber = tracing_subscriber::Registry::default()
-        .with(env_filter)
-        .with(fmt_layer);
-    tracing::subscriber::set_global_default(subscriber).expect("Failed to set subscriber");
+    // Fields formatted with `%` (eg `id = %self.id`) are recorded through
+    // their Display implementation, and thus always serialized as JSON strings
+    if cfg.log_json {
+        let subscriber = tracing_subscriber::Registry::default()
+            .with(env_filter)
+            .with(
+                fmt_layer
+                    .json()
+                    .with_current_span(true)
+                    .with_span_list(true),
+            );
+        tracing::subscriber::set_global_default(subscriber).expect("Failed to set subscriber");
+    } else {
+        let subscriber = tracing_subscriber::Registry::default()
+            .with(env_filter)
+            .with(fmt_layer);
+        tracing::subscriber::set_global_default(subscriber).expect("Failed to set subscriber");
+    }
 
     gst::init()?;
 
+    match plugins::check_required_plugins() {
+        Ok(unavailable) => {
+            for feature in unavailable {
+                warn!("{} is unavailable, missing GStreamer elements", feature);
+            }
+        }
+        Err(missing) => {
+            return Err(anyhow!(
+                "Missing required GStreamer elements: {}, check your GStreamer installation \
+                 (gst-plugins-base, gst-plugins-good and gst-plugins-bad)",
+                missing.join(", ")
+            ));
+        }
+    }
+
     let system = actix_rt::System::new();
-    system.block_on(server::run(cfg))?;
+    system.block_on(server::run(cfg, log_filter))?;
 
     Ok(())
 }
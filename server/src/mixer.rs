@@ -5,31 +5,227 @@
 
 use actix::prelude::*;
 use anyhow::{anyhow, Error};
+use futures::channel::oneshot;
+use futures::prelude::*;
 use gst::prelude::*;
 use gst_base::prelude::*;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::convert::TryFrom;
+use std::path::PathBuf;
+use std::pin::Pin;
 use std::sync::{Arc, Mutex, MutexGuard};
-use tracing::{debug, error, instrument, trace};
+use tokio::sync::watch;
+use tracing::{debug, error, instrument, trace, warn};
 
-use auteur_controlling::controller::{ControlPoint, MixerInfo, MixerSlotInfo, NodeInfo, State};
+use auteur_controlling::controller::{
+    ControlMode, ControlPoint, ControlPointSummary, ElementInfo, ImageFileFormat, MixerInfo,
+    MixerPreset, MixerSlotInfo, MixerSlotInputInfo, MixerSnapshot, NodeInfo, State,
+};
+use chrono::{DateTime, Utc};
 
 use crate::node::{
-    AddControlPointMessage, ConsumerMessage, GetNodeInfoMessage, GetProducerMessage, NodeManager,
-    NodeStatusMessage, RemoveControlPointMessage, ScheduleMessage, StartMessage, StopMessage,
-    StoppedMessage,
+    forward_producer_notifications, AddControlPointMessage, ConsumerMessage, GetLatencyMessage,
+    GetNodeInfoMessage, GetProducerMessage, GetStateMessage, NodeManager, NodeStatusMessage,
+    RemoveControlPointMessage, ScheduleMessage, StartMessage, StopMessage, StoppedMessage,
 };
 use crate::utils::{
-    get_now, make_element, ErrorMessage, PipelineManager, PropertyController, Schedulable, Setting,
-    SettingController, SettingSpec, StateChangeResult, StateMachine, StopManagerMessage,
-    StreamProducer,
+    get_clock_now, make_element, query_pipeline_latency, AudioLevelInfo, ErrorMessage,
+    PipelineLatency, PipelineManager, PropertyController, Schedulable, Setting, SettingController,
+    SettingSpec, StateChangeResult, StateMachine, StopManagerMessage, StreamProducer,
+    WaitForEosMessage,
 };
 
+/// How long to wait for our pipeline to reach PLAYING, in seconds
+const PREROLL_TIMEOUT_SECS: u64 = 10;
+/// How long to wait for a PTP or network clock to synchronize before
+/// starting our pipeline, in seconds
+const CLOCK_SYNC_TIMEOUT_SECS: u64 = 10;
+/// How long to wait for a preview of our output to be caught and encoded,
+/// in seconds
+const PREVIEW_TIMEOUT_SECS: u64 = 5;
+/// How long to wait for EOS to reach our outputs when stopping, in seconds
+const DRAIN_TIMEOUT_SECS: u64 = 5;
+/// By how much our output may lag behind real time beyond our latency
+/// before we consider we are falling behind, in milliseconds
+const FALLING_BEHIND_THRESHOLD_MS: u64 = 100;
+/// How often the inputs of slots with a watchdog are checked for
+/// stalls, in milliseconds
+const WATCHDOG_INTERVAL_MS: u64 = 100;
+/// The `compositor` pad properties a slot inherits from the slot it
+/// replaces
+const REPLACED_SLOT_PROPERTIES: &[&str] = &["xpos", "ypos", "width", "height", "zorder"];
+
+/// Counters accumulated by a buffer probe on the input of a slot,
+/// cheap enough to be updated for every buffer
+#[derive(Debug, Default)]
+struct SlotInputStats {
+    /// Total number of buffers received
+    n_buffers: u64,
+    /// PTS of the last buffer received
+    last_pts: Option<u64>,
+    /// Gap between the PTS of the last two buffers received
+    last_gap: Option<u64>,
+    /// Largest gap observed between the PTS of two consecutive buffers
+    max_gap: Option<u64>,
+    /// PTS at the start of the current rate measurement window
+    window_start: Option<u64>,
+    /// Buffers received in the current rate measurement window
+    window_buffers: u64,
+    /// Buffers per second over the last complete window
+    buffer_rate: f64,
+    /// The sample rate of the input, as announced by its caps
+    sample_rate: Option<i32>,
+}
+
+impl SlotInputStats {
+    /// Account for a new buffer
+    fn update(&mut self, pts: Option<u64>) {
+        self.n_buffers += 1;
+
+        let pts = match pts {
+            Some(pts) => pts,
+            None => return,
+        };
+
+        if let Some(last_pts) = self.last_pts {
+            let gap = pts.saturating_sub(last_pts);
+            self.last_gap = Some(gap);
+            self.max_gap = Some(self.max_gap.map_or(gap, |max_gap| max_gap.max(gap)));
+        }
+        self.last_pts = Some(pts);
+
+        match self.window_start {
+            Some(window_start) => {
+                let elapsed = pts.saturating_sub(window_start);
+
+                if elapsed >= 1_000_000_000 {
+                    self.buffer_rate =
+                        self.window_buffers as f64 * 1_000_000_000.0 / elapsed as f64;
+                    self.window_start = Some(pts);
+                    self.window_buffers = 0;
+                }
+            }
+            None => self.window_start = Some(pts),
+        }
+
+        self.window_buffers += 1;
+    }
+
+    /// Count the buffers flowing through a pad, and record the sample
+    /// rate of its caps, if any
+    fn track(pad: &gst::Pad) -> Arc<Mutex<Self>> {
+        let stats = Arc::new(Mutex::new(Self::default()));
+        let stats_clone = stats.clone();
+
+        pad.add_probe(
+            gst::PadProbeType::BUFFER | gst::PadProbeType::EVENT_DOWNSTREAM,
+            move |_pad, info| {
+                match info.data {
+                    Some(gst::PadProbeData::Buffer(ref buffer)) => {
+                        stats_clone.lock().unwrap().update(buffer.pts().nseconds());
+                    }
+                    Some(gst::PadProbeData::Event(ref ev)) => {
+                        if let gst::EventView::Caps(caps) = ev.view() {
+                            let sample_rate = caps
+                                .caps()
+                                .structure(0)
+                                .and_then(|s| s.get::<i32>("rate").ok());
+
+                            stats_clone.lock().unwrap().sample_rate = sample_rate;
+                        }
+                    }
+                    _ => (),
+                }
+
+                gst::PadProbeReturn::Ok
+            },
+        );
+
+        stats
+    }
+
+    fn info(&self) -> MixerSlotInputInfo {
+        MixerSlotInputInfo {
+            n_buffers: self.n_buffers,
+            buffer_rate: self.buffer_rate,
+            last_gap_ns: self.last_gap,
+            max_gap_ns: self.max_gap,
+        }
+    }
+}
+
+/// Holds the video of a slot on a frame, see [`Mixer::freeze_slot`]
+#[derive(Debug, Default)]
+struct SlotFreeze {
+    /// Whether the video of the slot is frozen
+    frozen: bool,
+    /// The frame repeated while frozen, the first one received after
+    /// freezing or after the caps changed
+    held: Option<gst::Buffer>,
+}
+
+impl SlotFreeze {
+    /// Replace the buffers flowing through a pad with the held frame
+    /// while frozen, timestamped as the buffers they replace
+    fn track(pad: &gst::Pad) -> Arc<Mutex<Self>> {
+        let freeze = Arc::new(Mutex::new(Self::default()));
+        let freeze_clone = freeze.clone();
+
+        pad.add_probe(
+            gst::PadProbeType::BUFFER | gst::PadProbeType::EVENT_DOWNSTREAM,
+            move |_pad, info| {
+                let mut freeze = freeze_clone.lock().unwrap();
+
+                match info.data {
+                    Some(gst::PadProbeData::Buffer(ref buffer)) => {
+                        if !freeze.frozen {
+                            return gst::PadProbeReturn::Ok;
+                        }
+
+                        let held = match freeze.held {
+                            Some(ref held) => held,
+                            None => {
+                                freeze.held = Some(buffer.clone());
+                                return gst::PadProbeReturn::Ok;
+                            }
+                        };
+
+                        let mut repeated = held.copy();
+                        {
+                            let repeated = repeated.get_mut().unwrap();
+                            repeated.set_pts(buffer.pts());
+                            repeated.set_dts(buffer.dts());
+                            repeated.set_duration(buffer.duration());
+                        }
+
+                        info.data = Some(gst::PadProbeData::Buffer(repeated));
+                    }
+                    Some(gst::PadProbeData::Event(ref ev)) => {
+                        // The held frame may not match the new caps
+                        if let gst::EventView::Caps(_) = ev.view() {
+                            freeze.held = None;
+                        }
+                    }
+                    _ => (),
+                }
+
+                gst::PadProbeReturn::Ok
+            },
+        );
+
+        freeze
+    }
+}
+
 /// Represents a connection to a producer
+///
+/// A slot may be connected to only one of the video and audio producers,
+/// no processing elements or mixer pad are created for the other one.
 struct ConsumerSlot {
     /// Video producer
-    video_producer: StreamProducer,
+    video_producer: Option<StreamProducer>,
     /// Audio producer
-    audio_producer: StreamProducer,
+    audio_producer: Option<StreamProducer>,
     /// Video input to `compositor`
     video_appsrc: gst_app::AppSrc,
     /// Audio input to `audiomixer`
@@ -41,9 +237,585 @@ struct ConsumerSlot {
     /// Volume of the `audiomixer` pad
     volume: f64,
     /// The video mixer pad
-    video_pad: gst::Pad,
+    video_pad: Option<gst::Pad>,
     /// The audio mixer pad
-    audio_pad: gst::Pad,
+    audio_pad: Option<gst::Pad>,
+    /// Statistics about the video input
+    video_stats: Arc<Mutex<SlotInputStats>>,
+    /// Statistics about the audio input
+    audio_stats: Arc<Mutex<SlotInputStats>>,
+    /// Whether the video is frozen, and on which frame
+    video_freeze: Arc<Mutex<SlotFreeze>>,
+    /// Radius of the rounded corners of the video, 0 for square corners
+    border_radius: u32,
+    /// Mix matrix of the audio, one row per output channel, if not
+    /// downmixing automatically
+    channel_map: Option<Vec<Vec<f32>>>,
+    /// Whether the audio conversion renegotiates when the format of the
+    /// input changes mid-stream
+    allow_audio_format_change: bool,
+    /// `audiodynamic` compressing the audio, if enabled
+    compressor: Option<gst::Element>,
+    /// `hqdn3d` reducing the noise of the video, if enabled and available
+    denoiser: Option<gst::Element>,
+    /// Human-readable name of the slot, for display only
+    label: Option<String>,
+    /// `textoverlay` drawing a label over the video, if available
+    label_overlay: Option<gst::Element>,
+    /// Override of `fallback-timeout` for this slot, in milliseconds
+    base_plate_timeout_ms: Option<i32>,
+    /// Slots with a non-zero priority suppress the base plate once
+    /// they have received video
+    priority: u8,
+    /// How the slot is shown when the mixer starts before it has
+    /// received video
+    late_start_mode: LateStartMode,
+    /// For how long the input may not provide any buffer before the
+    /// slot gets flushed, 0 for never
+    watchdog_timeout_ms: u32,
+    /// The progress of the input, as last observed by our watchdog
+    watchdog: SlotWatchdog,
+    /// Distinguishes this slot from the slots previously created with the
+    /// same id, for completing asynchronous connections
+    generation: u64,
+    /// Whether the slot is reconnected once its input reaches EOS
+    reconnect_on_eos: bool,
+    /// How long to wait at least before reconnecting, in milliseconds
+    reconnect_delay_ms: u32,
+    /// The configuration the slot was created with, for reconnecting
+    initial_config: Option<HashMap<String, serde_json::Value>>,
+    /// Latency of our `appsrc`s, instead of that reported by the producers
+    latency_ms: Option<u32>,
+}
+
+/// The progress of the input of a slot, as last observed by
+/// [`Mixer::check_slot_watchdogs`]
+#[derive(Debug, Default)]
+struct SlotWatchdog {
+    /// The total number of buffers received by the slot
+    n_buffers: u64,
+    /// When that number last changed, or the slot was last flushed
+    last_progress: Option<std::time::Instant>,
+}
+
+impl ConsumerSlot {
+    /// Start consuming from our producers
+    fn add_consumers(&self, id: &str) -> Result<(), Error> {
+        let latency = self
+            .latency_ms
+            .map(|latency| gst::ClockTime::from_mseconds(latency as u64));
+
+        if let Some(ref video_producer) = self.video_producer {
+            video_producer.add_consumer(&self.video_appsrc, id)?;
+            video_producer.set_consumer_latency(id, latency);
+        }
+        if let Some(ref audio_producer) = self.audio_producer {
+            audio_producer.add_consumer(&self.audio_appsrc, id)?;
+            audio_producer.set_consumer_latency(id, latency);
+        }
+
+        Ok(())
+    }
+
+    /// Stop consuming from our producers
+    fn remove_consumers(&self, id: &str) {
+        if let Some(ref video_producer) = self.video_producer {
+            video_producer.remove_consumer(id);
+        }
+        if let Some(ref audio_producer) = self.audio_producer {
+            audio_producer.remove_consumer(id);
+        }
+    }
+}
+
+/// The samples last caught by [`Mixer::next_synchronized_samples`]
+struct SnapshotState {
+    /// The last video sample
+    video: Option<gst::Sample>,
+    /// The last audio sample
+    audio: Option<gst::Sample>,
+    /// Notified once the video and audio samples are synchronized
+    sender: Option<oneshot::Sender<(gst::Sample, gst::Sample)>>,
+}
+
+/// An additional video output, scaled from our main video output
+struct ScaledOutput {
+    /// Output producer
+    producer: StreamProducer,
+    /// Scaling elements after our `tee`
+    bin: gst::Bin,
+    /// The `tee` pad feeding `bin`
+    tee_pad: gst::Pad,
+}
+
+/// Sent from the EOS probe of a slot with `reconnect-on-eos`
+#[derive(Debug)]
+struct SlotEosMessage {
+    /// Identifier of the slot
+    slot_id: String,
+    /// The generation of the slot, see [`ConsumerSlot::generation`]
+    generation: u64,
+}
+
+impl Message for SlotEosMessage {
+    type Result = ();
+}
+
+/// Reconnect a slot to its producers, with its initial configuration,
+/// once they output data again after the slot input reached EOS
+#[derive(Debug)]
+struct ReconnectSlotMessage {
+    /// Identifier of the slot
+    slot_id: String,
+    /// The generation of the slot, see [`ConsumerSlot::generation`]
+    generation: u64,
+}
+
+impl Message for ReconnectSlotMessage {
+    type Result = ();
+}
+
+/// Schedule the application of a preset, sent from [`NodeManager`]
+/// to [`Mixer`]
+#[derive(Debug)]
+pub struct ApplyPresetMessage {
+    /// The preset to apply
+    pub preset: MixerPreset,
+    /// When to apply the preset
+    pub cue_time: DateTime<Utc>,
+}
+
+impl Message for ApplyPresetMessage {
+    type Result = Result<(), Error>;
+}
+
+/// Schedule the application of a layout, sent from [`NodeManager`]
+/// to [`Mixer`]
+#[derive(Debug)]
+pub struct ApplyLayoutMessage {
+    /// The name of the layout to apply
+    pub layout: String,
+    /// When to apply the layout
+    pub cue_time: DateTime<Utc>,
+    /// Duration of the transition to the layout, in milliseconds
+    pub transition_ms: Option<u32>,
+}
+
+impl Message for ApplyLayoutMessage {
+    type Result = Result<(), Error>;
+}
+
+/// Dump the graph of the pipeline to a dot file, sent from
+/// [`NodeManager`] to [`Mixer`]
+#[derive(Debug)]
+pub struct DumpPipelineMessage;
+
+impl Message for DumpPipelineMessage {
+    type Result = Result<(), Error>;
+}
+
+/// Describe the elements of the audio path of the pipeline, from the
+/// slot inputs to the outputs, sent to [`Mixer`]
+#[derive(Debug)]
+pub struct GetAudioPipelineDescriptionMessage;
+
+impl Message for GetAudioPipelineDescriptionMessage {
+    type Result = Result<Vec<ElementInfo>, Error>;
+}
+
+/// Describe the elements of the video path of the pipeline, from the
+/// slot inputs to the outputs, sent to [`Mixer`]
+#[derive(Debug)]
+pub struct GetVideoPipelineDescriptionMessage;
+
+impl Message for GetVideoPipelineDescriptionMessage {
+    type Result = Result<Vec<ElementInfo>, Error>;
+}
+
+/// Set the text of the label of a slot, sent from [`NodeManager`] to
+/// [`Mixer`]
+#[derive(Debug)]
+pub struct SetSlotLabelMessage {
+    /// Identifier of the slot
+    pub slot_id: String,
+    /// The text of the label, empty to hide it
+    pub text: String,
+}
+
+impl Message for SetSlotLabelMessage {
+    type Result = Result<(), Error>;
+}
+
+/// Retrieve the control points of a single slot, sent to [`Mixer`]
+#[derive(Debug)]
+pub struct GetSlotControlPointsMessage {
+    /// Identifier of the slot
+    pub slot_id: String,
+}
+
+impl Message for GetSlotControlPointsMessage {
+    type Result = Result<HashMap<String, Vec<ControlPoint>>, Error>;
+}
+
+/// Remove all the pending control points of a slot property, sent to
+/// [`Mixer`]
+#[derive(Debug)]
+pub struct ClearSlotControlPointsMessage {
+    /// Identifier of the slot
+    pub slot_id: String,
+    /// The slot config key of the property, eg `video::xpos`
+    pub property: String,
+}
+
+impl Message for ClearSlotControlPointsMessage {
+    type Result = Result<(), Error>;
+}
+
+/// Resize the video output, scaling the layout of the slots along,
+/// sent to [`Mixer`]
+#[derive(Debug)]
+pub struct ResizeOutputPreservingSlotLayoutMessage {
+    /// The new width of the output
+    pub new_width: i32,
+    /// The new height of the output
+    pub new_height: i32,
+}
+
+impl Message for ResizeOutputPreservingSlotLayoutMessage {
+    type Result = Result<(), Error>;
+}
+
+/// Set the latency of our `compositor`, sent to [`Mixer`]
+#[derive(Debug)]
+pub struct SetCompositorLatencyMessage {
+    /// The additional latency, in nanoseconds
+    pub latency_ns: u64,
+}
+
+impl Message for SetCompositorLatencyMessage {
+    type Result = Result<(), Error>;
+}
+
+/// Set the latency of our `audiomixer`, sent to [`Mixer`]
+#[derive(Debug)]
+pub struct SetAudiomixerLatencyMessage {
+    /// The additional latency, in nanoseconds
+    pub latency_ns: u64,
+}
+
+impl Message for SetAudiomixerLatencyMessage {
+    type Result = Result<(), Error>;
+}
+
+/// Validate mixer settings and a slot configuration without applying
+/// them, sent from [`NodeManager`] to [`Mixer`]. Replies with the errors
+/// found, none when the configuration is valid
+#[derive(Debug)]
+pub struct ValidateConfigMessage {
+    /// Mixer settings, as in the `CreateMixer` command
+    pub settings: HashMap<String, serde_json::Value>,
+    /// A slot configuration, as in the `Connect` command
+    pub slot_config: HashMap<String, serde_json::Value>,
+}
+
+impl Message for ValidateConfigMessage {
+    type Result = Vec<String>;
+}
+
+/// Set a key-value pair of metadata on the output streams, sent from
+/// [`NodeManager`] to [`Mixer`]
+#[derive(Debug)]
+pub struct SetStreamMetadataMessage {
+    /// The metadata key
+    pub key: String,
+    /// The metadata value
+    pub value: String,
+}
+
+impl Message for SetStreamMetadataMessage {
+    type Result = Result<(), Error>;
+}
+
+/// Stop immediately, without draining the pipeline, sent from
+/// [`NodeManager`] to [`Mixer`]
+#[derive(Debug)]
+pub struct KillMessage;
+
+impl Message for KillMessage {
+    type Result = Result<(), Error>;
+}
+
+/// Subscribe to the measurements of the output audio level, sent
+/// to [`Mixer`]
+#[derive(Debug)]
+pub struct SubscribeAudioLevelMessage;
+
+impl Message for SubscribeAudioLevelMessage {
+    type Result = watch::Receiver<Option<AudioLevelInfo>>;
+}
+
+/// Retrieve the producer of the low bitrate audio monitor feed, sent
+/// to [`Mixer`]
+#[derive(Debug)]
+pub struct GetAudioMonitorProducerMessage;
+
+impl Message for GetAudioMonitorProducerMessage {
+    type Result = Result<StreamProducer, Error>;
+}
+
+/// Generate a JPEG thumbnail of the current video output, sent
+/// to [`Mixer`]
+#[derive(Debug)]
+pub struct PreviewOutputMessage {
+    /// Width of the thumbnail
+    pub width: u16,
+    /// Height of the thumbnail
+    pub height: u16,
+}
+
+impl Message for PreviewOutputMessage {
+    type Result = Result<Vec<u8>, Error>;
+}
+
+/// Capture the next video frame and audio buffer output at the same
+/// time, less than a frame apart, sent to [`Mixer`]
+#[derive(Debug)]
+pub struct TakeSnapshotMessage;
+
+impl Message for TakeSnapshotMessage {
+    type Result = Result<MixerSnapshot, Error>;
+}
+
+/// Update the background of `compositor`, sent to [`Mixer`]
+#[derive(Debug)]
+pub struct SetCompositorBackgroundMessage {
+    /// One of the choices of the `compositor-background` setting
+    pub background: String,
+}
+
+impl Message for SetCompositorBackgroundMessage {
+    type Result = Result<(), Error>;
+}
+
+/// Update whether our `compositor` and / or `audiomixer` ignore the
+/// slots that have not received any data yet, sent to [`Mixer`]
+///
+/// Applies immediately to the running elements, and persists across
+/// restarts through the `video-ignore-inactive-pads` and
+/// `audio-ignore-inactive-pads` settings.
+#[derive(Debug)]
+pub struct SetIgnoreInactivePadsMessage {
+    /// The new value for `compositor`, if any
+    pub video: Option<bool>,
+    /// The new value for `audiomixer`, if any
+    pub audio: Option<bool>,
+}
+
+impl Message for SetIgnoreInactivePadsMessage {
+    type Result = Result<(), Error>;
+}
+
+/// Update a controllable setting immediately, sent to [`Mixer`]
+///
+/// Equivalent to a control point with mode Set at the current time.
+#[derive(Debug)]
+pub struct UpdateSettingMessage {
+    /// The name of the setting
+    pub name: String,
+    /// The new value of the setting
+    pub value: serde_json::Value,
+}
+
+impl Message for UpdateSettingMessage {
+    type Result = Result<(), Error>;
+}
+
+/// Write the next frame of the video output to a file, sent to [`Mixer`]
+///
+/// Replies with the number of bytes written
+#[derive(Debug)]
+pub struct CaptureFrameToFileMessage {
+    /// Path of the file to write
+    pub path: PathBuf,
+    /// The format of the image
+    pub format: ImageFileFormat,
+}
+
+impl Message for CaptureFrameToFileMessage {
+    type Result = Result<u64, Error>;
+}
+
+/// Add a video output with a different resolution and framerate,
+/// sent to [`Mixer`]
+#[derive(Debug)]
+pub struct AddOutputScaleMessage {
+    /// Identifier of the output
+    pub output_id: String,
+    /// Width of the output
+    pub width: u32,
+    /// Height of the output
+    pub height: u32,
+    /// Framerate of the output
+    pub framerate: gst::Fraction,
+}
+
+impl Message for AddOutputScaleMessage {
+    type Result = Result<(), Error>;
+}
+
+/// Retrieve the producer of an output added with
+/// [`AddOutputScaleMessage`], sent to [`Mixer`]
+#[derive(Debug)]
+pub struct GetOutputProducerMessage {
+    /// Identifier of the output
+    pub output_id: String,
+}
+
+impl Message for GetOutputProducerMessage {
+    type Result = Result<StreamProducer, Error>;
+}
+
+/// Remove an output added with [`AddOutputScaleMessage`], sent
+/// to [`Mixer`]
+///
+/// Replies with the ids of the consumers of the output, which were
+/// removed along with it
+#[derive(Debug)]
+pub struct RemoveOutputMessage {
+    /// Identifier of the output
+    pub output_id: String,
+}
+
+impl Message for RemoveOutputMessage {
+    type Result = Result<Vec<String>, Error>;
+}
+
+/// The time source our mixing callbacks synchronize controllers against
+#[derive(Debug, Default, Clone)]
+struct ControlTiming {
+    /// The overridden clock of our pipeline, control points follow it
+    clock: Option<gst::Clock>,
+    /// Whether to follow the PTS of the output rather than the clock,
+    /// see `control-timing`
+    follow_pts: bool,
+    /// The first PTS observed when following PTS, and the time it maps
+    /// to, shared by the video and audio callbacks so that they agree on
+    /// the time of a given PTS
+    anchor: Arc<Mutex<Option<(gst::ClockTime, DateTime<Utc>)>>>,
+}
+
+impl ControlTiming {
+    /// The time at which control points should be evaluated for `pts`
+    ///
+    /// When following PTS, the first PTS is mapped to the current time
+    /// of the clock, and times advance with the PTS from there on: control
+    /// points keep being authored in wall-clock terms, but are evaluated
+    /// frame-accurately.
+    fn now(&self, pts: gst::ClockTime) -> DateTime<Utc> {
+        if !self.follow_pts || pts.is_none() {
+            return get_clock_now(self.clock.as_ref());
+        }
+
+        let clock = &self.clock;
+        let (anchor_pts, anchor_time) = *self
+            .anchor
+            .lock()
+            .unwrap()
+            .get_or_insert_with(|| (pts, get_clock_now(clock.as_ref())));
+
+        anchor_time
+            + chrono::Duration::nanoseconds(
+                pts.nseconds().unwrap() as i64 - anchor_pts.nseconds().unwrap() as i64,
+            )
+    }
+}
+
+/// How a slot connected before the mixer started is shown until its
+/// first video buffer arrives, see `video::late-start-mode`
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum LateStartMode {
+    /// Nothing is shown, the base plate falls back as usual
+    Mute,
+    /// A black placeholder is shown in place of the slot
+    Freeze,
+    /// The slot is transparent, the base plate is held off
+    Blank,
+}
+
+impl LateStartMode {
+    fn parse(mode: &str) -> Result<Self, Error> {
+        match mode {
+            "mute" => Ok(LateStartMode::Mute),
+            "freeze" => Ok(LateStartMode::Freeze),
+            "blank" => Ok(LateStartMode::Blank),
+            _ => Err(anyhow!(
+                "video::late-start-mode must be one of mute, freeze, blank, not {}",
+                mode
+            )),
+        }
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            LateStartMode::Mute => "mute",
+            LateStartMode::Freeze => "freeze",
+            LateStartMode::Blank => "blank",
+        }
+    }
+}
+
+/// A slot still waiting for its first video buffer, see [`LateStartMode`]
+#[derive(Debug)]
+enum LateStart {
+    /// Black frames are mixed in through a dedicated `compositor` pad
+    Freeze {
+        placeholder: gst::Element,
+        pad: gst::Pad,
+    },
+    /// The slot pad is transparent until then, and restored to `alpha`
+    Blank { alpha: f64 },
+}
+
+/// The images cycled through by our base plate when
+/// `fallback-slideshow` is set
+#[derive(Debug)]
+struct Slideshow {
+    /// Where we push encoded images, feeding `imagefreeze`
+    appsrc: gst_app::AppSrc,
+    /// The encoded images, read once when building the base plate
+    images: Vec<gst::Buffer>,
+    /// The index of the image to show next
+    next: usize,
+    /// For how long each image is shown
+    interval: gst::ClockTime,
+    /// The PTS at which the current image was last shown, unset while
+    /// the base plate is hidden
+    shown_since: gst::ClockTime,
+}
+
+impl Slideshow {
+    fn show_next(&mut self) {
+        let image = self.images[self.next].clone();
+
+        trace!(index = self.next, "showing next slideshow image");
+
+        if let Err(err) = self.appsrc.push_buffer(image) {
+            warn!("Failed to push slideshow image: {:?}", err);
+        }
+
+        self.next = (self.next + 1) % self.images.len();
+    }
+
+    /// Advance the slideshow if the current image was shown for long
+    /// enough, only called while the base plate is visible
+    fn advance(&mut self, pts: gst::ClockTime) {
+        if self.shown_since.is_none() {
+            self.shown_since = pts;
+        } else if pts - self.shown_since >= self.interval {
+            self.show_next();
+            self.shown_since = pts;
+        }
+    }
 }
 
 /// Used from our `compositor::samples_selected` callback
@@ -53,6 +825,11 @@ pub struct VideoMixingState {
     base_plate_timeout: gst::ClockTime,
     /// Whether our base plate is opaque
     showing_base_plate: bool,
+    /// The `compositor` pad of our idle graphic, shown and hidden along
+    /// with our base plate
+    idle_graphic_pad: Option<gst::Pad>,
+    /// Our fallback slideshow, if any
+    slideshow: Option<Slideshow>,
     /// Our slot controllers
     slot_controllers: Option<HashMap<String, PropertyController>>,
     /// Our controllers (width, height, ...)
@@ -61,6 +838,41 @@ pub struct VideoMixingState {
     last_pts: gst::ClockTime,
     /// For resizing our output video stream
     capsfilter: Option<gst::Element>,
+    /// The source pads of our mixers, for offsetting our output timestamps
+    output_pads: Vec<gst::Pad>,
+    /// Per-slot overrides of `fallback-timeout`, compositor pad name -> timeout
+    slot_timeouts: HashMap<String, gst::ClockTime>,
+    /// Non-zero slot priorities, compositor pad name -> priority
+    slot_priorities: HashMap<String, u8>,
+    /// Output PTS at which priority slots last had a sample selected,
+    /// compositor pad name -> PTS in nanoseconds
+    priority_selected_pts: HashMap<String, u64>,
+    /// Slots waiting for their first buffer since we started,
+    /// compositor pad name -> late start
+    late_starts: HashMap<String, LateStart>,
+    /// PTS of the last sample selected on each slot pad, compositor pad
+    /// name -> PTS in nanoseconds
+    selected_pts: HashMap<String, u64>,
+    /// How late our last output frame was produced relative to real
+    /// time, in nanoseconds, see [`Mixer::update_processing_lag`]
+    processing_lag: Option<u64>,
+    /// Whether that lag exceeds our latency by more than
+    /// [`FALLING_BEHIND_THRESHOLD_MS`]
+    falling_behind: bool,
+    /// The timeout after which to fall back to the base plate, the
+    /// largest one among the slots that were last selected, if any
+    fallback_timeout: Option<gst::ClockTime>,
+    /// The `compositor` pad of our watermark, if any
+    watermark_pad: Option<gst::Pad>,
+    /// What our controllers are synchronized against
+    control_timing: ControlTiming,
+    /// Slot zorders to apply before mixing the next frame, see
+    /// [`Mixer::set_slot_order`]
+    pending_zorders: Option<Vec<(gst::Pad, u32)>>,
+    /// Output resize to apply before mixing the next frame, from
+    /// (width, height) to (width, height), see
+    /// [`Mixer::resize_output_preserving_slot_layout`]
+    pending_resize: Option<((i32, i32), (i32, i32))>,
 }
 
 /// Used from our `audiomixer::samples_selected` callback
@@ -68,8 +880,23 @@ pub struct VideoMixingState {
 pub struct AudioMixingState {
     /// Our slot controllers
     slot_controllers: Option<HashMap<String, PropertyController>>,
+    /// Our controllers for audio settings (audio-eq-low-gain-db, ...)
+    mixer_controllers: Option<HashMap<String, SettingController>>,
+    /// Our output equalizer, if any
+    equalizer: Option<gst::Element>,
+    /// The `audiomixer` pad of our base signal, unless it is silence
+    base_signal_pad: Option<gst::Pad>,
+    /// For how long no pad other than our base signal has selected samples
+    base_signal_timeout: gst::ClockTime,
+    /// Whether our base signal is audible
+    sounding_base_signal: bool,
+    /// What our controllers are synchronized against
+    control_timing: ControlTiming,
     /// The last observed PTS, for interpolating
     last_pts: gst::ClockTime,
+    /// PTS of the last sample selected on each slot pad, audiomixer pad
+    /// name -> PTS in nanoseconds
+    selected_pts: HashMap<String, u64>,
 }
 
 /// The Mixer actor
@@ -84,8 +911,18 @@ pub struct Mixer {
     video_producer: StreamProducer,
     /// Output audio producer
     audio_producer: StreamProducer,
+    /// Output mono audio monitor producer, only part of the pipeline
+    /// when `enable-audio-monitor` is set
+    audio_monitor_producer: StreamProducer,
     /// Input connection points
     consumer_slots: HashMap<String, ConsumerSlot>,
+    /// The generation of the slot created last
+    slot_generation: u64,
+    /// Splits our video output between `video_producer` and our
+    /// scaled outputs, only inserted once an output is added
+    video_tee: Option<gst::Element>,
+    /// Additional scaled video outputs
+    scaled_outputs: HashMap<String, ScaledOutput>,
     /// `audiomixer`
     audio_mixer: gst::Element,
     /// `compositor`
@@ -98,6 +935,27 @@ pub struct Mixer {
     state_machine: StateMachine,
     /// Our output settings
     settings: HashMap<String, Arc<Mutex<Setting>>>,
+    /// The last measurements of our output `level` element
+    last_audio_level: watch::Receiver<Option<AudioLevelInfo>>,
+    /// Handed over to our pipeline manager for publishing the
+    /// measurements of our output `level` element
+    audio_level_sender: Option<watch::Sender<Option<AudioLevelInfo>>>,
+    /// Whether our pipeline has successfully reached PLAYING
+    prerolled: bool,
+    /// Whether we were told to stop without draining our pipeline
+    killed: bool,
+    /// Named layouts, layout name -> slot id -> slot config key -> value
+    layouts: HashMap<String, HashMap<String, HashMap<String, serde_json::Value>>>,
+    /// The clock of our pipeline, when overridden with a network or
+    /// PTP clock
+    clock: Option<gst::Clock>,
+    /// Metadata of our output streams, sent as tags to our consumers
+    stream_metadata: HashMap<String, String>,
+    /// Whether we reported that we were falling behind real time
+    reported_falling_behind: bool,
+    /// Properties of our `compositor` and `audiomixer`, by
+    /// `compositor::` or `audiomixer::` prefixed config key
+    element_config: HashMap<String, serde_json::Value>,
 }
 
 impl Actor for Mixer {
@@ -105,14 +963,32 @@ impl Actor for Mixer {
 
     #[instrument(level = "debug", name = "starting", skip(self, ctx), fields(id = %self.id))]
     fn started(&mut self, ctx: &mut Self::Context) {
-        self.pipeline_manager = Some(
-            PipelineManager::new(
-                self.pipeline.clone(),
-                ctx.address().downgrade().recipient(),
-                &self.id,
-            )
-            .start(),
+        let mut pipeline_manager = PipelineManager::new(
+            self.pipeline.clone(),
+            ctx.address().downgrade().recipient(),
+            &self.id,
         );
+
+        if let Some(audio_level_sender) = self.audio_level_sender.take() {
+            pipeline_manager = pipeline_manager.track_audio_level("level", audio_level_sender);
+        }
+
+        self.pipeline_manager = Some(pipeline_manager.start());
+
+        self.start_heartbeat(ctx);
+    }
+
+    #[instrument(level = "debug", name = "stopping", skip(self, ctx), fields(id = %self.id))]
+    fn stopping(&mut self, ctx: &mut Self::Context) -> Running {
+        self.stop_schedule(ctx);
+
+        if self.drain(ctx) {
+            self.state_machine.state = State::Stopping;
+            Running::Continue
+        } else {
+            debug!("no need to drain");
+            Running::Stop
+        }
     }
 
     #[instrument(level = "debug", name = "stopped", skip(self, _ctx), fields(id = %self.id))]
@@ -122,8 +998,7 @@ impl Actor for Mixer {
         }
 
         for (id, slot) in self.consumer_slots.drain() {
-            slot.video_producer.remove_consumer(&id);
-            slot.audio_producer.remove_consumer(&id);
+            slot.remove_consumers(&id);
         }
 
         NodeManager::from_registry().do_send(StoppedMessage {
@@ -179,14 +1054,130 @@ impl Mixer {
         );
 
         settings.insert(
-            "fallback-image".to_string(),
+            "output-audio-bits".to_string(),
             Arc::new(Mutex::new(Setting {
-                name: "fallback-image".to_string(),
+                name: "output-audio-bits".to_string(),
+                spec: SettingSpec::Enum {
+                    current: "16".into(),
+                    choices: &["16", "24", "32"],
+                },
+                controllable: false,
+            })),
+        );
+
+        settings.insert(
+            "output-channel-mode".to_string(),
+            Arc::new(Mutex::new(Setting {
+                name: "output-channel-mode".to_string(),
+                spec: SettingSpec::Enum {
+                    current: "stereo".to_string(),
+                    choices: &["stereo", "mono", "5.1", "pass-through"],
+                },
+                controllable: false,
+            })),
+        );
+
+        settings.insert(
+            "fallback-image".to_string(),
+            Arc::new(Mutex::new(Setting {
+                name: "fallback-image".to_string(),
+                spec: SettingSpec::Str { current: "".into() },
+                controllable: false,
+            })),
+        );
+
+        settings.insert(
+            "fallback-slideshow".to_string(),
+            Arc::new(Mutex::new(Setting {
+                name: "fallback-slideshow".to_string(),
+                spec: SettingSpec::Str { current: "".into() },
+                controllable: false,
+            })),
+        );
+
+        settings.insert(
+            "fallback-slideshow-interval".to_string(),
+            Arc::new(Mutex::new(Setting {
+                name: "fallback-slideshow-interval".to_string(),
+                spec: SettingSpec::I32 {
+                    min: 1,
+                    max: 2147483647,
+                    current: 5000,
+                },
+                controllable: false,
+            })),
+        );
+
+        settings.insert(
+            "idle-graphic".to_string(),
+            Arc::new(Mutex::new(Setting {
+                name: "idle-graphic".to_string(),
                 spec: SettingSpec::Str { current: "".into() },
                 controllable: false,
             })),
         );
 
+        settings.insert(
+            "watermark-image".to_string(),
+            Arc::new(Mutex::new(Setting {
+                name: "watermark-image".to_string(),
+                spec: SettingSpec::Str { current: "".into() },
+                controllable: false,
+            })),
+        );
+
+        settings.insert(
+            "watermark-x".to_string(),
+            Arc::new(Mutex::new(Setting {
+                name: "watermark-x".to_string(),
+                spec: SettingSpec::I32 {
+                    min: -2147483648,
+                    max: 2147483647,
+                    current: 0,
+                },
+                controllable: true,
+            })),
+        );
+
+        settings.insert(
+            "watermark-y".to_string(),
+            Arc::new(Mutex::new(Setting {
+                name: "watermark-y".to_string(),
+                spec: SettingSpec::I32 {
+                    min: -2147483648,
+                    max: 2147483647,
+                    current: 0,
+                },
+                controllable: true,
+            })),
+        );
+
+        settings.insert(
+            "watermark-alpha".to_string(),
+            Arc::new(Mutex::new(Setting {
+                name: "watermark-alpha".to_string(),
+                spec: SettingSpec::F64 {
+                    min: 0.0,
+                    max: 1.0,
+                    current: 1.0,
+                },
+                controllable: true,
+            })),
+        );
+
+        settings.insert(
+            "watermark-scale".to_string(),
+            Arc::new(Mutex::new(Setting {
+                name: "watermark-scale".to_string(),
+                spec: SettingSpec::F64 {
+                    min: 0.01,
+                    max: 100.0,
+                    current: 1.0,
+                },
+                controllable: false,
+            })),
+        );
+
         settings.insert(
             "fallback-timeout".to_string(),
             Arc::new(Mutex::new(Setting {
@@ -200,1064 +1191,8854 @@ impl Mixer {
             })),
         );
 
-        settings
-    }
+        settings.insert(
+            "audio-base-signal".to_string(),
+            Arc::new(Mutex::new(Setting {
+                name: "audio-base-signal".to_string(),
+                spec: SettingSpec::Enum {
+                    current: "silence".into(),
+                    choices: &["silence", "tone", "pink-noise"],
+                },
+                controllable: false,
+            })),
+        );
+
+        settings.insert(
+            "audio-base-level-db".to_string(),
+            Arc::new(Mutex::new(Setting {
+                name: "audio-base-level-db".to_string(),
+                spec: SettingSpec::F64 {
+                    min: -60.0,
+                    max: 0.0,
+                    current: -18.0,
+                },
+                controllable: false,
+            })),
+        );
+
+        settings.insert(
+            "enable-audio-monitor".to_string(),
+            Arc::new(Mutex::new(Setting {
+                name: "enable-audio-monitor".to_string(),
+                spec: SettingSpec::Bool { current: false },
+                controllable: false,
+            })),
+        );
+
+        settings.insert(
+            "audio-monitor-sample-rate".to_string(),
+            Arc::new(Mutex::new(Setting {
+                name: "audio-monitor-sample-rate".to_string(),
+                spec: SettingSpec::I32 {
+                    min: 1,
+                    max: 2147483647,
+                    current: 8000,
+                },
+                controllable: false,
+            })),
+        );
+
+        settings.insert(
+            "video-ignore-inactive-pads".to_string(),
+            Arc::new(Mutex::new(Setting {
+                name: "video-ignore-inactive-pads".to_string(),
+                spec: SettingSpec::Bool { current: true },
+                controllable: false,
+            })),
+        );
+
+        settings.insert(
+            "audio-ignore-inactive-pads".to_string(),
+            Arc::new(Mutex::new(Setting {
+                name: "audio-ignore-inactive-pads".to_string(),
+                spec: SettingSpec::Bool { current: true },
+                controllable: false,
+            })),
+        );
+
+        settings.insert(
+            "use-ptp".to_string(),
+            Arc::new(Mutex::new(Setting {
+                name: "use-ptp".to_string(),
+                spec: SettingSpec::Bool { current: false },
+                controllable: false,
+            })),
+        );
+
+        settings.insert(
+            "ptp-domain".to_string(),
+            Arc::new(Mutex::new(Setting {
+                name: "ptp-domain".to_string(),
+                spec: SettingSpec::I32 {
+                    min: 0,
+                    max: 255,
+                    current: 0,
+                },
+                controllable: false,
+            })),
+        );
+
+        settings.insert(
+            "program-number".to_string(),
+            Arc::new(Mutex::new(Setting {
+                name: "program-number".to_string(),
+                spec: SettingSpec::I32 {
+                    min: 1,
+                    max: 65535,
+                    current: 1,
+                },
+                controllable: false,
+            })),
+        );
+
+        settings.insert(
+            "stream-id".to_string(),
+            Arc::new(Mutex::new(Setting {
+                name: "stream-id".to_string(),
+                spec: SettingSpec::Str { current: "".into() },
+                controllable: false,
+            })),
+        );
+
+        settings.insert(
+            "control-timing".to_string(),
+            Arc::new(Mutex::new(Setting {
+                name: "control-timing".to_string(),
+                spec: SettingSpec::Enum {
+                    current: "wall-clock".into(),
+                    choices: &["wall-clock", "pts"],
+                },
+                controllable: false,
+            })),
+        );
+
+        settings.insert(
+            "net-clock-address".to_string(),
+            Arc::new(Mutex::new(Setting {
+                name: "net-clock-address".to_string(),
+                spec: SettingSpec::Str { current: "".into() },
+                controllable: false,
+            })),
+        );
+
+        settings.insert(
+            "net-clock-port".to_string(),
+            Arc::new(Mutex::new(Setting {
+                name: "net-clock-port".to_string(),
+                spec: SettingSpec::I32 {
+                    min: 1,
+                    max: 65535,
+                    current: 8554,
+                },
+                controllable: false,
+            })),
+        );
+
+        settings.insert(
+            "pixel-aspect-ratio".to_string(),
+            Arc::new(Mutex::new(Setting {
+                name: "pixel-aspect-ratio".to_string(),
+                spec: SettingSpec::Fraction {
+                    current: gst::Fraction::new(1, 1),
+                },
+                controllable: false,
+            })),
+        );
+
+        settings.insert(
+            "format".to_string(),
+            Arc::new(Mutex::new(Setting {
+                name: "format".to_string(),
+                spec: SettingSpec::Enum {
+                    current: "AYUV".into(),
+                    choices: &[
+                        "AYUV", "VUYA", "BGRA", "ARGB", "RGBA", "ABGR", "Y444", "Y42B", "YUY2",
+                        "UYVY", "YVYU", "I420", "YV12", "NV12", "NV21", "Y41B", "RGB", "BGR",
+                        "xRGB", "xBGR", "RGBx", "BGRx",
+                    ],
+                },
+                controllable: false,
+            })),
+        );
+
+        settings.insert(
+            "colorimetry".to_string(),
+            Arc::new(Mutex::new(Setting {
+                name: "colorimetry".to_string(),
+                spec: SettingSpec::Enum {
+                    current: "bt601".into(),
+                    choices: &[
+                        "bt601",
+                        "bt709",
+                        "smpte240m",
+                        "sRGB",
+                        "bt2020",
+                        "bt2100-pq",
+                        "bt2100-hlg",
+                    ],
+                },
+                controllable: false,
+            })),
+        );
+
+        settings.insert(
+            "chroma-site".to_string(),
+            Arc::new(Mutex::new(Setting {
+                name: "chroma-site".to_string(),
+                spec: SettingSpec::Enum {
+                    current: "jpeg".into(),
+                    choices: &["jpeg", "mpeg2", "dv", "alt-line", "cosited"],
+                },
+                controllable: false,
+            })),
+        );
+
+        settings.insert(
+            "compositor-background".to_string(),
+            Arc::new(Mutex::new(Setting {
+                name: "compositor-background".to_string(),
+                spec: SettingSpec::Enum {
+                    current: "black".into(),
+                    choices: &["black", "white", "transparent", "checker"],
+                },
+                controllable: false,
+            })),
+        );
+
+        for name in &[
+            "audio-eq-low-gain-db",
+            "audio-eq-mid-gain-db",
+            "audio-eq-high-gain-db",
+        ] {
+            settings.insert(
+                name.to_string(),
+                Arc::new(Mutex::new(Setting {
+                    name: name.to_string(),
+                    spec: SettingSpec::F64 {
+                        min: -24.0,
+                        max: 12.0,
+                        current: 0.0,
+                    },
+                    controllable: true,
+                })),
+            );
+        }
+
+        settings.insert(
+            "audio-eq-low-freq-hz".to_string(),
+            Arc::new(Mutex::new(Setting {
+                name: "audio-eq-low-freq-hz".to_string(),
+                spec: SettingSpec::F64 {
+                    min: 20.0,
+                    max: 20000.0,
+                    current: 100.0,
+                },
+                controllable: false,
+            })),
+        );
+
+        settings.insert(
+            "audio-eq-high-freq-hz".to_string(),
+            Arc::new(Mutex::new(Setting {
+                name: "audio-eq-high-freq-hz".to_string(),
+                spec: SettingSpec::F64 {
+                    min: 20.0,
+                    max: 20000.0,
+                    current: 11000.0,
+                },
+                controllable: false,
+            })),
+        );
+
+        settings.insert(
+            "output-qos".to_string(),
+            Arc::new(Mutex::new(Setting {
+                name: "output-qos".to_string(),
+                spec: SettingSpec::Bool { current: false },
+                controllable: false,
+            })),
+        );
+
+        settings.insert(
+            "output-max-lateness-ms".to_string(),
+            Arc::new(Mutex::new(Setting {
+                name: "output-max-lateness-ms".to_string(),
+                spec: SettingSpec::I32 {
+                    min: -1,
+                    max: 2147483647,
+                    current: -1,
+                },
+                controllable: false,
+            })),
+        );
+
+        settings.insert(
+            "consumer-removal-timeout".to_string(),
+            Arc::new(Mutex::new(Setting {
+                name: "consumer-removal-timeout".to_string(),
+                spec: SettingSpec::I32 {
+                    min: 0,
+                    max: 2147483647,
+                    current: 0,
+                },
+                controllable: false,
+            })),
+        );
+
+        settings.insert(
+            "output-pts-offset-ns".to_string(),
+            Arc::new(Mutex::new(Setting {
+                name: "output-pts-offset-ns".to_string(),
+                spec: SettingSpec::I64 {
+                    min: -3_600_000_000_000,
+                    max: 3_600_000_000_000,
+                    current: 0,
+                },
+                controllable: true,
+            })),
+        );
+
+        settings
+    }
+
+    fn setting(&self, name: &str) -> Option<MutexGuard<Setting>> {
+        self.settings
+            .get(name)
+            .map(|setting| setting.lock().unwrap())
+    }
+
+    fn settings(&self) -> HashMap<String, serde_json::Value> {
+        self.settings
+            .iter()
+            .map(|(id, setting)| (id.clone(), setting.lock().unwrap().as_value()))
+            .collect()
+    }
+
+    /// The caps currently negotiated on the appsink of an output producer
+    fn output_caps(producer: &StreamProducer) -> Option<String> {
+        producer
+            .appsink()
+            .static_pad("sink")
+            .and_then(|pad| pad.current_caps())
+            .map(|caps| caps.to_string())
+    }
+
+    fn control_points(&self) -> HashMap<String, Vec<ControlPoint>> {
+        let video_mixing_state = self.video_mixing_state.lock().unwrap();
+        let audio_mixing_state = self.audio_mixing_state.lock().unwrap();
+
+        video_mixing_state
+            .mixer_controllers
+            .as_ref()
+            .unwrap()
+            .iter()
+            .chain(
+                audio_mixing_state
+                    .mixer_controllers
+                    .as_ref()
+                    .unwrap()
+                    .iter(),
+            )
+            .map(|(id, controller)| (id.clone(), controller.control_points()))
+            .collect()
+    }
+
+    /// Summarize the controllers of our settings
+    fn control_point_summaries(&self) -> HashMap<String, ControlPointSummary> {
+        let video_mixing_state = self.video_mixing_state.lock().unwrap();
+        let audio_mixing_state = self.audio_mixing_state.lock().unwrap();
+
+        video_mixing_state
+            .mixer_controllers
+            .as_ref()
+            .unwrap()
+            .iter()
+            .chain(
+                audio_mixing_state
+                    .mixer_controllers
+                    .as_ref()
+                    .unwrap()
+                    .iter(),
+            )
+            .map(|(id, controller)| (id.clone(), controller.summary()))
+            .collect()
+    }
+
+    /// Create a mixer
+    pub fn new(
+        id: &str,
+        config: Option<HashMap<String, serde_json::Value>>,
+    ) -> Result<Self, Error> {
+        let pipeline = gst::Pipeline::new(None);
+
+        let audio_appsink =
+            gst::ElementFactory::make("appsink", Some(&format!("mixer-audio-appsink-{}", id)))
+                .unwrap()
+                .downcast::<gst_app::AppSink>()
+                .unwrap();
+
+        let video_appsink =
+            gst::ElementFactory::make("appsink", Some(&format!("mixer-video-appsink-{}", id)))
+                .unwrap()
+                .downcast::<gst_app::AppSink>()
+                .unwrap();
+
+        let audio_monitor_appsink = gst::ElementFactory::make(
+            "appsink",
+            Some(&format!("mixer-audio-monitor-appsink-{}", id)),
+        )
+        .unwrap()
+        .downcast::<gst_app::AppSink>()
+        .unwrap();
+
+        let audio_mixer = make_element("audiomixer", Some("audiomixer")).unwrap();
+        let video_mixer = make_element("compositor", Some("compositor")).unwrap();
+
+        pipeline
+            .add_many(&[&audio_appsink, &video_appsink])
+            .unwrap();
+
+        let mut mixer_settings = Mixer::create_settings();
+        let mut layouts = HashMap::new();
+        let mut element_config = HashMap::new();
+
+        if let Some(config) = config {
+            for (key, value) in config {
+                if key == "layouts" {
+                    layouts = serde_json::from_value(value)
+                        .map_err(|err| anyhow!("Invalid layouts for mixer {}: {}", id, err))?;
+                } else if let Some((element, property)) =
+                    Mixer::element_config_target(&key, &video_mixer, &audio_mixer)
+                {
+                    PropertyController::validate_value(property, element.upcast_ref(), &value)?;
+                    element_config.insert(key, value);
+                } else if let Some(setting) = mixer_settings.get_mut(&key) {
+                    let mut setting = setting.lock().unwrap();
+                    SettingController::validate_value(&setting, &value)?;
+                    SettingController::set_from_value(&mut setting, &value);
+                } else {
+                    return Err(anyhow!("No setting with name {} on mixers", key));
+                }
+            }
+        }
+
+        // Late output buffers are dropped rather than accumulating delay
+        // when enabled
+        let qos = mixer_settings["output-qos"]
+            .lock()
+            .unwrap()
+            .as_bool()
+            .unwrap();
+        let max_lateness = match mixer_settings["output-max-lateness-ms"]
+            .lock()
+            .unwrap()
+            .as_i32()
+            .unwrap()
+        {
+            -1 => -1i64,
+            ms => ms as i64 * 1_000_000,
+        };
+
+        for appsink in &[&audio_appsink, &video_appsink, &audio_monitor_appsink] {
+            appsink.set_property("qos", &qos).unwrap();
+            appsink.set_property("max-lateness", &max_lateness).unwrap();
+        }
+
+        let (audio_level_sender, last_audio_level) = watch::channel(None);
+
+        let audio_producer = StreamProducer::from(&audio_appsink);
+        let video_producer = StreamProducer::from(&video_appsink);
+        let audio_monitor_producer = StreamProducer::from(&audio_monitor_appsink);
+
+        let consumer_removal_timeout = Mixer::consumer_removal_timeout(&mixer_settings);
+
+        for producer in &[&audio_producer, &video_producer, &audio_monitor_producer] {
+            forward_producer_notifications(producer, id);
+            producer.set_auto_remove_timeout(consumer_removal_timeout);
+        }
+
+        Ok(Self {
+            id: id.to_string(),
+            pipeline,
+            pipeline_manager: None,
+            audio_producer,
+            video_producer,
+            audio_monitor_producer,
+            consumer_slots: HashMap::new(),
+            slot_generation: 0,
+            video_tee: None,
+            scaled_outputs: HashMap::new(),
+            audio_mixer,
+            video_mixer,
+            video_mixing_state: Arc::new(Mutex::new(VideoMixingState {
+                base_plate_timeout: gst::CLOCK_TIME_NONE,
+                showing_base_plate: false,
+                idle_graphic_pad: None,
+                slideshow: None,
+                slot_controllers: Some(HashMap::new()),
+                mixer_controllers: Some(HashMap::new()),
+                last_pts: gst::CLOCK_TIME_NONE,
+                capsfilter: None,
+                output_pads: vec![],
+                slot_timeouts: HashMap::new(),
+                slot_priorities: HashMap::new(),
+                priority_selected_pts: HashMap::new(),
+                late_starts: HashMap::new(),
+                selected_pts: HashMap::new(),
+                processing_lag: None,
+                falling_behind: false,
+                fallback_timeout: None,
+                watermark_pad: None,
+                control_timing: ControlTiming::default(),
+                pending_zorders: None,
+                pending_resize: None,
+            })),
+            audio_mixing_state: Arc::new(Mutex::new(AudioMixingState {
+                slot_controllers: Some(HashMap::new()),
+                mixer_controllers: Some(HashMap::new()),
+                equalizer: None,
+                base_signal_pad: None,
+                base_signal_timeout: gst::CLOCK_TIME_NONE,
+                sounding_base_signal: false,
+                control_timing: ControlTiming::default(),
+                last_pts: gst::CLOCK_TIME_NONE,
+                selected_pts: HashMap::new(),
+            })),
+            state_machine: StateMachine::default(),
+            settings: mixer_settings,
+            last_audio_level,
+            audio_level_sender: Some(audio_level_sender),
+            prerolled: false,
+            killed: false,
+            layouts,
+            clock: None,
+            stream_metadata: HashMap::new(),
+            reported_falling_behind: false,
+            element_config,
+        })
+    }
+
+    /// Resolve a `compositor::` or `audiomixer::` prefixed config key to
+    /// the element and property it sets
+    fn element_config_target<'a>(
+        key: &'a str,
+        video_mixer: &'a gst::Element,
+        audio_mixer: &'a gst::Element,
+    ) -> Option<(&'a gst::Element, &'a str)> {
+        if let Some(property) = key.strip_prefix("compositor::") {
+            Some((video_mixer, property))
+        } else if let Some(property) = key.strip_prefix("audiomixer::") {
+            Some((audio_mixer, property))
+        } else {
+            None
+        }
+    }
+
+    /// For how long consumers of our outputs may fail to accept samples
+    /// before they are disconnected, if at all
+    fn consumer_removal_timeout(
+        settings: &HashMap<String, Arc<Mutex<Setting>>>,
+    ) -> Option<std::time::Duration> {
+        match settings["consumer-removal-timeout"]
+            .lock()
+            .unwrap()
+            .as_i32()
+            .unwrap()
+        {
+            0 => None,
+            ms => Some(std::time::Duration::from_millis(ms as u64)),
+        }
+    }
+
+    /// The raw audio format of our output
+    ///
+    /// 24-bit samples are padded to 32 bits, as `audiomixer` doesn't
+    /// handle packed 24-bit samples.
+    fn output_audio_format(&self) -> &'static str {
+        match self.setting("output-audio-bits").unwrap().as_str().unwrap() {
+            "24" => "S24_32LE",
+            "32" => "S32LE",
+            _ => "S16LE",
+        }
+    }
+
+    /// The number of channels `audiomixer` mixes in according to our
+    /// `output-channel-mode`, None when left to negotiation
+    ///
+    /// Mono mixes are mixed in stereo, and downmixed afterwards.
+    fn mix_channels(&self) -> Option<i32> {
+        match self
+            .setting("output-channel-mode")
+            .unwrap()
+            .as_str()
+            .unwrap()
+        {
+            "pass-through" => None,
+            "5.1" => Some(6),
+            _ => Some(2),
+        }
+    }
+
+    /// The number of channels of our audio output, None when left
+    /// to negotiation
+    fn output_channels(&self) -> Option<i32> {
+        match self
+            .setting("output-channel-mode")
+            .unwrap()
+            .as_str()
+            .unwrap()
+        {
+            "mono" => Some(1),
+            _ => self.mix_channels(),
+        }
+    }
+
+    /// Raw audio caps for our base signal
+    ///
+    /// In pass-through mode, the base signal is the first input of
+    /// `audiomixer` and would impose its own mono layout: it follows that
+    /// of the earliest connected slot whose audio was negotiated instead,
+    /// and falls back to stereo when there is none.
+    ///
+    /// This is only decided once, when the mixer starts: slots usually
+    /// negotiate after that, the mix is then effectively stereo whatever
+    /// the layout of its inputs.
+    fn base_audio_caps(&self, audio_format: &str, sample_rate: i32) -> gst::Caps {
+        let mix_channels = self.mix_channels();
+
+        if mix_channels.is_some() {
+            return Mixer::raw_audio_caps(audio_format, sample_rate, mix_channels);
+        }
+
+        let layout = self
+            .consumer_slots
+            .values()
+            .filter_map(|slot| {
+                let caps = slot
+                    .audio_producer
+                    .as_ref()?
+                    .appsink()
+                    .static_pad("sink")
+                    .unwrap()
+                    .current_caps()?;
+                let s = caps.structure(0)?;
+                let channels = s.get::<i32>("channels").ok()?;
+                let mask = s.get::<gst::Bitmask>("channel-mask").ok();
+
+                Some((slot.generation, channels, mask))
+            })
+            .min_by_key(|(generation, _, _)| *generation);
+
+        match layout {
+            Some((_, channels, mask)) => {
+                let mut caps = Mixer::raw_audio_caps(audio_format, sample_rate, Some(channels));
+
+                if let Some(mask) = mask {
+                    caps.make_mut().set_simple(&[("channel-mask", &mask)]);
+                }
+
+                caps
+            }
+            None => Mixer::raw_audio_caps(audio_format, sample_rate, Some(2)),
+        }
+    }
+
+    /// Raw audio caps, restricted to `channels` when set
+    ///
+    /// Layouts of more than two channels take the first positions in
+    /// GStreamer's order, for 5.1: front left, front right, front center,
+    /// LFE, rear left and rear right.
+    fn raw_audio_caps(audio_format: &str, sample_rate: i32, channels: Option<i32>) -> gst::Caps {
+        let mut builder = gst::Caps::builder("audio/x-raw")
+            .field("format", &audio_format)
+            .field("rate", &sample_rate);
+
+        if let Some(channels) = channels {
+            builder = builder.field("channels", &channels);
+
+            if channels > 2 {
+                let mask = (1u64 << channels) - 1;
+                builder = builder.field("channel-mask", &gst::Bitmask::new(mask));
+            }
+        }
+
+        builder.build()
+    }
+
+    fn parse_slot_config_key(property: &str) -> Result<(bool, &str), Error> {
+        let split: Vec<&str> = property.splitn(2, "::").collect();
+
+        match split.len() {
+            2 => match split[0] {
+                "video" => Ok((true, split[1])),
+                "audio" => Ok((false, split[1])),
+                _ => Err(anyhow!(
+                    "Slot controller property media type must be one of {audio, video}"
+                )),
+            },
+            _ => Err(anyhow!(
+                "Slot controller property name must be in form media-type::property-name"
+            )),
+        }
+    }
+
+    /// Resolve a slot config key to the object and property it controls
+    ///
+    /// Most properties are those of the `compositor` and `audiomixer` pads,
+    /// pts-offset-ns is the offset of the slot `appsrc` source pad.
+    fn slot_property_target<'a>(
+        slot: &ConsumerSlot,
+        key: &'a str,
+    ) -> Result<(bool, gst::Object, &'a str), Error> {
+        let (is_video, property) = Mixer::parse_slot_config_key(key)?;
+
+        let pad = if is_video {
+            slot.video_pad.as_ref()
+        } else {
+            slot.audio_pad.as_ref()
+        }
+        .ok_or_else(|| {
+            anyhow!(
+                "slot has no {} input",
+                if is_video { "video" } else { "audio" }
+            )
+        })?;
+
+        let (object, property) = match (is_video, property) {
+            (true, "pts-offset-ns") => (
+                slot.video_appsrc.static_pad("src").unwrap().upcast(),
+                "offset",
+            ),
+            (false, "pts-offset-ns") => (
+                slot.audio_appsrc.static_pad("src").unwrap().upcast(),
+                "offset",
+            ),
+            (true, "is-live") | (true, "format") | (true, "do-timestamp") => {
+                (slot.video_appsrc.clone().upcast(), property)
+            }
+            (false, "is-live") | (false, "format") | (false, "do-timestamp") => {
+                (slot.audio_appsrc.clone().upcast(), property)
+            }
+            (true, "denoise-spatial") | (true, "denoise-temporal") => match slot.denoiser {
+                Some(ref denoiser) => (
+                    denoiser.clone().upcast(),
+                    if property == "denoise-spatial" {
+                        "luma-spatial"
+                    } else {
+                        "luma-temporal"
+                    },
+                ),
+                None => return Err(anyhow!("video::{} requires video::denoise", property)),
+            },
+            (true, "label-text") | (true, "label-font") => match slot.label_overlay {
+                Some(ref overlay) => (
+                    overlay.clone().upcast(),
+                    if property == "label-text" {
+                        "text"
+                    } else {
+                        "font-desc"
+                    },
+                ),
+                None => return Err(anyhow!("slot cannot be labelled, textoverlay is missing")),
+            },
+            (false, "compressor-ratio") => match slot.compressor {
+                Some(ref compressor) => (compressor.clone().upcast(), "ratio"),
+                None => {
+                    return Err(anyhow!(
+                        "audio::compressor-ratio requires a negative audio::compressor-threshold-db"
+                    ))
+                }
+            },
+            _ => (pad.clone().upcast(), property),
+        };
+
+        Ok((is_video, object, property))
+    }
+
+    /// Connect an input slot to `compositor` and `audiomixer`
+    ///
+    /// The processing elements are added and linked immediately, the
+    /// returned future resolves once their state was synchronized with
+    /// that of the pipeline. The caller is then expected to complete the
+    /// connection with [`Mixer::complete_slot_connection`].
+    #[instrument(level = "debug", name = "connecting", skip(pipeline, slot))]
+    fn connect_slot(
+        pipeline: &gst::Pipeline,
+        slot: &mut ConsumerSlot,
+        mixer_id: &str,
+        id: &str,
+        width: i32,
+        height: i32,
+        sample_rate: i32,
+        audio_format: &'static str,
+        channels: Option<i32>,
+    ) -> Pin<Box<dyn Future<Output = Result<(), Error>>>> {
+        if let Err(err) = Mixer::link_slot(pipeline, slot, sample_rate, audio_format, channels) {
+            return Box::pin(future::ready(Err(err)));
+        }
+
+        let pipeline = pipeline.clone();
+        let video_bin = slot.video_bin.clone();
+        let audio_bin = slot.audio_bin.clone();
+
+        Box::pin(async move {
+            pipeline
+                .call_async_future(move |_pipeline| -> Result<(), Error> {
+                    for bin in video_bin.iter().chain(audio_bin.iter()) {
+                        bin.sync_state_with_parent()?;
+                    }
+
+                    Ok(())
+                })
+                .await
+        })
+    }
+
+    /// Whether the slot with id `id` is still the one of that generation
+    fn is_current_slot(&self, id: &str, generation: u64) -> bool {
+        self.consumer_slots
+            .get(id)
+            .map_or(false, |slot| slot.generation == generation)
+    }
+
+    /// Add a slot as a consumer of its producers, once the future returned
+    /// by [`Mixer::connect_slot`] resolved with `res`, or disconnect it if
+    /// that failed
+    ///
+    /// The slot may have been disconnected in the meantime, possibly
+    /// replaced by another one with the same id: it is then left alone.
+    fn complete_slot_connection(
+        &mut self,
+        id: &str,
+        generation: u64,
+        res: Result<(), Error>,
+    ) -> Result<(), Error> {
+        if !self.is_current_slot(id, generation) {
+            return Err(anyhow!("Slot {} was disconnected while connecting", id));
+        }
+
+        let res = res.and_then(|_| self.consumer_slots[id].add_consumers(id));
+
+        if res.is_err() {
+            let _ = self.disconnect(id);
+        }
+
+        res
+    }
+
+    /// Wait for the probes catching samples to send them through `receiver`,
+    /// removing the probes if they didn't within [`PREVIEW_TIMEOUT_SECS`]
+    async fn receive_samples<T>(
+        receiver: oneshot::Receiver<T>,
+        probes: Vec<(gst::Pad, gst::PadProbeId)>,
+        description: &str,
+    ) -> Result<T, Error> {
+        match tokio::time::timeout(
+            std::time::Duration::from_secs(PREVIEW_TIMEOUT_SECS),
+            receiver,
+        )
+        .await
+        {
+            Ok(res) => res.map_err(|_| anyhow!("No {} output", description)),
+            Err(_) => {
+                // Already removed if they caught a sample
+                for (pad, probe_id) in probes {
+                    pad.remove_probe(probe_id);
+                }
+
+                Err(anyhow!(
+                    "No {} output within {} seconds",
+                    description,
+                    PREVIEW_TIMEOUT_SECS
+                ))
+            }
+        }
+    }
+
+    /// Catch the next sample output by a video producer, without consuming
+    /// it from the producer
+    fn next_video_sample(
+        producer: &StreamProducer,
+    ) -> (
+        oneshot::Receiver<gst::Sample>,
+        Vec<(gst::Pad, gst::PadProbeId)>,
+    ) {
+        let (sender, receiver) = oneshot::channel::<gst::Sample>();
+        let sender = Mutex::new(Some(sender));
+        let pad = producer.appsink().static_pad("sink").unwrap();
+
+        let probe_id = pad.add_probe(gst::PadProbeType::BUFFER, move |pad, info| {
+            if let Some(gst::PadProbeData::Buffer(ref buffer)) = info.data {
+                if let Some(sender) = sender.lock().unwrap().take() {
+                    let caps = pad.current_caps();
+                    let mut builder = gst::Sample::builder().buffer(buffer);
+
+                    if let Some(ref caps) = caps {
+                        builder = builder.caps(caps);
+                    }
+
+                    let _ = sender.send(builder.build());
+                }
+            }
+
+            gst::PadProbeReturn::Remove
+        });
+
+        let probes = probe_id
+            .map(|probe_id| (pad, probe_id))
+            .into_iter()
+            .collect();
+
+        (receiver, probes)
+    }
+
+    /// Catch the next pair of video and audio samples output by our
+    /// producers that are synchronized, without consuming them from
+    /// the producers
+    fn next_synchronized_samples(
+        video_producer: &StreamProducer,
+        audio_producer: &StreamProducer,
+    ) -> (
+        oneshot::Receiver<(gst::Sample, gst::Sample)>,
+        Vec<(gst::Pad, gst::PadProbeId)>,
+    ) {
+        let (sender, receiver) = oneshot::channel::<(gst::Sample, gst::Sample)>();
+        let state = Arc::new(Mutex::new(SnapshotState {
+            video: None,
+            audio: None,
+            sender: Some(sender),
+        }));
+        let mut probes = vec![];
+
+        for (producer, is_video) in &[(video_producer, true), (audio_producer, false)] {
+            let state = state.clone();
+            let is_video = *is_video;
+            let pad = producer.appsink().static_pad("sink").unwrap();
+
+            let probe_id = pad.add_probe(gst::PadProbeType::BUFFER, move |pad, info| {
+                let mut state = state.lock().unwrap();
+
+                // The other probe caught the pair already
+                if state.sender.is_none() {
+                    return gst::PadProbeReturn::Remove;
+                }
+
+                if let Some(gst::PadProbeData::Buffer(ref buffer)) = info.data {
+                    let caps = pad.current_caps();
+                    let mut builder = gst::Sample::builder().buffer(buffer);
+
+                    if let Some(ref caps) = caps {
+                        builder = builder.caps(caps);
+                    }
+
+                    if is_video {
+                        state.video = Some(builder.build());
+                    } else {
+                        state.audio = Some(builder.build());
+                    }
+
+                    let synchronized = match (&state.video, &state.audio) {
+                        (Some(video), Some(audio)) => Mixer::samples_synchronized(video, audio),
+                        _ => false,
+                    };
+
+                    if synchronized {
+                        let samples = (state.video.take().unwrap(), state.audio.take().unwrap());
+                        let _ = state.sender.take().unwrap().send(samples);
+
+                        return gst::PadProbeReturn::Remove;
+                    }
+                }
+
+                gst::PadProbeReturn::Ok
+            });
+
+            probes.extend(probe_id.map(|probe_id| (pad, probe_id)));
+        }
+
+        (receiver, probes)
+    }
+
+    /// Whether a video and an audio sample are less than a video frame
+    /// apart. The duration of a frame is that of the video buffer, or
+    /// derived from the framerate of the video caps
+    fn samples_synchronized(video: &gst::Sample, audio: &gst::Sample) -> bool {
+        let pts = |sample: &gst::Sample| sample.buffer().and_then(|buffer| buffer.pts().nseconds());
+
+        let (video_pts, audio_pts) = match (pts(video), pts(audio)) {
+            (Some(video_pts), Some(audio_pts)) => (video_pts, audio_pts),
+            _ => return false,
+        };
+
+        let frame_duration = video
+            .buffer()
+            .and_then(|buffer| buffer.duration().nseconds())
+            .or_else(|| {
+                video
+                    .caps()
+                    .and_then(|caps| caps.structure(0))
+                    .and_then(|s| s.get::<gst::Fraction>("framerate").ok())
+                    .filter(|framerate| *framerate.numer() > 0)
+                    .map(|framerate| {
+                        1_000_000_000u64
+                            .mul_div_floor(*framerate.denom() as u64, *framerate.numer() as u64)
+                            .unwrap()
+                    })
+            });
+
+        match frame_duration {
+            Some(frame_duration) => {
+                let delta = if video_pts > audio_pts {
+                    video_pts - audio_pts
+                } else {
+                    audio_pts - video_pts
+                };
+
+                delta < frame_duration
+            }
+            None => false,
+        }
+    }
+
+    /// Convert interleaved raw audio samples to floats
+    fn samples_to_f32(format: &str, data: &[u8]) -> Result<Vec<f32>, Error> {
+        Ok(match format {
+            "S16LE" => data
+                .chunks_exact(2)
+                .map(|s| i16::from_le_bytes([s[0], s[1]]) as f32 / 32768.0)
+                .collect(),
+            "S24_32LE" => data
+                .chunks_exact(4)
+                // Shift the sign bit in place, then back with sign extension
+                .map(|s| (i32::from_le_bytes([0, s[0], s[1], s[2]]) >> 8) as f32 / 8388608.0)
+                .collect(),
+            "S32LE" => data
+                .chunks_exact(4)
+                .map(|s| i32::from_le_bytes([s[0], s[1], s[2], s[3]]) as f32 / 2147483648.0)
+                .collect(),
+            "F32LE" => data
+                .chunks_exact(4)
+                .map(|s| f32::from_le_bytes([s[0], s[1], s[2], s[3]]))
+                .collect(),
+            _ => return Err(anyhow!("Unsupported audio format {}", format)),
+        })
+    }
+
+    /// Build a snapshot out of a pair of synchronized samples
+    async fn encode_snapshot(
+        video: gst::Sample,
+        audio: gst::Sample,
+    ) -> Result<MixerSnapshot, Error> {
+        let video_s = video
+            .caps()
+            .and_then(|caps| caps.structure(0).map(|s| s.to_owned()))
+            .ok_or_else(|| anyhow!("Video sample has no caps"))?;
+        let audio_s = audio
+            .caps()
+            .and_then(|caps| caps.structure(0).map(|s| s.to_owned()))
+            .ok_or_else(|| anyhow!("Audio sample has no caps"))?;
+
+        let video_width = video_s.get::<i32>("width")? as u32;
+        let video_height = video_s.get::<i32>("height")? as u32;
+        let audio_sample_rate = audio_s.get::<i32>("rate")? as u32;
+        let audio_channels = audio_s.get::<i32>("channels")? as u32;
+        let audio_format = audio_s.get::<String>("format")?;
+
+        let timestamp_ns = video
+            .buffer()
+            .and_then(|buffer| buffer.pts().nseconds())
+            .unwrap_or(0);
+
+        let audio_pcm = {
+            let buffer = audio
+                .buffer()
+                .ok_or_else(|| anyhow!("Audio sample has no buffer"))?;
+            let map = buffer.map_readable()?;
+
+            Mixer::samples_to_f32(&audio_format, map.as_slice())?
+        };
+
+        let video_jpeg =
+            Mixer::encode_preview(video, video_width as u16, video_height as u16).await?;
+
+        Ok(MixerSnapshot {
+            video_jpeg,
+            audio_pcm,
+            video_width,
+            video_height,
+            audio_sample_rate,
+            audio_channels,
+            timestamp_ns,
+        })
+    }
+
+    /// Scale a video sample to the requested dimensions and encode
+    /// it as JPEG, with a one-shot pipeline
+    async fn encode_preview(
+        sample: gst::Sample,
+        width: u16,
+        height: u16,
+    ) -> Result<Vec<u8>, Error> {
+        let pipeline = gst::Pipeline::new(None);
+
+        let appsrc = make_element("appsrc", None)?;
+        let vconv = make_element("videoconvert", None)?;
+        let vscale = make_element("videoscale", None)?;
+        let capsfilter = make_element("capsfilter", None)?;
+        let jpegenc = make_element("jpegenc", None)?;
+        let appsink = make_element("appsink", None)?;
+
+        pipeline.add_many(&[&appsrc, &vconv, &vscale, &capsfilter, &jpegenc, &appsink])?;
+        gst::Element::link_many(&[&appsrc, &vconv, &vscale, &capsfilter, &jpegenc, &appsink])?;
+
+        capsfilter
+            .set_property(
+                "caps",
+                &gst::Caps::builder("video/x-raw")
+                    .field("width", &(width as i32))
+                    .field("height", &(height as i32))
+                    .field("pixel-aspect-ratio", &gst::Fraction::new(1, 1))
+                    .build(),
+            )
+            .unwrap();
+
+        let appsrc = appsrc.downcast::<gst_app::AppSrc>().unwrap();
+        let appsink = appsink.downcast::<gst_app::AppSink>().unwrap();
+
+        appsrc.set_format(gst::Format::Time);
+        appsink.set_property("sync", &false).unwrap();
+
+        pipeline
+            .call_async_future(move |pipeline| -> Result<Vec<u8>, Error> {
+                pipeline.set_state(gst::State::Playing)?;
+
+                let res = appsrc
+                    .push_sample(&sample)
+                    .and_then(|_| appsrc.end_of_stream())
+                    .map_err(|err| anyhow!("Failed to push sample: {:?}", err))
+                    .and_then(|_| {
+                        // Bounded, in case encoding errors out and never
+                        // reaches EOS
+                        appsink
+                            .try_pull_sample(PREVIEW_TIMEOUT_SECS * gst::SECOND)
+                            .ok_or_else(|| anyhow!("Failed to encode preview"))
+                    })
+                    .and_then(|sample| {
+                        let buffer = sample
+                            .buffer()
+                            .ok_or_else(|| anyhow!("Encoded preview has no buffer"))?;
+                        let map = buffer.map_readable()?;
+
+                        Ok(map.as_slice().to_vec())
+                    });
+
+                let _ = pipeline.set_state(gst::State::Null);
+
+                res
+            })
+            .await
+    }
+
+    /// Encode a video sample as an image file, with a one-shot pipeline
+    ///
+    /// Returns the size of the written file
+    async fn encode_to_file(
+        sample: gst::Sample,
+        path: PathBuf,
+        format: ImageFileFormat,
+    ) -> Result<u64, Error> {
+        let pipeline = gst::Pipeline::new(None);
+
+        let appsrc = make_element("appsrc", None)?;
+        let vconv = make_element("videoconvert", None)?;
+        let enc = match format {
+            ImageFileFormat::Jpeg => make_element("jpegenc", None)?,
+            ImageFileFormat::Png => make_element("pngenc", None)?,
+        };
+        let filesink = make_element("filesink", None)?;
+
+        pipeline.add_many(&[&appsrc, &vconv, &enc, &filesink])?;
+        gst::Element::link_many(&[&appsrc, &vconv, &enc, &filesink])?;
+
+        filesink
+            .set_property("location", &path.display().to_string())
+            .unwrap();
+        filesink.set_property("sync", &false).unwrap();
+
+        let appsrc = appsrc.downcast::<gst_app::AppSrc>().unwrap();
+
+        appsrc.set_format(gst::Format::Time);
+
+        pipeline
+            .call_async_future(move |pipeline| -> Result<u64, Error> {
+                pipeline.set_state(gst::State::Playing)?;
+
+                let res = appsrc
+                    .push_sample(&sample)
+                    .and_then(|_| appsrc.end_of_stream())
+                    .map_err(|err| anyhow!("Failed to push sample: {:?}", err))
+                    .and_then(|_| {
+                        // Bounded, in case the pipeline never reaches EOS
+                        let msg = pipeline
+                            .bus()
+                            .unwrap()
+                            .timed_pop_filtered(
+                                PREVIEW_TIMEOUT_SECS * gst::SECOND,
+                                &[gst::MessageType::Eos, gst::MessageType::Error],
+                            )
+                            .ok_or_else(|| anyhow!("Timed out writing {}", path.display()))?;
+
+                        match msg.view() {
+                            gst::MessageView::Error(err) => Err(anyhow!(
+                                "Failed to write {}: {}",
+                                path.display(),
+                                err.error()
+                            )),
+                            _ => Ok(std::fs::metadata(&path)?.len()),
+                        }
+                    });
+
+                let _ = pipeline.set_state(gst::State::Null);
+
+                res
+            })
+            .await
+    }
+
+    /// Add the processing elements of an input slot to the pipeline, and
+    /// link them to `compositor` and `audiomixer`
+    ///
+    /// Only the media types the slot was connected with are linked.
+    fn link_slot(
+        pipeline: &gst::Pipeline,
+        slot: &mut ConsumerSlot,
+        sample_rate: i32,
+        audio_format: &'static str,
+        channels: Option<i32>,
+    ) -> Result<(), Error> {
+        if let Some(ref video_pad) = slot.video_pad {
+            slot.video_bin = Some(Mixer::link_slot_video(
+                pipeline,
+                &slot.video_appsrc,
+                video_pad,
+                slot.border_radius,
+                slot.denoiser.as_ref(),
+                slot.label_overlay.as_ref(),
+            )?);
+        }
+
+        if let Some(ref audio_pad) = slot.audio_pad {
+            audio_pad.set_property("volume", &slot.volume).unwrap();
+
+            slot.audio_bin = Some(Mixer::link_slot_audio(
+                pipeline,
+                &slot.audio_appsrc,
+                audio_pad,
+                sample_rate,
+                audio_format,
+                channels,
+                slot.channel_map.as_ref(),
+                slot.allow_audio_format_change,
+                slot.compressor.as_ref(),
+            )?);
+        }
+
+        Ok(())
+    }
+
+    /// Build a denoiser for the video of a slot, `hqdn3d` is not part of
+    /// the core GStreamer plugin sets and may not be available
+    fn build_denoiser() -> Option<gst::Element> {
+        match make_element("hqdn3d", None) {
+            Ok(denoiser) => Some(denoiser),
+            Err(err) => {
+                warn!("Not denoising the slot: {}", err);
+                None
+            }
+        }
+    }
+
+    /// Build an overlay for labelling the video of a slot, empty until
+    /// its text is set
+    fn build_label_overlay() -> Option<gst::Element> {
+        match make_element("textoverlay", None) {
+            Ok(overlay) => {
+                overlay.set_property("text", &"").unwrap();
+                overlay.set_property_from_str("valign", "top");
+                overlay.set_property_from_str("halign", "left");
+                overlay.set_property("font-desc", &"Sans 16").unwrap();
+                overlay.set_property("color", &0xFFFFFFFFu32).unwrap();
+
+                Some(overlay)
+            }
+            Err(err) => {
+                warn!("Not labelling the slot: {}", err);
+                None
+            }
+        }
+    }
+
+    /// The `textoverlay` alignments for a `video::label-position`
+    fn parse_label_position(position: &str) -> Result<(&'static str, &'static str), Error> {
+        match position {
+            "top-left" => Ok(("top", "left")),
+            "top-right" => Ok(("top", "right")),
+            "bottom-left" => Ok(("bottom", "left")),
+            "bottom-right" => Ok(("bottom", "right")),
+            "center" => Ok(("center", "center")),
+            _ => Err(anyhow!(
+                "video::label-position must be one of top-left, top-right, bottom-left, bottom-right or center"
+            )),
+        }
+    }
+
+    /// The `video::label-position` for the alignments of a `textoverlay`
+    fn label_position(overlay: &gst::Element) -> String {
+        let valign = overlay.property("valign").unwrap().serialize().unwrap();
+        let halign = overlay.property("halign").unwrap().serialize().unwrap();
+
+        match (valign.as_str(), halign.as_str()) {
+            ("center", _) => "center".to_string(),
+            (valign, halign) => format!("{}-{}", valign, halign),
+        }
+    }
+
+    /// Parse a `video::label-color`, an ARGB hexadecimal string such as
+    /// 0xFFFFFFFF
+    fn parse_label_color(value: &serde_json::Value) -> Result<u32, Error> {
+        value
+            .as_str()
+            .and_then(|color| {
+                u32::from_str_radix(
+                    color
+                        .trim_start_matches("0x")
+                        .trim_start_matches("0X")
+                        .trim_start_matches('#'),
+                    16,
+                )
+                .ok()
+            })
+            .ok_or_else(|| anyhow!("video::label-color must be an ARGB hexadecimal string"))
+    }
+
+    /// Build a soft-knee compressor for the audio of a slot
+    fn build_compressor(threshold_db: f64) -> Result<gst::Element, Error> {
+        let compressor = make_element("audiodynamic", None)?;
+
+        compressor.set_property_from_str("mode", "compressor");
+        compressor.set_property_from_str("characteristics", "soft-knee");
+        // Expressed as a linear amplitude
+        compressor
+            .set_property("threshold", &(10f64.powf(threshold_db / 20.0) as f32))
+            .unwrap();
+
+        Ok(compressor)
+    }
+
+    /// Check the format of a slot `appsrc`, compositor and audiomixer
+    /// only accept buffers in time format
+    fn validate_appsrc_format(key: &str, value: &serde_json::Value) -> Result<(), Error> {
+        match value.as_str() {
+            Some("time") => Ok(()),
+            _ => Err(anyhow!("{} must be \"time\", got {}", key, value)),
+        }
+    }
+
+    /// Parse an `audio::channel-map` slot config value into a mix matrix
+    /// for our two output channels
+    fn parse_channel_map(value: &serde_json::Value) -> Result<Vec<Vec<f32>>, Error> {
+        if let Some(shorthand) = value.as_str() {
+            return match shorthand {
+                "left" => Ok(vec![vec![1.0, 0.0], vec![1.0, 0.0]]),
+                "right" => Ok(vec![vec![0.0, 1.0], vec![0.0, 1.0]]),
+                _ => Err(anyhow!(
+                    "audio::channel-map must be one of left, right, or a matrix"
+                )),
+            };
+        }
+
+        let matrix: Vec<Vec<f32>> = serde_json::from_value(value.clone())
+            .map_err(|err| anyhow!("Invalid audio::channel-map: {}", err))?;
+
+        if matrix.len() != 2 {
+            return Err(anyhow!(
+                "audio::channel-map must have one row per output channel (2)"
+            ));
+        }
+
+        if matrix[0].is_empty() || matrix[0].len() != matrix[1].len() {
+            return Err(anyhow!(
+                "audio::channel-map rows must have one column per input channel"
+            ));
+        }
+
+        Ok(matrix)
+    }
+
+    /// Check that an `audio::channel-map` has one column per channel of
+    /// its input, when the producer has already negotiated its caps
+    fn validate_channel_map_input(
+        channel_map: &[Vec<f32>],
+        producer: &StreamProducer,
+    ) -> Result<(), Error> {
+        let channels = producer
+            .appsink()
+            .static_pad("sink")
+            .and_then(|pad| pad.current_caps())
+            .and_then(|caps| {
+                caps.structure(0)
+                    .and_then(|s| s.get::<i32>("channels").ok())
+            });
+
+        match channels {
+            Some(channels) if channels as usize != channel_map[0].len() => Err(anyhow!(
+                "audio::channel-map has {} columns, but the input has {} channels",
+                channel_map[0].len(),
+                channels
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /// Build a converter for the video of an input slot, only applying the
+    /// matrix of its input colorimetry, `compositor` converts to that of
+    /// our output
+    fn make_slot_videoconvert() -> Result<gst::Element, Error> {
+        let vconv = make_element("videoconvert", None)?;
+
+        vconv.set_property_from_str("matrix-mode", "input-only");
+
+        Ok(vconv)
+    }
+
+    /// Build the video processing elements of an input slot, and link
+    /// them to their `compositor` pad
+    fn link_slot_video(
+        pipeline: &gst::Pipeline,
+        appsrc: &gst_app::AppSrc,
+        mixer_pad: &gst::Pad,
+        border_radius: u32,
+        denoiser: Option<&gst::Element>,
+        label_overlay: Option<&gst::Element>,
+    ) -> Result<gst::Bin, Error> {
+        let video_bin = gst::Bin::new(None);
+        let vqueue = make_element("queue", None)?;
+        let vappsrc_elem: &gst::Element = appsrc.upcast_ref();
+
+        video_bin.add_many(&[vappsrc_elem, &vqueue])?;
+
+        let mut video_elements = vec![vappsrc_elem.clone()];
+
+        if let Some(denoiser) = denoiser {
+            let vconv = Mixer::make_slot_videoconvert()?;
+
+            video_bin.add_many(&[&vconv, denoiser])?;
+            video_elements.push(vconv);
+            video_elements.push(denoiser.clone());
+        }
+
+        if border_radius > 0 {
+            match make_element("roundedcorners", None) {
+                Ok(roundedcorners) => {
+                    let vconv = Mixer::make_slot_videoconvert()?;
+
+                    roundedcorners
+                        .set_property("border-radius-px", &border_radius)
+                        .unwrap();
+
+                    video_bin.add_many(&[&vconv, &roundedcorners])?;
+                    video_elements.push(vconv);
+                    video_elements.push(roundedcorners);
+                }
+                Err(err) => {
+                    warn!("Not rounding the corners of the slot: {}", err);
+                }
+            }
+        }
+
+        video_elements.push(vqueue);
+
+        if let Some(label_overlay) = label_overlay {
+            video_bin.add(label_overlay)?;
+            video_elements.push(label_overlay.clone());
+        }
+
+        pipeline.add(&video_bin)?;
+
+        let last = video_elements.last().unwrap();
+        let ghost =
+            gst::GhostPad::with_target(Some("src"), &last.static_pad("src").unwrap()).unwrap();
+        video_bin.add_pad(&ghost).unwrap();
+
+        gst::Element::link_many(&video_elements.iter().collect::<Vec<_>>())?;
+
+        let srcpad = video_bin.static_pad("src").unwrap();
+        srcpad.link(mixer_pad).unwrap();
+
+        Ok(video_bin)
+    }
+
+    /// Build the audio processing elements of an input slot, and link
+    /// them to their `audiomixer` pad
+    fn link_slot_audio(
+        pipeline: &gst::Pipeline,
+        appsrc: &gst_app::AppSrc,
+        mixer_pad: &gst::Pad,
+        sample_rate: i32,
+        audio_format: &str,
+        channels: Option<i32>,
+        channel_map: Option<&Vec<Vec<f32>>>,
+        allow_format_change: bool,
+        compressor: Option<&gst::Element>,
+    ) -> Result<gst::Bin, Error> {
+        let audio_bin = gst::Bin::new(None);
+
+        let aconv = make_element("audioconvert", None)?;
+
+        // Channel maps are expressed for a stereo mix, they feed the front
+        // left and right channels of wider ones
+        let channel_map = channel_map.map(|channel_map| {
+            let mut channel_map = channel_map.clone();
+
+            if let Some(channels) = channels.filter(|channels| *channels > 2) {
+                let n_inputs = channel_map[0].len();
+                channel_map.resize(channels as usize, vec![0.0; n_inputs]);
+            }
+
+            channel_map
+        });
+
+        if let Some(ref channel_map) = channel_map {
+            aconv
+                .set_property("mix-matrix", &Mixer::mix_matrix(channel_map))
+                .unwrap();
+        }
+
+        // Operates in passthrough, without touching the samples, when the
+        // input already has the sample rate of the mixer
+        let aresample = make_element("audioresample", None)?;
+        let acapsfilter = make_element("capsfilter", None)?;
+        let aqueue = make_element("queue", None)?;
+        let compressor_conv = make_element("audioconvert", None)?;
+
+        acapsfilter
+            .set_property(
+                "caps",
+                &Mixer::raw_audio_caps(audio_format, sample_rate, channels),
+            )
+            .unwrap();
+
+        let aappsrc_elem: &gst::Element = appsrc.upcast_ref();
+
+        let mut audio_elements = vec![aappsrc_elem, &aconv, &aresample, &acapsfilter];
+
+        // audiodynamic only handles S16 and F32, deeper samples are
+        // converted ahead of it, and back to our format by audiomixer
+        if let Some(compressor) = compressor {
+            if audio_format != "S16LE" {
+                audio_elements.push(&compressor_conv);
+            }
+            audio_elements.push(compressor);
+        }
+
+        audio_elements.push(&aqueue);
+
+        audio_bin.add_many(&audio_elements)?;
+
+        pipeline.add(&audio_bin)?;
+
+        let ghost =
+            gst::GhostPad::with_target(Some("src"), &aqueue.static_pad("src").unwrap()).unwrap();
+        audio_bin.add_pad(&ghost).unwrap();
+
+        gst::Element::link_many(&audio_elements)?;
+
+        let srcpad = audio_bin.static_pad("src").unwrap();
+        srcpad.link(mixer_pad).unwrap();
+
+        if allow_format_change {
+            Mixer::track_audio_format_changes(&aconv, &acapsfilter, channel_map);
+        }
+
+        Ok(audio_bin)
+    }
+
+    /// The `audioconvert` mix matrix for an `audio::channel-map`
+    fn mix_matrix(channel_map: &[Vec<f32>]) -> gst::Array {
+        let rows = channel_map
+            .iter()
+            .map(|row| {
+                gst::Array::from(
+                    row.iter()
+                        .map(|coefficient| coefficient.to_send_value())
+                        .collect::<Vec<_>>(),
+                )
+                .to_send_value()
+            })
+            .collect::<Vec<_>>();
+
+        gst::Array::from(rows)
+    }
+
+    /// Renegotiate the audio conversion of a slot when the format of its
+    /// input changes mid-stream
+    ///
+    /// The caps of `acapsfilter` remain those of `audiomixer`, which
+    /// requires all its inputs in the same format: `audioconvert` and
+    /// `audioresample` are made to negotiate anew with a reconfigure
+    /// event instead. A mix matrix is only applied while it matches the
+    /// number of input channels, the input is downmixed automatically
+    /// otherwise.
+    fn track_audio_format_changes(
+        aconv: &gst::Element,
+        acapsfilter: &gst::Element,
+        channel_map: Option<Vec<Vec<f32>>>,
+    ) {
+        let aconv_weak = aconv.downgrade();
+        let acapsfilter = acapsfilter.downgrade();
+        let current_caps: Mutex<Option<gst::Caps>> = Mutex::new(None);
+
+        aconv.static_pad("sink").unwrap().add_probe(
+            gst::PadProbeType::EVENT_DOWNSTREAM,
+            move |_pad, info| {
+                let caps = match info.data {
+                    Some(gst::PadProbeData::Event(ref ev)) => match ev.view() {
+                        gst::EventView::Caps(caps) => caps.caps_owned(),
+                        _ => return gst::PadProbeReturn::Ok,
+                    },
+                    _ => return gst::PadProbeReturn::Ok,
+                };
+
+                let previous = match current_caps.lock().unwrap().replace(caps.clone()) {
+                    Some(previous) if previous != caps => previous,
+                    _ => return gst::PadProbeReturn::Ok,
+                };
+
+                let (aconv, acapsfilter) = match (aconv_weak.upgrade(), acapsfilter.upgrade()) {
+                    (Some(aconv), Some(acapsfilter)) => (aconv, acapsfilter),
+                    _ => return gst::PadProbeReturn::Ok,
+                };
+
+                debug!(element = %aconv.name(), "Audio format changed from {} to {}", previous, caps);
+
+                if let Some(ref channel_map) = channel_map {
+                    let n_channels = channel_map[0].len();
+                    let channels = caps
+                        .structure(0)
+                        .and_then(|s| s.get::<i32>("channels").ok());
+
+                    if channels == Some(n_channels as i32) {
+                        aconv
+                            .set_property("mix-matrix", &Mixer::mix_matrix(channel_map))
+                            .unwrap();
+                    } else {
+                        warn!(element = %aconv.name(), "Input no longer has {} channels, downmixing automatically", n_channels);
+                        aconv
+                            .set_property(
+                                "mix-matrix",
+                                &gst::Array::from(Vec::<gst::glib::SendValue>::new()),
+                            )
+                            .unwrap();
+                    }
+                }
+
+                let _ = acapsfilter
+                    .static_pad("sink")
+                    .unwrap()
+                    .push_event(gst::event::Reconfigure::new());
+
+                gst::PadProbeReturn::Ok
+            },
+        );
+    }
+
+    /// Whether `pad` mixes in the placeholder of a late slot
+    fn is_placeholder_pad(late_starts: &HashMap<String, LateStart>, pad: &gst::Pad) -> bool {
+        late_starts.values().any(|late_start| match late_start {
+            LateStart::Freeze {
+                pad: placeholder_pad,
+                ..
+            } => placeholder_pad == pad,
+            LateStart::Blank { .. } => false,
+        })
+    }
+
+    /// Show a late slot as usual once it has received its first buffer
+    fn end_late_start(agg: &gst_base::Aggregator, slot_pad: &gst::Pad, late_start: LateStart) {
+        debug!(pad = %slot_pad.name(), "late slot received its first buffer");
+
+        match late_start {
+            LateStart::Freeze { placeholder, pad } => {
+                pad.set_property("alpha", &0.0f64).unwrap();
+
+                // Not from the aggregating thread
+                agg.call_async(move |agg| {
+                    let _ = placeholder.set_state(gst::State::Null);
+
+                    if let Some(bin) = placeholder
+                        .parent()
+                        .and_then(|parent| parent.downcast::<gst::Bin>().ok())
+                    {
+                        let _ = bin.remove(&placeholder);
+                    }
+
+                    agg.release_request_pad(&pad);
+                });
+            }
+            LateStart::Blank { alpha } => {
+                slot_pad.set_property("alpha", &alpha).unwrap();
+            }
+        }
+    }
+
+    /// Prepare showing a slot until its first video buffer arrives,
+    /// according to its `video::late-start-mode`
+    ///
+    /// The black placeholder of the freeze mode covers the area of the
+    /// slot, or the whole output when the slot is not resized.
+    fn start_late_slot(
+        &self,
+        slot: &ConsumerSlot,
+        width: i32,
+        height: i32,
+    ) -> Result<Option<LateStart>, Error> {
+        let slot_pad = match slot.video_pad {
+            Some(ref pad) => pad,
+            None => return Ok(None),
+        };
+
+        match slot.late_start_mode {
+            LateStartMode::Mute => Ok(None),
+            LateStartMode::Blank => {
+                let alpha = slot_pad.property("alpha").unwrap().get::<f64>().unwrap();
+
+                slot_pad.set_property("alpha", &0.0f64).unwrap();
+
+                Ok(Some(LateStart::Blank { alpha }))
+            }
+            LateStartMode::Freeze => {
+                let size = |property: &str, default: i32| match slot_pad
+                    .property(property)
+                    .unwrap()
+                    .get::<i32>()
+                    .unwrap()
+                {
+                    size if size > 0 => size,
+                    _ => default,
+                };
+
+                let bin = gst::Bin::new(None);
+                let vsrc = make_element("videotestsrc", None)?;
+                let capsfilter = make_element("capsfilter", None)?;
+
+                vsrc.set_property("is-live", &true).unwrap();
+                vsrc.set_property_from_str("pattern", "black");
+                capsfilter
+                    .set_property(
+                        "caps",
+                        &gst::Caps::builder("video/x-raw")
+                            .field("width", &size("width", width))
+                            .field("height", &size("height", height))
+                            .build(),
+                    )
+                    .unwrap();
+
+                bin.add_many(&[&vsrc, &capsfilter])?;
+                vsrc.link(&capsfilter)?;
+
+                let ghost =
+                    gst::GhostPad::with_target(Some("src"), &capsfilter.static_pad("src").unwrap())
+                        .unwrap();
+                bin.add_pad(&ghost).unwrap();
+
+                self.pipeline.add(&bin)?;
+
+                let pad = self
+                    .video_mixer
+                    .request_pad_simple("sink_%u")
+                    .ok_or_else(|| anyhow!("Failed to request placeholder pad from compositor"))?;
+
+                for property in &["xpos", "ypos", "zorder"] {
+                    pad.set_property(property, &slot_pad.property(property).unwrap())
+                        .unwrap();
+                }
+
+                bin.static_pad("src").unwrap().link(&pad)?;
+
+                Ok(Some(LateStart::Freeze {
+                    placeholder: bin.upcast(),
+                    pad,
+                }))
+            }
+        }
+    }
+
+    /// Whether `name` matches `pattern`, where `*` matches any sequence
+    /// of characters and `?` any single character
+    fn wildcard_match(pattern: &[char], name: &[char]) -> bool {
+        match (pattern.first(), name.first()) {
+            (None, None) => true,
+            (Some('*'), _) => {
+                Mixer::wildcard_match(&pattern[1..], name)
+                    || (!name.is_empty() && Mixer::wildcard_match(pattern, &name[1..]))
+            }
+            (Some('?'), Some(_)) => Mixer::wildcard_match(&pattern[1..], &name[1..]),
+            (Some(p), Some(n)) if p == n => Mixer::wildcard_match(&pattern[1..], &name[1..]),
+            _ => false,
+        }
+    }
+
+    /// List the images of a slideshow, `location` is either a directory,
+    /// in which case all of its files are listed, or a path whose file name
+    /// may contain wildcards. Images are sorted by path.
+    fn slideshow_images(location: &str) -> Result<Vec<PathBuf>, Error> {
+        let path = std::path::Path::new(location);
+
+        let (dir, pattern) = if path.is_dir() {
+            (path, None)
+        } else {
+            let pattern: Vec<char> = path
+                .file_name()
+                .ok_or_else(|| anyhow!("Invalid slideshow location {}", location))?
+                .to_string_lossy()
+                .chars()
+                .collect();
+            let dir = match path.parent() {
+                Some(parent) if !parent.as_os_str().is_empty() => parent,
+                _ => std::path::Path::new("."),
+            };
+            (dir, Some(pattern))
+        };
+
+        let mut images = vec![];
+
+        for entry in std::fs::read_dir(dir)? {
+            let entry = entry?;
+
+            if !entry.file_type()?.is_file() {
+                continue;
+            }
+
+            if let Some(ref pattern) = pattern {
+                let name: Vec<char> = entry.file_name().to_string_lossy().chars().collect();
+
+                if !Mixer::wildcard_match(pattern, &name) {
+                    continue;
+                }
+            }
+
+            images.push(entry.path());
+        }
+
+        if images.is_empty() {
+            return Err(anyhow!("No slideshow images found at {}", location));
+        }
+
+        images.sort();
+
+        Ok(images)
+    }
+
+    /// Build the base plate. It may be either a live videotestsrc, an
+    /// imagefreeze'd image when a fallback image was specified, or a
+    /// slideshow when a fallback slideshow was specified
+    #[instrument(level = "debug", name = "building base plate", skip(self), fields(id = %self.id))]
+    fn build_base_plate(&mut self, width: i32, height: i32) -> Result<gst::Element, Error> {
+        let bin = gst::Bin::new(None);
+        let fallback_image = self
+            .setting("fallback-image")
+            .unwrap()
+            .as_str()
+            .unwrap()
+            .to_string();
+        let fallback_slideshow = self
+            .setting("fallback-slideshow")
+            .unwrap()
+            .as_str()
+            .unwrap()
+            .to_string();
+
+        if !fallback_image.is_empty() && !fallback_slideshow.is_empty() {
+            return Err(anyhow!(
+                "fallback-image and fallback-slideshow are mutually exclusive"
+            ));
+        }
+
+        let ghost = match (fallback_image.as_str(), fallback_slideshow.as_str()) {
+            ("", "") => {
+                let vsrc = make_element("videotestsrc", None)?;
+                vsrc.set_property("is-live", &true).unwrap();
+                vsrc.set_property_from_str("pattern", "black");
+
+                bin.add(&vsrc)?;
+
+                gst::GhostPad::with_target(Some("src"), &vsrc.static_pad("src").unwrap()).unwrap()
+            }
+            ("", _) => {
+                // multifilesrc would only handle numbered file names, and
+                // would push images at its own pace, we feed the images
+                // ourselves instead so that the slideshow only advances
+                // while the base plate is shown
+                let images = Mixer::slideshow_images(&fallback_slideshow)?
+                    .iter()
+                    .map(|path| std::fs::read(path).map(gst::Buffer::from_mut_slice))
+                    .collect::<Result<Vec<gst::Buffer>, std::io::Error>>()?;
+                let interval = self
+                    .setting("fallback-slideshow-interval")
+                    .unwrap()
+                    .as_i32()
+                    .unwrap() as u64;
+
+                debug!(n_images = images.len(), "building slideshow");
+
+                let appsrc = make_element("appsrc", None)?
+                    .downcast::<gst_app::AppSrc>()
+                    .unwrap();
+                let decodebin = make_element("decodebin3", None)?;
+                let vconv = make_element("videoconvert", None)?;
+                let imagefreeze = make_element("imagefreeze", None)?;
+
+                imagefreeze.set_property("is-live", &true).unwrap();
+                imagefreeze.set_property("allow-replace", &true).unwrap();
+
+                bin.add_many(&[appsrc.upcast_ref(), &decodebin, &vconv, &imagefreeze])?;
+
+                let vconv_clone = vconv.downgrade();
+                decodebin.connect_pad_added(move |_bin, pad| {
+                    if let Some(vconv) = vconv_clone.upgrade() {
+                        let sinkpad = vconv.static_pad("sink").unwrap();
+                        pad.link(&sinkpad).unwrap();
+                    }
+                });
+
+                appsrc.link(&decodebin)?;
+                vconv.link(&imagefreeze)?;
+
+                let mut slideshow = Slideshow {
+                    appsrc,
+                    images,
+                    next: 0,
+                    interval: interval * gst::MSECOND,
+                    shown_since: gst::CLOCK_TIME_NONE,
+                };
+
+                // Queue up the first image, the next ones are pushed from
+                // our samples_selected callback
+                slideshow.show_next();
+
+                self.video_mixing_state.lock().unwrap().slideshow = Some(slideshow);
+
+                gst::GhostPad::with_target(Some("src"), &imagefreeze.static_pad("src").unwrap())
+                    .unwrap()
+            }
+            _ => {
+                let filesrc = make_element("filesrc", None)?;
+                let decodebin = make_element("decodebin3", None)?;
+                let vconv = make_element("videoconvert", None)?;
+                let imagefreeze = make_element("imagefreeze", None)?;
+
+                filesrc.set_property("location", fallback_image).unwrap();
+                imagefreeze.set_property("is-live", &true).unwrap();
+
+                bin.add_many(&[&filesrc, &decodebin, &imagefreeze, &vconv])?;
+
+                let imagefreeze_clone = imagefreeze.downgrade();
+                decodebin.connect_pad_added(move |_bin, pad| {
+                    if let Some(imagefreeze) = imagefreeze_clone.upgrade() {
+                        let sinkpad = imagefreeze.static_pad("sink").unwrap();
+                        pad.link(&sinkpad).unwrap();
+                    }
+                });
+
+                filesrc.link(&decodebin)?;
+
+                gst::GhostPad::with_target(Some("src"), &imagefreeze.static_pad("src").unwrap())
+                    .unwrap()
+            }
+        };
+
+        bin.add_pad(&ghost).unwrap();
+
+        Ok(bin.upcast())
+    }
+
+    /// Layer `idle-graphic` above our base plate, through a dedicated
+    /// `compositor` pad, hidden until we fall back to the base plate
+    #[instrument(level = "debug", name = "adding idle graphic", skip(self), fields(id = %self.id))]
+    fn add_idle_graphic(&self, location: &str, width: i32, height: i32) -> Result<gst::Pad, Error> {
+        let bin = gst::Bin::new(None);
+        let filesrc = make_element("filesrc", None)?;
+        let decodebin = make_element("decodebin3", None)?;
+        let vconv = make_element("videoconvert", None)?;
+        let imagefreeze = make_element("imagefreeze", None)?;
+
+        filesrc.set_property("location", location).unwrap();
+        imagefreeze.set_property("is-live", &true).unwrap();
+
+        bin.add_many(&[&filesrc, &decodebin, &vconv, &imagefreeze])?;
+
+        let vconv_clone = vconv.downgrade();
+        decodebin.connect_pad_added(move |_bin, pad| {
+            if let Some(vconv) = vconv_clone.upgrade() {
+                let sinkpad = vconv.static_pad("sink").unwrap();
+                pad.link(&sinkpad).unwrap();
+            }
+        });
+
+        filesrc.link(&decodebin)?;
+        vconv.link(&imagefreeze)?;
+
+        let ghost =
+            gst::GhostPad::with_target(Some("src"), &imagefreeze.static_pad("src").unwrap())
+                .unwrap();
+        bin.add_pad(&ghost).unwrap();
+
+        self.pipeline.add(&bin)?;
+
+        let pad = self
+            .video_mixer
+            .request_pad_simple("sink_%u")
+            .ok_or_else(|| anyhow!("Failed to request idle graphic pad from compositor"))?;
+
+        // Same zorder as the base plate, compositor keeps the pads with
+        // equal zorders in their request order: above the base plate,
+        // below the slots
+        pad.set_property("zorder", &0u32).unwrap();
+        pad.set_property("alpha", &0.0f64).unwrap();
+        pad.set_property("width", &width).unwrap();
+        pad.set_property("height", &height).unwrap();
+        pad.set_property_from_str("sizing-policy", "keep-aspect-ratio");
+
+        ghost.link(&pad)?;
+
+        Ok(pad)
+    }
+
+    /// Overlay `watermark-image` on top of our output, through a
+    /// dedicated `compositor` pad
+    #[instrument(level = "debug", name = "adding watermark", skip(self), fields(id = %self.id))]
+    fn add_watermark(&self, location: &str) -> Result<gst::Pad, Error> {
+        let x = self.setting("watermark-x").unwrap().as_i32().unwrap();
+        let y = self.setting("watermark-y").unwrap().as_i32().unwrap();
+        let alpha = self.setting("watermark-alpha").unwrap().as_f64().unwrap();
+        let scale = self.setting("watermark-scale").unwrap().as_f64().unwrap();
+
+        let bin = gst::Bin::new(None);
+        let filesrc = make_element("filesrc", None)?;
+        let decodebin = make_element("decodebin3", None)?;
+        let vconv = make_element("videoconvert", None)?;
+        let imagefreeze = make_element("imagefreeze", None)?;
+
+        filesrc.set_property("location", location).unwrap();
+        imagefreeze.set_property("is-live", &true).unwrap();
+
+        bin.add_many(&[&filesrc, &decodebin, &vconv, &imagefreeze])?;
+
+        filesrc.link(&decodebin)?;
+        vconv.link(&imagefreeze)?;
+
+        let ghost =
+            gst::GhostPad::with_target(Some("src"), &imagefreeze.static_pad("src").unwrap())
+                .unwrap();
+        bin.add_pad(&ghost).unwrap();
+
+        self.pipeline.add(&bin)?;
+
+        let pad = self
+            .video_mixer
+            .request_pad_simple("sink_%u")
+            .ok_or_else(|| anyhow!("Failed to request watermark pad from compositor"))?;
+
+        // Always on top of the slots
+        pad.set_property("zorder", &u32::MAX).unwrap();
+        pad.set_property("xpos", &x).unwrap();
+        pad.set_property("ypos", &y).unwrap();
+        pad.set_property("alpha", &alpha).unwrap();
+
+        // The size of the image is only known once it is decoded
+        let vconv_clone = vconv.downgrade();
+        let pad_clone = pad.downgrade();
+        decodebin.connect_pad_added(move |_bin, src_pad| {
+            if let (Some(vconv), Some(pad)) = (vconv_clone.upgrade(), pad_clone.upgrade()) {
+                if let Some(s) = src_pad
+                    .current_caps()
+                    .as_ref()
+                    .and_then(|caps| caps.structure(0))
+                {
+                    if let (Ok(width), Ok(height)) = (s.get::<i32>("width"), s.get::<i32>("height"))
+                    {
+                        let width = (width as f64 * scale).round() as i32;
+                        let height = (height as f64 * scale).round() as i32;
+                        pad.set_property("width", &width).unwrap();
+                        pad.set_property("height", &height).unwrap();
+                    }
+                }
+
+                let sinkpad = vconv.static_pad("sink").unwrap();
+                src_pad.link(&sinkpad).unwrap();
+            }
+        });
+
+        ghost.link(&pad)?;
+
+        Ok(pad)
+    }
+
+    /// Update slot controllers
+    #[instrument(
+        name = "Updating audio mixing state",
+        level = "trace",
+        skip(mixing_state)
+    )]
+    fn update_audio_mixing_state(
+        agg: &gst_base::Aggregator,
+        id: &str,
+        pts: gst::ClockTime,
+        mixing_state: &mut AudioMixingState,
+        timeout: gst::ClockTime,
+    ) {
+        if let Some(base_signal_pad) = mixing_state.base_signal_pad.clone() {
+            Mixer::update_base_signal(agg, &base_signal_pad, pts, mixing_state, timeout);
+        }
+
+        for pad in agg.sink_pads() {
+            if Some(&pad) == mixing_state.base_signal_pad.as_ref() {
+                continue;
+            }
+
+            let agg_pad: &gst_base::AggregatorPad = pad.downcast_ref().unwrap();
+            if let Some(pts) = agg
+                .peek_next_sample(agg_pad)
+                .and_then(|sample| sample.buffer().and_then(|buffer| buffer.pts().nseconds()))
+            {
+                mixing_state
+                    .selected_pts
+                    .insert(pad.name().to_string(), pts);
+            }
+        }
+
+        let duration = if mixing_state.last_pts.is_none() {
+            gst::CLOCK_TIME_NONE
+        } else {
+            pts - mixing_state.last_pts
+        };
+        let now = mixing_state.control_timing.now(pts);
+
+        mixing_state.slot_controllers = Some(Mixer::synchronize_slot_controllers(
+            agg,
+            id,
+            now,
+            duration,
+            &mut mixing_state.slot_controllers.take().unwrap(),
+        ));
+
+        mixing_state.mixer_controllers = Some(Mixer::synchronize_audio_mixer_controllers(
+            now,
+            duration,
+            &mut mixing_state.mixer_controllers.take().unwrap(),
+            &mixing_state.equalizer,
+        ));
+
+        mixing_state.last_pts = pts;
+    }
+
+    /// Make our base signal audible once no other pad has selected
+    /// samples for `timeout`, and mute it as soon as one does, as we do
+    /// with our video base plate
+    fn update_base_signal(
+        agg: &gst_base::Aggregator,
+        base_signal_pad: &gst::Pad,
+        pts: gst::ClockTime,
+        mixing_state: &mut AudioMixingState,
+        timeout: gst::ClockTime,
+    ) {
+        let base_signal_only = agg
+            .sink_pads()
+            .iter()
+            .filter(|pad| *pad != base_signal_pad)
+            .all(|pad| {
+                agg.peek_next_sample(pad.downcast_ref::<gst_base::AggregatorPad>().unwrap())
+                    .is_none()
+            });
+
+        if base_signal_only {
+            if mixing_state.base_signal_timeout.is_none() {
+                mixing_state.base_signal_timeout = pts;
+            } else if !mixing_state.sounding_base_signal
+                && pts - mixing_state.base_signal_timeout > timeout
+            {
+                debug!("falling back to base signal {:?}", base_signal_pad);
+                base_signal_pad.set_property("volume", &1.0f64).unwrap();
+                mixing_state.sounding_base_signal = true;
+            }
+        } else {
+            if mixing_state.sounding_base_signal {
+                debug!("muting base signal: {:?}", base_signal_pad);
+                base_signal_pad.set_property("volume", &0.0f64).unwrap();
+                mixing_state.sounding_base_signal = false;
+            }
+            mixing_state.base_signal_timeout = gst::CLOCK_TIME_NONE;
+        }
+    }
+
+    /// Whether a setting is controlled from our `audiomixer` callback
+    fn is_audio_setting(name: &str) -> bool {
+        name.starts_with("audio-eq-")
+    }
+
+    /// Build an equalizer for our audio output, with a low shelf, a
+    /// peak and a high shelf band
+    fn build_equalizer(&self) -> Result<gst::Element, Error> {
+        let low_freq = self
+            .setting("audio-eq-low-freq-hz")
+            .unwrap()
+            .as_f64()
+            .unwrap();
+        let high_freq = self
+            .setting("audio-eq-high-freq-hz")
+            .unwrap()
+            .as_f64()
+            .unwrap();
+
+        if low_freq >= high_freq {
+            return Err(anyhow!(
+                "audio-eq-low-freq-hz ({}) must be lower than audio-eq-high-freq-hz ({})",
+                low_freq,
+                high_freq
+            ));
+        }
+
+        // The first and last bands are shelves by default
+        let equalizer = make_element("equalizer-nbands", None)?;
+        equalizer.set_property("num-bands", &3u32).unwrap();
+
+        let mid_freq = (low_freq * high_freq).sqrt();
+
+        for (index, (freq, bandwidth, gain)) in [
+            (low_freq, low_freq, "audio-eq-low-gain-db"),
+            (mid_freq, mid_freq, "audio-eq-mid-gain-db"),
+            (high_freq, high_freq, "audio-eq-high-gain-db"),
+        ]
+        .iter()
+        .enumerate()
+        {
+            let band = Mixer::equalizer_band(&equalizer, index as u32);
+            let gain = self.setting(gain).unwrap().as_f64().unwrap();
+
+            band.set_property("freq", freq).unwrap();
+            band.set_property("bandwidth", bandwidth).unwrap();
+            band.set_property("gain", &gain).unwrap();
+        }
+
+        Ok(equalizer)
+    }
+
+    /// Get a band of an `equalizer-nbands`
+    fn equalizer_band(equalizer: &gst::Element, index: u32) -> gst::glib::Object {
+        equalizer
+            .dynamic_cast_ref::<gst::ChildProxy>()
+            .unwrap()
+            .child_by_index(index)
+            .unwrap()
+    }
+
+    #[instrument(
+        name = "synchronizing audio mixer controllers",
+        level = "trace",
+        skip(controllers, equalizer)
+    )]
+    fn synchronize_audio_mixer_controllers(
+        now: DateTime<Utc>,
+        duration: gst::ClockTime,
+        controllers: &mut HashMap<String, SettingController>,
+        equalizer: &Option<gst::Element>,
+    ) -> HashMap<String, SettingController> {
+        let mut updated_controllers = HashMap::new();
+
+        for (id, mut controller) in controllers.drain() {
+            let setting = controller.setting.clone();
+
+            if !controller.synchronize(now, duration) {
+                updated_controllers.insert(id.clone(), controller);
+            }
+
+            if let Some(equalizer) = equalizer {
+                let index = match id.as_str() {
+                    "audio-eq-low-gain-db" => 0,
+                    "audio-eq-mid-gain-db" => 1,
+                    "audio-eq-high-gain-db" => 2,
+                    _ => continue,
+                };
+
+                let gain = setting.lock().unwrap().as_f64().unwrap();
+                Mixer::equalizer_band(equalizer, index)
+                    .set_property("gain", &gain)
+                    .unwrap();
+            }
+        }
+
+        updated_controllers
+    }
+
+    #[instrument(
+        name = "synchronizing slot controllers",
+        level = "trace",
+        skip(controllers)
+    )]
+    fn synchronize_slot_controllers(
+        agg: &gst_base::Aggregator,
+        id: &str,
+        now: DateTime<Utc>,
+        duration: gst::ClockTime,
+        controllers: &mut HashMap<String, PropertyController>,
+    ) -> HashMap<String, PropertyController> {
+        let mut updated_controllers = HashMap::new();
+
+        for (id, mut controller) in controllers.drain() {
+            if !controller.synchronize(now, duration) {
+                updated_controllers.insert(id, controller);
+            }
+        }
+
+        updated_controllers
+    }
+
+    #[instrument(
+        name = "synchronizing mixer controllers",
+        level = "trace",
+        skip(controllers)
+    )]
+    fn synchronize_mixer_controllers(
+        agg: &gst_base::Aggregator,
+        base_plate_pads: &[gst::Pad],
+        id: &str,
+        now: DateTime<Utc>,
+        duration: gst::ClockTime,
+        controllers: &mut HashMap<String, SettingController>,
+        capsfilter: &Option<gst::Element>,
+        output_pads: &[gst::Pad],
+        watermark_pad: &Option<gst::Pad>,
+    ) -> HashMap<String, SettingController> {
+        let mut updated_controllers = HashMap::new();
+        let mut caps = capsfilter.as_ref().map(|capsfilter| {
+            capsfilter
+                .property("caps")
+                .unwrap()
+                .get::<gst::Caps>()
+                .unwrap()
+        });
+
+        for (id, mut controller) in controllers.drain() {
+            let setting = controller.setting.clone();
+
+            if !controller.synchronize(now, duration) {
+                updated_controllers.insert(id.clone(), controller);
+            }
+            if let Some(ref mut caps) = caps {
+                if id == "width" {
+                    let width = setting.lock().unwrap().as_i32().unwrap();
+                    caps.make_mut().set_simple(&[("width", &width)]);
+                    for pad in base_plate_pads {
+                        pad.set_property("width", &width).unwrap();
+                    }
+                } else if id == "height" {
+                    let height = setting.lock().unwrap().as_i32().unwrap();
+                    caps.make_mut().set_simple(&[("height", &height)]);
+                    for pad in base_plate_pads {
+                        pad.set_property("height", &height).unwrap();
+                    }
+                }
+            }
+
+            if id == "output-pts-offset-ns" {
+                let offset = setting.lock().unwrap().as_i64().unwrap();
+                for pad in output_pads {
+                    pad.set_offset(offset);
+                }
+            }
+
+            if let Some(watermark_pad) = watermark_pad {
+                if id == "watermark-x" {
+                    let x = setting.lock().unwrap().as_i32().unwrap();
+                    watermark_pad.set_property("xpos", &x).unwrap();
+                } else if id == "watermark-y" {
+                    let y = setting.lock().unwrap().as_i32().unwrap();
+                    watermark_pad.set_property("ypos", &y).unwrap();
+                } else if id == "watermark-alpha" {
+                    let alpha = setting.lock().unwrap().as_f64().unwrap();
+                    watermark_pad.set_property("alpha", &alpha).unwrap();
+                }
+            }
+        }
+
+        if let Some(capsfilter) = capsfilter {
+            capsfilter.set_property("caps", &caps.unwrap()).unwrap();
+        }
+
+        updated_controllers
+    }
+
+    /// Whether `pad` belongs to a priority slot that had a sample selected
+    /// within its fallback timeout
+    fn is_priority_slot_active(
+        mixing_state: &VideoMixingState,
+        pad: &gst::Pad,
+        pts: gst::ClockTime,
+        timeout: gst::ClockTime,
+    ) -> bool {
+        let name = pad.name();
+
+        if !mixing_state.slot_priorities.contains_key(name.as_str()) {
+            return false;
+        }
+
+        let timeout = mixing_state
+            .slot_timeouts
+            .get(name.as_str())
+            .copied()
+            .unwrap_or(timeout);
+
+        match (
+            pts.nseconds(),
+            mixing_state.priority_selected_pts.get(name.as_str()),
+            timeout.nseconds(),
+        ) {
+            (Some(pts), Some(selected_pts), Some(timeout)) => {
+                pts.saturating_sub(*selected_pts) <= timeout
+            }
+            _ => false,
+        }
+    }
+
+    /// Show or hide our base plate, update slot controllers
+    #[instrument(
+        name = "Updating video mixing state",
+        level = "trace",
+        skip(mixing_state)
+    )]
+    fn update_video_mixing_state(
+        agg: &gst_base::Aggregator,
+        id: &str,
+        pts: gst::ClockTime,
+        mixing_state: &mut VideoMixingState,
+        timeout: gst::ClockTime,
+    ) {
+        let mut base_plate_only = true;
+        let mut selected_timeout: Option<gst::ClockTime> = None;
+
+        let base_plate_pad = agg.static_pad("sink_0").unwrap();
+        // Our base plate along with the layers shown and hidden with it
+        let base_plate_pads = std::iter::once(base_plate_pad.clone())
+            .chain(mixing_state.idle_graphic_pad.clone())
+            .collect::<Vec<_>>();
+
+        if let Some(zorders) = mixing_state.pending_zorders.take() {
+            Mixer::apply_zorders(&zorders, &mixing_state.late_starts);
+        }
+
+        if let Some((from, to)) = mixing_state.pending_resize.take() {
+            Mixer::scale_slot_layout(agg.upcast_ref(), mixing_state, from, to);
+
+            for pad in &base_plate_pads {
+                pad.set_property("width", &to.0).unwrap();
+                pad.set_property("height", &to.1).unwrap();
+            }
+
+            if let Some(ref capsfilter) = mixing_state.capsfilter {
+                let mut caps = capsfilter
+                    .property("caps")
+                    .unwrap()
+                    .get::<gst::Caps>()
+                    .unwrap();
+                caps.make_mut()
+                    .set_simple(&[("width", &to.0), ("height", &to.1)]);
+                capsfilter.set_property("caps", &caps).unwrap();
+            }
+        }
+
+        for pad in agg.sink_pads() {
+            if base_plate_pads.contains(&pad)
+                || Some(&pad) == mixing_state.watermark_pad.as_ref()
+                || Mixer::is_placeholder_pad(&mixing_state.late_starts, &pad)
+            {
+                continue;
+            }
+
+            let agg_pad: &gst_base::AggregatorPad = pad.downcast_ref().unwrap();
+            if let Some(sample) = agg.peek_next_sample(agg_pad) {
+                trace!(pad = %pad.name(), "selected non-base plate sample {:?}", sample);
+                base_plate_only = false;
+
+                if let Some(pts) = sample.buffer().and_then(|buffer| buffer.pts().nseconds()) {
+                    mixing_state
+                        .selected_pts
+                        .insert(pad.name().to_string(), pts);
+                }
+
+                if let Some(late_start) = mixing_state.late_starts.remove(pad.name().as_str()) {
+                    Mixer::end_late_start(agg, &pad, late_start);
+                }
+
+                if let Some(output_pts) = pts.nseconds() {
+                    if mixing_state
+                        .slot_priorities
+                        .contains_key(pad.name().as_str())
+                    {
+                        mixing_state
+                            .priority_selected_pts
+                            .insert(pad.name().to_string(), output_pts);
+                    }
+                }
+
+                let pad_timeout = mixing_state
+                    .slot_timeouts
+                    .get(pad.name().as_str())
+                    .copied()
+                    .unwrap_or(timeout);
+
+                selected_timeout = match selected_timeout {
+                    Some(selected_timeout) if selected_timeout >= pad_timeout => {
+                        Some(selected_timeout)
+                    }
+                    _ => Some(pad_timeout),
+                };
+            } else if Mixer::is_priority_slot_active(mixing_state, &pad, pts, timeout) {
+                // Primary slots hold off the base plate even while they
+                // momentarily provide no video
+                trace!(pad = %pad.name(), "priority slot suppressing base plate");
+                base_plate_only = false;
+            } else if mixing_state.late_starts.contains_key(pad.name().as_str()) {
+                trace!(pad = %pad.name(), "late slot suppressing base plate");
+                base_plate_only = false;
+            }
+        }
+
+        if base_plate_only {
+            let timeout = mixing_state.fallback_timeout.unwrap_or(timeout);
+
+            if mixing_state.base_plate_timeout.is_none() {
+                mixing_state.base_plate_timeout = pts;
+            } else if !mixing_state.showing_base_plate
+                && pts - mixing_state.base_plate_timeout > timeout
+            {
+                debug!("falling back to base plate {:?}", base_plate_pads);
+                for pad in &base_plate_pads {
+                    pad.set_property("alpha", &1.0f64).unwrap();
+                }
+                mixing_state.showing_base_plate = true;
+            }
+        } else {
+            if mixing_state.showing_base_plate {
+                debug!("hiding base plate: {:?}", base_plate_pads);
+                for pad in &base_plate_pads {
+                    pad.set_property("alpha", &0.0f64).unwrap();
+                }
+                mixing_state.showing_base_plate = false;
+            }
+            mixing_state.base_plate_timeout = gst::CLOCK_TIME_NONE;
+            mixing_state.fallback_timeout = selected_timeout;
+        }
+
+        // The slideshow is paused while hidden, and resumes with a full
+        // interval for the current image once shown again
+        let showing_base_plate = mixing_state.showing_base_plate;
+        if let Some(ref mut slideshow) = mixing_state.slideshow {
+            if showing_base_plate {
+                slideshow.advance(pts);
+            } else {
+                slideshow.shown_since = gst::CLOCK_TIME_NONE;
+            }
+        }
+
+        let duration = if mixing_state.last_pts.is_none() {
+            gst::CLOCK_TIME_NONE
+        } else {
+            pts - mixing_state.last_pts
+        };
+        let now = mixing_state.control_timing.now(pts);
+
+        mixing_state.slot_controllers = Some(Mixer::synchronize_slot_controllers(
+            agg,
+            id,
+            now,
+            duration,
+            &mut mixing_state.slot_controllers.take().unwrap(),
+        ));
+
+        mixing_state.mixer_controllers = Some(Mixer::synchronize_mixer_controllers(
+            agg,
+            &base_plate_pads,
+            id,
+            now,
+            duration,
+            &mut mixing_state.mixer_controllers.take().unwrap(),
+            &mixing_state.capsfilter,
+            &mixing_state.output_pads,
+            &mixing_state.watermark_pad,
+        ));
+
+        mixing_state.last_pts = pts;
+    }
+
+    /// Measure how late compositor produces a frame relative to real
+    /// time: the running time of the clock minus that of the frame.
+    ///
+    /// A live aggregator times out and produces its frames once our
+    /// latency has elapsed, any lag beyond that means we are not keeping
+    /// up with real time.
+    fn update_processing_lag(
+        agg: &gst_base::Aggregator,
+        segment: &gst::Segment,
+        pts: gst::ClockTime,
+        mixing_state: &mut VideoMixingState,
+    ) {
+        let running_time = match segment
+            .downcast_ref::<gst::ClockTime>()
+            .and_then(|segment| segment.to_running_time(pts).nseconds())
+        {
+            Some(running_time) => running_time,
+            None => return,
+        };
+
+        let now = match agg
+            .clock()
+            .and_then(|clock| clock.time().nseconds())
+            .zip(agg.base_time().nseconds())
+        {
+            Some((time, base_time)) => time.saturating_sub(base_time),
+            None => return,
+        };
+
+        let lag = now.saturating_sub(running_time);
+        let latency = agg.latency().nseconds().unwrap_or(0);
+
+        mixing_state.processing_lag = Some(lag);
+        mixing_state.falling_behind = lag > latency + FALLING_BEHIND_THRESHOLD_MS * 1_000_000;
+    }
+
+    /// Warn listeners once whenever we start falling behind real time
+    fn check_processing_load(&mut self) {
+        let falling_behind = self.video_mixing_state.lock().unwrap().falling_behind;
+
+        if falling_behind && !self.reported_falling_behind {
+            NodeManager::from_registry().do_send(NodeStatusMessage::Warning {
+                id: self.id.clone(),
+                message: "Mixer is falling behind real time, the machine may be oversubscribed"
+                    .to_string(),
+            });
+        } else if !falling_behind && self.reported_falling_behind {
+            debug!(id = %self.id, "caught up with real time");
+        }
+
+        self.reported_falling_behind = falling_behind;
+    }
+
+    /// Flush the slots with a `watchdog-timeout-ms` whose input hasn't
+    /// provided any buffer for that long, and notify listeners
+    ///
+    /// Flushing is retried once per timeout for as long as the input
+    /// stalls. Frozen slots are left alone, as are slots that are not
+    /// linked yet.
+    fn check_slot_watchdogs(&mut self) {
+        let now = std::time::Instant::now();
+        let mut stalled = vec![];
+
+        for (slot_id, slot) in self.consumer_slots.iter_mut() {
+            if slot.watchdog_timeout_ms == 0
+                || (slot.video_bin.is_none() && slot.audio_bin.is_none())
+            {
+                continue;
+            }
+
+            let n_buffers = slot.video_stats.lock().unwrap().n_buffers
+                + slot.audio_stats.lock().unwrap().n_buffers;
+            let frozen = slot.video_freeze.lock().unwrap().frozen;
+
+            match slot.watchdog.last_progress {
+                Some(last_progress) if !frozen && n_buffers == slot.watchdog.n_buffers => {
+                    let timeout = std::time::Duration::from_millis(slot.watchdog_timeout_ms as u64);
+
+                    if now.duration_since(last_progress) >= timeout {
+                        slot.watchdog.last_progress = Some(now);
+                        stalled.push((slot_id.clone(), slot.watchdog_timeout_ms));
+                    }
+                }
+                _ => {
+                    slot.watchdog.n_buffers = n_buffers;
+                    slot.watchdog.last_progress = Some(now);
+                }
+            }
+        }
+
+        for (slot_id, timeout_ms) in stalled {
+            warn!(id = %self.id, slot_id = %slot_id, "slot input stalled for {}ms, flushing", timeout_ms);
+
+            let message = match self.flush_slot(&slot_id) {
+                Ok(()) => format!(
+                    "Slot {} received no data for {} ms, flushed it",
+                    slot_id, timeout_ms
+                ),
+                Err(err) => format!(
+                    "Slot {} received no data for {} ms, failed to flush it: {}",
+                    slot_id, timeout_ms, err
+                ),
+            };
+
+            NodeManager::from_registry().do_send(NodeStatusMessage::Warning {
+                id: self.id.clone(),
+                message,
+            });
+        }
+    }
+
+    /// Initialize the PTP subsystem if needed, and create a clock
+    /// synchronized to the master of the given PTP domain
+    #[instrument(level = "debug", name = "creating PTP clock")]
+    fn ptp_clock(domain: u32) -> Result<gst::Clock, Error> {
+        gst_net::PtpClock::init(None, &[])
+            .map_err(|err| anyhow!("Failed to initialize PTP subsystem: {}", err))?;
+
+        Ok(gst_net::PtpClock::new(None, domain).upcast())
+    }
+
+    /// Resolves once `clock` has synchronized, without blocking any
+    /// thread, or errors out after [`CLOCK_SYNC_TIMEOUT_SECS`]
+    fn clock_synced_future(clock: Option<gst::Clock>) -> impl Future<Output = Result<(), Error>> {
+        let (sender, receiver) = oneshot::channel::<()>();
+        let sender = Mutex::new(Some(sender));
+
+        // Connect before checking, so that we can't miss the signal
+        let handler = clock.as_ref().map(|clock| {
+            clock.connect_synced(move |_clock, synced| {
+                if synced {
+                    if let Some(sender) = sender.lock().unwrap().take() {
+                        let _ = sender.send(());
+                    }
+                }
+            })
+        });
+
+        async move {
+            let (clock, handler) = match (clock, handler) {
+                (Some(clock), Some(handler)) => (clock, handler),
+                _ => return Ok(()),
+            };
+
+            let res = if clock.is_synced() {
+                Ok(())
+            } else {
+                debug!("waiting for clock to synchronize");
+
+                match tokio::time::timeout(
+                    std::time::Duration::from_secs(CLOCK_SYNC_TIMEOUT_SECS),
+                    receiver,
+                )
+                .await
+                {
+                    Ok(_) => Ok(()),
+                    Err(_) => Err(anyhow!(
+                        "clock did not synchronize within {} seconds",
+                        CLOCK_SYNC_TIMEOUT_SECS
+                    )),
+                }
+            };
+
+            clock.disconnect(handler);
+
+            res
+        }
+    }
+
+    /// Start our pipeline when cue_time is reached
+    #[instrument(level = "debug", name = "mixing", skip(self, ctx), fields(id = %self.id))]
+    fn start_pipeline(&mut self, ctx: &mut Context<Self>) -> Result<StateChangeResult, Error> {
+        let width = self.setting("width").unwrap().as_i32().unwrap();
+        let height = self.setting("height").unwrap().as_i32().unwrap();
+        let sample_rate = self.setting("sample-rate").unwrap().as_i32().unwrap();
+        let audio_format = self.output_audio_format();
+        let mix_channels = self.mix_channels();
+        let output_channels = self.output_channels();
+        let pixel_aspect_ratio = self
+            .setting("pixel-aspect-ratio")
+            .unwrap()
+            .as_fraction()
+            .unwrap();
+        let format = self
+            .setting("format")
+            .unwrap()
+            .as_str()
+            .unwrap()
+            .to_string();
+        let colorimetry = self
+            .setting("colorimetry")
+            .unwrap()
+            .as_str()
+            .unwrap()
+            .to_string();
+        let chroma_site = self
+            .setting("chroma-site")
+            .unwrap()
+            .as_str()
+            .unwrap()
+            .to_string();
+
+        let net_clock_address = self
+            .setting("net-clock-address")
+            .unwrap()
+            .as_str()
+            .unwrap()
+            .to_string();
+
+        if self.setting("use-ptp").unwrap().as_bool().unwrap() {
+            if !net_clock_address.is_empty() {
+                return Err(anyhow!(
+                    "use-ptp and net-clock-address are mutually exclusive"
+                ));
+            }
+
+            let domain = self.setting("ptp-domain").unwrap().as_i32().unwrap() as u32;
+            let clock = Mixer::ptp_clock(domain)?;
+
+            debug!(domain = %domain, "using PTP clock");
+
+            self.clock = Some(clock);
+        } else if !net_clock_address.is_empty() {
+            let port = self.setting("net-clock-port").unwrap().as_i32().unwrap();
+            let clock =
+                gst_net::NetClientClock::new(None, &net_clock_address, port, gst::ClockTime(None))
+                    .upcast::<gst::Clock>();
+
+            debug!(address = %net_clock_address, port = %port, "using network clock");
+
+            self.clock = Some(clock);
+        }
+
+        if let Some(ref clock) = self.clock {
+            self.pipeline.use_clock(Some(clock));
+        }
+
+        let control_timing = ControlTiming {
+            clock: self.clock.clone(),
+            follow_pts: self.setting("control-timing").unwrap().as_str().unwrap() == "pts",
+            anchor: Arc::new(Mutex::new(None)),
+        };
+        self.video_mixing_state.lock().unwrap().control_timing = control_timing.clone();
+        self.audio_mixing_state.lock().unwrap().control_timing = control_timing;
+
+        let vsrc = self.build_base_plate(width, height)?;
+        let vqueue = make_element("queue", None)?;
+        let vcapsfilter = make_element("capsfilter", None)?;
+        // Enforces the output colorimetry, only applying its matrix
+        let voutconv = make_element("videoconvert", None)?;
+
+        voutconv.set_property_from_str("matrix-mode", "output-only");
+
+        let asrc = make_element("audiotestsrc", None)?;
+        let asrccapsfilter = make_element("capsfilter", None)?;
+        let aqueue = make_element("queue", None)?;
+        let acapsfilter = make_element("capsfilter", None)?;
+        let level = make_element("level", Some("level"))?;
+        let aresample = make_element("audioresample", None)?;
+        let aresamplecapsfilter = make_element("capsfilter", None)?;
+
+        let background = self
+            .setting("compositor-background")
+            .unwrap()
+            .as_str()
+            .unwrap()
+            .to_string();
+
+        if background == "transparent"
+            && !gst_video::VideoFormatInfo::from_format(gst_video::VideoFormat::from_string(
+                &format,
+            ))
+            .has_alpha()
+        {
+            warn!(format = %format, "transparent background requested, but the output format has no alpha channel");
+        }
+
+        // Our settings take precedence
+        for (key, value) in &self.element_config {
+            let (element, property) =
+                Mixer::element_config_target(key, &self.video_mixer, &self.audio_mixer).unwrap();
+
+            debug!(key = %key, value = %value, "Setting mixer element property");
+
+            PropertyController::set_property_from_value(element.upcast_ref(), property, value);
+        }
+
+        self.video_mixer
+            .set_property_from_str("background", &background);
+        self.video_mixer
+            .set_property(
+                "start-time-selection",
+                &gst_base::AggregatorStartTimeSelection::First,
+            )
+            .unwrap();
+        self.video_mixer
+            .set_property(
+                "ignore-inactive-pads",
+                &self
+                    .setting("video-ignore-inactive-pads")
+                    .unwrap()
+                    .as_bool()
+                    .unwrap(),
+            )
+            .unwrap();
+
+        vcapsfilter
+            .set_property(
+                "caps",
+                &gst::Caps::builder("video/x-raw")
+                    .field("width", &width)
+                    .field("height", &height)
+                    .field("framerate", &gst::Fraction::new(30, 1))
+                    .field("pixel-aspect-ratio", &pixel_aspect_ratio)
+                    .field("format", &format)
+                    .field("colorimetry", &colorimetry)
+                    .field("chroma-site", &chroma_site)
+                    .field("interlace-mode", &"progressive")
+                    .build(),
+            )
+            .unwrap();
+        asrc.set_property("is-live", &true).unwrap();
+
+        let base_signal = self
+            .setting("audio-base-signal")
+            .unwrap()
+            .as_str()
+            .unwrap()
+            .to_string();
+
+        match base_signal.as_str() {
+            "silence" => asrc.set_property("volume", &0.).unwrap(),
+            signal => {
+                let level_db = self
+                    .setting("audio-base-level-db")
+                    .unwrap()
+                    .as_f64()
+                    .unwrap();
+
+                // A 1 kHz line-up tone, or pink noise
+                if signal == "tone" {
+                    asrc.set_property_from_str("wave", "sine");
+                    asrc.set_property("freq", &1000.0f64).unwrap();
+                } else {
+                    asrc.set_property_from_str("wave", "pink-noise");
+                }
+                asrc.set_property("volume", &10f64.powf(level_db / 20.0))
+                    .unwrap();
+            }
+        }
+        self.audio_mixer
+            .set_property(
+                "start-time-selection",
+                &gst_base::AggregatorStartTimeSelection::First,
+            )
+            .unwrap();
+        self.audio_mixer
+            .set_property(
+                "ignore-inactive-pads",
+                &self
+                    .setting("audio-ignore-inactive-pads")
+                    .unwrap()
+                    .as_bool()
+                    .unwrap(),
+            )
+            .unwrap();
+
+        asrccapsfilter
+            .set_property("caps", &self.base_audio_caps(audio_format, sample_rate))
+            .unwrap();
+
+        acapsfilter
+            .set_property(
+                "caps",
+                &Mixer::raw_audio_caps(audio_format, sample_rate, output_channels),
+            )
+            .unwrap();
+
+        aresamplecapsfilter
+            .set_property(
+                "caps",
+                &gst::Caps::builder("audio/x-raw")
+                    .field("rate", &sample_rate)
+                    .build(),
+            )
+            .unwrap();
+
+        self.pipeline.add_many(&[
+            &vsrc,
+            &vqueue,
+            &self.video_mixer,
+            &vcapsfilter,
+            &voutconv,
+            &asrc,
+            &asrccapsfilter,
+            &aqueue,
+            &self.audio_mixer,
+            &acapsfilter,
+            &level,
+            &aresample,
+            &aresamplecapsfilter,
+        ])?;
+
+        gst::Element::link_many(&[
+            &vsrc,
+            &vqueue,
+            &self.video_mixer,
+            &vcapsfilter,
+            &voutconv,
+            self.video_producer.appsink().upcast_ref(),
+        ])?;
+
+        let base_plate_pad = self.video_mixer.static_pad("sink_0").unwrap();
+
+        base_plate_pad.set_property("alpha", &0.0f64).unwrap();
+        base_plate_pad.set_property("width", &width).unwrap();
+        base_plate_pad.set_property("height", &height).unwrap();
+        base_plate_pad.set_property_from_str("sizing-policy", "keep-aspect-ratio");
+
+        let watermark_image = self
+            .setting("watermark-image")
+            .unwrap()
+            .as_str()
+            .unwrap()
+            .to_string();
+
+        let watermark_pad = if watermark_image.is_empty() {
+            None
+        } else {
+            Some(self.add_watermark(&watermark_image)?)
+        };
+
+        let idle_graphic = self
+            .setting("idle-graphic")
+            .unwrap()
+            .as_str()
+            .unwrap()
+            .to_string();
+
+        let idle_graphic_pad = if idle_graphic.is_empty() {
+            None
+        } else {
+            Some(self.add_idle_graphic(&idle_graphic, width, height)?)
+        };
+
+        let equalizer = if [
+            "audio-eq-low-gain-db",
+            "audio-eq-mid-gain-db",
+            "audio-eq-high-gain-db",
+        ]
+        .iter()
+        .any(|name| self.setting(name).unwrap().as_f64().unwrap() != 0.0)
+        {
+            let equalizer = self.build_equalizer()?;
+            self.pipeline.add(&equalizer)?;
+            Some(equalizer)
+        } else {
+            None
+        };
+
+        gst::Element::link_many(&[&asrc, &asrccapsfilter, &aqueue, &self.audio_mixer])?;
+
+        // Only audible once no slot is, see update_base_signal
+        let base_signal_pad = if base_signal == "silence" {
+            None
+        } else {
+            let pad = aqueue.static_pad("src").unwrap().peer().unwrap();
+            pad.set_property("volume", &0.0f64).unwrap();
+            Some(pad)
+        };
+
+        let mut mix_elements = vec![&self.audio_mixer];
+        // Downmixes mono outputs
+        let mut needs_output_conv = output_channels != mix_channels;
+
+        if let Some(ref equalizer) = equalizer {
+            mix_elements.push(equalizer);
+            // equalizer-nbands only handles S16, F32 and F64
+            needs_output_conv |= audio_format != "S16LE";
+        }
+
+        let output_conv = if needs_output_conv {
+            Some(make_element("audioconvert", None)?)
+        } else {
+            None
+        };
+
+        if let Some(ref output_conv) = output_conv {
+            self.pipeline.add(output_conv)?;
+            mix_elements.push(output_conv);
+        }
+
+        mix_elements.push(&acapsfilter);
+
+        gst::Element::link_many(&mix_elements)?;
+
+        gst::Element::link_many(&[&acapsfilter, &level, &aresample, &aresamplecapsfilter])?;
+
+        if self
+            .setting("enable-audio-monitor")
+            .unwrap()
+            .as_bool()
+            .unwrap()
+        {
+            let monitor_sample_rate = self
+                .setting("audio-monitor-sample-rate")
+                .unwrap()
+                .as_i32()
+                .unwrap();
+
+            self.link_audio_monitor(&aresamplecapsfilter, monitor_sample_rate)?;
+        } else {
+            aresamplecapsfilter.link(self.audio_producer.appsink())?;
+        }
+
+        let mut late_starts = HashMap::new();
+
+        for slot in self.consumer_slots.values() {
+            if let Some(late_start) = self.start_late_slot(slot, width, height)? {
+                late_starts.insert(
+                    slot.video_pad.as_ref().unwrap().name().to_string(),
+                    late_start,
+                );
+            }
+        }
+
+        self.video_mixing_state.lock().unwrap().late_starts = late_starts;
+
+        let mut slot_futures = vec![];
+
+        for (id, slot) in self.consumer_slots.iter_mut() {
+            let fut = Mixer::connect_slot(
+                &self.pipeline,
+                slot,
+                &self.id,
+                id,
+                width,
+                height,
+                sample_rate,
+                audio_format,
+                mix_channels,
+            );
+
+            slot_futures.push((id.clone(), slot.generation, fut));
+        }
+
+        for (slot_id, generation, fut) in slot_futures {
+            ctx.spawn(fut.into_actor(self).map(move |res, slf, ctx| {
+                // Not an error for us if the slot was disconnected meanwhile
+                if !slf.is_current_slot(&slot_id, generation) {
+                    return;
+                }
+
+                if let Err(err) = slf.complete_slot_connection(&slot_id, generation, res) {
+                    ctx.notify(ErrorMessage(format!(
+                        "Failed to connect slot {} of mixer {}: {}",
+                        slot_id, slf.id, err
+                    )));
+                }
+            }));
+        }
+
+        // Shift the running time of our output, both streams are offset
+        // from the mixers' source pads in order to remain in sync.
+        //
+        // Offsetting the pipeline base time instead would shift the
+        // running time of every element: our slots and the aggregators'
+        // deadlines would be thrown off along with the output, a live
+        // update would make the pipeline jump, and negative offsets
+        // can't be expressed when the base time is smaller than them.
+        let output_pts_offset = self
+            .setting("output-pts-offset-ns")
+            .unwrap()
+            .as_i64()
+            .unwrap();
+        let output_pads = vec![
+            self.video_mixer.static_pad("src").unwrap(),
+            self.audio_mixer.static_pad("src").unwrap(),
+        ];
+
+        for pad in &output_pads {
+            pad.set_offset(output_pts_offset);
+        }
+
+        let video_mixing_state = self.video_mixing_state.clone();
+        {
+            let mut mixing_state = video_mixing_state.lock().unwrap();
+            mixing_state.capsfilter = Some(vcapsfilter);
+            mixing_state.output_pads = output_pads;
+            mixing_state.watermark_pad = watermark_pad;
+            mixing_state.idle_graphic_pad = idle_graphic_pad;
+        }
+        let id = self.id.clone();
+        let timeout =
+            self.setting("fallback-timeout").unwrap().as_i32().unwrap() as u64 * gst::MSECOND;
+
+        self.video_mixer
+            .set_property("emit-signals", &true)
+            .unwrap();
+        self.video_mixer
+            .downcast_ref::<gst_base::Aggregator>()
+            .unwrap()
+            .connect_samples_selected(
+                move |agg: &gst_base::Aggregator, segment, pts, _dts, _duration, _info| {
+                    let mut mixing_state = video_mixing_state.lock().unwrap();
+                    Mixer::update_processing_lag(agg, segment, pts, &mut *mixing_state);
+                    Mixer::update_video_mixing_state(agg, &id, pts, &mut *mixing_state, timeout);
+                },
+            );
+
+        ctx.run_interval(std::time::Duration::from_secs(1), |s, _ctx| {
+            s.check_processing_load();
+        });
+
+        ctx.run_interval(
+            std::time::Duration::from_millis(WATCHDOG_INTERVAL_MS),
+            |s, _ctx| {
+                s.check_slot_watchdogs();
+            },
+        );
+
+        let audio_mixing_state = self.audio_mixing_state.clone();
+        {
+            let mut mixing_state = audio_mixing_state.lock().unwrap();
+            mixing_state.equalizer = equalizer;
+            mixing_state.base_signal_pad = base_signal_pad;
+        }
+        let id = self.id.clone();
+
+        self.audio_mixer
+            .set_property("emit-signals", &true)
+            .unwrap();
+        self.audio_mixer
+            .downcast_ref::<gst_base::Aggregator>()
+            .unwrap()
+            .connect_samples_selected(
+                move |agg: &gst_base::Aggregator, _segment, pts, _dts, _duration, _info| {
+                    let mut mixing_state = audio_mixing_state.lock().unwrap();
+                    Mixer::update_audio_mixing_state(agg, &id, pts, &mut *mixing_state, timeout);
+                },
+            );
+
+        // Only report ourselves as started once the pipeline has actually
+        // reached PLAYING, our producers start forwarding at that point.
+        // Running on a clock that isn't synchronized yet would throw off
+        // our timestamps and control points, wait for it first
+        let pipeline = self.pipeline.clone();
+        let fut = Mixer::clock_synced_future(self.clock.clone()).and_then(move |_| {
+            pipeline.call_async_future(move |pipeline| -> Result<(), Error> {
+                pipeline.set_state(gst::State::Playing)?;
+
+                match pipeline.state(PREROLL_TIMEOUT_SECS * gst::SECOND) {
+                    (Err(err), _, _) => Err(anyhow!("{}", err)),
+                    (Ok(_), gst::State::Playing, _) => Ok(()),
+                    (Ok(_), current, pending) => Err(anyhow!(
+                        "timed out prerolling, current state {:?}, pending state {:?}",
+                        current,
+                        pending
+                    )),
+                }
+            })
+        });
+
+        ctx.spawn(fut.into_actor(self).map(|res, slf, ctx| match res {
+            Ok(()) => {
+                debug!(id = %slf.id, "pipeline prerolled");
+
+                slf.prerolled = true;
+
+                if slf.state_machine.state == State::Starting {
+                    slf.do_schedule(ctx);
+                }
+            }
+            Err(err) => {
+                ctx.notify(ErrorMessage(format!(
+                    "Failed to start mixer {}: {}",
+                    slf.id, err
+                )));
+            }
+        }));
+
+        Ok(StateChangeResult::Success)
+    }
+
+    /// Split our audio output between our main audio producer and a
+    /// mono, resampled branch for our audio monitor producer
+    fn link_audio_monitor(&self, src: &gst::Element, sample_rate: i32) -> Result<(), Error> {
+        let tee = make_element("tee", None)?;
+        let queue = make_element("queue", None)?;
+        let monitor_queue = make_element("queue", None)?;
+        let monitor_aconv = make_element("audioconvert", None)?;
+        let monitor_aresample = make_element("audioresample", None)?;
+        let monitor_capsfilter = make_element("capsfilter", None)?;
+        let monitor_appsink: &gst::Element = self.audio_monitor_producer.appsink().upcast_ref();
+
+        monitor_capsfilter
+            .set_property(
+                "caps",
+                &gst::Caps::builder("audio/x-raw")
+                    .field("rate", &sample_rate)
+                    .field("channels", &1)
+                    .build(),
+            )
+            .unwrap();
+
+        self.pipeline.add_many(&[
+            &tee,
+            &queue,
+            &monitor_queue,
+            &monitor_aconv,
+            &monitor_aresample,
+            &monitor_capsfilter,
+            monitor_appsink,
+        ])?;
+
+        gst::Element::link_many(&[
+            src,
+            &tee,
+            &queue,
+            self.audio_producer.appsink().upcast_ref(),
+        ])?;
+        gst::Element::link_many(&[
+            &tee,
+            &monitor_queue,
+            &monitor_aconv,
+            &monitor_aresample,
+            &monitor_capsfilter,
+            monitor_appsink,
+        ])?;
+
+        Ok(())
+    }
+
+    /// Start forwarding our output once our pipeline has prerolled
+    fn start_forwarding(&mut self) -> Result<StateChangeResult, Error> {
+        if !self.prerolled {
+            trace!(id = %self.id, "waiting for pipeline to preroll");
+            return Ok(StateChangeResult::Pending);
+        }
+
+        self.video_producer.forward();
+        self.audio_producer.forward();
+        self.audio_monitor_producer.forward();
+
+        for output in self.scaled_outputs.values() {
+            output.producer.forward();
+        }
+
+        Ok(StateChangeResult::Success)
+    }
+
+    /// Insert a `tee` between our output `capsfilter` and our video
+    /// `appsink`, if not already present
+    ///
+    /// The `tee` is linked in from an idle probe, as data may already
+    /// be flowing. If that fails, the `tee` is removed again and the
+    /// `appsink` relinked, see [`Mixer::prune_failed_video_tee`].
+    fn ensure_video_tee(&mut self) -> Result<gst::Element, Error> {
+        self.prune_failed_video_tee()?;
+
+        if let Some(ref tee) = self.video_tee {
+            return Ok(tee.clone());
+        }
+
+        let capsfilter = self
+            .video_mixing_state
+            .lock()
+            .unwrap()
+            .capsfilter
+            .clone()
+            .ok_or_else(|| anyhow!("Mixer {} has not started mixing yet", self.id))?;
+
+        let tee = make_element("tee", None)?;
+        let queue = make_element("queue", None)?;
+        let appsink: gst::Element = self.video_producer.appsink().clone().upcast();
+
+        tee.set_property("allow-not-linked", &true).unwrap();
+
+        self.pipeline.add_many(&[&tee, &queue])?;
+        tee.link(&queue)?;
+
+        let id = self.id.clone();
+        let pipeline = self.pipeline.clone();
+        let tee_clone = tee.clone();
+
+        capsfilter.static_pad("src").unwrap().add_probe(
+            gst::PadProbeType::IDLE,
+            move |pad, _info| {
+                let appsink_pad = appsink.static_pad("sink").unwrap();
+
+                let res = || -> Result<(), Error> {
+                    pad.unlink(&appsink_pad)?;
+                    tee_clone.sync_state_with_parent()?;
+                    queue.sync_state_with_parent()?;
+                    pad.link(&tee_clone.static_pad("sink").unwrap())?;
+                    queue.link(&appsink)?;
+
+                    Ok(())
+                };
+
+                if let Err(err) = res() {
+                    error!(id = %id, "Failed to insert video output tee: {}", err);
+
+                    // Restore the original output, and remove the tee from
+                    // the pipeline, the scaled outputs are rolled back
+                    // the next time the mixer looks at them
+                    let _ = pad.unlink(&tee_clone.static_pad("sink").unwrap());
+                    let _ = queue.unlink(&appsink);
+                    let _ = pad.link(&appsink_pad);
+                    let _ = tee_clone.set_state(gst::State::Null);
+                    let _ = queue.set_state(gst::State::Null);
+                    let _ = pipeline.remove_many(&[&tee_clone, &queue]);
+
+                    NodeManager::from_registry().do_send(NodeStatusMessage::Warning {
+                        id: id.clone(),
+                        message: format!("Failed to insert video output tee: {}", err),
+                    });
+                }
+
+                gst::PadProbeReturn::Remove
+            },
+        );
+
+        self.video_tee = Some(tee.clone());
+
+        Ok(tee)
+    }
+
+    /// Roll back the scaled outputs if our video `tee` could not be
+    /// inserted, in which case it was removed from the pipeline
+    fn prune_failed_video_tee(&mut self) -> Result<(), Error> {
+        match self.video_tee {
+            Some(ref tee) if tee.parent().is_none() => (),
+            _ => return Ok(()),
+        }
+
+        self.video_tee = None;
+
+        let output_ids: Vec<String> = self.scaled_outputs.keys().cloned().collect();
+
+        for output_id in &output_ids {
+            let output = self.scaled_outputs.remove(output_id).unwrap();
+
+            for consumer_id in output.producer.get_consumer_ids() {
+                output.producer.remove_consumer(&consumer_id);
+            }
+
+            let _ = output.bin.set_state(gst::State::Null);
+            let _ = self.pipeline.remove(&output.bin);
+        }
+
+        if output_ids.is_empty() {
+            Ok(())
+        } else {
+            Err(anyhow!(
+                "Mixer {} failed to insert its video output tee, removed outputs {}",
+                self.id,
+                output_ids.join(", ")
+            ))
+        }
+    }
+
+    /// Add a scaled video output
+    #[instrument(level = "debug", name = "adding-output", skip(self), fields(id = %self.id))]
+    fn add_output_scale(
+        &mut self,
+        output_id: &str,
+        width: u32,
+        height: u32,
+        framerate: gst::Fraction,
+    ) -> Result<(), Error> {
+        if self.scaled_outputs.contains_key(output_id) {
+            return Err(anyhow!(
+                "mixer {} already has an output with id {}",
+                self.id,
+                output_id
+            ));
+        }
+
+        if width == 0 || height == 0 || *framerate.numer() <= 0 || *framerate.denom() <= 0 {
+            return Err(anyhow!(
+                "Invalid output scale {}x{}@{}/{}",
+                width,
+                height,
+                framerate.numer(),
+                framerate.denom()
+            ));
+        }
+
+        let tee = self.ensure_video_tee()?;
+
+        let bin = gst::Bin::new(None);
+        let queue = make_element("queue", None)?;
+        let vscale = make_element("videoscale", None)?;
+        let vrate = make_element("videorate", None)?;
+        let capsfilter = make_element("capsfilter", None)?;
+        let appsink = gst::ElementFactory::make(
+            "appsink",
+            Some(&format!("mixer-video-appsink-{}-{}", self.id, output_id)),
+        )
+        .unwrap()
+        .downcast::<gst_app::AppSink>()
+        .unwrap();
+
+        capsfilter
+            .set_property(
+                "caps",
+                &gst::Caps::builder("video/x-raw")
+                    .field("width", &(width as i32))
+                    .field("height", &(height as i32))
+                    .field("framerate", &framerate)
+                    .build(),
+            )
+            .unwrap();
+
+        bin.add_many(&[&queue, &vscale, &vrate, &capsfilter, appsink.upcast_ref()])?;
+        gst::Element::link_many(&[&queue, &vscale, &vrate, &capsfilter, appsink.upcast_ref()])?;
+
+        let ghost =
+            gst::GhostPad::with_target(Some("sink"), &queue.static_pad("sink").unwrap()).unwrap();
+        bin.add_pad(&ghost).unwrap();
+
+        let producer = StreamProducer::from(&appsink);
+        forward_producer_notifications(&producer, &self.id);
+        producer.set_auto_remove_timeout(Mixer::consumer_removal_timeout(&self.settings));
+
+        self.pipeline.add(&bin)?;
+
+        let tee_pad = match tee.request_pad_simple("src_%u") {
+            Some(tee_pad) => tee_pad,
+            None => {
+                let _ = self.pipeline.remove(&bin);
+                return Err(anyhow!(
+                    "Failed to request a tee pad for output {} of mixer {}",
+                    output_id,
+                    self.id
+                ));
+            }
+        };
+
+        if let Err(err) = tee_pad
+            .link(&ghost)
+            .map_err(Error::from)
+            .and_then(|_| bin.sync_state_with_parent().map_err(Error::from))
+        {
+            let _ = bin.set_state(gst::State::Null);
+            let _ = self.pipeline.remove(&bin);
+            tee.release_request_pad(&tee_pad);
+            return Err(anyhow!(
+                "Failed to insert output {} in mixer {}: {}",
+                output_id,
+                self.id,
+                err
+            ));
+        }
+
+        if self.state_machine.state == State::Started {
+            producer.forward();
+        }
+
+        debug!(output_id = %output_id, "added scaled output");
+
+        self.scaled_outputs.insert(
+            output_id.to_string(),
+            ScaledOutput {
+                producer,
+                bin,
+                tee_pad,
+            },
+        );
+
+        Ok(())
+    }
+
+    /// Remove a scaled video output
+    ///
+    /// The `tee` pad is unlinked from an idle probe, as data may be
+    /// flowing, the pad is then released and the scaling elements
+    /// removed from the application thread of the pipeline.
+    #[instrument(level = "debug", name = "removing-output", skip(self), fields(id = %self.id))]
+    fn remove_output(&mut self, output_id: &str) -> Result<Vec<String>, Error> {
+        self.prune_failed_video_tee()?;
+
+        let output = self
+            .scaled_outputs
+            .remove(output_id)
+            .ok_or_else(|| anyhow!("mixer {} has no output with id {}", self.id, output_id))?;
+
+        let consumer_ids = output.producer.get_consumer_ids();
+
+        for consumer_id in &consumer_ids {
+            output.producer.remove_consumer(consumer_id);
+        }
+
+        let tee = match self.video_tee {
+            Some(ref tee) => tee.clone(),
+            None => {
+                let _ = output.bin.set_state(gst::State::Null);
+                let _ = self.pipeline.remove(&output.bin);
+                return Ok(consumer_ids);
+            }
+        };
+
+        let id = self.id.clone();
+        let pipeline = self.pipeline.clone();
+        let bin = output.bin;
+
+        output
+            .tee_pad
+            .add_probe(gst::PadProbeType::IDLE, move |pad, _info| {
+                if let Some(peer) = pad.peer() {
+                    let _ = pad.unlink(&peer);
+                }
+
+                let tee = tee.clone();
+                let pad = pad.clone();
+                let bin = bin.clone();
+                let id = id.clone();
+
+                pipeline.call_async(move |pipeline| {
+                    tee.release_request_pad(&pad);
+
+                    if let Err(err) = bin.set_state(gst::State::Null) {
+                        error!(id = %id, "Failed to stop scaled output: {}", err);
+                    }
+
+                    let _ = pipeline.remove(&bin);
+                });
+
+                gst::PadProbeReturn::Remove
+            });
+
+        Ok(consumer_ids)
+    }
+
+    /// Implement Connect command
+    ///
+    /// When the mixer is already running, the slot is connected
+    /// asynchronously, and disconnected again if that fails.
+    ///
+    /// With `replace`, an existing slot with the same id is disconnected
+    /// first, once the configuration of the new slot was validated. The
+    /// new slot then takes over the position, size and stacking order of
+    /// the old one, unless its configuration sets them. Our base plate
+    /// only falls back once no slot has provided video for its timeout:
+    /// it is not shown while the new producer catches up, as long as it
+    /// does so within that timeout, the area of the slot is empty until
+    /// then.
+    #[instrument(level = "debug", name = "connecting", skip(self, video_producer, audio_producer), fields(id = %self.id))]
+    fn connect(
+        &mut self,
+        ctx: &mut Context<Self>,
+        link_id: &str,
+        video_producer: Option<&StreamProducer>,
+        audio_producer: Option<&StreamProducer>,
+        config: Option<HashMap<String, serde_json::Value>>,
+        replace: bool,
+    ) -> ResponseActFuture<Self, Result<(), Error>> {
+        let replaced_layout = match self.consumer_slots.get(link_id) {
+            Some(slot) if replace => {
+                if let Some(ref config) = config {
+                    let errors = self.validate_config(&HashMap::new(), config);
+
+                    if !errors.is_empty() {
+                        return Box::pin(actix::fut::ready(Err(anyhow!(
+                            "Invalid configuration for slot {}: {}",
+                            link_id,
+                            errors.join(", ")
+                        ))));
+                    }
+                }
+
+                let layout = slot.video_pad.as_ref().map(|pad| {
+                    REPLACED_SLOT_PROPERTIES
+                        .iter()
+                        .map(|property| (*property, pad.property(property).unwrap()))
+                        .collect::<Vec<_>>()
+                });
+
+                debug!(slot = %link_id, "replacing slot");
+
+                if let Err(err) = self.disconnect(link_id) {
+                    return Box::pin(actix::fut::ready(Err(err)));
+                }
+
+                layout
+            }
+            _ => None,
+        };
+
+        let configured_keys = config
+            .as_ref()
+            .map(|config| config.keys().cloned().collect::<HashSet<_>>())
+            .unwrap_or_default();
+
+        if let Err(err) = self.create_slot(link_id, video_producer, audio_producer, config) {
+            return Box::pin(actix::fut::ready(Err(err)));
+        }
+
+        if self.consumer_slots[link_id].reconnect_on_eos {
+            Mixer::watch_slot_eos(ctx, link_id, &self.consumer_slots[link_id]);
+        }
+
+        if let (Some(layout), Some(video_pad)) = (
+            replaced_layout,
+            self.consumer_slots[link_id].video_pad.as_ref(),
+        ) {
+            for (property, value) in layout {
+                if !configured_keys.contains(&format!("video::{}", property)) {
+                    video_pad.set_property(property, &value).unwrap();
+                }
+            }
+        }
+
+        if self.state_machine.state != State::Started {
+            return Box::pin(actix::fut::ready(Ok(())));
+        }
+
+        let width = self.setting("width").unwrap().as_i32().unwrap();
+        let height = self.setting("height").unwrap().as_i32().unwrap();
+        let sample_rate = self.setting("sample-rate").unwrap().as_i32().unwrap();
+        let audio_format = self.output_audio_format();
+        let mix_channels = self.mix_channels();
+
+        let slot = self.consumer_slots.get_mut(link_id).unwrap();
+        let generation = slot.generation;
+        let fut = Mixer::connect_slot(
+            &self.pipeline,
+            slot,
+            &self.id,
+            link_id,
+            width,
+            height,
+            sample_rate,
+            audio_format,
+            mix_channels,
+        );
+
+        let link_id = link_id.to_string();
+
+        Box::pin(
+            fut.into_actor(self)
+                .map(move |res, slf, _ctx| slf.complete_slot_connection(&link_id, generation, res)),
+        )
+    }
+
+    /// Notify ourselves once the input of a slot reaches EOS, for
+    /// reconnecting it
+    fn watch_slot_eos(ctx: &mut Context<Self>, link_id: &str, slot: &ConsumerSlot) {
+        let appsrc = match slot.video_pad {
+            Some(_) => &slot.video_appsrc,
+            None => &slot.audio_appsrc,
+        };
+        let addr = ctx.address().downgrade();
+        let slot_id = link_id.to_string();
+        let generation = slot.generation;
+
+        appsrc
+            .static_pad("src")
+            .unwrap()
+            .add_probe(gst::PadProbeType::EVENT_DOWNSTREAM, move |_pad, info| {
+                if let Some(gst::PadProbeData::Event(ref ev)) = info.data {
+                    if ev.type_() == gst::EventType::Eos {
+                        if let Some(addr) = addr.upgrade() {
+                            addr.do_send(SlotEosMessage {
+                                slot_id: slot_id.clone(),
+                                generation,
+                            });
+                        }
+                    }
+                }
+
+                gst::PadProbeReturn::Ok
+            })
+            .unwrap();
+    }
+
+    /// Apply the initial configuration of a slot
+    fn apply_slot_config(
+        slot: &mut ConsumerSlot,
+        config: HashMap<String, serde_json::Value>,
+    ) -> Result<(), Error> {
+        // The compressor must exist for its other keys to be applied
+        if let Some(value) = config.get("audio::compressor-threshold-db") {
+            let threshold_db = value
+                .as_f64()
+                .ok_or_else(|| anyhow!("audio::compressor-threshold-db must be a number"))?;
+
+            if slot.audio_pad.is_none() {
+                return Err(anyhow!("slot has no audio input"));
+            }
+
+            if threshold_db < 0.0 {
+                slot.compressor = Some(Mixer::build_compressor(threshold_db)?);
+            }
+        }
+
+        // Likewise for the denoiser
+        let denoise = match config.get("video::denoise") {
+            Some(value) => value
+                .as_bool()
+                .ok_or_else(|| anyhow!("video::denoise must be a boolean"))?,
+            None => false,
+        };
+
+        if denoise {
+            if slot.video_pad.is_none() {
+                return Err(anyhow!("slot has no video input"));
+            }
+
+            slot.denoiser = Mixer::build_denoiser();
+        }
+
+        for (key, value) in config {
+            match key.as_str() {
+                "audio::compressor-threshold-db" | "video::denoise" => (),
+                // We already warned about the denoiser not being available
+                "video::denoise-spatial" | "video::denoise-temporal"
+                    if denoise && slot.denoiser.is_none() => {}
+                // Likewise for the label overlay
+                "video::label-text"
+                | "video::label-font"
+                | "video::label-position"
+                | "video::label-color"
+                    if slot.video_pad.is_some() && slot.label_overlay.is_none() => {}
+                "video::label-position" => {
+                    let overlay = slot
+                        .label_overlay
+                        .as_ref()
+                        .ok_or_else(|| anyhow!("slot has no video input"))?;
+                    let (valign, halign) = Mixer::parse_label_position(
+                        value
+                            .as_str()
+                            .ok_or_else(|| anyhow!("video::label-position must be a string"))?,
+                    )?;
+
+                    overlay.set_property_from_str("valign", valign);
+                    overlay.set_property_from_str("halign", halign);
+                }
+                "video::label-color" => {
+                    let overlay = slot
+                        .label_overlay
+                        .as_ref()
+                        .ok_or_else(|| anyhow!("slot has no video input"))?;
+
+                    overlay
+                        .set_property("color", &Mixer::parse_label_color(&value)?)
+                        .unwrap();
+                }
+                "watchdog-timeout-ms" => {
+                    slot.watchdog_timeout_ms = value
+                        .as_u64()
+                        .and_then(|timeout| u32::try_from(timeout).ok())
+                        .ok_or_else(|| {
+                            anyhow!("watchdog-timeout-ms must be a 32-bit unsigned integer")
+                        })?;
+                }
+                "reconnect-on-eos" => {
+                    slot.reconnect_on_eos = value
+                        .as_bool()
+                        .ok_or_else(|| anyhow!("reconnect-on-eos must be a boolean"))?;
+                }
+                "reconnect-delay-ms" => {
+                    slot.reconnect_delay_ms = value
+                        .as_u64()
+                        .and_then(|delay| u32::try_from(delay).ok())
+                        .ok_or_else(|| {
+                            anyhow!("reconnect-delay-ms must be a 32-bit unsigned integer")
+                        })?;
+                }
+                "latency-ms" => {
+                    slot.latency_ms = Some(
+                        value
+                            .as_u64()
+                            .and_then(|latency| u32::try_from(latency).ok())
+                            .ok_or_else(|| {
+                                anyhow!("latency-ms must be a 32-bit unsigned integer")
+                            })?,
+                    );
+                }
+                "label" => {
+                    slot.label = Some(
+                        value
+                            .as_str()
+                            .ok_or_else(|| anyhow!("label must be a string"))?
+                            .to_string(),
+                    );
+                }
+                "video::late-start-mode" => {
+                    if slot.video_pad.is_none() {
+                        return Err(anyhow!("slot has no video input"));
+                    }
+
+                    slot.late_start_mode = LateStartMode::parse(
+                        value
+                            .as_str()
+                            .ok_or_else(|| anyhow!("video::late-start-mode must be a string"))?,
+                    )?;
+                }
+                "priority" => {
+                    slot.priority = value
+                        .as_u64()
+                        .and_then(|priority| u8::try_from(priority).ok())
+                        .ok_or_else(|| anyhow!("priority must be an 8-bit unsigned integer"))?;
+                }
+                // Applied to the pad again upon linking
+                "audio::volume" => {
+                    let (_, object, property) = Mixer::slot_property_target(&slot, &key)?;
+
+                    PropertyController::validate_value(property, &object, &value)?;
+
+                    slot.volume = value
+                        .as_f64()
+                        .ok_or_else(|| anyhow!("audio::volume must be a number"))?;
+                    PropertyController::set_property_from_value(&object, property, &value);
+                }
+                "video::base-plate-timeout-ms" => {
+                    let timeout = value
+                        .as_i64()
+                        .and_then(|timeout| i32::try_from(timeout).ok())
+                        .ok_or_else(|| {
+                            anyhow!("video::base-plate-timeout-ms must be a 32-bit integer")
+                        })?;
+
+                    slot.base_plate_timeout_ms = if timeout > 0 { Some(timeout) } else { None };
+                }
+                "audio::channel-map" => {
+                    if slot.audio_pad.is_none() {
+                        return Err(anyhow!("slot has no audio input"));
+                    }
+
+                    slot.channel_map = Some(Mixer::parse_channel_map(&value)?);
+                }
+                "audio::allow-format-change" => {
+                    if slot.audio_pad.is_none() {
+                        return Err(anyhow!("slot has no audio input"));
+                    }
+
+                    slot.allow_audio_format_change = value
+                        .as_bool()
+                        .ok_or_else(|| anyhow!("audio::allow-format-change must be a boolean"))?;
+                }
+                "video::format" | "audio::format" => {
+                    Mixer::validate_appsrc_format(&key, &value)?;
+
+                    let (_, object, property) = Mixer::slot_property_target(&slot, &key)?;
+
+                    PropertyController::validate_value(property, &object, &value)?;
+                    PropertyController::set_property_from_value(&object, property, &value);
+                }
+                "video::border-radius" => {
+                    slot.border_radius = value
+                        .as_u64()
+                        .and_then(|radius| u32::try_from(radius).ok())
+                        .ok_or_else(|| {
+                            anyhow!("video::border-radius must be a 32-bit unsigned integer")
+                        })?;
+                }
+                _ => {
+                    let (_, object, property) = Mixer::slot_property_target(&slot, &key)?;
+
+                    PropertyController::validate_value(property, &object, &value)?;
+
+                    debug!("Setting initial slot config {} {}", key, value);
+
+                    PropertyController::set_property_from_value(&object, property, &value);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Release the mixer pads requested for a slot
+    fn release_slot_pads(&self, slot: &ConsumerSlot) {
+        if let Some(ref pad) = slot.video_pad {
+            self.video_mixer.release_request_pad(pad);
+        }
+
+        if let Some(ref pad) = slot.audio_pad {
+            self.audio_mixer.release_request_pad(pad);
+        }
+    }
+
+    /// Build a slot, its pads are expected to be requested already
+    fn build_slot(
+        link_id: &str,
+        video_producer: Option<&StreamProducer>,
+        audio_producer: Option<&StreamProducer>,
+        video_pad: Option<gst::Pad>,
+        audio_pad: Option<gst::Pad>,
+    ) -> ConsumerSlot {
+        let video_appsrc = gst::ElementFactory::make(
+            "appsrc",
+            Some(&format!("mixer-slot-video-appsrc-{}", link_id)),
+        )
+        .unwrap()
+        .downcast::<gst_app::AppSrc>()
+        .unwrap();
+        let audio_appsrc = gst::ElementFactory::make(
+            "appsrc",
+            Some(&format!("mixer-slot-audio-appsrc-{}", link_id)),
+        )
+        .unwrap()
+        .downcast::<gst_app::AppSrc>()
+        .unwrap();
+
+        for appsrc in &[&video_appsrc, &audio_appsrc] {
+            appsrc.set_format(gst::Format::Time);
+            appsrc.set_is_live(true);
+            appsrc.set_handle_segment_change(true);
+        }
+
+        let video_stats = SlotInputStats::track(&video_appsrc.static_pad("src").unwrap());
+        let audio_stats = SlotInputStats::track(&audio_appsrc.static_pad("src").unwrap());
+        let video_freeze = SlotFreeze::track(&video_appsrc.static_pad("src").unwrap());
+        let label_overlay = match video_pad {
+            Some(_) => Mixer::build_label_overlay(),
+            None => None,
+        };
+
+        ConsumerSlot {
+            video_producer: video_producer.cloned(),
+            audio_producer: audio_producer.cloned(),
+            video_appsrc,
+            audio_appsrc,
+            audio_bin: None,
+            video_bin: None,
+            volume: 1.0,
+            video_pad,
+            audio_pad,
+            video_stats,
+            audio_stats,
+            video_freeze,
+            border_radius: 0,
+            channel_map: None,
+            allow_audio_format_change: false,
+            compressor: None,
+            denoiser: None,
+            label: None,
+            label_overlay,
+            base_plate_timeout_ms: None,
+            priority: 0,
+            late_start_mode: LateStartMode::Mute,
+            watchdog_timeout_ms: 0,
+            watchdog: SlotWatchdog::default(),
+            generation: 0,
+            reconnect_on_eos: false,
+            reconnect_delay_ms: 0,
+            initial_config: None,
+            latency_ms: None,
+        }
+    }
+
+    /// Create a pad of the type `mixer` would provide, without requesting
+    /// it, for validating slot configurations
+    fn make_unlinked_pad(mixer: &gst::Element) -> gst::Pad {
+        let templ = mixer.pad_template("sink_%u").unwrap();
+
+        gst::glib::Object::with_type(
+            templ.gtype(),
+            &[
+                ("name", &"dry-run"),
+                ("direction", &gst::PadDirection::Sink),
+                ("template", &templ),
+            ],
+        )
+        .unwrap()
+        .downcast::<gst::Pad>()
+        .unwrap()
+    }
+
+    /// Update the latency of one of our mixers, possibly while running
+    ///
+    /// The aggregator posts a latency message when its latency changes,
+    /// the pipeline then redistributes the latency to its sinks.
+    fn set_aggregator_latency(mixer: &gst::Element, latency_ns: u64) -> Result<(), Error> {
+        if latency_ns >= i64::MAX as u64 {
+            return Err(anyhow!("Invalid latency {}", latency_ns));
+        }
+
+        debug!(mixer = %mixer.name(), latency_ns, "setting latency");
+
+        mixer
+            .set_property("latency", &latency_ns)
+            .map_err(|err| anyhow!("Failed to set latency on {}: {}", mixer.name(), err))
+    }
+
+    /// Validate mixer settings and a slot configuration, as the
+    /// `CreateMixer` and `Connect` commands would
+    ///
+    /// The slot configuration is applied to a throwaway slot, with
+    /// both video and audio, whose pads are not requested from our
+    /// mixers. Every key is checked separately, along with the keys
+    /// it depends on, so that all errors can be reported at once.
+    #[instrument(level = "debug", name = "validating config", skip(self), fields(id = %self.id))]
+    fn validate_config(
+        &self,
+        settings: &HashMap<String, serde_json::Value>,
+        slot_config: &HashMap<String, serde_json::Value>,
+    ) -> Vec<String> {
+        let mut errors = vec![];
+
+        for (key, value) in settings {
+            if let Some((element, property)) =
+                Mixer::element_config_target(key, &self.video_mixer, &self.audio_mixer)
+            {
+                if let Err(err) =
+                    PropertyController::validate_value(property, element.upcast_ref(), value)
+                {
+                    errors.push(format!("{}: {}", key, err));
+                }
+                continue;
+            }
+
+            match self.settings.get(key) {
+                Some(setting) => {
+                    if let Err(err) =
+                        SettingController::validate_value(&setting.lock().unwrap(), value)
+                    {
+                        errors.push(format!("{}: {}", key, err));
+                    }
+                }
+                None => errors.push(format!("{}: no setting with that name on mixers", key)),
+            }
+        }
+
+        let dry_run_slot = || {
+            Mixer::build_slot(
+                "dry-run",
+                None,
+                None,
+                Some(Mixer::make_unlinked_pad(&self.video_mixer)),
+                Some(Mixer::make_unlinked_pad(&self.audio_mixer)),
+            )
+        };
+
+        // The compressor and the denoiser are only built when requested
+        let dependencies: HashMap<String, serde_json::Value> = slot_config
+            .iter()
+            .filter(|(key, _)| {
+                key.as_str() == "audio::compressor-threshold-db" || key.as_str() == "video::denoise"
+            })
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect();
+
+        if let Err(err) = Mixer::apply_slot_config(&mut dry_run_slot(), dependencies.clone()) {
+            errors.push(format!("{}", err));
+            return errors;
+        }
+
+        for (key, value) in slot_config {
+            if dependencies.contains_key(key) {
+                continue;
+            }
+
+            let mut config = dependencies.clone();
+            config.insert(key.clone(), value.clone());
+
+            if let Err(err) = Mixer::apply_slot_config(&mut dry_run_slot(), config) {
+                errors.push(format!("{}: {}", key, err));
+            }
+        }
+
+        errors
+    }
+
+    /// Create an input slot, and apply its initial configuration
+    fn create_slot(
+        &mut self,
+        link_id: &str,
+        video_producer: Option<&StreamProducer>,
+        audio_producer: Option<&StreamProducer>,
+        config: Option<HashMap<String, serde_json::Value>>,
+    ) -> Result<(), Error> {
+        if self.consumer_slots.contains_key(link_id) {
+            return Err(anyhow!("mixer {} already has link {}", self.id, link_id));
+        }
+
+        if video_producer.is_none() && audio_producer.is_none() {
+            return Err(anyhow!(
+                "link {} to mixer {} has neither audio nor video",
+                link_id,
+                self.id
+            ));
+        }
+
+        let video_pad = match video_producer {
+            Some(_) => Some(
+                self.video_mixer
+                    .request_pad_simple("sink_%u")
+                    .ok_or_else(|| anyhow!("failed to request video mixer pad"))?,
+            ),
+            None => None,
+        };
+
+        let audio_pad = match audio_producer {
+            Some(_) => match self.audio_mixer.request_pad_simple("sink_%u") {
+                Some(pad) => Some(pad),
+                None => {
+                    if let Some(video_pad) = video_pad {
+                        self.video_mixer.release_request_pad(&video_pad);
+                    }
+                    return Err(anyhow!("failed to request audio mixer pad"));
+                }
+            },
+            None => None,
+        };
+
+        let mut slot = Mixer::build_slot(
+            link_id,
+            video_producer,
+            audio_producer,
+            video_pad,
+            audio_pad,
+        );
+
+        self.slot_generation += 1;
+        slot.generation = self.slot_generation;
+        slot.initial_config = config.clone();
+
+        if let Some(config) = config {
+            if let Err(err) = Mixer::apply_slot_config(&mut slot, config) {
+                self.release_slot_pads(&slot);
+                return Err(err);
+            }
+        }
+
+        // A mismatch would otherwise only be detected upon negotiation,
+        // erroring out the whole mixer
+        if let (Some(channel_map), Some(audio_producer)) = (&slot.channel_map, audio_producer) {
+            if let Err(err) = Mixer::validate_channel_map_input(channel_map, audio_producer) {
+                self.release_slot_pads(&slot);
+                return Err(err);
+            }
+        }
+
+        if let (Some(timeout), Some(video_pad)) = (slot.base_plate_timeout_ms, &slot.video_pad) {
+            self.video_mixing_state
+                .lock()
+                .unwrap()
+                .slot_timeouts
+                .insert(video_pad.name().to_string(), timeout as u64 * gst::MSECOND);
+        }
+
+        match slot.video_pad {
+            Some(ref video_pad) if slot.priority > 0 => {
+                self.video_mixing_state
+                    .lock()
+                    .unwrap()
+                    .slot_priorities
+                    .insert(video_pad.name().to_string(), slot.priority);
+            }
+            _ => (),
+        }
+
+        self.consumer_slots.insert(link_id.to_string(), slot);
+
+        Ok(())
+    }
+
+    /// Implement Disconnect command
+    ///
+    /// Teardown is ordered so that no sample reaches a half torn down slot:
+    ///
+    /// * the appsrcs are flushed, any pending or later push fails
+    ///   immediately and their streaming threads stop pushing downstream
+    /// * the slot is removed as a consumer of its producers, which waits
+    ///   for any push in progress
+    /// * the bins are brought down and removed from the pipeline
+    /// * the mixer pads are released last, once nothing is linked to them
+    #[instrument(level = "debug", name = "disconnecting", skip(self), fields(id = %self.id))]
+    fn disconnect(&mut self, slot_id: &str) -> Result<(), Error> {
+        if let Some(slot) = self.consumer_slots.remove(slot_id) {
+            for appsrc in &[&slot.video_appsrc, &slot.audio_appsrc] {
+                appsrc.send_event(gst::event::FlushStart::new());
+            }
+            slot.remove_consumers(slot_id);
+            if let Some(ref video_pad) = slot.video_pad {
+                let mut mixing_state = self.video_mixing_state.lock().unwrap();
+
+                mixing_state.slot_timeouts.remove(video_pad.name().as_str());
+                mixing_state
+                    .slot_priorities
+                    .remove(video_pad.name().as_str());
+                mixing_state
+                    .priority_selected_pts
+                    .remove(video_pad.name().as_str());
+                mixing_state.selected_pts.remove(video_pad.name().as_str());
+
+                if let Some(LateStart::Freeze { placeholder, pad }) =
+                    mixing_state.late_starts.remove(video_pad.name().as_str())
+                {
+                    placeholder.set_locked_state(true);
+                    let _ = placeholder.set_state(gst::State::Null);
+                    let _ = self.pipeline.remove(&placeholder);
+                    self.video_mixer.release_request_pad(&pad);
+                }
+            }
+            if let Some(ref audio_pad) = slot.audio_pad {
+                self.audio_mixing_state
+                    .lock()
+                    .unwrap()
+                    .selected_pts
+                    .remove(audio_pad.name().as_str());
+            }
+            for bin in slot.video_bin.iter().chain(slot.audio_bin.iter()) {
+                bin.set_locked_state(true);
+                bin.set_state(gst::State::Null).unwrap();
+                self.pipeline.remove(bin).unwrap();
+            }
+            // The pads were requested upon creating the slot, even if the
+            // mixer never started and the bins were thus never linked
+            self.release_slot_pads(&slot);
+
+            Ok(())
+        } else {
+            Err(anyhow!("mixer {} has no slot with id {}", self.id, slot_id))
+        }
+    }
+
+    /// Implement FlushSlot command
+    ///
+    /// The appsrcs of the slot are flushed, dropping their queued samples,
+    /// and compositor and audiomixer drop those of the slot pads. A
+    /// keyframe is then requested for decoding to resume cleanly.
+    ///
+    /// Control points are evaluated against the time of our output, not
+    /// that of the slot, any interpolation in progress thus carries on
+    /// from the current time.
+    #[instrument(level = "debug", name = "flushing-slot", skip(self), fields(id = %self.id))]
+    fn flush_slot(&mut self, slot_id: &str) -> Result<(), Error> {
+        let slot = self
+            .consumer_slots
+            .get(slot_id)
+            .ok_or_else(|| anyhow!("mixer {} has no slot with id {}", self.id, slot_id))?;
+
+        if slot.video_bin.is_none() && slot.audio_bin.is_none() {
+            return Err(anyhow!("slot {} is not linked yet", slot_id));
+        }
+
+        for (appsrc, bin) in &[
+            (&slot.video_appsrc, &slot.video_bin),
+            (&slot.audio_appsrc, &slot.audio_bin),
+        ] {
+            if bin.is_some() {
+                appsrc.send_event(gst::event::FlushStart::new());
+                // Keep the running time of the slot in line with ours
+                appsrc.send_event(gst::event::FlushStop::new(false));
+            }
+        }
+
+        if let Some(ref video_producer) = slot.video_producer {
+            video_producer.request_keyframe();
+        }
+
+        Ok(())
+    }
+
+    /// Implement FreezeSlot command
+    ///
+    /// While frozen, each frame received from the producer is replaced
+    /// with the first one received after freezing, keeping its
+    /// timestamps: the slot keeps presenting samples to `compositor` at
+    /// the rate of its input, and is not considered inactive by our base
+    /// plate fallback. Conversely, a frozen slot whose input stalls
+    /// stops presenting samples, and falls back as usual.
+    ///
+    /// Audio keeps flowing, and the held frame is refreshed when the
+    /// caps of the input change.
+    #[instrument(level = "debug", name = "freezing-slot", skip(self), fields(id = %self.id))]
+    fn freeze_slot(&mut self, slot_id: &str, frozen: bool) -> Result<(), Error> {
+        let slot = self
+            .consumer_slots
+            .get(slot_id)
+            .ok_or_else(|| anyhow!("mixer {} has no slot with id {}", self.id, slot_id))?;
+
+        if slot.video_pad.is_none() {
+            return Err(anyhow!("slot {} has no video input", slot_id));
+        }
+
+        let mut freeze = slot.video_freeze.lock().unwrap();
+
+        freeze.frozen = frozen;
+        freeze.held = None;
+
+        Ok(())
+    }
+
+    /// Implement SetSlotOrder command
+    ///
+    /// The listed slots get zorders from 1 upwards, right above our base
+    /// plate, the other slots are stacked above them in their current
+    /// order. While mixing, the zorders are applied from our
+    /// `samples_selected` callback, so that no frame is mixed with only
+    /// some of them applied.
+    #[instrument(level = "debug", name = "ordering-slots", skip(self), fields(id = %self.id))]
+    fn set_slot_order(&mut self, order: &[String]) -> Result<(), Error> {
+        let mut listed = HashSet::new();
+
+        for slot_id in order {
+            if !self.consumer_slots.contains_key(slot_id) {
+                return Err(anyhow!("mixer {} has no slot with id {}", self.id, slot_id));
+            }
+
+            if !listed.insert(slot_id.as_str()) {
+                return Err(anyhow!("slot {} is listed more than once", slot_id));
+            }
+        }
+
+        let mut unlisted = self
+            .consumer_slots
+            .iter()
+            .filter(|(id, _)| !listed.contains(id.as_str()))
+            .filter_map(|(id, slot)| {
+                slot.video_pad.as_ref().map(|pad| {
+                    let zorder = pad.property("zorder").unwrap().get::<u32>().unwrap();
+                    (zorder, id.as_str(), pad)
+                })
+            })
+            .collect::<Vec<_>>();
+
+        unlisted.sort_by(|a, b| (a.0, a.1).cmp(&(b.0, b.1)));
+
+        let zorders = order
+            .iter()
+            .filter_map(|slot_id| self.consumer_slots[slot_id].video_pad.as_ref())
+            .chain(unlisted.into_iter().map(|(_, _, pad)| pad))
+            .zip(1u32..)
+            .map(|(pad, zorder)| (pad.clone(), zorder))
+            .collect::<Vec<_>>();
+
+        let mut mixing_state = self.video_mixing_state.lock().unwrap();
+
+        if self.state_machine.state == State::Started {
+            mixing_state.pending_zorders = Some(zorders);
+        } else {
+            Mixer::apply_zorders(&zorders, &mixing_state.late_starts);
+        }
+
+        Ok(())
+    }
+
+    /// Implement ResizeOutputPreservingSlotLayout
+    ///
+    /// The position and size of the slots, as well as the size of the
+    /// base plate, are scaled along with the output. Slots without an
+    /// explicit size keep the size of their input, and the watermark
+    /// keeps its position. While mixing, the new layout is applied from
+    /// our `samples_selected` callback, along with the new caps, so that
+    /// no frame is mixed with only part of it applied.
+    #[instrument(level = "debug", name = "resizing-output", skip(self), fields(id = %self.id))]
+    fn resize_output_preserving_slot_layout(
+        &mut self,
+        new_width: i32,
+        new_height: i32,
+    ) -> Result<(), Error> {
+        let sizes = [("width", new_width), ("height", new_height)];
+
+        for (name, value) in &sizes {
+            SettingController::validate_value(&self.setting(name).unwrap(), &(*value).into())?;
+        }
+
+        let old_size = (
+            self.setting("width").unwrap().as_i32().unwrap(),
+            self.setting("height").unwrap().as_i32().unwrap(),
+        );
+        let new_size = (new_width, new_height);
+
+        for (name, value) in &sizes {
+            SettingController::set_from_value(&mut self.setting(name).unwrap(), &(*value).into());
+        }
+        let mut mixing_state = self.video_mixing_state.lock().unwrap();
+
+        if self.state_machine.state == State::Started {
+            // The slots are still laid out for the size preceding a
+            // resize that wasn't applied yet
+            let from = match mixing_state.pending_resize {
+                Some((from, _)) => from,
+                None => old_size,
+            };
+
+            mixing_state.pending_resize = Some((from, new_size));
+        } else {
+            // Our caps and base plate are set up from our settings upon
+            // starting
+            Mixer::scale_slot_layout(&self.video_mixer, &mixing_state, old_size, new_size);
+        }
+
+        Ok(())
+    }
+
+    /// Scale the position and size of the slot pads of `compositor`,
+    /// laid out for an output of `from` (width, height), to an output of
+    /// `to`
+    ///
+    /// The base plate and idle graphic are sized after the output rather
+    /// than scaled, and the watermark keeps its position.
+    fn scale_slot_layout(
+        compositor: &gst::Element,
+        mixing_state: &VideoMixingState,
+        from: (i32, i32),
+        to: (i32, i32),
+    ) {
+        let scale = |value: i32, from: i32, to: i32| -> i32 {
+            (value as i64 * to as i64 / from.max(1) as i64) as i32
+        };
+
+        for pad in compositor.sink_pads() {
+            if pad.name() == "sink_0"
+                || Some(&pad) == mixing_state.watermark_pad.as_ref()
+                || Some(&pad) == mixing_state.idle_graphic_pad.as_ref()
+            {
+                continue;
+            }
+
+            for (property, from, to) in &[
+                ("xpos", from.0, to.0),
+                ("ypos", from.1, to.1),
+                ("width", from.0, to.0),
+                ("height", from.1, to.1),
+            ] {
+                let value = pad.property(property).unwrap().get::<i32>().unwrap();
+
+                // A size of 0 is that of the input
+                if value != 0 || !matches!(*property, "width" | "height") {
+                    pad.set_property(property, &scale(value, *from, *to))
+                        .unwrap();
+                }
+            }
+
+            trace!(pad = %pad.name(), "scaled layout from {:?} to {:?}", from, to);
+        }
+    }
+
+    /// Set the zorder of slot pads, along with that of the placeholders
+    /// of the slots starting late
+    fn apply_zorders(zorders: &[(gst::Pad, u32)], late_starts: &HashMap<String, LateStart>) {
+        for (pad, zorder) in zorders {
+            debug!(pad = %pad.name(), zorder = %zorder, "Updating zorder");
+
+            pad.set_property("zorder", zorder).unwrap();
+
+            if let Some(LateStart::Freeze {
+                pad: placeholder_pad,
+                ..
+            }) = late_starts.get(pad.name().as_str())
+            {
+                placeholder_pad.set_property("zorder", zorder).unwrap();
+            }
+        }
+    }
+
+    /// Implement AddControlPoint command for slots
+    #[instrument(level = "debug", name = "controlling-slot", skip(self), fields(id = %self.id))]
+    fn add_slot_control_point(
+        &mut self,
+        slot_id: &str,
+        property: &str,
+        point: ControlPoint,
+    ) -> Result<(), Error> {
+        self.push_slot_control_point(slot_id, property, point, true)
+    }
+
+    /// Schedule a control point for a slot property, only validating it
+    /// if `validate` is true
+    fn push_slot_control_point(
+        &mut self,
+        slot_id: &str,
+        property: &str,
+        point: ControlPoint,
+        validate: bool,
+    ) -> Result<(), Error> {
+        if let Some(slot) = self.consumer_slots.get(slot_id) {
+            let (_, key_property) = Mixer::parse_slot_config_key(property)?;
+            let (is_video, object, property) = Mixer::slot_property_target(slot, property)?;
+
+            debug!(slot_id = %slot_id, object_name = %object.name(), property = %property, "Upserting controller");
+
+            if validate {
+                if key_property == "format" {
+                    Mixer::validate_appsrc_format(property, &point.value)?;
+                }
+                PropertyController::validate_control_point(property, &object, &point)?;
+            }
+
+            let id = slot_id.to_owned() + key_property;
+
+            if is_video {
+                let mut mixing_state = self.video_mixing_state.lock().unwrap();
+
+                mixing_state
+                    .slot_controllers
+                    .as_mut()
+                    .unwrap()
+                    .entry(id)
+                    .or_insert_with(|| PropertyController::new(slot_id, object, property))
+                    .push_control_point(point);
+            } else {
+                let mut mixing_state = self.audio_mixing_state.lock().unwrap();
+
+                mixing_state
+                    .slot_controllers
+                    .as_mut()
+                    .unwrap()
+                    .entry(id)
+                    .or_insert_with(|| PropertyController::new(slot_id, object, property))
+                    .push_control_point(point);
+            }
+
+            Ok(())
+        } else {
+            Err(anyhow!("mixer {} has no slot with id {}", self.id, slot_id))
+        }
+    }
+
+    /// Implement RemoveControlPoint command for slots
+    #[instrument(level = "debug", name = "removing control point", skip(self), fields(id = %self.id))]
+    fn remove_slot_control_point(&mut self, controller_id: &str, slot_id: &str, property: &str) {
+        let split: Vec<&str> = property.splitn(2, "::").collect();
+
+        let (is_video, property) = match split.len() {
+            2 => match split[0] {
+                "video" => (true, split[1]),
+                "audio" => (false, split[1]),
+                _ => {
+                    return;
+                }
+            },
+            _ => {
+                return;
+            }
+        };
+
+        let id = slot_id.to_owned() + property;
+
+        if is_video {
+            let mut mixing_state = self.video_mixing_state.lock().unwrap();
+
+            if let Some(controller) = mixing_state.slot_controllers.as_mut().unwrap().get_mut(&id) {
+                controller.remove_control_point(controller_id);
+            }
+        } else {
+            let mut mixing_state = self.audio_mixing_state.lock().unwrap();
+
+            if let Some(controller) = mixing_state.slot_controllers.as_mut().unwrap().get_mut(&id) {
+                controller.remove_control_point(controller_id);
+            }
+        }
+    }
+
+    /// Remove all the pending control points of a slot property, which
+    /// keeps its current value
+    #[instrument(level = "debug", name = "clearing control points", skip(self), fields(id = %self.id))]
+    fn clear_slot_control_points(&mut self, slot_id: &str, property: &str) -> Result<(), Error> {
+        if !self.consumer_slots.contains_key(slot_id) {
+            return Err(anyhow!("mixer {} has no slot with id {}", self.id, slot_id));
+        }
+
+        let (is_video, key_property) = Mixer::parse_slot_config_key(property)?;
+        let id = slot_id.to_owned() + key_property;
+
+        let mut video_mixing_state = self.video_mixing_state.lock().unwrap();
+        let mut audio_mixing_state = self.audio_mixing_state.lock().unwrap();
+
+        let slot_controllers = if is_video {
+            video_mixing_state.slot_controllers.as_mut().unwrap()
+        } else {
+            audio_mixing_state.slot_controllers.as_mut().unwrap()
+        };
+
+        if let Some(controller) = slot_controllers.get_mut(&id) {
+            controller.clear_control_points();
+        }
+
+        Ok(())
+    }
+
+    fn slot_control_points(&self) -> HashMap<String, HashMap<String, Vec<ControlPoint>>> {
+        let mut ret = HashMap::new();
+
+        let mixing_state = self.video_mixing_state.lock().unwrap();
+
+        // Controllers are identified by slot id + the property name in the
+        // slot config key, which may differ from the controlled property
+        for (id, controller) in mixing_state.slot_controllers.as_ref().unwrap() {
+            ret.entry(controller.controllee_id.clone())
+                .or_insert_with(HashMap::new)
+                .insert(
+                    "video::".to_owned() + &id[controller.controllee_id.len()..],
+                    controller.control_points(),
+                );
+        }
+
+        let mixing_state = self.audio_mixing_state.lock().unwrap();
+
+        for (id, controller) in mixing_state.slot_controllers.as_ref().unwrap() {
+            ret.entry(controller.controllee_id.clone())
+                .or_insert_with(HashMap::new)
+                .insert(
+                    "audio::".to_owned() + &id[controller.controllee_id.len()..],
+                    controller.control_points(),
+                );
+        }
+
+        ret
+    }
+
+    /// The control points of a single slot, keyed as in
+    /// [`Self::slot_control_points`]
+    fn control_points_of_slot(&self, slot_id: &str) -> HashMap<String, Vec<ControlPoint>> {
+        let mut ret = HashMap::new();
+
+        let video_mixing_state = self.video_mixing_state.lock().unwrap();
+        let audio_mixing_state = self.audio_mixing_state.lock().unwrap();
+
+        for (prefix, controllers) in &[
+            ("video::", &video_mixing_state.slot_controllers),
+            ("audio::", &audio_mixing_state.slot_controllers),
+        ] {
+            for (id, controller) in controllers.as_ref().unwrap() {
+                if controller.controllee_id == slot_id {
+                    ret.insert(
+                        prefix.to_string() + &id[slot_id.len()..],
+                        controller.control_points(),
+                    );
+                }
+            }
+        }
+
+        ret
+    }
+
+    /// Summarize the controllers of our slots, keyed as in
+    /// [`Self::slot_control_points`]
+    fn slot_control_point_summaries(
+        &self,
+    ) -> HashMap<String, HashMap<String, ControlPointSummary>> {
+        let mut ret = HashMap::new();
+
+        let video_mixing_state = self.video_mixing_state.lock().unwrap();
+        let audio_mixing_state = self.audio_mixing_state.lock().unwrap();
+
+        for (prefix, controllers) in &[
+            ("video::", &video_mixing_state.slot_controllers),
+            ("audio::", &audio_mixing_state.slot_controllers),
+        ] {
+            for (id, controller) in controllers.as_ref().unwrap() {
+                ret.entry(controller.controllee_id.clone())
+                    .or_insert_with(HashMap::new)
+                    .insert(
+                        prefix.to_string() + &id[controller.controllee_id.len()..],
+                        controller.summary(),
+                    );
+            }
+        }
+
+        ret
+    }
+
+    fn slot_settings(&self) -> HashMap<String, HashMap<String, serde_json::Value>> {
+        let mut ret = HashMap::new();
+
+        for (id, slot) in &self.consumer_slots {
+            let mut properties = HashMap::new();
+
+            properties.insert("priority".to_string(), slot.priority.into());
+
+            if let Some(ref video_pad) = slot.video_pad {
+                properties.extend(PropertyController::properties(
+                    video_pad.upcast_ref(),
+                    "video::",
+                ));
+                properties.insert(
+                    "video::pts-offset-ns".to_string(),
+                    slot.video_appsrc.static_pad("src").unwrap().offset().into(),
+                );
+                properties.insert("video::denoise".to_string(), slot.denoiser.is_some().into());
+                properties.insert(
+                    "video::late-start-mode".to_string(),
+                    slot.late_start_mode.as_str().into(),
+                );
+                if let Some(ref overlay) = slot.label_overlay {
+                    for (key, property) in &[
+                        ("video::label-text", "text"),
+                        ("video::label-font", "font-desc"),
+                    ] {
+                        properties.insert(
+                            key.to_string(),
+                            overlay
+                                .property(property)
+                                .unwrap()
+                                .get::<Option<String>>()
+                                .unwrap()
+                                .unwrap_or_default()
+                                .into(),
+                        );
+                    }
+                    properties.insert(
+                        "video::label-position".to_string(),
+                        Mixer::label_position(overlay).into(),
+                    );
+                    properties.insert(
+                        "video::label-color".to_string(),
+                        format!(
+                            "0x{:08X}",
+                            overlay.property("color").unwrap().get::<u32>().unwrap()
+                        )
+                        .into(),
+                    );
+                }
+                if let Some(ref denoiser) = slot.denoiser {
+                    let mut denoiser_properties =
+                        PropertyController::properties(denoiser.upcast_ref(), "");
+
+                    for (key, property) in &[
+                        ("video::denoise-spatial", "luma-spatial"),
+                        ("video::denoise-temporal", "luma-temporal"),
+                    ] {
+                        if let Some(value) = denoiser_properties.remove(*property) {
+                            properties.insert(key.to_string(), value);
+                        }
+                    }
+                }
+            }
+            if let Some(ref audio_pad) = slot.audio_pad {
+                properties.extend(PropertyController::properties(
+                    audio_pad.upcast_ref(),
+                    "audio::",
+                ));
+                if let Some(ref compressor) = slot.compressor {
+                    let threshold = compressor
+                        .property("threshold")
+                        .unwrap()
+                        .get::<f32>()
+                        .unwrap();
+
+                    properties.insert(
+                        "audio::compressor-threshold-db".to_string(),
+                        (20.0 * (threshold as f64).log10()).into(),
+                    );
+                    properties.insert(
+                        "audio::compressor-ratio".to_string(),
+                        compressor
+                            .property("ratio")
+                            .unwrap()
+                            .get::<f32>()
+                            .unwrap()
+                            .into(),
+                    );
+                }
+                properties.insert(
+                    "audio::pts-offset-ns".to_string(),
+                    slot.audio_appsrc.static_pad("src").unwrap().offset().into(),
+                );
+                properties.insert(
+                    "audio::allow-format-change".to_string(),
+                    slot.allow_audio_format_change.into(),
+                );
+            }
+
+            ret.insert(id.clone(), properties);
+        }
+
+        ret
+    }
+
+    /// The element a pad ultimately exchanges data with, through the
+    /// ghost pads of bins
+    fn linked_element(pad: &gst::Pad) -> Option<gst::Element> {
+        let mut peer = pad.peer()?;
+
+        loop {
+            if let Some(ghost) = peer.downcast_ref::<gst::GhostPad>() {
+                // Into the bin
+                peer = ghost.target()?;
+            } else if let Some(ghost) = peer
+                .parent()
+                .and_then(|parent| parent.downcast::<gst::GhostPad>().ok())
+            {
+                // Out of the bin, from the internal pad of its ghost pad
+                peer = ghost.peer()?;
+            } else {
+                return peer.parent_element();
+            }
+        }
+    }
+
+    /// Describe the elements linked to `element`, directly or not, in
+    /// the order they are reached from it. Bins themselves are not
+    /// described, only the elements they contain.
+    fn describe_linked_elements(element: &gst::Element) -> Vec<ElementInfo> {
+        let mut visited = HashSet::new();
+        let mut queue = VecDeque::new();
+        let mut ret = vec![];
+
+        visited.insert(element.clone());
+        queue.push_back(element.clone());
+
+        while let Some(element) = queue.pop_front() {
+            for pad in element.pads() {
+                if let Some(linked) = Mixer::linked_element(&pad) {
+                    if visited.insert(linked.clone()) {
+                        queue.push_back(linked);
+                    }
+                }
+            }
+
+            ret.push(ElementInfo {
+                name: element.name().to_string(),
+                type_name: element.type_().name().to_string(),
+                state: format!("{:?}", element.current_state()).to_lowercase(),
+                properties: PropertyController::properties(element.upcast_ref(), ""),
+            });
+        }
+
+        ret
+    }
+
+    /// Implement AddControlPoint command for the mixer
+    #[instrument(level = "debug", name = "controlling", skip(self), fields(id = %self.id))]
+    fn add_control_point(&mut self, property: String, point: ControlPoint) -> Result<(), Error> {
+        if let Some(setting) = self.settings.get(&property) {
+            SettingController::validate_control_point(&setting.lock().unwrap(), &point)?;
+
+            if Mixer::is_audio_setting(&property) {
+                let mut mixing_state = self.audio_mixing_state.lock().unwrap();
+
+                // Only inserted upon starting, when a gain is non-zero
+                if self.state_machine.state != State::Initial && mixing_state.equalizer.is_none() {
+                    return Err(anyhow!(
+                        "mixer {} was started without an equalizer, {} cannot be controlled",
+                        self.id,
+                        property
+                    ));
+                }
+
+                mixing_state
+                    .mixer_controllers
+                    .as_mut()
+                    .unwrap()
+                    .entry(property)
+                    .or_insert_with(|| SettingController::new(&self.id, setting.clone()))
+                    .push_control_point(point);
+
+                return Ok(());
+            }
+
+            let mut mixing_state = self.video_mixing_state.lock().unwrap();
+
+            mixing_state
+                .mixer_controllers
+                .as_mut()
+                .unwrap()
+                .entry(property)
+                .or_insert_with(|| SettingController::new(&self.id, setting.clone()))
+                .push_control_point(point);
+
+            Ok(())
+        } else {
+            Err(anyhow!(
+                "mixer {} has no setting with name {}",
+                self.id,
+                property
+            ))
+        }
+    }
+
+    /// Implement RemoveControlPoint command for the mixer
+    #[instrument(level = "debug", name = "removing control point", skip(self), fields(id = %self.id))]
+    fn remove_control_point(&mut self, controller_id: &str, property: &str) {
+        let mut mixing_state = self.video_mixing_state.lock().unwrap();
+
+        if let Some(controller) = mixing_state
+            .slot_controllers
+            .as_mut()
+            .unwrap()
+            .get_mut(property)
+        {
+            controller.remove_control_point(controller_id);
+        }
+    }
+
+    /// Translate our stream metadata to a tag list
+    ///
+    /// Keys naming a string GStreamer tag, eg `title`, map to that tag,
+    /// others are carried as extended comments, `key=value`
+    fn stream_tags(&self) -> Result<gst::TagList, Error> {
+        let mut tags = gst::TagList::new();
+
+        {
+            let tags = tags.get_mut().unwrap();
+
+            for (key, value) in &self.stream_metadata {
+                if gst::tags::tag_exists(key) {
+                    if gst::tags::tag_get_type(key) != String::static_type() {
+                        return Err(anyhow!("tag {} does not hold strings", key));
+                    }
+
+                    tags.add_generic(key, &value.to_send_value(), gst::TagMergeMode::Replace)
+                        .map_err(|err| anyhow!("failed to set tag {}: {:?}", key, err))?;
+                } else {
+                    tags.add::<gst::tags::ExtendedComment>(
+                        &format!("{}={}", key, value).as_str(),
+                        gst::TagMergeMode::Append,
+                    );
+                }
+            }
+        }
+
+        Ok(tags)
+    }
+
+    /// Implement SetStreamMetadata command
+    ///
+    /// The whole metadata is sent to the consumers of our producers as
+    /// a tag event, replacing the tags previously sent
+    #[instrument(level = "debug", name = "setting stream metadata", skip(self), fields(id = %self.id))]
+    fn set_stream_metadata(&mut self, key: String, value: String) -> Result<(), Error> {
+        if key.is_empty() {
+            return Err(anyhow!("metadata key cannot be empty"));
+        }
+
+        let old_value = self.stream_metadata.insert(key.clone(), value);
+
+        let tags = match self.stream_tags() {
+            Ok(tags) => tags,
+            Err(err) => {
+                match old_value {
+                    Some(old_value) => self.stream_metadata.insert(key, old_value),
+                    None => self.stream_metadata.remove(&key),
+                };
+                return Err(err);
+            }
+        };
+
+        self.video_producer.send_tags(&tags);
+        self.audio_producer.send_tags(&tags);
+
+        Ok(())
+    }
+
+    /// Implement ApplyPreset command
+    #[instrument(level = "debug", name = "applying preset", skip(self), fields(id = %self.id))]
+    fn apply_preset(&mut self, preset: MixerPreset, cue_time: DateTime<Utc>) -> Result<(), Error> {
+        self.schedule_preset("preset", &preset, cue_time, None)
+    }
+
+    /// Implement ApplyLayout command
+    ///
+    /// The layout is turned into a preset hiding the slots it doesn't map
+    #[instrument(level = "debug", name = "applying layout", skip(self), fields(id = %self.id))]
+    fn apply_layout(
+        &mut self,
+        layout: &str,
+        cue_time: DateTime<Utc>,
+        transition_ms: Option<u32>,
+    ) -> Result<(), Error> {
+        let mut slot_configs = self
+            .layouts
+            .get(layout)
+            .ok_or_else(|| anyhow!("mixer {} has no layout with name {}", self.id, layout))?
+            .clone();
+
+        // Layouts are defined ahead of time, the slots they mention may
+        // not be connected (yet)
+        let mut missing_slots = slot_configs
+            .keys()
+            .filter(|slot_id| !self.consumer_slots.contains_key(*slot_id))
+            .cloned()
+            .collect::<Vec<_>>();
+
+        if !missing_slots.is_empty() {
+            missing_slots.sort();
+
+            for slot_id in &missing_slots {
+                slot_configs.remove(slot_id);
+            }
+
+            NodeManager::from_registry().do_send(NodeStatusMessage::Warning {
+                id: self.id.clone(),
+                message: format!(
+                    "Skipping slots {} of layout {}, they are not connected",
+                    missing_slots.join(", "),
+                    layout
+                ),
+            });
+        }
+
+        // Slots the layout doesn't mention are hidden, audio-only slots
+        // have no alpha to hide or show them with
+        for (slot_id, slot) in &self.consumer_slots {
+            if slot.video_pad.is_none() {
+                continue;
+            }
+
+            let alpha = if slot_configs.contains_key(slot_id) {
+                1.0
+            } else {
+                0.0
+            };
+
+            slot_configs
+                .entry(slot_id.clone())
+                .or_insert_with(HashMap::new)
+                .entry("video::alpha".to_string())
+                .or_insert_with(|| alpha.into());
+        }
+
+        let preset = MixerPreset {
+            slot_configs,
+            mixer_settings: HashMap::new(),
+        };
+
+        let transition = transition_ms
+            .filter(|ms| *ms > 0)
+            .map(|ms| chrono::Duration::milliseconds(ms as i64));
+
+        self.schedule_preset("layout", &preset, cue_time, transition)
+    }
+
+    /// Schedule control points for all the properties of a preset
+    ///
+    /// The preset is validated as a whole before scheduling any control point.
+    /// When a transition is specified, numeric slot properties are
+    /// interpolated from their value at cue_time to that of the preset at
+    /// cue_time + transition, they are held until cue_time.
+    fn schedule_preset(
+        &mut self,
+        prefix: &str,
+        preset: &MixerPreset,
+        cue_time: DateTime<Utc>,
+        transition: Option<chrono::Duration>,
+    ) -> Result<(), Error> {
+        let point_id = format!("{}-{}", prefix, cue_time.to_rfc3339());
+        let make_point = |value: &serde_json::Value| ControlPoint {
+            id: point_id.clone(),
+            time: cue_time,
+            value: value.clone(),
+            mode: ControlMode::Set,
+        };
+
+        let mut slot_points: Vec<(String, String, ControlPoint)> = vec![];
+        let mut end_times = vec![cue_time];
+
+        for (slot_id, config) in &preset.slot_configs {
+            let slot = self
+                .consumer_slots
+                .get(slot_id)
+                .ok_or_else(|| anyhow!("mixer {} has no slot with id {}", self.id, slot_id))?;
+
+            for (key, value) in config {
+                let (_, object, property) = Mixer::slot_property_target(slot, key)?;
+
+                let interpolatable = transition
+                    .and_then(|_| PropertyController::interpolatable_value(&object, property))
+                    .is_some();
+
+                match transition {
+                    Some(transition) if interpolatable => {
+                        let end_point = ControlPoint {
+                            id: format!("{}-end", point_id),
+                            time: cue_time + transition,
+                            value: value.clone(),
+                            mode: ControlMode::Interpolate,
+                        };
+
+                        PropertyController::validate_control_point(property, &object, &end_point)?;
+
+                        end_times.push(end_point.time);
+                        // Sampled once cue_time is reached rather than now
+                        let hold_point = make_point(&serde_json::Value::Null);
+
+                        slot_points.push((slot_id.clone(), key.clone(), hold_point));
+                        slot_points.push((slot_id.clone(), key.clone(), end_point));
+                    }
+                    _ => {
+                        let point = make_point(value);
+
+                        PropertyController::validate_control_point(property, &object, &point)?;
+
+                        slot_points.push((slot_id.clone(), key.clone(), point));
+                    }
+                }
+            }
+        }
+
+        for (name, value) in &preset.mixer_settings {
+            let setting = self
+                .settings
+                .get(name)
+                .ok_or_else(|| anyhow!("mixer {} has no setting with name {}", self.id, name))?;
+
+            SettingController::validate_control_point(
+                &setting.lock().unwrap(),
+                &make_point(value),
+            )?;
+        }
+
+        {
+            let mut video_mixing_state = self.video_mixing_state.lock().unwrap();
+            let mut audio_mixing_state = self.audio_mixing_state.lock().unwrap();
+
+            for (slot_id, config) in &preset.slot_configs {
+                for key in config.keys() {
+                    let (is_video, property) = Mixer::parse_slot_config_key(key)?;
+
+                    let slot_controllers = if is_video {
+                        video_mixing_state.slot_controllers.as_mut().unwrap()
+                    } else {
+                        audio_mixing_state.slot_controllers.as_mut().unwrap()
+                    };
+
+                    if let Some(controller) =
+                        slot_controllers.get_mut(&(slot_id.to_owned() + property))
+                    {
+                        for time in &end_times {
+                            controller.remove_control_points_at(*time);
+                        }
+                    }
+                }
+            }
+
+            for name in preset.mixer_settings.keys() {
+                let mixer_controllers = if Mixer::is_audio_setting(name) {
+                    audio_mixing_state.mixer_controllers.as_mut().unwrap()
+                } else {
+                    video_mixing_state.mixer_controllers.as_mut().unwrap()
+                };
+
+                if let Some(controller) = mixer_controllers.get_mut(name) {
+                    controller.remove_control_points_at(cue_time);
+                }
+            }
+        }
+
+        // Validated above, except for the hold points of transitions
+        for (slot_id, key, point) in slot_points {
+            self.push_slot_control_point(&slot_id, &key, point, false)?;
+        }
+
+        for (name, value) in &preset.mixer_settings {
+            self.add_control_point(name.clone(), make_point(value))?;
+        }
+
+        Ok(())
+    }
+
+    /// Send EOS through our pipeline, so that the consumers of our
+    /// outputs can finalize, and stop once it has reached our outputs
+    // Returns true if calling code should wait before fully stopping
+    #[instrument(level = "debug", name = "checking if draining is needed", skip(self, ctx), fields(id = %self.id))]
+    fn drain(&mut self, ctx: &mut Context<Self>) -> bool {
+        if self.killed || !self.prerolled {
+            return false;
+        }
+
+        match self.state_machine.state {
+            // Stopping when reaching our scheduled end time
+            State::Started | State::Stopping => {
+                let pipeline_manager = self.pipeline_manager.as_ref().unwrap();
+
+                debug!("draining");
+
+                // Our inputs are not needed anymore, and would otherwise
+                // keep pushing to our appsrcs after EOS
+                for (id, slot) in self.consumer_slots.iter() {
+                    slot.remove_consumers(id);
+                }
+
+                // Sent to all our sources, both appsrcs and base plate
+                self.pipeline.send_event(gst::event::Eos::new());
+
+                let fut = pipeline_manager
+                    .send(WaitForEosMessage)
+                    .timeout(std::time::Duration::from_secs(DRAIN_TIMEOUT_SECS))
+                    .into_actor(self)
+                    .then(|res, slf, ctx| {
+                        let span = tracing::debug_span!("stopping", id = %slf.id);
+                        let _guard = span.enter();
+                        if res.is_err() {
+                            warn!("timed out waiting for EOS");
+                        } else {
+                            debug!("drained");
+                        }
+                        slf.state_machine.state = State::Stopped;
+                        ctx.stop();
+                        actix::fut::ready(())
+                    });
+
+                ctx.wait(fut);
+
+                true
+            }
+            _ => false,
+        }
+    }
+
+    #[instrument(level = "debug", skip(self, ctx), fields(id = %self.id))]
+    fn stop(&mut self, ctx: &mut Context<Self>) {
+        self.stop_schedule(ctx);
+        ctx.stop();
+    }
+}
+
+impl Schedulable<Self> for Mixer {
+    fn state_machine(&self) -> &StateMachine {
+        &self.state_machine
+    }
+
+    fn state_machine_mut(&mut self) -> &mut StateMachine {
+        &mut self.state_machine
+    }
+
+    fn node_id(&self) -> &str {
+        &self.id
+    }
+
+    #[instrument(level = "debug", skip(self, ctx), fields(id = %self.id))]
+    fn transition(
+        &mut self,
+        ctx: &mut Context<Self>,
+        target: State,
+    ) -> Result<StateChangeResult, Error> {
+        match target {
+            State::Initial => Ok(StateChangeResult::Skip),
+            State::Starting => self.start_pipeline(ctx),
+            State::Started => self.start_forwarding(),
+            State::Stopping => Ok(StateChangeResult::Skip),
+            State::Stopped => {
+                self.stop(ctx);
+                Ok(StateChangeResult::Success)
+            }
+        }
+    }
+}
+
+impl Handler<ConsumerMessage> for Mixer {
+    type Result = ResponseActFuture<Self, Result<(), Error>>;
+
+    fn handle(&mut self, msg: ConsumerMessage, ctx: &mut Context<Self>) -> Self::Result {
+        match msg {
+            ConsumerMessage::Connect {
+                link_id,
+                video_producer,
+                audio_producer,
+                config,
+                replace,
+            } => self.connect(
+                ctx,
+                &link_id,
+                video_producer.as_ref(),
+                audio_producer.as_ref(),
+                config,
+                replace,
+            ),
+            ConsumerMessage::Disconnect { slot_id } => {
+                Box::pin(actix::fut::ready(self.disconnect(&slot_id)))
+            }
+            ConsumerMessage::FlushSlot { slot_id } => {
+                Box::pin(actix::fut::ready(self.flush_slot(&slot_id)))
+            }
+            ConsumerMessage::FreezeSlot { slot_id, frozen } => {
+                Box::pin(actix::fut::ready(self.freeze_slot(&slot_id, frozen)))
+            }
+            ConsumerMessage::SetSlotOrder { order } => {
+                Box::pin(actix::fut::ready(self.set_slot_order(&order)))
+            }
+            ConsumerMessage::AddControlPoint {
+                slot_id,
+                property,
+                control_point,
+            } => Box::pin(actix::fut::ready(self.add_slot_control_point(
+                &slot_id,
+                &property,
+                control_point,
+            ))),
+            ConsumerMessage::RemoveControlPoint {
+                controller_id,
+                slot_id,
+                property,
+            } => {
+                self.remove_slot_control_point(&controller_id, &slot_id, &property);
+                Box::pin(actix::fut::ready(Ok(())))
+            }
+        }
+    }
+}
+
+impl Handler<StartMessage> for Mixer {
+    type Result = MessageResult<StartMessage>;
+
+    fn handle(&mut self, msg: StartMessage, ctx: &mut Context<Self>) -> Self::Result {
+        MessageResult(self.start_schedule(ctx, msg.cue_time, msg.end_time))
+    }
+}
+
+impl Handler<ErrorMessage> for Mixer {
+    type Result = ();
+
+    fn handle(&mut self, msg: ErrorMessage, ctx: &mut Context<Self>) -> Self::Result {
+        error!("Got error message '{}' on destination {}", msg.0, self.id,);
+
+        NodeManager::from_registry().do_send(NodeStatusMessage::Error {
+            id: self.id.clone(),
+            message: msg.0,
+        });
+
+        gst::debug_bin_to_dot_file_with_ts(
+            &self.pipeline,
+            gst::DebugGraphDetails::all(),
+            format!("error-mixer-{}", self.id),
+        );
+
+        ctx.stop();
+    }
+}
+
+impl Handler<GetProducerMessage> for Mixer {
+    type Result = MessageResult<GetProducerMessage>;
+
+    fn handle(&mut self, _msg: GetProducerMessage, _ctx: &mut Context<Self>) -> Self::Result {
+        MessageResult(Ok((
+            self.video_producer.clone(),
+            self.audio_producer.clone(),
+        )))
+    }
+}
+
+impl Handler<ScheduleMessage> for Mixer {
+    type Result = Result<(), Error>;
+
+    fn handle(&mut self, msg: ScheduleMessage, ctx: &mut Context<Self>) -> Self::Result {
+        self.reschedule(ctx, msg.cue_time, msg.end_time)
+    }
+}
+
+impl Handler<StopMessage> for Mixer {
+    type Result = Result<(), Error>;
+
+    fn handle(&mut self, _msg: StopMessage, ctx: &mut Context<Self>) -> Self::Result {
+        ctx.stop();
+        Ok(())
+    }
+}
+
+impl Handler<KillMessage> for Mixer {
+    type Result = Result<(), Error>;
+
+    fn handle(&mut self, _msg: KillMessage, ctx: &mut Context<Self>) -> Self::Result {
+        self.killed = true;
+        ctx.stop();
+        Ok(())
+    }
+}
+
+impl Handler<GetLatencyMessage> for Mixer {
+    type Result = ResponseFuture<Result<PipelineLatency, Error>>;
+
+    fn handle(&mut self, _msg: GetLatencyMessage, _ctx: &mut Context<Self>) -> Self::Result {
+        query_pipeline_latency(self.pipeline_manager.clone(), &self.id)
+    }
+}
+
+impl Handler<GetStateMessage> for Mixer {
+    type Result = MessageResult<GetStateMessage>;
+
+    fn handle(&mut self, _msg: GetStateMessage, _ctx: &mut Context<Self>) -> Self::Result {
+        MessageResult(self.state_machine.state)
+    }
+}
+
+impl Handler<GetNodeInfoMessage> for Mixer {
+    type Result = Result<NodeInfo, Error>;
+
+    fn handle(&mut self, _msg: GetNodeInfoMessage, _ctx: &mut Context<Self>) -> Self::Result {
+        let video_mixing_state = self.video_mixing_state.lock().unwrap();
+        let audio_mixing_state = self.audio_mixing_state.lock().unwrap();
+        let selected_pts = |pad: &Option<gst::Pad>, selected_pts: &HashMap<String, u64>| {
+            pad.as_ref()
+                .and_then(|pad| selected_pts.get(pad.name().as_str()).copied())
+                .unwrap_or(0)
+        };
+        let n_dropped = |producer: &Option<StreamProducer>, id: &str| {
+            producer
+                .as_ref()
+                .and_then(|producer| producer.stats().n_dropped_samples.get(id).copied())
+                .unwrap_or(0)
+        };
+
+        let slots = self
+            .consumer_slots
+            .iter()
+            .map(|(id, slot)| {
+                let video_input = slot.video_stats.lock().unwrap().info();
+                let audio_input = slot.audio_stats.lock().unwrap().info();
+
+                (
+                    id.clone(),
+                    MixerSlotInfo {
+                        // Control points update the pad only
+                        volume: slot.audio_pad.as_ref().map_or(slot.volume, |pad| {
+                            pad.property("volume").unwrap().get::<f64>().unwrap()
+                        }),
+                        label: slot.label.clone(),
+                        native_sample_rate: slot.audio_stats.lock().unwrap().sample_rate,
+                        video_buffers_dropped: n_dropped(&slot.video_producer, id),
+                        audio_buffers_dropped: n_dropped(&slot.audio_producer, id),
+                        last_video_pts_ns: selected_pts(
+                            &slot.video_pad,
+                            &video_mixing_state.selected_pts,
+                        ),
+                        last_audio_pts_ns: selected_pts(
+                            &slot.audio_pad,
+                            &audio_mixing_state.selected_pts,
+                        ),
+                        video_input,
+                        audio_input,
+                    },
+                )
+            })
+            .collect();
+
+        drop(video_mixing_state);
+        drop(audio_mixing_state);
+
+        Ok(NodeInfo::Mixer(MixerInfo {
+            slots,
+            consumer_slot_ids: self.video_producer.get_consumer_ids(),
+            cue_time: self.state_machine.cue_time,
+            end_time: self.state_machine.end_time,
+            state: self.state_machine.state,
+            settings: self.settings(),
+            control_points: self.control_points(),
+            slot_settings: self.slot_settings(),
+            slot_control_points: self.slot_control_points(),
+            control_point_summaries: self.control_point_summaries(),
+            slot_control_point_summaries: self.slot_control_point_summaries(),
+            base_plate_active: self.video_mixing_state.lock().unwrap().showing_base_plate,
+            processing_lag_ns: self.video_mixing_state.lock().unwrap().processing_lag,
+            falling_behind: self.video_mixing_state.lock().unwrap().falling_behind,
+            video_forwarding: self.video_producer.is_forwarding(),
+            audio_forwarding: self.audio_producer.is_forwarding(),
+            program_number: self.setting("program-number").unwrap().as_i32().unwrap() as u32,
+            stream_id: self
+                .setting("stream-id")
+                .unwrap()
+                .as_str()
+                .unwrap()
+                .to_string(),
+            stream_metadata: self.stream_metadata.clone(),
+            video_caps: Mixer::output_caps(&self.video_producer),
+            audio_caps: Mixer::output_caps(&self.audio_producer),
+        }))
+    }
+}
+
+impl Handler<AddControlPointMessage> for Mixer {
+    type Result = Result<(), Error>;
+
+    fn handle(&mut self, msg: AddControlPointMessage, _ctx: &mut Context<Self>) -> Self::Result {
+        self.add_control_point(msg.property, msg.control_point)
+    }
+}
+
+impl Handler<ApplyPresetMessage> for Mixer {
+    type Result = Result<(), Error>;
+
+    fn handle(&mut self, msg: ApplyPresetMessage, _ctx: &mut Context<Self>) -> Self::Result {
+        self.apply_preset(msg.preset, msg.cue_time)
+    }
+}
+
+impl Handler<ApplyLayoutMessage> for Mixer {
+    type Result = Result<(), Error>;
+
+    fn handle(&mut self, msg: ApplyLayoutMessage, _ctx: &mut Context<Self>) -> Self::Result {
+        self.apply_layout(&msg.layout, msg.cue_time, msg.transition_ms)
+    }
+}
+
+impl Handler<SlotEosMessage> for Mixer {
+    type Result = ();
+
+    fn handle(&mut self, msg: SlotEosMessage, ctx: &mut Context<Self>) {
+        // EOS is also sent to the slots when stopping
+        if self.state_machine.state != State::Started {
+            return;
+        }
+
+        let slot = match self.consumer_slots.get(&msg.slot_id) {
+            Some(slot) if slot.generation == msg.generation => slot,
+            _ => return,
+        };
+
+        debug!(id = %self.id, slot_id = %msg.slot_id, "slot input reached EOS, reconnecting once its producers output data");
+
+        // The producers would otherwise send EOS again to the new slot
+        let next_streams = future::join_all(
+            slot.video_producer
+                .iter()
+                .chain(slot.audio_producer.iter())
+                .map(|producer| producer.next_stream()),
+        );
+        let delay = tokio::time::sleep(std::time::Duration::from_millis(
+            slot.reconnect_delay_ms as u64,
+        ));
+
+        ctx.spawn(
+            future::join(next_streams, delay)
+                .into_actor(self)
+                .map(move |_, _slf, ctx| {
+                    ctx.notify(ReconnectSlotMessage {
+                        slot_id: msg.slot_id,
+                        generation: msg.generation,
+                    })
+                }),
+        );
+    }
+}
+
+impl Handler<ReconnectSlotMessage> for Mixer {
+    type Result = ();
+
+    fn handle(&mut self, msg: ReconnectSlotMessage, ctx: &mut Context<Self>) {
+        let (video_producer, audio_producer, config) = match self.consumer_slots.get(&msg.slot_id) {
+            Some(slot) if slot.generation == msg.generation => (
+                slot.video_producer.clone(),
+                slot.audio_producer.clone(),
+                slot.initial_config.clone(),
+            ),
+            _ => {
+                debug!(id = %self.id, slot_id = %msg.slot_id, "slot was disconnected, not reconnecting");
+                return;
+            }
+        };
+
+        debug!(id = %self.id, slot_id = %msg.slot_id, "reconnecting slot");
+
+        let fut = self.connect(
+            ctx,
+            &msg.slot_id,
+            video_producer.as_ref(),
+            audio_producer.as_ref(),
+            config,
+            true,
+        );
+        let slot_id = msg.slot_id;
+
+        ctx.spawn(fut.map(move |res, slf, _ctx| {
+            if let Err(err) = res {
+                NodeManager::from_registry().do_send(NodeStatusMessage::Warning {
+                    id: slf.id.clone(),
+                    message: format!("Failed to reconnect slot {}: {}", slot_id, err),
+                });
+            }
+        }));
+    }
+}
+
+impl Handler<GetAudioMonitorProducerMessage> for Mixer {
+    type Result = MessageResult<GetAudioMonitorProducerMessage>;
+
+    fn handle(
+        &mut self,
+        _msg: GetAudioMonitorProducerMessage,
+        _ctx: &mut Context<Self>,
+    ) -> Self::Result {
+        if self
+            .setting("enable-audio-monitor")
+            .unwrap()
+            .as_bool()
+            .unwrap()
+        {
+            MessageResult(Ok(self.audio_monitor_producer.clone()))
+        } else {
+            MessageResult(Err(anyhow!(
+                "Audio monitor is not enabled on mixer {}",
+                self.id
+            )))
+        }
+    }
+}
+
+impl Handler<SubscribeAudioLevelMessage> for Mixer {
+    type Result = MessageResult<SubscribeAudioLevelMessage>;
+
+    fn handle(
+        &mut self,
+        _msg: SubscribeAudioLevelMessage,
+        _ctx: &mut Context<Self>,
+    ) -> Self::Result {
+        MessageResult(self.last_audio_level.clone())
+    }
+}
+
+impl Handler<PreviewOutputMessage> for Mixer {
+    type Result = ResponseActFuture<Self, Result<Vec<u8>, Error>>;
+
+    fn handle(&mut self, msg: PreviewOutputMessage, _ctx: &mut Context<Self>) -> Self::Result {
+        if self.state_machine.state != State::Started {
+            let err = anyhow!("Mixer {} must be started to preview its output", self.id);
+            return Box::pin(actix::fut::err(err));
+        }
+
+        let (sample_receiver, probes) = Mixer::next_video_sample(&self.video_producer);
+
+        Box::pin(
+            async move {
+                let sample =
+                    Mixer::receive_samples(sample_receiver, probes, "video sample was").await?;
+
+                Mixer::encode_preview(sample, msg.width, msg.height).await
+            }
+            .into_actor(self),
+        )
+    }
+}
+
+impl Handler<TakeSnapshotMessage> for Mixer {
+    type Result = ResponseActFuture<Self, Result<MixerSnapshot, Error>>;
+
+    fn handle(&mut self, _msg: TakeSnapshotMessage, _ctx: &mut Context<Self>) -> Self::Result {
+        if self.state_machine.state != State::Started {
+            let err = anyhow!("Mixer {} must be started to take a snapshot", self.id);
+            return Box::pin(actix::fut::err(err));
+        }
+
+        let (samples_receiver, probes) =
+            Mixer::next_synchronized_samples(&self.video_producer, &self.audio_producer);
+
+        Box::pin(
+            async move {
+                let (video, audio) =
+                    Mixer::receive_samples(samples_receiver, probes, "synchronized").await?;
+
+                Mixer::encode_snapshot(video, audio).await
+            }
+            .into_actor(self),
+        )
+    }
+}
+
+impl Handler<UpdateSettingMessage> for Mixer {
+    type Result = Result<(), Error>;
+
+    fn handle(&mut self, msg: UpdateSettingMessage, _ctx: &mut Context<Self>) -> Self::Result {
+        let now = get_clock_now(self.clock.as_ref());
+
+        self.add_control_point(
+            msg.name,
+            ControlPoint {
+                id: format!("update-{}", now.to_rfc3339()),
+                time: now,
+                value: msg.value,
+                mode: ControlMode::Set,
+            },
+        )
+    }
+}
+
+impl Handler<SetCompositorBackgroundMessage> for Mixer {
+    type Result = Result<(), Error>;
+
+    fn handle(
+        &mut self,
+        msg: SetCompositorBackgroundMessage,
+        _ctx: &mut Context<Self>,
+    ) -> Self::Result {
+        let value = serde_json::Value::from(msg.background.as_str());
+        let mut setting = self.settings["compositor-background"].lock().unwrap();
+
+        SettingController::validate_value(&setting, &value)?;
+        SettingController::set_from_value(&mut setting, &value);
+
+        // Applies from the next output frame, no need to restart
+        self.video_mixer
+            .set_property_from_str("background", &msg.background);
+
+        Ok(())
+    }
+}
+
+impl Handler<SetIgnoreInactivePadsMessage> for Mixer {
+    type Result = Result<(), Error>;
+
+    fn handle(
+        &mut self,
+        msg: SetIgnoreInactivePadsMessage,
+        _ctx: &mut Context<Self>,
+    ) -> Self::Result {
+        for (value, setting_name, mixer) in &[
+            (msg.video, "video-ignore-inactive-pads", &self.video_mixer),
+            (msg.audio, "audio-ignore-inactive-pads", &self.audio_mixer),
+        ] {
+            if let Some(value) = value {
+                let mut setting = self.settings[*setting_name].lock().unwrap();
+
+                SettingController::set_from_value(&mut setting, &serde_json::Value::from(*value));
+
+                mixer.set_property("ignore-inactive-pads", value).unwrap();
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Handler<CaptureFrameToFileMessage> for Mixer {
+    type Result = ResponseActFuture<Self, Result<u64, Error>>;
+
+    fn handle(&mut self, msg: CaptureFrameToFileMessage, _ctx: &mut Context<Self>) -> Self::Result {
+        if self.state_machine.state != State::Started {
+            let err = anyhow!("Mixer {} must be started to capture its output", self.id);
+            return Box::pin(actix::fut::err(err));
+        }
+
+        let (sample_receiver, probes) = Mixer::next_video_sample(&self.video_producer);
+
+        Box::pin(
+            async move {
+                let sample =
+                    Mixer::receive_samples(sample_receiver, probes, "video sample was").await?;
+
+                Mixer::encode_to_file(sample, msg.path, msg.format).await
+            }
+            .into_actor(self),
+        )
+    }
+}
+
+impl Handler<AddOutputScaleMessage> for Mixer {
+    type Result = Result<(), Error>;
+
+    fn handle(&mut self, msg: AddOutputScaleMessage, _ctx: &mut Context<Self>) -> Self::Result {
+        self.add_output_scale(&msg.output_id, msg.width, msg.height, msg.framerate)
+    }
+}
+
+impl Handler<GetOutputProducerMessage> for Mixer {
+    type Result = MessageResult<GetOutputProducerMessage>;
+
+    fn handle(&mut self, msg: GetOutputProducerMessage, _ctx: &mut Context<Self>) -> Self::Result {
+        if let Err(err) = self.prune_failed_video_tee() {
+            return MessageResult(Err(err));
+        }
+
+        MessageResult(
+            self.scaled_outputs
+                .get(&msg.output_id)
+                .map(|output| output.producer.clone())
+                .ok_or_else(|| {
+                    anyhow!("mixer {} has no output with id {}", self.id, msg.output_id)
+                }),
+        )
+    }
+}
+
+impl Handler<RemoveOutputMessage> for Mixer {
+    type Result = Result<Vec<String>, Error>;
+
+    fn handle(&mut self, msg: RemoveOutputMessage, _ctx: &mut Context<Self>) -> Self::Result {
+        self.remove_output(&msg.output_id)
+    }
+}
+
+impl Handler<DumpPipelineMessage> for Mixer {
+    type Result = Result<(), Error>;
+
+    fn handle(&mut self, _msg: DumpPipelineMessage, _ctx: &mut Context<Self>) -> Self::Result {
+        if std::env::var_os("GST_DEBUG_DUMP_DOT_DIR").is_none() {
+            return Err(anyhow!(
+                "Cannot dump pipeline of mixer {}: GST_DEBUG_DUMP_DOT_DIR is not set",
+                self.id
+            ));
+        }
+
+        debug!(id = %self.id, "dumping pipeline");
+
+        gst::debug_bin_to_dot_file_with_ts(
+            &self.pipeline,
+            gst::DebugGraphDetails::all(),
+            format!("dump-mixer-{}", self.id),
+        );
+
+        Ok(())
+    }
+}
+
+impl Handler<GetAudioPipelineDescriptionMessage> for Mixer {
+    type Result = MessageResult<GetAudioPipelineDescriptionMessage>;
+
+    fn handle(
+        &mut self,
+        _msg: GetAudioPipelineDescriptionMessage,
+        _ctx: &mut Context<Self>,
+    ) -> Self::Result {
+        MessageResult(Ok(Mixer::describe_linked_elements(&self.audio_mixer)))
+    }
+}
+
+impl Handler<GetVideoPipelineDescriptionMessage> for Mixer {
+    type Result = MessageResult<GetVideoPipelineDescriptionMessage>;
+
+    fn handle(
+        &mut self,
+        _msg: GetVideoPipelineDescriptionMessage,
+        _ctx: &mut Context<Self>,
+    ) -> Self::Result {
+        MessageResult(Ok(Mixer::describe_linked_elements(&self.video_mixer)))
+    }
+}
+
+impl Handler<GetSlotControlPointsMessage> for Mixer {
+    type Result = Result<HashMap<String, Vec<ControlPoint>>, Error>;
+
+    fn handle(
+        &mut self,
+        msg: GetSlotControlPointsMessage,
+        _ctx: &mut Context<Self>,
+    ) -> Self::Result {
+        if !self.consumer_slots.contains_key(&msg.slot_id) {
+            return Err(anyhow!(
+                "mixer {} has no slot with id {}",
+                self.id,
+                msg.slot_id
+            ));
+        }
+
+        Ok(self.control_points_of_slot(&msg.slot_id))
+    }
+}
+
+impl Handler<ResizeOutputPreservingSlotLayoutMessage> for Mixer {
+    type Result = Result<(), Error>;
+
+    fn handle(
+        &mut self,
+        msg: ResizeOutputPreservingSlotLayoutMessage,
+        _ctx: &mut Context<Self>,
+    ) -> Self::Result {
+        self.resize_output_preserving_slot_layout(msg.new_width, msg.new_height)
+    }
+}
+
+impl Handler<SetCompositorLatencyMessage> for Mixer {
+    type Result = Result<(), Error>;
+
+    fn handle(
+        &mut self,
+        msg: SetCompositorLatencyMessage,
+        _ctx: &mut Context<Self>,
+    ) -> Self::Result {
+        Mixer::set_aggregator_latency(&self.video_mixer, msg.latency_ns)
+    }
+}
+
+impl Handler<SetAudiomixerLatencyMessage> for Mixer {
+    type Result = Result<(), Error>;
+
+    fn handle(
+        &mut self,
+        msg: SetAudiomixerLatencyMessage,
+        _ctx: &mut Context<Self>,
+    ) -> Self::Result {
+        Mixer::set_aggregator_latency(&self.audio_mixer, msg.latency_ns)
+    }
+}
+
+impl Handler<ValidateConfigMessage> for Mixer {
+    type Result = MessageResult<ValidateConfigMessage>;
+
+    fn handle(&mut self, msg: ValidateConfigMessage, _ctx: &mut Context<Self>) -> Self::Result {
+        MessageResult(self.validate_config(&msg.settings, &msg.slot_config))
+    }
+}
+
+impl Handler<ClearSlotControlPointsMessage> for Mixer {
+    type Result = Result<(), Error>;
+
+    fn handle(
+        &mut self,
+        msg: ClearSlotControlPointsMessage,
+        _ctx: &mut Context<Self>,
+    ) -> Self::Result {
+        self.clear_slot_control_points(&msg.slot_id, &msg.property)
+    }
+}
+
+impl Handler<SetSlotLabelMessage> for Mixer {
+    type Result = Result<(), Error>;
+
+    fn handle(&mut self, msg: SetSlotLabelMessage, _ctx: &mut Context<Self>) -> Self::Result {
+        let slot = self
+            .consumer_slots
+            .get(&msg.slot_id)
+            .ok_or_else(|| anyhow!("mixer {} has no slot with id {}", self.id, msg.slot_id))?;
+
+        let overlay = slot
+            .label_overlay
+            .as_ref()
+            .ok_or_else(|| anyhow!("slot {} cannot be labelled", msg.slot_id))?;
+
+        debug!(id = %self.id, slot_id = %msg.slot_id, text = %msg.text, "setting slot label");
+
+        overlay.set_property("text", &msg.text).unwrap();
+
+        Ok(())
+    }
+}
+
+impl Handler<SetStreamMetadataMessage> for Mixer {
+    type Result = Result<(), Error>;
+
+    fn handle(&mut self, msg: SetStreamMetadataMessage, _ctx: &mut Context<Self>) -> Self::Result {
+        self.set_stream_metadata(msg.key, msg.value)
+    }
+}
+
+impl Handler<RemoveControlPointMessage> for Mixer {
+    type Result = ();
+
+    fn handle(&mut self, msg: RemoveControlPointMessage, _ctx: &mut Context<Self>) -> Self::Result {
+        self.remove_control_point(&msg.controller_id, &msg.property)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::node::{CommandMessage, PreviewMixerOutputMessage, SubscribeMixerAudioLevelMessage};
+    use crate::utils::get_now;
+    use crate::utils::tests::*;
+    use auteur_controlling::controller::{
+        Command, CommandResult, ConnectMedia, DuplicateLinkPolicy,
+    };
+    use test_env_log::test;
+
+    fn make_producer(name: &str) -> StreamProducer {
+        let appsink = gst::ElementFactory::make("appsink", Some(name))
+            .unwrap()
+            .downcast::<gst_app::AppSink>()
+            .unwrap();
+
+        StreamProducer::from(&appsink)
+    }
+
+    #[actix_rt::test]
+    #[test]
+    async fn test_get_slot_control_points() {
+        gst::init().unwrap();
+
+        let addr = Mixer::new("test-mixer", None).unwrap().start();
+
+        for link_id in &["slot-0", "slot-1"] {
+            addr.send(ConsumerMessage::Connect {
+                link_id: link_id.to_string(),
+                video_producer: Some(make_producer(&format!("{}-video-producer", link_id))),
+                audio_producer: None,
+                config: None,
+                replace: false,
+            })
+            .await
+            .unwrap()
+            .unwrap();
+
+            addr.send(ConsumerMessage::AddControlPoint {
+                slot_id: link_id.to_string(),
+                property: "video::xpos".to_string(),
+                control_point: ControlPoint {
+                    id: "test-controller".to_string(),
+                    time: get_now() + chrono::Duration::seconds(60),
+                    value: 100.into(),
+                    mode: ControlMode::Set,
+                },
+            })
+            .await
+            .unwrap()
+            .unwrap();
+        }
+
+        let control_points = addr
+            .send(GetSlotControlPointsMessage {
+                slot_id: "slot-1".to_string(),
+            })
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(control_points.len(), 1);
+        assert_eq!(control_points["video::xpos"].len(), 1);
+
+        assert!(addr
+            .send(GetSlotControlPointsMessage {
+                slot_id: "slot-2".to_string(),
+            })
+            .await
+            .unwrap()
+            .is_err());
+    }
+
+    #[actix_rt::test]
+    #[test]
+    async fn test_slot_buffer_statistics() {
+        gst::init().unwrap();
+
+        create_mixer("test-producer", None).await.unwrap();
+        create_mixer("test-mixer", None).await.unwrap();
+        connect("link-0", "test-producer", "test-mixer")
+            .await
+            .unwrap();
+
+        start_node("test-producer", None, None).await.unwrap();
+        start_node("test-mixer", None, None).await.unwrap();
+
+        let slot_info = || async {
+            match node_info_unchecked("test-mixer").await {
+                NodeInfo::Mixer(mut info) => info.slots.remove("link-0").unwrap(),
+                _ => unreachable!(),
+            }
+        };
+
+        let mut info = slot_info().await;
+        for _ in 0..50 {
+            if info.video_input.n_buffers > 0
+                && info.audio_input.n_buffers > 0
+                && info.last_video_pts_ns > 0
+                && info.last_audio_pts_ns > 0
+            {
+                break;
+            }
+
+            tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+            info = slot_info().await;
+        }
+
+        assert!(info.video_input.n_buffers > 0);
+        assert!(info.audio_input.n_buffers > 0);
+        assert!(info.last_video_pts_ns > 0);
+        assert!(info.last_audio_pts_ns > 0);
+    }
+
+    #[actix_rt::test]
+    #[test]
+    async fn test_get_slot_control_points_command() {
+        gst::init().unwrap();
+
+        create_source("test-source", &asset_uri("ball.mp4"))
+            .await
+            .unwrap();
+        create_mixer("test-mixer", None).await.unwrap();
+        connect("link-0", "test-source", "test-mixer")
+            .await
+            .unwrap();
+
+        run_command(Command::AddControlPoint {
+            controllee_id: "link-0".to_string(),
+            property: "video::xpos".to_string(),
+            control_point: ControlPoint {
+                id: "test-controller".to_string(),
+                time: get_now() + chrono::Duration::seconds(60),
+                value: 100.into(),
+                mode: ControlMode::Set,
+            },
+        })
+        .await
+        .unwrap();
+
+        let get_control_points = |slot_id: &str| {
+            NodeManager::from_registry().send(CommandMessage {
+                command: Command::GetSlotControlPoints {
+                    id: "test-mixer".to_string(),
+                    slot_id: slot_id.to_string(),
+                },
+            })
+        };
+
+        match get_control_points("link-0").await.unwrap() {
+            CommandResult::ControlPoints(control_points) => {
+                assert_eq!(control_points.len(), 1);
+                assert_eq!(control_points["video::xpos"][0].id, "test-controller");
+            }
+            res => panic!("Unexpected result {:?}", res),
+        }
+
+        match get_control_points("link-1").await.unwrap() {
+            CommandResult::Error(_) => (),
+            res => panic!("Unexpected result {:?}", res),
+        }
+    }
+
+    #[actix_rt::test]
+    #[test]
+    async fn test_get_pipeline_description_command() {
+        gst::init().unwrap();
+
+        create_mixer("test-mixer", None).await.unwrap();
+
+        let describe = |command: Command, type_name: &str| {
+            let type_name = type_name.to_string();
+
+            NodeManager::from_registry()
+                .send(CommandMessage { command })
+                .map(move |res| {
+                    // As received by a controller
+                    let res = serde_json::to_string(&res.unwrap()).unwrap();
+
+                    match serde_json::from_str::<CommandResult>(&res).unwrap() {
+                        CommandResult::PipelineDescription(elements) => {
+                            let mixer = elements
+                                .iter()
+                                .find(|element| element.type_name == type_name)
+                                .unwrap();
+                            // Not started yet
+                            assert_eq!(mixer.state, "null");
+                            assert!(mixer.properties.contains_key("latency"));
+                        }
+                        res => panic!("Unexpected result {:?}", res),
+                    }
+                })
+        };
+
+        describe(
+            Command::GetAudioPipelineDescription {
+                id: "test-mixer".to_string(),
+            },
+            "GstAudioMixer",
+        )
+        .await;
+        describe(
+            Command::GetVideoPipelineDescription {
+                id: "test-mixer".to_string(),
+            },
+            "GstCompositor",
+        )
+        .await;
+
+        match NodeManager::from_registry()
+            .send(CommandMessage {
+                command: Command::GetAudioPipelineDescription {
+                    id: "no-such-mixer".to_string(),
+                },
+            })
+            .await
+            .unwrap()
+        {
+            CommandResult::Error(err) => assert!(err.contains("No node with id")),
+            res => panic!("Unexpected result {:?}", res),
+        }
+    }
+
+    #[actix_rt::test]
+    #[test]
+    async fn test_clear_slot_control_points() {
+        gst::init().unwrap();
+
+        let addr = Mixer::new("test-mixer", None).unwrap().start();
+
+        addr.send(ConsumerMessage::Connect {
+            link_id: "slot-0".to_string(),
+            video_producer: Some(make_producer("slot-0-video-producer")),
+            audio_producer: None,
+            config: None,
+            replace: false,
+        })
+        .await
+        .unwrap()
+        .unwrap();
+
+        for (id, property) in &[("first", "video::xpos"), ("second", "video::ypos")] {
+            addr.send(ConsumerMessage::AddControlPoint {
+                slot_id: "slot-0".to_string(),
+                property: property.to_string(),
+                control_point: ControlPoint {
+                    id: id.to_string(),
+                    time: get_now() + chrono::Duration::seconds(60),
+                    value: 100.into(),
+                    mode: ControlMode::Set,
+                },
+            })
+            .await
+            .unwrap()
+            .unwrap();
+        }
+
+        addr.send(ClearSlotControlPointsMessage {
+            slot_id: "slot-0".to_string(),
+            property: "video::xpos".to_string(),
+        })
+        .await
+        .unwrap()
+        .unwrap();
+
+        let control_points = addr
+            .send(GetSlotControlPointsMessage {
+                slot_id: "slot-0".to_string(),
+            })
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert!(control_points["video::xpos"].is_empty());
+        assert_eq!(control_points["video::ypos"].len(), 1);
+
+        assert!(addr
+            .send(ClearSlotControlPointsMessage {
+                slot_id: "slot-1".to_string(),
+                property: "video::xpos".to_string(),
+            })
+            .await
+            .unwrap()
+            .is_err());
+    }
+
+    #[actix_rt::test]
+    #[test]
+    async fn test_clear_slot_control_points_command() {
+        gst::init().unwrap();
+
+        create_source("test-source", &asset_uri("ball.mp4"))
+            .await
+            .unwrap();
+        create_mixer("test-mixer", None).await.unwrap();
+        connect("link-0", "test-source", "test-mixer")
+            .await
+            .unwrap();
+
+        for property in &["video::xpos", "video::ypos"] {
+            run_command(Command::AddControlPoint {
+                controllee_id: "link-0".to_string(),
+                property: property.to_string(),
+                control_point: ControlPoint {
+                    id: "test-controller".to_string(),
+                    time: get_now() + chrono::Duration::seconds(60),
+                    value: 100.into(),
+                    mode: ControlMode::Set,
+                },
+            })
+            .await
+            .unwrap();
+        }
+
+        let clear_control_points = |slot_id: &str| {
+            run_command(Command::ClearSlotControlPoints {
+                id: "test-mixer".to_string(),
+                slot_id: slot_id.to_string(),
+                property: "video::xpos".to_string(),
+            })
+        };
+
+        clear_control_points("link-0").await.unwrap();
+        assert!(clear_control_points("link-1").await.is_err());
+
+        match NodeManager::from_registry()
+            .send(CommandMessage {
+                command: Command::GetSlotControlPoints {
+                    id: "test-mixer".to_string(),
+                    slot_id: "link-0".to_string(),
+                },
+            })
+            .await
+            .unwrap()
+        {
+            CommandResult::ControlPoints(control_points) => {
+                assert!(control_points["video::xpos"].is_empty());
+                assert_eq!(control_points["video::ypos"].len(), 1);
+            }
+            res => panic!("Unexpected result {:?}", res),
+        }
+    }
+
+    #[test]
+    fn test_output_channel_mode() {
+        gst::init().unwrap();
+
+        let channels = |mode: &str| {
+            let mut config = HashMap::new();
+            config.insert("output-channel-mode".to_string(), serde_json::json!(mode));
+
+            Mixer::new("test-mixer", Some(config))
+                .map(|mixer| (mixer.mix_channels(), mixer.output_channels()))
+        };
+
+        assert_eq!(channels("stereo").unwrap(), (Some(2), Some(2)));
+        assert_eq!(channels("mono").unwrap(), (Some(2), Some(1)));
+        assert_eq!(channels("5.1").unwrap(), (Some(6), Some(6)));
+        assert_eq!(channels("pass-through").unwrap(), (None, None));
+        assert!(channels("7.1").is_err());
+
+        let mut config = HashMap::new();
+        config.insert(
+            "output-channel-mode".to_string(),
+            serde_json::json!("pass-through"),
+        );
+        let mixer = Mixer::new("test-mixer", Some(config)).unwrap();
+        let caps = mixer.base_audio_caps("S16LE", 48000);
+
+        // Without any negotiated slot, the base signal is stereo
+        assert_eq!(
+            caps.structure(0).unwrap().get::<i32>("channels").unwrap(),
+            2
+        );
+
+        let caps = Mixer::raw_audio_caps("S16LE", 48000, Some(6));
+        let s = caps.structure(0).unwrap();
+
+        assert_eq!(s.get::<i32>("channels").unwrap(), 6);
+        assert_eq!(
+            s.get::<gst::Bitmask>("channel-mask").unwrap(),
+            gst::Bitmask::new(0x3f)
+        );
+
+        let caps = Mixer::raw_audio_caps("S16LE", 48000, None);
+
+        assert!(!caps.structure(0).unwrap().has_field("channels"));
+    }
+
+    #[test]
+    fn test_validate_config() {
+        gst::init().unwrap();
+
+        let mixer = Mixer::new("test-mixer", None).unwrap();
+        let n_pads = mixer.video_mixer.sink_pads().len();
+
+        let settings: HashMap<String, serde_json::Value> = vec![
+            ("width".to_string(), serde_json::json!(1280)),
+            ("height".to_string(), serde_json::json!(0)),
+            ("unknown".to_string(), serde_json::json!(0)),
+        ]
+        .into_iter()
+        .collect();
+        let slot_config: HashMap<String, serde_json::Value> = vec![
+            ("video::alpha".to_string(), serde_json::json!(0.5)),
+            ("video::xpos".to_string(), serde_json::json!("left")),
+            (
+                "audio::compressor-ratio".to_string(),
+                serde_json::json!(2.0),
+            ),
+            ("label".to_string(), serde_json::json!("camera")),
+        ]
+        .into_iter()
+        .collect();
+
+        let mut errors = mixer.validate_config(&settings, &slot_config);
+        errors.sort();
+
+        assert_eq!(errors.len(), 4);
+        assert!(errors[0].starts_with("audio::compressor-ratio: "));
+        assert!(errors[1].starts_with("height: "));
+        assert!(errors[2].starts_with("unknown: "));
+        assert!(errors[3].starts_with("video::xpos: "));
+
+        // Nothing was requested from our mixers
+        assert_eq!(mixer.video_mixer.sink_pads().len(), n_pads);
+        assert!(mixer.consumer_slots.is_empty());
+
+        assert!(mixer
+            .validate_config(&HashMap::new(), &HashMap::new())
+            .is_empty());
+    }
+
+    #[test]
+    fn test_slot_freeze() {
+        gst::init().unwrap();
+
+        let srcpad = gst::Pad::new(Some("src"), gst::PadDirection::Src);
+        let received = Arc::new(Mutex::new(vec![]));
+        let received_clone = received.clone();
+        let sinkpad = gst::Pad::builder(Some("sink"), gst::PadDirection::Sink)
+            .chain_function(move |_pad, _parent, buffer| {
+                let data = buffer.map_readable().unwrap().as_slice()[0];
+                received_clone
+                    .lock()
+                    .unwrap()
+                    .push((buffer.pts().mseconds().unwrap(), data));
+                Ok(gst::FlowSuccess::Ok)
+            })
+            .event_function(|_pad, _parent, _event| true)
+            .build();
+
+        srcpad.link(&sinkpad).unwrap();
+        srcpad.set_active(true).unwrap();
+        sinkpad.set_active(true).unwrap();
+
+        let freeze = SlotFreeze::track(&srcpad);
+
+        srcpad.push_event(gst::event::StreamStart::new("test"));
+        srcpad.push_event(gst::event::Caps::new(&gst::Caps::new_simple(
+            "video/x-raw",
+            &[],
+        )));
+        srcpad.push_event(gst::event::Segment::new(&gst::FormattedSegment::<
+            gst::ClockTime,
+        >::new()));
+
+        let push = |pts: u64, data: u8| {
+            let mut buffer = gst::Buffer::from_slice(vec![data]);
+            buffer
+                .get_mut()
+                .unwrap()
+                .set_pts(gst::ClockTime::from_mseconds(pts));
+            srcpad.push(buffer).unwrap();
+        };
+
+        push(0, 0);
+        freeze.lock().unwrap().frozen = true;
+        push(40, 1);
+        push(80, 2);
+        push(120, 3);
+        freeze.lock().unwrap().frozen = false;
+        push(160, 4);
+
+        assert_eq!(
+            *received.lock().unwrap(),
+            vec![(0, 0), (40, 1), (80, 1), (120, 1), (160, 4)]
+        );
+    }
+
+    #[actix_rt::test]
+    #[test]
+    async fn test_connect_volume() {
+        gst::init().unwrap();
+
+        let addr = Mixer::new("test-mixer", None).unwrap().start();
+
+        let connect = |link_id: &str, volume: f64| {
+            let mut config = HashMap::new();
+            config.insert("audio::volume".to_string(), serde_json::json!(volume));
+
+            addr.send(ConsumerMessage::Connect {
+                link_id: link_id.to_string(),
+                video_producer: None,
+                audio_producer: Some(make_producer(&format!("{}-audio-producer", link_id))),
+                config: Some(config),
+                replace: false,
+            })
+        };
+
+        connect("slot-0", 0.5).await.unwrap().unwrap();
+        assert!(connect("slot-1", 11.0).await.unwrap().is_err());
+
+        let info = match addr.send(GetNodeInfoMessage).await.unwrap().unwrap() {
+            NodeInfo::Mixer(info) => info,
+            _ => unreachable!(),
+        };
+
+        assert_eq!(info.slots.len(), 1);
+        assert!((info.slots["slot-0"].volume - 0.5).abs() < f64::EPSILON);
+        assert_eq!(
+            info.slot_settings["slot-0"]["audio::volume"],
+            serde_json::json!(0.5)
+        );
+    }
+
+    #[actix_rt::test]
+    #[test]
+    async fn test_connect_appsrc_format() {
+        gst::init().unwrap();
+
+        let addr = Mixer::new("test-mixer", None).unwrap().start();
+
+        let connect = |link_id: &str, format: &str| {
+            let mut config = HashMap::new();
+            config.insert("audio::format".to_string(), serde_json::json!(format));
+
+            addr.send(ConsumerMessage::Connect {
+                link_id: link_id.to_string(),
+                video_producer: None,
+                audio_producer: Some(make_producer(&format!("{}-audio-producer", link_id))),
+                config: Some(config),
+                replace: false,
+            })
+        };
+
+        connect("slot-0", "time").await.unwrap().unwrap();
+        assert!(connect("slot-1", "bytes").await.unwrap().is_err());
+        assert!(connect("slot-2", "buffers").await.unwrap().is_err());
+
+        let info = match addr.send(GetNodeInfoMessage).await.unwrap().unwrap() {
+            NodeInfo::Mixer(info) => info,
+            _ => unreachable!(),
+        };
+
+        assert_eq!(info.slots.len(), 1);
+        assert!(info.slots.contains_key("slot-0"));
+    }
+
+    #[test]
+    fn test_slot_latency() {
+        gst::init().unwrap();
+
+        let producer = make_producer("slot-0-video-producer");
+        let mut slot = Mixer::build_slot("slot-0", Some(&producer), None, None, None);
+
+        let mut config = HashMap::new();
+        config.insert("latency-ms".to_string(), serde_json::json!(50));
+        Mixer::apply_slot_config(&mut slot, config).unwrap();
+
+        slot.add_consumers("slot-0").unwrap();
+
+        assert_eq!(
+            slot.video_appsrc
+                .property("min-latency")
+                .unwrap()
+                .get::<i64>()
+                .unwrap(),
+            50_000_000
+        );
+
+        let mut config = HashMap::new();
+        config.insert("latency-ms".to_string(), serde_json::json!(-1));
+        assert!(Mixer::apply_slot_config(&mut slot, config).is_err());
+    }
+
+    #[actix_rt::test]
+    #[test]
+    async fn test_replace_slot() {
+        gst::init().unwrap();
+
+        let addr = Mixer::new("test-mixer", None).unwrap().start();
+
+        let connect = |config: Vec<(&str, serde_json::Value)>, replace: bool| {
+            addr.send(ConsumerMessage::Connect {
+                link_id: "slot-0".to_string(),
+                video_producer: Some(make_producer("slot-0-video-producer")),
+                audio_producer: None,
+                config: Some(
+                    config
+                        .into_iter()
+                        .map(|(key, value)| (key.to_string(), value))
+                        .collect(),
+                ),
+                replace,
+            })
+        };
+
+        connect(
+            vec![
+                ("video::xpos", serde_json::json!(100)),
+                ("video::ypos", serde_json::json!(50)),
+            ],
+            false,
+        )
+        .await
+        .unwrap()
+        .unwrap();
+
+        // Duplicates are rejected unless replacing
+        assert!(connect(vec![], false).await.unwrap().is_err());
+
+        // An invalid configuration leaves the existing slot in place
+        assert!(
+            connect(vec![("video::xpos", serde_json::json!("left"))], true)
+                .await
+                .unwrap()
+                .is_err()
+        );
+
+        connect(vec![("video::ypos", serde_json::json!(10))], true)
+            .await
+            .unwrap()
+            .unwrap();
+
+        let info = match addr.send(GetNodeInfoMessage).await.unwrap().unwrap() {
+            NodeInfo::Mixer(info) => info,
+            _ => unreachable!(),
+        };
+
+        assert_eq!(info.slots.len(), 1);
+        assert_eq!(
+            info.slot_settings["slot-0"]["video::xpos"],
+            serde_json::json!(100)
+        );
+        assert_eq!(
+            info.slot_settings["slot-0"]["video::ypos"],
+            serde_json::json!(10)
+        );
+    }
+
+    #[actix_rt::test]
+    #[test]
+    async fn test_set_slot_order() {
+        gst::init().unwrap();
+
+        let addr = Mixer::new("test-mixer", None).unwrap().start();
+
+        for link_id in &["slot-0", "slot-1", "slot-2", "slot-3"] {
+            addr.send(ConsumerMessage::Connect {
+                link_id: link_id.to_string(),
+                video_producer: Some(make_producer(&format!("{}-video-producer", link_id))),
+                audio_producer: None,
+                config: None,
+                replace: false,
+            })
+            .await
+            .unwrap()
+            .unwrap();
+        }
+
+        let set_order = |order: &[&str]| {
+            addr.send(ConsumerMessage::SetSlotOrder {
+                order: order.iter().map(|id| id.to_string()).collect(),
+            })
+        };
+
+        assert!(set_order(&["slot-0", "slot-4"]).await.unwrap().is_err());
+        assert!(set_order(&["slot-0", "slot-0"]).await.unwrap().is_err());
+
+        set_order(&["slot-3", "slot-1"]).await.unwrap().unwrap();
+
+        let zorders = || async {
+            let info = match addr.send(GetNodeInfoMessage).await.unwrap().unwrap() {
+                NodeInfo::Mixer(info) => info,
+                _ => unreachable!(),
+            };
+
+            ["slot-0", "slot-1", "slot-2", "slot-3"]
+                .iter()
+                .map(|id| info.slot_settings[*id]["video::zorder"].as_u64().unwrap())
+                .collect::<Vec<_>>()
+        };
+
+        // Unlisted slots are stacked above, in their previous order
+        assert_eq!(zorders().await, vec![3, 2, 4, 1]);
+
+        set_order(&["slot-2"]).await.unwrap().unwrap();
+
+        assert_eq!(zorders().await, vec![4, 3, 1, 2]);
+    }
+
+    /// Push 44.1 kHz stereo then 48 kHz mono to the audio of a slot,
+    /// returns whether the mix reached EOS at the end of the input without
+    /// errors, and the number of rows of the mix matrix of the slot
+    /// afterwards
+    fn audio_format_change(
+        channel_map: Option<Vec<Vec<f32>>>,
+        allow_format_change: bool,
+    ) -> (bool, usize) {
+        let pipeline = gst::Pipeline::new(None);
+        let audiomixer = make_element("audiomixer", None).unwrap();
+        let appsink = gst::ElementFactory::make("appsink", None)
+            .unwrap()
+            .downcast::<gst_app::AppSink>()
+            .unwrap();
+        appsink.set_property("sync", &false).unwrap();
+        pipeline
+            .add_many(&[&audiomixer, appsink.upcast_ref()])
+            .unwrap();
+        audiomixer.link(&appsink).unwrap();
+
+        let appsrc = gst::ElementFactory::make("appsrc", None)
+            .unwrap()
+            .downcast::<gst_app::AppSrc>()
+            .unwrap();
+        appsrc.set_format(gst::Format::Time);
+
+        let mixer_pad = audiomixer.request_pad_simple("sink_%u").unwrap();
+
+        Mixer::link_slot_audio(
+            &pipeline,
+            &appsrc,
+            &mixer_pad,
+            48000,
+            "S16LE",
+            Some(2),
+            channel_map.as_ref(),
+            allow_format_change,
+            None,
+        )
+        .unwrap();
+
+        let aconv = appsrc
+            .static_pad("src")
+            .unwrap()
+            .peer()
+            .unwrap()
+            .parent_element()
+            .unwrap();
+
+        pipeline.set_state(gst::State::Playing).unwrap();
+
+        // 10 millisecond buffers of silence
+        let push = |rate: i32, channels: i32, from: u64, to: u64| {
+            let caps = gst::Caps::builder("audio/x-raw")
+                .field("format", &"S16LE")
+                .field("layout", &"interleaved")
+                .field("rate", &rate)
+                .field("channels", &channels)
+                .build();
+            appsrc.set_caps(Some(&caps));
+
+            for i in from..to {
+                let size = (rate / 100 * channels * 2) as usize;
+                let mut buffer = gst::Buffer::with_size(size).unwrap();
+                {
+                    let buffer = buffer.get_mut().unwrap();
+                    buffer.set_pts(i * 10 * gst::MSECOND);
+                    buffer.set_duration(10 * gst::MSECOND);
+                }
+                // Pushing fails once the pipeline errored out
+                let _ = appsrc.push_buffer(buffer);
+            }
+        };
+
+        push(44100, 2, 0, 10);
+        // As when a live encoder is reconfigured
+        push(48000, 1, 10, 20);
+        let _ = appsrc.end_of_stream();
+
+        let mut end = gst::ClockTime::from_seconds(0);
+        while let Some(sample) = appsink.try_pull_sample(5 * gst::SECOND) {
+            let buffer = sample.buffer().unwrap();
+            end = buffer.pts() + buffer.duration();
+        }
+
+        let flowed = appsink.is_eos()
+            && end >= 190 * gst::MSECOND
+            && pipeline
+                .bus()
+                .unwrap()
+                .pop_filtered(&[gst::MessageType::Error])
+                .is_none();
+
+        let n_rows = aconv
+            .property("mix-matrix")
+            .unwrap()
+            .get::<gst::Array>()
+            .unwrap()
+            .as_slice()
+            .len();
+
+        pipeline.set_state(gst::State::Null).unwrap();
+
+        (flowed, n_rows)
+    }
+
+    #[test]
+    fn test_audio_format_change() {
+        gst::init().unwrap();
+
+        // The mix keeps flowing past the format change, up to EOS
+        assert_eq!(audio_format_change(None, true), (true, 0));
+    }
+
+    #[test]
+    fn test_audio_format_change_channel_map_fallback() {
+        gst::init().unwrap();
+
+        // The stereo channel map no longer matches the mono input, it is
+        // set aside for the automatic downmix
+        let channel_map = vec![vec![1.0, 0.0], vec![0.0, 1.0]];
+        assert_eq!(audio_format_change(Some(channel_map), true), (true, 0));
+    }
+
+    #[test]
+    fn test_audio_format_change_not_allowed() {
+        gst::init().unwrap();
+
+        // The channel map is kept as is, and can't convert the mono input
+        let channel_map = vec![vec![1.0, 0.0], vec![0.0, 1.0]];
+        assert_eq!(audio_format_change(Some(channel_map), false), (false, 2));
+    }
+
+    #[actix_rt::test]
+    #[test]
+    async fn test_channel_map_dimensions() {
+        gst::init().unwrap();
+
+        // A mono producer, its caps negotiated
+        let pipeline = gst::Pipeline::new(None);
+        let src = gst::ElementFactory::make("audiotestsrc", None).unwrap();
+        src.set_property("num-buffers", &1i32).unwrap();
+        let capsfilter = gst::ElementFactory::make("capsfilter", None).unwrap();
+        capsfilter
+            .set_property(
+                "caps",
+                &gst::Caps::builder("audio/x-raw")
+                    .field("channels", &1)
+                    .build(),
+            )
+            .unwrap();
+        let appsink = gst::ElementFactory::make("appsink", None)
+            .unwrap()
+            .downcast::<gst_app::AppSink>()
+            .unwrap();
+        appsink.set_sync(false);
+        pipeline
+            .add_many(&[&src, &capsfilter, appsink.upcast_ref()])
+            .unwrap();
+        gst::Element::link_many(&[&src, &capsfilter, appsink.upcast_ref()]).unwrap();
+
+        let audio_producer = StreamProducer::from(&appsink);
+
+        pipeline.set_state(gst::State::Playing).unwrap();
+        pipeline
+            .bus()
+            .unwrap()
+            .timed_pop_filtered(5 * gst::SECOND, &[gst::MessageType::Eos])
+            .expect("timed out waiting for EOS");
+
+        let addr = Mixer::new("test-mixer", None).unwrap().start();
+
+        let connect = |link_id: &str, value: serde_json::Value| {
+            let mut config = HashMap::new();
+            config.insert("audio::channel-map".to_string(), value);
+
+            addr.send(ConsumerMessage::Connect {
+                link_id: link_id.to_string(),
+                video_producer: None,
+                audio_producer: Some(audio_producer.clone()),
+                config: Some(config),
+                replace: false,
+            })
+        };
+
+        // Two columns for a mono input
+        assert!(connect("slot-0", serde_json::json!("left"))
+            .await
+            .unwrap()
+            .is_err());
+        assert!(
+            connect("slot-0", serde_json::json!([[1.0, 0.0], [0.0, 1.0]]))
+                .await
+                .unwrap()
+                .is_err()
+        );
+        connect("slot-0", serde_json::json!([[1.0], [0.5]]))
+            .await
+            .unwrap()
+            .unwrap();
+
+        // Malformed matrices are reported without an input
+        let validate = |value: serde_json::Value| {
+            let mut slot_config = HashMap::new();
+            slot_config.insert("audio::channel-map".to_string(), value);
+
+            addr.send(ValidateConfigMessage {
+                settings: HashMap::new(),
+                slot_config,
+            })
+        };
+
+        assert!(validate(serde_json::json!([[1.0], [0.5]]))
+            .await
+            .unwrap()
+            .is_empty());
+        assert_eq!(
+            validate(serde_json::json!([[1.0, 0.0], [1.0]]))
+                .await
+                .unwrap()
+                .len(),
+            1
+        );
+        assert_eq!(
+            validate(serde_json::json!([[1.0, 0.0]]))
+                .await
+                .unwrap()
+                .len(),
+            1
+        );
+
+        pipeline.set_state(gst::State::Null).unwrap();
+    }
+
+    #[actix_rt::test]
+    #[test]
+    async fn test_allow_audio_format_change_config() {
+        gst::init().unwrap();
+
+        let addr = Mixer::new("test-mixer", None).unwrap().start();
+
+        let connect = |link_id: &str, value: serde_json::Value, audio: bool| {
+            let mut config = HashMap::new();
+            config.insert("audio::allow-format-change".to_string(), value);
+
+            addr.send(ConsumerMessage::Connect {
+                link_id: link_id.to_string(),
+                video_producer: Some(make_producer(&format!("{}-video-producer", link_id))),
+                audio_producer: if audio {
+                    Some(make_producer(&format!("{}-audio-producer", link_id)))
+                } else {
+                    None
+                },
+                config: Some(config),
+                replace: false,
+            })
+        };
+
+        connect("slot-0", serde_json::json!(true), true)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(connect("slot-1", serde_json::json!("yes"), true)
+            .await
+            .unwrap()
+            .is_err());
+        assert!(connect("slot-2", serde_json::json!(true), false)
+            .await
+            .unwrap()
+            .is_err());
+
+        let info = match addr.send(GetNodeInfoMessage).await.unwrap().unwrap() {
+            NodeInfo::Mixer(info) => info,
+            _ => unreachable!(),
+        };
+
+        assert_eq!(info.slots.len(), 1);
+        assert_eq!(
+            info.slot_settings["slot-0"]["audio::allow-format-change"],
+            serde_json::json!(true)
+        );
+    }
+
+    #[actix_rt::test]
+    #[test]
+    async fn test_resize_output_preserving_slot_layout() {
+        gst::init().unwrap();
+
+        let mut settings = HashMap::new();
+        settings.insert("width".to_string(), serde_json::json!(1920));
+        settings.insert("height".to_string(), serde_json::json!(1080));
+        let addr = Mixer::new("test-mixer", Some(settings)).unwrap().start();
+
+        let mut config = HashMap::new();
+        config.insert("video::xpos".to_string(), serde_json::json!(960));
+        config.insert("video::ypos".to_string(), serde_json::json!(540));
+        config.insert("video::width".to_string(), serde_json::json!(960));
+
+        addr.send(ConsumerMessage::Connect {
+            link_id: "slot-0".to_string(),
+            video_producer: Some(make_producer("slot-0-video-producer")),
+            audio_producer: None,
+            config: Some(config),
+            replace: false,
+        })
+        .await
+        .unwrap()
+        .unwrap();
+
+        assert!(addr
+            .send(ResizeOutputPreservingSlotLayoutMessage {
+                new_width: -1,
+                new_height: 720,
+            })
+            .await
+            .unwrap()
+            .is_err());
+
+        addr.send(ResizeOutputPreservingSlotLayoutMessage {
+            new_width: 1280,
+            new_height: 720,
+        })
+        .await
+        .unwrap()
+        .unwrap();
+
+        let info = match addr.send(GetNodeInfoMessage).await.unwrap().unwrap() {
+            NodeInfo::Mixer(info) => info,
+            _ => unreachable!(),
+        };
+
+        assert_eq!(info.settings["width"], serde_json::json!(1280));
+        assert_eq!(info.settings["height"], serde_json::json!(720));
+
+        let slot_settings = &info.slot_settings["slot-0"];
+        assert_eq!(slot_settings["video::xpos"], serde_json::json!(640));
+        assert_eq!(slot_settings["video::ypos"], serde_json::json!(360));
+        assert_eq!(slot_settings["video::width"], serde_json::json!(640));
+        // Left to the size of the input
+        assert_eq!(slot_settings["video::height"], serde_json::json!(0));
+    }
+
+    #[actix_rt::test]
+    #[test]
+    async fn test_resize_mixer_output_command() {
+        gst::init().unwrap();
+
+        let mut config = HashMap::new();
+        config.insert("width".to_string(), serde_json::json!(1920));
+        config.insert("height".to_string(), serde_json::json!(1080));
+        create_mixer("test-mixer", Some(config)).await.unwrap();
+
+        run_command(Command::ResizeMixerOutput {
+            id: "test-mixer".to_string(),
+            width: 1280,
+            height: 720,
+        })
+        .await
+        .unwrap();
+
+        let info = match node_info_unchecked("test-mixer").await {
+            NodeInfo::Mixer(info) => info,
+            _ => unreachable!(),
+        };
+
+        assert_eq!(info.settings["width"], serde_json::json!(1280));
+        assert_eq!(info.settings["height"], serde_json::json!(720));
+
+        assert!(run_command(Command::ResizeMixerOutput {
+            id: "test-mixer".to_string(),
+            width: 0,
+            height: 720,
+        })
+        .await
+        .is_err());
+    }
+
+    #[test]
+    fn test_pending_resizes() {
+        gst::init().unwrap();
+
+        let mut settings = HashMap::new();
+        settings.insert("width".to_string(), serde_json::json!(1920));
+        settings.insert("height".to_string(), serde_json::json!(1080));
+        let mut mixer = Mixer::new("test-mixer", Some(settings)).unwrap();
+        // As if started, resizes are then applied with the next frame
+        mixer.state_machine.state = State::Started;
+
+        mixer
+            .resize_output_preserving_slot_layout(1280, 720)
+            .unwrap();
+        mixer
+            .resize_output_preserving_slot_layout(640, 360)
+            .unwrap();
+
+        // Slots are scaled from the size they were laid out for
+        assert_eq!(
+            mixer.video_mixing_state.lock().unwrap().pending_resize,
+            Some(((1920, 1080), (640, 360)))
+        );
+    }
+
+    #[actix_rt::test]
+    #[test]
+    async fn test_apply_layout_transition() {
+        gst::init().unwrap();
+
+        let mut settings = HashMap::new();
+        settings.insert(
+            "layouts".to_string(),
+            serde_json::json!({
+                "main": {
+                    "slot-0": {"video::xpos": 100},
+                    "slot-1": {"video::xpos": 200},
+                }
+            }),
+        );
+        let addr = Mixer::new("test-mixer", Some(settings)).unwrap().start();
+
+        addr.send(ConsumerMessage::Connect {
+            link_id: "slot-0".to_string(),
+            video_producer: Some(make_producer("slot-0-video-producer")),
+            audio_producer: None,
+            config: None,
+            replace: false,
+        })
+        .await
+        .unwrap()
+        .unwrap();
+
+        let cue_time = get_now() + chrono::Duration::seconds(60);
+
+        // slot-1 isn't connected, and is skipped
+        addr.send(ApplyLayoutMessage {
+            layout: "main".to_string(),
+            cue_time,
+            transition_ms: Some(1000),
+        })
+        .await
+        .unwrap()
+        .unwrap();
+
+        let mut points = addr
+            .send(GetSlotControlPointsMessage {
+                slot_id: "slot-0".to_string(),
+            })
+            .await
+            .unwrap()
+            .unwrap()
+            .remove("video::xpos")
+            .unwrap();
+        points.sort_by_key(|point| point.time);
+
+        // The start value is only sampled at cue_time
+        assert_eq!(points.len(), 2);
+        assert_eq!(points[0].time, cue_time);
+        assert_eq!(points[0].value, serde_json::Value::Null);
+        assert_eq!(points[1].time, cue_time + chrono::Duration::seconds(1));
+        assert_eq!(points[1].value, serde_json::json!(100));
+    }
+
+    #[actix_rt::test]
+    #[test]
+    async fn test_late_start_mode_config() {
+        gst::init().unwrap();
+
+        let addr = Mixer::new("test-mixer", None).unwrap().start();
+
+        let connect = |link_id: &str, mode: &str, video: bool| {
+            let mut config = HashMap::new();
+            config.insert(
+                "video::late-start-mode".to_string(),
+                serde_json::json!(mode),
+            );
+
+            addr.send(ConsumerMessage::Connect {
+                link_id: link_id.to_string(),
+                video_producer: if video {
+                    Some(make_producer(&format!("{}-video-producer", link_id)))
+                } else {
+                    None
+                },
+                audio_producer: Some(make_producer(&format!("{}-audio-producer", link_id))),
+                config: Some(config),
+                replace: false,
+            })
+        };
+
+        connect("slot-0", "freeze", true).await.unwrap().unwrap();
+        assert!(connect("slot-1", "bogus", true).await.unwrap().is_err());
+        assert!(connect("slot-2", "blank", false).await.unwrap().is_err());
+
+        let info = match addr.send(GetNodeInfoMessage).await.unwrap().unwrap() {
+            NodeInfo::Mixer(info) => info,
+            _ => unreachable!(),
+        };
+
+        assert_eq!(info.slots.len(), 1);
+        assert_eq!(
+            info.slot_settings["slot-0"]["video::late-start-mode"],
+            serde_json::json!("freeze")
+        );
+    }
+
+    /// Start a mixer with one slot per late start mode, whose producers
+    /// never output anything, and wait until it mixed past its fallback
+    /// timeout
+    async fn start_with_late_slots(
+        id: &str,
+        modes: &[&str],
+    ) -> (Addr<Mixer>, Arc<Mutex<VideoMixingState>>) {
+        let mut settings = HashMap::new();
+        settings.insert("fallback-timeout".to_string(), serde_json::json!(100));
+
+        let mixer = Mixer::new(id, Some(settings)).unwrap();
+        let mixing_state = mixer.video_mixing_state.clone();
+        let addr = mixer.start();
+
+        for (i, mode) in modes.iter().enumerate() {
+            let mut config = HashMap::new();
+            config.insert(
+                "video::late-start-mode".to_string(),
+                serde_json::json!(mode),
+            );
+            config.insert("video::xpos".to_string(), serde_json::json!(100 + i));
+            config.insert("video::alpha".to_string(), serde_json::json!(0.5));
+
+            addr.send(ConsumerMessage::Connect {
+                link_id: format!("slot-{}", i),
+                video_producer: Some(make_producer(&format!("{}-slot-{}-producer", id, i))),
+                audio_producer: None,
+                config: Some(config),
+                replace: false,
+            })
+            .await
+            .unwrap()
+            .unwrap();
+        }
+
+        addr.send(StartMessage {
+            cue_time: None,
+            end_time: None,
+        })
+        .await
+        .unwrap()
+        .unwrap();
+
+        for _ in 0..50 {
+            if mixing_state.lock().unwrap().last_pts > 500 * gst::MSECOND {
+                break;
+            }
+
+            tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+        }
+        assert!(mixing_state.lock().unwrap().last_pts > 500 * gst::MSECOND);
+
+        (addr, mixing_state)
+    }
+
+    #[actix_rt::test]
+    #[test]
+    async fn test_late_start_modes() {
+        gst::init().unwrap();
+
+        // Slots in freeze and blank modes hold off the base plate
+        let (addr, mixing_state) =
+            start_with_late_slots("test-mixer", &["freeze", "blank", "mute"]).await;
+
+        {
+            let mixing_state = mixing_state.lock().unwrap();
+
+            assert!(!mixing_state.showing_base_plate);
+            assert_eq!(mixing_state.late_starts.len(), 2);
+
+            for (pad_name, late_start) in &mixing_state.late_starts {
+                match late_start {
+                    // The placeholder is mixed in place of the slot
+                    LateStart::Freeze { placeholder, pad } => {
+                        assert!(placeholder.parent().is_some());
+                        assert_ne!(pad.name().as_str(), pad_name);
+                        assert_eq!(pad.property("xpos").unwrap().get::<i32>().unwrap(), 100);
+                        let alpha = pad.property("alpha").unwrap().get::<f64>().unwrap();
+                        assert!((alpha - 1.0).abs() < f64::EPSILON);
+                    }
+                    // The slot is transparent, its alpha is restored later
+                    LateStart::Blank { alpha } => {
+                        assert!((alpha - 0.5).abs() < f64::EPSILON);
+
+                        let compositor = mixing_state.output_pads[0].parent_element().unwrap();
+                        let slot_pad = compositor.static_pad(pad_name).unwrap();
+                        assert_eq!(
+                            slot_pad.property("xpos").unwrap().get::<i32>().unwrap(),
+                            101
+                        );
+                        let slot_alpha = slot_pad.property("alpha").unwrap().get::<f64>().unwrap();
+                        assert!(slot_alpha.abs() < f64::EPSILON);
+                    }
+                }
+            }
+        }
+
+        let info = match addr.send(GetNodeInfoMessage).await.unwrap().unwrap() {
+            NodeInfo::Mixer(info) => info,
+            _ => unreachable!(),
+        };
+        assert!(!info.base_plate_active);
+
+        // Muted slots don't
+        let (_addr, mixing_state) = start_with_late_slots("test-mixer-mute", &["mute"]).await;
+
+        let mixing_state = mixing_state.lock().unwrap();
+        assert!(mixing_state.late_starts.is_empty());
+        assert!(mixing_state.showing_base_plate);
+    }
+
+    #[test]
+    fn test_add_idle_graphic() {
+        gst::init().unwrap();
+
+        let mixer = Mixer::new("test-mixer", None).unwrap();
+        let base_plate_pad = mixer.video_mixer.request_pad_simple("sink_%u").unwrap();
+
+        let pad = mixer
+            .add_idle_graphic("/path/to/idle.png", 320, 240)
+            .unwrap();
+
+        assert_eq!(base_plate_pad.name(), "sink_0");
+        assert_ne!(pad, base_plate_pad);
 
-    fn setting(&self, name: &str) -> Option<MutexGuard<Setting>> {
-        self.settings
-            .get(name)
-            .map(|setting| setting.lock().unwrap())
+        let property = |name: &str| pad.property(name).unwrap();
+        assert_eq!(property("zorder").get::<u32>().unwrap(), 0);
+        assert_eq!(property("alpha").get::<f64>().unwrap(), 0.0);
+        assert_eq!(property("width").get::<i32>().unwrap(), 320);
+        assert_eq!(property("height").get::<i32>().unwrap(), 240);
+        assert!(pad.is_linked());
     }
 
-    fn settings(&self) -> HashMap<String, serde_json::Value> {
-        self.settings
-            .iter()
-            .map(|(id, setting)| (id.clone(), setting.lock().unwrap().as_value()))
-            .collect()
+    #[test]
+    fn test_describe_linked_elements() {
+        gst::init().unwrap();
+
+        let pipeline = gst::Pipeline::new(None);
+
+        let bin = gst::Bin::new(Some("audio-bin"));
+        let asrc = make_element("audiotestsrc", Some("asrc")).unwrap();
+        let aqueue = make_element("queue", Some("aqueue")).unwrap();
+        bin.add_many(&[&asrc, &aqueue]).unwrap();
+        asrc.link(&aqueue).unwrap();
+        let ghost =
+            gst::GhostPad::with_target(Some("src"), &aqueue.static_pad("src").unwrap()).unwrap();
+        bin.add_pad(&ghost).unwrap();
+
+        let amixer = make_element("audiomixer", Some("amixer")).unwrap();
+        let asink = make_element("fakesink", Some("asink")).unwrap();
+        let vsrc = make_element("videotestsrc", Some("vsrc")).unwrap();
+        let vsink = make_element("fakesink", Some("vsink")).unwrap();
+
+        pipeline
+            .add_many(&[bin.upcast_ref(), &amixer, &asink, &vsrc, &vsink])
+            .unwrap();
+        bin.link(&amixer).unwrap();
+        amixer.link(&asink).unwrap();
+        vsrc.link(&vsink).unwrap();
+
+        let names = Mixer::describe_linked_elements(&amixer)
+            .into_iter()
+            .map(|info| info.name)
+            .collect::<HashSet<_>>();
+
+        assert_eq!(
+            names,
+            ["asrc", "aqueue", "amixer", "asink"]
+                .iter()
+                .map(|name| name.to_string())
+                .collect::<HashSet<_>>()
+        );
     }
 
-    fn control_points(&self) -> HashMap<String, Vec<ControlPoint>> {
-        let mixing_state = self.video_mixing_state.lock().unwrap();
+    #[actix_rt::test]
+    #[test]
+    async fn test_slot_watchdog() {
+        gst::init().unwrap();
 
-        mixing_state
-            .mixer_controllers
-            .as_ref()
+        let mut mixer = Mixer::new("test-mixer", None).unwrap();
+
+        let mut slot = Mixer::build_slot("slot-0", None, None, None, None);
+        // As if linked
+        slot.audio_bin = Some(gst::Bin::new(None));
+        slot.watchdog_timeout_ms = 20;
+        mixer.consumer_slots.insert("slot-0".to_string(), slot);
+
+        let last_progress = |mixer: &Mixer| mixer.consumer_slots["slot-0"].watchdog.last_progress;
+
+        mixer.check_slot_watchdogs();
+        let started = last_progress(&mixer).unwrap();
+
+        // Not stalled for long enough yet
+        mixer.check_slot_watchdogs();
+        assert_eq!(last_progress(&mixer), Some(started));
+
+        std::thread::sleep(std::time::Duration::from_millis(30));
+
+        // Flushed, the next flush is due a timeout later
+        mixer.check_slot_watchdogs();
+        let flushed = last_progress(&mixer).unwrap();
+        assert!(flushed > started);
+
+        // Progress is observed again
+        mixer.consumer_slots["slot-0"]
+            .audio_stats
+            .lock()
             .unwrap()
-            .iter()
-            .map(|(id, controller)| (id.clone(), controller.control_points()))
-            .collect()
+            .update(None);
+        mixer.check_slot_watchdogs();
+        assert!(last_progress(&mixer).unwrap() > flushed);
+        assert_eq!(mixer.consumer_slots["slot-0"].watchdog.n_buffers, 1);
     }
 
-    /// Create a mixer
-    pub fn new(
-        id: &str,
-        config: Option<HashMap<String, serde_json::Value>>,
-    ) -> Result<Self, Error> {
+    #[actix_rt::test]
+    #[test]
+    async fn test_reconnect_on_eos() {
+        gst::init().unwrap();
+
         let pipeline = gst::Pipeline::new(None);
+        let src = gst::ElementFactory::make("appsrc", None)
+            .unwrap()
+            .downcast::<gst_app::AppSrc>()
+            .unwrap();
+        src.set_format(gst::Format::Time);
+        let appsink = gst::ElementFactory::make("appsink", None)
+            .unwrap()
+            .downcast::<gst_app::AppSink>()
+            .unwrap();
+        appsink.set_sync(false);
+        pipeline
+            .add_many(&[src.upcast_ref(), appsink.upcast_ref()])
+            .unwrap();
+        src.link(&appsink).unwrap();
 
-        let audio_appsink =
-            gst::ElementFactory::make("appsink", Some(&format!("mixer-audio-appsink-{}", id)))
-                .unwrap()
-                .downcast::<gst_app::AppSink>()
-                .unwrap();
+        let producer = StreamProducer::from(&appsink);
 
-        let video_appsink =
-            gst::ElementFactory::make("appsink", Some(&format!("mixer-video-appsink-{}", id)))
-                .unwrap()
-                .downcast::<gst_app::AppSink>()
-                .unwrap();
+        pipeline.set_state(gst::State::Playing).unwrap();
+        src.end_of_stream().unwrap();
+        pipeline
+            .bus()
+            .unwrap()
+            .timed_pop_filtered(5 * gst::SECOND, &[gst::MessageType::Eos])
+            .expect("timed out waiting for EOS");
+
+        let mut mixer = Mixer::new("test-mixer", None).unwrap();
+        // As if started, with a slot whose input just reached EOS
+        mixer.state_machine.state = State::Started;
+        let mut slot = Mixer::build_slot("slot-0", Some(&producer), None, None, None);
+        slot.reconnect_on_eos = true;
+        slot.generation = 1;
+        mixer.slot_generation = 1;
+        mixer.consumer_slots.insert("slot-0".to_string(), slot);
+        let addr = mixer.start();
+
+        addr.send(SlotEosMessage {
+            slot_id: "slot-0".to_string(),
+            generation: 1,
+        })
+        .await
+        .unwrap();
 
-        let audio_mixer = make_element("audiomixer", Some("audiomixer")).unwrap();
-        let video_mixer = make_element("compositor", Some("compositor")).unwrap();
+        // Not reconnected while the producer is still at EOS
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+        assert!(producer.get_consumer_ids().is_empty());
 
-        pipeline
-            .add_many(&[&audio_appsink, &video_appsink])
+        // Data flows again after a flush
+        src.send_event(gst::event::FlushStart::new());
+        src.send_event(gst::event::FlushStop::new(true));
+        src.push_buffer(gst::Buffer::with_size(1).unwrap()).unwrap();
+
+        for _ in 0..50 {
+            if !producer.get_consumer_ids().is_empty() {
+                break;
+            }
+
+            tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+        }
+
+        assert_eq!(producer.get_consumer_ids(), vec!["slot-0".to_string()]);
+
+        pipeline.set_state(gst::State::Null).unwrap();
+    }
+
+    #[test]
+    fn test_complete_slot_connection() {
+        gst::init().unwrap();
+
+        let mut mixer = Mixer::new("test-mixer", None).unwrap();
+        let producer = make_producer("slot-0-video-producer");
+
+        let add_slot = |mixer: &mut Mixer| {
+            let mut slot = Mixer::build_slot("slot-0", Some(&producer), None, None, None);
+            mixer.slot_generation += 1;
+            slot.generation = mixer.slot_generation;
+            mixer.consumer_slots.insert("slot-0".to_string(), slot);
+            mixer.slot_generation
+        };
+
+        // Disconnected while connecting: the producer gains no consumer
+        let generation = add_slot(&mut mixer);
+        mixer.disconnect("slot-0").unwrap();
+        assert!(mixer
+            .complete_slot_connection("slot-0", generation, Ok(()))
+            .is_err());
+        assert!(producer.get_consumer_ids().is_empty());
+
+        // Replaced while connecting: the failure of the previous connection
+        // leaves the new slot alone
+        let stale_generation = add_slot(&mut mixer);
+        mixer.disconnect("slot-0").unwrap();
+        let generation = add_slot(&mut mixer);
+        assert!(mixer
+            .complete_slot_connection("slot-0", stale_generation, Err(anyhow!("failed")))
+            .is_err());
+        assert!(mixer.consumer_slots.contains_key("slot-0"));
+
+        mixer
+            .complete_slot_connection("slot-0", generation, Ok(()))
             .unwrap();
+        assert_eq!(producer.get_consumer_ids(), vec!["slot-0".to_string()]);
+    }
 
-        let mut mixer_settings = Mixer::create_settings();
+    #[test]
+    fn test_consumer_removal_timeout() {
+        gst::init().unwrap();
 
-        if let Some(config) = config {
-            for (key, value) in config {
-                if let Some(setting) = mixer_settings.get_mut(&key) {
-                    let mut setting = setting.lock().unwrap();
-                    SettingController::validate_value(&setting, &value)?;
-                    SettingController::set_from_value(&mut setting, &value);
-                } else {
-                    return Err(anyhow!("No setting with name {} on mixers", key));
-                }
+        let mixer = Mixer::new("test-mixer", None).unwrap();
+        assert_eq!(Mixer::consumer_removal_timeout(&mixer.settings), None);
+
+        let mut config = HashMap::new();
+        config.insert(
+            "consumer-removal-timeout".to_string(),
+            serde_json::json!(2000),
+        );
+
+        let mixer = Mixer::new("test-mixer", Some(config)).unwrap();
+        assert_eq!(
+            Mixer::consumer_removal_timeout(&mixer.settings),
+            Some(std::time::Duration::from_secs(2))
+        );
+    }
+
+    #[test]
+    fn test_scaled_outputs() {
+        gst::init().unwrap();
+
+        let mut mixer = Mixer::new("test-mixer", None).unwrap();
+
+        // As if mixing had started, with no data flowing yet
+        let capsfilter = make_element("capsfilter", None).unwrap();
+        let appsink: gst::Element = mixer.video_producer.appsink().clone().upcast();
+        mixer.pipeline.add_many(&[&capsfilter, &appsink]).unwrap();
+        capsfilter.link(&appsink).unwrap();
+        mixer.video_mixing_state.lock().unwrap().capsfilter = Some(capsfilter);
+
+        let framerate = gst::Fraction::new(30, 1);
+
+        mixer.add_output_scale("small", 64, 36, framerate).unwrap();
+        assert!(mixer.add_output_scale("small", 64, 36, framerate).is_err());
+
+        let tee = mixer.video_tee.clone().unwrap();
+        // One pad for the original output, one for the scaled one
+        assert_eq!(tee.src_pads().len(), 2);
+
+        let bin = mixer.scaled_outputs["small"].bin.clone();
+
+        mixer.remove_output("small").unwrap();
+        assert!(mixer.remove_output("small").is_err());
+
+        // The pad is released asynchronously
+        for _ in 0..50 {
+            if tee.src_pads().len() == 1 && bin.parent().is_none() {
+                break;
             }
+
+            std::thread::sleep(std::time::Duration::from_millis(10));
         }
 
-        Ok(Self {
-            id: id.to_string(),
-            pipeline,
-            pipeline_manager: None,
-            audio_producer: StreamProducer::from(&audio_appsink),
-            video_producer: StreamProducer::from(&video_appsink),
-            consumer_slots: HashMap::new(),
-            audio_mixer,
-            video_mixer,
-            video_mixing_state: Arc::new(Mutex::new(VideoMixingState {
-                base_plate_timeout: gst::CLOCK_TIME_NONE,
-                showing_base_plate: false,
-                slot_controllers: Some(HashMap::new()),
-                mixer_controllers: Some(HashMap::new()),
-                last_pts: gst::CLOCK_TIME_NONE,
-                capsfilter: None,
-            })),
-            audio_mixing_state: Arc::new(Mutex::new(AudioMixingState {
-                slot_controllers: Some(HashMap::new()),
-                last_pts: gst::CLOCK_TIME_NONE,
-            })),
-            state_machine: StateMachine::default(),
-            settings: mixer_settings,
-        })
+        assert_eq!(tee.src_pads().len(), 1);
+        assert!(bin.parent().is_none());
+
+        // Outputs are rolled back if the tee could not be inserted
+        mixer.add_output_scale("small", 64, 36, framerate).unwrap();
+        mixer.pipeline.remove(&tee).unwrap();
+
+        let err = mixer.remove_output("small").unwrap_err();
+        assert!(err.to_string().contains("removed outputs small"));
+        assert!(mixer.video_tee.is_none());
+        assert!(mixer.scaled_outputs.is_empty());
     }
 
-    fn parse_slot_config_key(property: &str) -> Result<(bool, &str), Error> {
-        let split: Vec<&str> = property.splitn(2, "::").collect();
+    #[actix_rt::test]
+    #[test]
+    async fn test_output_sample_timeouts() {
+        gst::init().unwrap();
+
+        let mut mixer = Mixer::new("test-mixer", None).unwrap();
+        // As if started, though nothing will ever be output
+        mixer.state_machine.state = State::Started;
+        let addr = mixer.start();
+
+        // Time progresses immediately while waiting for the timeout
+        tokio::time::pause();
+
+        let err = addr
+            .send(PreviewOutputMessage {
+                width: 64,
+                height: 36,
+            })
+            .await
+            .unwrap()
+            .unwrap_err();
 
-        match split.len() {
-            2 => match split[0] {
-                "video" => Ok((true, split[1])),
-                "audio" => Ok((false, split[1])),
-                _ => Err(anyhow!(
-                    "Slot controller property media type must be one of {audio, video}"
-                )),
-            },
-            _ => Err(anyhow!(
-                "Slot controller property name must be in form media-type::property-name"
-            )),
+        assert!(err.to_string().contains("within"));
+
+        let err = addr
+            .send(CaptureFrameToFileMessage {
+                path: std::env::temp_dir().join("test-mixer-capture.png"),
+                format: ImageFileFormat::Png,
+            })
+            .await
+            .unwrap()
+            .unwrap_err();
+
+        assert!(err.to_string().contains("within"));
+
+        let err = addr.send(TakeSnapshotMessage).await.unwrap().unwrap_err();
+
+        assert!(err.to_string().contains("within"));
+    }
+
+    #[actix_rt::test]
+    #[test]
+    async fn test_take_snapshot_command() {
+        gst::init().unwrap();
+
+        create_mixer("test-mixer", None).await.unwrap();
+
+        let err = run_command(Command::TakeMixerSnapshot {
+            id: "test-mixer".to_string(),
+        })
+        .await
+        .unwrap_err();
+        assert!(err.to_string().contains("must be started"));
+
+        // As received by a controller
+        let res = serde_json::to_string(&CommandResult::Snapshot(MixerSnapshot {
+            video_jpeg: vec![0xff, 0xd8],
+            audio_pcm: vec![0.5, -0.5],
+            video_width: 1920,
+            video_height: 1080,
+            audio_sample_rate: 48000,
+            audio_channels: 2,
+            timestamp_ns: 40_000_000,
+        }))
+        .unwrap();
+
+        match serde_json::from_str::<CommandResult>(&res).unwrap() {
+            CommandResult::Snapshot(snapshot) => {
+                assert_eq!(snapshot.video_jpeg, vec![0xff, 0xd8]);
+                assert_eq!(snapshot.audio_pcm, vec![0.5, -0.5]);
+                assert_eq!((snapshot.video_width, snapshot.video_height), (1920, 1080));
+                assert_eq!(snapshot.audio_sample_rate, 48000);
+                assert_eq!(snapshot.audio_channels, 2);
+                assert_eq!(snapshot.timestamp_ns, 40_000_000);
+            }
+            res => panic!("Unexpected result {:?}", res),
         }
     }
 
-    /// Connect an input slot to `compositor` and `audiomixer`
-    #[instrument(level = "debug", name = "connecting", skip(pipeline, slot))]
-    fn connect_slot(
-        pipeline: &gst::Pipeline,
-        slot: &mut ConsumerSlot,
-        mixer_id: &str,
-        id: &str,
-        width: i32,
-        height: i32,
-        sample_rate: i32,
-    ) -> Result<(), Error> {
-        let video_bin = gst::Bin::new(None);
-        let audio_bin = gst::Bin::new(None);
+    #[test]
+    fn test_element_config() {
+        gst::init().unwrap();
 
-        let aconv = make_element("audioconvert", None)?;
-        let aresample = make_element("audioresample", None)?;
-        let acapsfilter = make_element("capsfilter", None)?;
-        let aqueue = make_element("queue", None)?;
-        let vqueue = make_element("queue", None)?;
+        let config = |key: &str, value: serde_json::Value| {
+            let mut config = HashMap::new();
+            config.insert(key.to_string(), value);
+            Mixer::new("test-mixer", Some(config))
+        };
 
-        acapsfilter
-            .set_property(
-                "caps",
-                &gst::Caps::builder("audio/x-raw")
-                    .field("channels", &2)
-                    .field("format", &"S16LE")
-                    .field("rate", &sample_rate)
-                    .build(),
-            )
-            .unwrap();
+        let mixer = config("compositor::latency", serde_json::json!(40_000_000)).unwrap();
+        assert_eq!(
+            mixer.element_config["compositor::latency"],
+            serde_json::json!(40_000_000)
+        );
 
-        let vappsrc_elem: &gst::Element = slot.video_appsrc.upcast_ref();
-        let aappsrc_elem: &gst::Element = slot.audio_appsrc.upcast_ref();
+        assert!(config("audiomixer::latency", serde_json::json!(1_000_000)).is_ok());
+        assert!(config("audiomixer::latency", serde_json::json!("soon")).is_err());
+        assert!(config("compositor::no-such-property", serde_json::json!(0)).is_err());
+        assert!(config("videomixer::latency", serde_json::json!(0)).is_err());
+
+        let settings: HashMap<String, serde_json::Value> = vec![
+            (
+                "compositor::background".to_string(),
+                serde_json::json!("white"),
+            ),
+            ("audiomixer::latency".to_string(), serde_json::json!(true)),
+        ]
+        .into_iter()
+        .collect();
+
+        let errors = mixer.validate_config(&settings, &HashMap::new());
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].starts_with("audiomixer::latency: "));
+    }
 
-        video_bin.add_many(&[vappsrc_elem, &vqueue])?;
+    #[test]
+    fn test_output_lateness() {
+        gst::init().unwrap();
+
+        let mixer = Mixer::new("test-mixer", None).unwrap();
+        let appsink = mixer.video_producer.appsink();
+
+        assert!(!appsink.property("qos").unwrap().get::<bool>().unwrap());
+        assert_eq!(
+            appsink
+                .property("max-lateness")
+                .unwrap()
+                .get::<i64>()
+                .unwrap(),
+            -1
+        );
 
-        audio_bin.add_many(&[aappsrc_elem, &aconv, &aresample, &acapsfilter, &aqueue])?;
+        let mut config = HashMap::new();
+        config.insert("output-qos".to_string(), serde_json::json!(true));
+        config.insert("output-max-lateness-ms".to_string(), serde_json::json!(40));
 
-        pipeline.add_many(&[&video_bin, &audio_bin])?;
+        let mixer = Mixer::new("test-mixer", Some(config)).unwrap();
 
-        video_bin.sync_state_with_parent()?;
-        audio_bin.sync_state_with_parent()?;
+        for appsink in &[
+            mixer.video_producer.appsink(),
+            mixer.audio_producer.appsink(),
+        ] {
+            assert!(appsink.property("qos").unwrap().get::<bool>().unwrap());
+            assert_eq!(
+                appsink
+                    .property("max-lateness")
+                    .unwrap()
+                    .get::<i64>()
+                    .unwrap(),
+                40_000_000
+            );
+        }
+    }
 
-        let ghost =
-            gst::GhostPad::with_target(Some("src"), &vqueue.static_pad("src").unwrap()).unwrap();
-        video_bin.add_pad(&ghost).unwrap();
+    #[actix_rt::test]
+    #[test]
+    async fn test_set_mixer_latency() {
+        gst::init().unwrap();
 
-        let ghost =
-            gst::GhostPad::with_target(Some("src"), &aqueue.static_pad("src").unwrap()).unwrap();
-        audio_bin.add_pad(&ghost).unwrap();
+        let mixer = Mixer::new("test-mixer", None).unwrap();
+        let video_mixer = mixer.video_mixer.clone();
+        let audio_mixer = mixer.audio_mixer.clone();
+        let addr = mixer.start();
+
+        addr.send(SetCompositorLatencyMessage {
+            latency_ns: 200_000_000,
+        })
+        .await
+        .unwrap()
+        .unwrap();
+        addr.send(SetAudiomixerLatencyMessage {
+            latency_ns: 100_000_000,
+        })
+        .await
+        .unwrap()
+        .unwrap();
 
-        slot.audio_pad.set_property("volume", &slot.volume).unwrap();
+        assert_eq!(
+            video_mixer
+                .property("latency")
+                .unwrap()
+                .get::<u64>()
+                .unwrap(),
+            200_000_000
+        );
+        assert_eq!(
+            audio_mixer
+                .property("latency")
+                .unwrap()
+                .get::<u64>()
+                .unwrap(),
+            100_000_000
+        );
 
-        gst::Element::link_many(&[aappsrc_elem, &aconv, &aresample, &acapsfilter, &aqueue])?;
-        gst::Element::link_many(&[vappsrc_elem, &vqueue])?;
+        assert!(addr
+            .send(SetCompositorLatencyMessage {
+                latency_ns: u64::MAX,
+            })
+            .await
+            .unwrap()
+            .is_err());
+    }
 
-        let srcpad = audio_bin.static_pad("src").unwrap();
-        srcpad.link(&slot.audio_pad).unwrap();
+    #[actix_rt::test]
+    #[test]
+    async fn test_set_mixer_latency_commands() {
+        gst::init().unwrap();
 
-        let srcpad = video_bin.static_pad("src").unwrap();
-        srcpad.link(&slot.video_pad).unwrap();
+        create_source("test-source", &asset_uri("ball.mp4"))
+            .await
+            .unwrap();
+        create_mixer("test-mixer", None).await.unwrap();
 
-        slot.audio_bin = Some(audio_bin);
-        slot.video_bin = Some(video_bin);
+        let set_latencies = |id: &str, latency_ns: u64| {
+            let id = id.to_string();
 
-        slot.video_producer.add_consumer(&slot.video_appsrc, id);
-        slot.audio_producer.add_consumer(&slot.audio_appsrc, id);
+            async move {
+                run_command(Command::SetCompositorLatency {
+                    id: id.clone(),
+                    latency_ns,
+                })
+                .await?;
+                run_command(Command::SetAudiomixerLatency { id, latency_ns }).await
+            }
+        };
 
-        Ok(())
+        set_latencies("test-mixer", 100_000_000).await.unwrap();
+        assert!(set_latencies("test-mixer", u64::MAX).await.is_err());
+        assert!(set_latencies("test-source", 100_000_000).await.is_err());
     }
 
-    /// Build the base plate. It may be either a live videotestsrc, or an
-    /// imagefreeze'd image when a fallback image was specified
-    #[instrument(level = "debug", name = "building base plate", skip(self), fields(id = %self.id))]
-    fn build_base_plate(&mut self, width: i32, height: i32) -> Result<gst::Element, Error> {
-        let bin = gst::Bin::new(None);
-        let fallback_image = self
-            .setting("fallback-image")
-            .unwrap()
-            .as_str()
+    #[actix_rt::test]
+    #[test]
+    async fn test_set_ignore_inactive_pads() {
+        gst::init().unwrap();
+
+        let mixer = Mixer::new("test-mixer", None).unwrap();
+        let video_mixer = mixer.video_mixer.clone();
+        let audio_mixer = mixer.audio_mixer.clone();
+        let addr = mixer.start();
+
+        // The settings are only applied to the elements when starting,
+        // compare with whatever they hold until then
+        let video_value = video_mixer
+            .property("ignore-inactive-pads")
             .unwrap()
-            .to_string();
+            .get::<bool>()
+            .unwrap();
 
-        let ghost = match fallback_image.as_str() {
-            "" => {
-                let vsrc = make_element("videotestsrc", None)?;
-                vsrc.set_property("is-live", &true).unwrap();
-                vsrc.set_property_from_str("pattern", "black");
+        addr.send(SetIgnoreInactivePadsMessage {
+            video: None,
+            audio: Some(false),
+        })
+        .await
+        .unwrap()
+        .unwrap();
 
-                bin.add(&vsrc)?;
+        assert_eq!(
+            video_mixer
+                .property("ignore-inactive-pads")
+                .unwrap()
+                .get::<bool>()
+                .unwrap(),
+            video_value
+        );
+        assert!(!audio_mixer
+            .property("ignore-inactive-pads")
+            .unwrap()
+            .get::<bool>()
+            .unwrap());
 
-                gst::GhostPad::with_target(Some("src"), &vsrc.static_pad("src").unwrap()).unwrap()
-            }
-            _ => {
-                let filesrc = make_element("filesrc", None)?;
-                let decodebin = make_element("decodebin3", None)?;
-                let vconv = make_element("videoconvert", None)?;
-                let imagefreeze = make_element("imagefreeze", None)?;
+        addr.send(SetIgnoreInactivePadsMessage {
+            video: Some(false),
+            audio: Some(true),
+        })
+        .await
+        .unwrap()
+        .unwrap();
+
+        let info = match addr.send(GetNodeInfoMessage).await.unwrap().unwrap() {
+            NodeInfo::Mixer(info) => info,
+            _ => unreachable!(),
+        };
 
-                filesrc.set_property("location", fallback_image).unwrap();
-                imagefreeze.set_property("is-live", &true).unwrap();
+        assert_eq!(
+            info.settings["video-ignore-inactive-pads"],
+            serde_json::json!(false)
+        );
+        assert_eq!(
+            info.settings["audio-ignore-inactive-pads"],
+            serde_json::json!(true)
+        );
+        assert!(!video_mixer
+            .property("ignore-inactive-pads")
+            .unwrap()
+            .get::<bool>()
+            .unwrap());
+    }
 
-                bin.add_many(&[&filesrc, &decodebin, &imagefreeze, &vconv])?;
+    #[actix_rt::test]
+    #[test]
+    async fn test_set_ignore_inactive_pads_command() {
+        gst::init().unwrap();
 
-                let imagefreeze_clone = imagefreeze.downgrade();
-                decodebin.connect_pad_added(move |_bin, pad| {
-                    if let Some(imagefreeze) = imagefreeze_clone.upgrade() {
-                        let sinkpad = imagefreeze.static_pad("sink").unwrap();
-                        pad.link(&sinkpad).unwrap();
-                    }
-                });
+        create_mixer("test-mixer", None).await.unwrap();
 
-                filesrc.link(&decodebin)?;
+        run_command(Command::SetMixerIgnoreInactivePads {
+            id: "test-mixer".to_string(),
+            video: Some(true),
+            audio: None,
+        })
+        .await
+        .unwrap();
 
-                gst::GhostPad::with_target(Some("src"), &imagefreeze.static_pad("src").unwrap())
-                    .unwrap()
-            }
+        let info = match node_info_unchecked("test-mixer").await {
+            NodeInfo::Mixer(info) => info,
+            _ => unreachable!(),
         };
 
-        bin.add_pad(&ghost).unwrap();
+        assert_eq!(
+            info.settings["video-ignore-inactive-pads"],
+            serde_json::json!(true)
+        );
 
-        Ok(bin.upcast())
-    }
+        // Omitted values are left untouched
+        let command: Command = serde_json::from_str(
+            r#"{"setmixerignoreinactivepads": {"id": "test-mixer", "audio": false}}"#,
+        )
+        .unwrap();
+        run_command(command).await.unwrap();
 
-    /// Update slot controllers
-    #[instrument(
-        name = "Updating audio mixing state",
-        level = "trace",
-        skip(mixing_state)
-    )]
-    fn update_audio_mixing_state(
-        agg: &gst_base::Aggregator,
-        id: &str,
-        pts: gst::ClockTime,
-        mixing_state: &mut AudioMixingState,
-    ) {
-        let duration = if mixing_state.last_pts.is_none() {
-            gst::CLOCK_TIME_NONE
-        } else {
-            pts - mixing_state.last_pts
+        let info = match node_info_unchecked("test-mixer").await {
+            NodeInfo::Mixer(info) => info,
+            _ => unreachable!(),
         };
 
-        mixing_state.slot_controllers = Some(Mixer::synchronize_slot_controllers(
-            agg,
-            id,
-            duration,
-            &mut mixing_state.slot_controllers.take().unwrap(),
-        ));
+        assert_eq!(
+            info.settings["video-ignore-inactive-pads"],
+            serde_json::json!(true)
+        );
+        assert_eq!(
+            info.settings["audio-ignore-inactive-pads"],
+            serde_json::json!(false)
+        );
 
-        mixing_state.last_pts = pts;
+        assert!(run_command(Command::SetMixerIgnoreInactivePads {
+            id: "test-source".to_string(),
+            video: Some(true),
+            audio: None,
+        })
+        .await
+        .is_err());
     }
 
-    #[instrument(
-        name = "synchronizing slot controllers",
-        level = "trace",
-        skip(controllers)
-    )]
-    fn synchronize_slot_controllers(
-        agg: &gst_base::Aggregator,
-        id: &str,
-        duration: gst::ClockTime,
-        controllers: &mut HashMap<String, PropertyController>,
-    ) -> HashMap<String, PropertyController> {
-        let now = get_now();
-        let mut updated_controllers = HashMap::new();
+    #[test]
+    fn test_output_audio_bits() {
+        gst::init().unwrap();
 
-        for (id, mut controller) in controllers.drain() {
-            if !controller.synchronize(now, duration) {
-                updated_controllers.insert(id, controller);
-            }
+        for (bits, format) in &[("16", "S16LE"), ("24", "S24_32LE"), ("32", "S32LE")] {
+            let mut config = HashMap::new();
+            config.insert("output-audio-bits".to_string(), serde_json::json!(bits));
+
+            let mixer = Mixer::new("test-mixer", Some(config)).unwrap();
+            assert_eq!(mixer.output_audio_format(), *format);
         }
 
-        updated_controllers
+        for bits in &[serde_json::json!("20"), serde_json::json!(24)] {
+            let mut config = HashMap::new();
+            config.insert("output-audio-bits".to_string(), bits.clone());
+
+            assert!(Mixer::new("test-mixer", Some(config)).is_err());
+        }
     }
 
-    #[instrument(
-        name = "synchronizing mixer controllers",
-        level = "trace",
-        skip(controllers)
-    )]
-    fn synchronize_mixer_controllers(
-        agg: &gst_base::Aggregator,
-        base_plate_pad: &gst::Pad,
-        id: &str,
-        duration: gst::ClockTime,
-        controllers: &mut HashMap<String, SettingController>,
-        capsfilter: &Option<gst::Element>,
-    ) -> HashMap<String, SettingController> {
-        let now = get_now();
-        let mut updated_controllers = HashMap::new();
-        let mut caps = capsfilter.as_ref().map(|capsfilter| {
-            capsfilter
-                .property("caps")
-                .unwrap()
-                .get::<gst::Caps>()
-                .unwrap()
-        });
+    #[test]
+    fn test_priority_slot_active() {
+        gst::init().unwrap();
+
+        let mixer = Mixer::new("test-mixer", None).unwrap();
+        let mut mixing_state = mixer.video_mixing_state.lock().unwrap();
+        let pad = gst::Pad::new(Some("sink_1"), gst::PadDirection::Sink);
+        let timeout = gst::SECOND;
+
+        let is_active = |mixing_state: &VideoMixingState, pts: u64| {
+            Mixer::is_priority_slot_active(
+                mixing_state,
+                &pad,
+                gst::ClockTime::from_mseconds(pts),
+                timeout,
+            )
+        };
 
-        for (id, mut controller) in controllers.drain() {
-            let setting = controller.setting.clone();
+        // Not a priority slot
+        mixing_state
+            .priority_selected_pts
+            .insert("sink_1".to_string(), 1_000_000_000);
+        assert!(!is_active(&mixing_state, 1_500));
 
-            if !controller.synchronize(now, duration) {
-                updated_controllers.insert(id.clone(), controller);
-            }
-            if let Some(ref mut caps) = caps {
-                if id == "width" {
-                    let width = setting.lock().unwrap().as_i32().unwrap();
-                    caps.make_mut().set_simple(&[("width", &width)]);
-                    base_plate_pad.set_property("width", &width).unwrap();
-                } else if id == "height" {
-                    let height = setting.lock().unwrap().as_i32().unwrap();
-                    caps.make_mut().set_simple(&[("height", &height)]);
-                    base_plate_pad.set_property("height", &height).unwrap();
-                }
-            }
-        }
+        // Never selected
+        mixing_state.slot_priorities.insert("sink_1".to_string(), 1);
+        mixing_state.priority_selected_pts.clear();
+        assert!(!is_active(&mixing_state, 1_500));
 
-        if let Some(capsfilter) = capsfilter {
-            capsfilter.set_property("caps", &caps.unwrap()).unwrap();
-        }
+        // Only within the fallback timeout of the last selected sample
+        mixing_state
+            .priority_selected_pts
+            .insert("sink_1".to_string(), 1_000_000_000);
+        assert!(is_active(&mixing_state, 1_500));
+        assert!(is_active(&mixing_state, 2_000));
+        assert!(!is_active(&mixing_state, 2_500));
 
-        updated_controllers
+        // Or within that of the slot
+        mixing_state
+            .slot_timeouts
+            .insert("sink_1".to_string(), 3 * gst::SECOND);
+        assert!(is_active(&mixing_state, 2_500));
     }
 
-    /// Show or hide our base plate, update slot controllers
-    #[instrument(
-        name = "Updating video mixing state",
-        level = "trace",
-        skip(mixing_state)
-    )]
-    fn update_video_mixing_state(
-        agg: &gst_base::Aggregator,
-        id: &str,
-        pts: gst::ClockTime,
-        mixing_state: &mut VideoMixingState,
-        timeout: gst::ClockTime,
-    ) {
-        let mut base_plate_only = true;
-
-        let base_plate_pad = agg.static_pad("sink_0").unwrap();
+    #[actix_rt::test]
+    #[test]
+    async fn test_priority_slot_setting() {
+        gst::init().unwrap();
 
-        for pad in agg.sink_pads() {
-            if pad == base_plate_pad {
-                continue;
-            }
+        let addr = Mixer::new("test-mixer", None).unwrap().start();
 
-            let agg_pad: &gst_base::AggregatorPad = pad.downcast_ref().unwrap();
-            if let Some(sample) = agg.peek_next_sample(agg_pad) {
-                trace!(pad = %pad.name(), "selected non-base plate sample {:?}", sample);
-                base_plate_only = false;
-                break;
-            }
-        }
+        let mut config = HashMap::new();
+        config.insert("priority".to_string(), serde_json::json!(2));
 
-        if base_plate_only {
-            if mixing_state.base_plate_timeout.is_none() {
-                mixing_state.base_plate_timeout = pts;
-            } else if !mixing_state.showing_base_plate
-                && pts - mixing_state.base_plate_timeout > timeout
-            {
-                debug!("falling back to base plate {:?}", base_plate_pad);
-                base_plate_pad.set_property("alpha", &1.0f64).unwrap();
-                mixing_state.showing_base_plate = true;
-            }
-        } else {
-            if mixing_state.showing_base_plate {
-                debug!("hiding base plate: {:?}", base_plate_pad);
-                base_plate_pad.set_property("alpha", &0.0f64).unwrap();
-                mixing_state.showing_base_plate = false;
-            }
-            mixing_state.base_plate_timeout = gst::CLOCK_TIME_NONE;
-        }
+        addr.send(ConsumerMessage::Connect {
+            link_id: "slot-0".to_string(),
+            video_producer: Some(make_producer("slot-0-video-producer")),
+            audio_producer: None,
+            config: Some(config),
+            replace: false,
+        })
+        .await
+        .unwrap()
+        .unwrap();
 
-        let duration = if mixing_state.last_pts.is_none() {
-            gst::CLOCK_TIME_NONE
-        } else {
-            pts - mixing_state.last_pts
+        let info = match addr.send(GetNodeInfoMessage).await.unwrap().unwrap() {
+            NodeInfo::Mixer(info) => info,
+            _ => unreachable!(),
         };
 
-        mixing_state.slot_controllers = Some(Mixer::synchronize_slot_controllers(
-            agg,
-            id,
-            duration,
-            &mut mixing_state.slot_controllers.take().unwrap(),
-        ));
-
-        mixing_state.mixer_controllers = Some(Mixer::synchronize_mixer_controllers(
-            agg,
-            &base_plate_pad,
-            id,
-            duration,
-            &mut mixing_state.mixer_controllers.take().unwrap(),
-            &mixing_state.capsfilter,
-        ));
-
-        mixing_state.last_pts = pts;
+        assert_eq!(
+            info.slot_settings["slot-0"]["priority"],
+            serde_json::json!(2)
+        );
     }
 
-    /// Start our pipeline when cue_time is reached
-    #[instrument(level = "debug", name = "mixing", skip(self, ctx), fields(id = %self.id))]
-    fn start_pipeline(&mut self, ctx: &mut Context<Self>) -> Result<StateChangeResult, Error> {
-        let width = self.setting("width").unwrap().as_i32().unwrap();
-        let height = self.setting("height").unwrap().as_i32().unwrap();
-        let sample_rate = self.setting("sample-rate").unwrap().as_i32().unwrap();
-
-        let vsrc = self.build_base_plate(width, height)?;
-        let vqueue = make_element("queue", None)?;
-        let vcapsfilter = make_element("capsfilter", None)?;
+    #[test]
+    fn test_control_timing_follows_pts() {
+        let timing = ControlTiming {
+            follow_pts: true,
+            ..Default::default()
+        };
 
-        let asrc = make_element("audiotestsrc", None)?;
-        let asrccapsfilter = make_element("capsfilter", None)?;
-        let aqueue = make_element("queue", None)?;
-        let acapsfilter = make_element("capsfilter", None)?;
-        let level = make_element("level", None)?;
-        let aresample = make_element("audioresample", None)?;
-        let aresamplecapsfilter = make_element("capsfilter", None)?;
+        let start = timing.now(gst::ClockTime::from_seconds(10));
 
-        self.video_mixer
-            .set_property_from_str("background", "black");
-        self.video_mixer
-            .set_property(
-                "start-time-selection",
-                &gst_base::AggregatorStartTimeSelection::First,
-            )
-            .unwrap();
-        self.video_mixer
-            .set_property("ignore-inactive-pads", &true)
-            .unwrap();
+        assert_eq!(
+            timing.now(gst::ClockTime::from_mseconds(10_040)),
+            start + chrono::Duration::milliseconds(40)
+        );
+        assert_eq!(
+            timing.now(gst::ClockTime::from_seconds(12)),
+            start + chrono::Duration::seconds(2)
+        );
 
-        vcapsfilter
-            .set_property(
-                "caps",
-                &gst::Caps::builder("video/x-raw")
-                    .field("width", &width)
-                    .field("height", &height)
-                    .field("framerate", &gst::Fraction::new(30, 1))
-                    .field("pixel-aspect-ratio", &gst::Fraction::new(1, 1))
-                    .field("format", &"AYUV")
-                    .field("colorimetry", &"bt601")
-                    .field("chroma-site", &"jpeg")
-                    .field("interlace-mode", &"progressive")
-                    .build(),
-            )
-            .unwrap();
-        asrc.set_property("is-live", &true).unwrap();
-        asrc.set_property("volume", &0.).unwrap();
-        self.audio_mixer
-            .set_property(
-                "start-time-selection",
-                &gst_base::AggregatorStartTimeSelection::First,
-            )
-            .unwrap();
-        self.audio_mixer
-            .set_property("ignore-inactive-pads", &true)
-            .unwrap();
+        // The video and audio callbacks share the anchor of the first PTS
+        let video_timing = ControlTiming {
+            follow_pts: true,
+            ..Default::default()
+        };
+        let audio_timing = video_timing.clone();
 
-        asrccapsfilter
-            .set_property(
-                "caps",
-                &gst::Caps::builder("audio/x-raw")
-                    .field("channels", &2)
-                    .field("format", &"S16LE")
-                    .field("rate", &sample_rate)
-                    .build(),
-            )
-            .unwrap();
+        let start = video_timing.now(gst::ClockTime::from_seconds(10));
+        std::thread::sleep(std::time::Duration::from_millis(10));
 
-        acapsfilter
-            .set_property(
-                "caps",
-                &gst::Caps::builder("audio/x-raw")
-                    .field("channels", &2)
-                    .field("format", &"S16LE")
-                    .field("rate", &sample_rate)
-                    .build(),
-            )
-            .unwrap();
+        assert_eq!(audio_timing.now(gst::ClockTime::from_seconds(10)), start);
+        assert_eq!(
+            audio_timing.now(gst::ClockTime::from_mseconds(10_020)),
+            video_timing.now(gst::ClockTime::from_mseconds(10_020))
+        );
+    }
 
-        aresamplecapsfilter
-            .set_property(
-                "caps",
-                &gst::Caps::builder("audio/x-raw")
-                    .field("rate", &sample_rate)
-                    .build(),
-            )
-            .unwrap();
+    #[test]
+    fn test_label_config() {
+        gst::init().unwrap();
 
-        self.pipeline.add_many(&[
-            &vsrc,
-            &vqueue,
-            &self.video_mixer,
-            &vcapsfilter,
-            &asrc,
-            &asrccapsfilter,
-            &aqueue,
-            &self.audio_mixer,
-            &acapsfilter,
-            &level,
-            &aresample,
-            &aresamplecapsfilter,
-        ])?;
+        assert_eq!(
+            Mixer::parse_label_color(&serde_json::json!("0x80FF0000")).unwrap(),
+            0x80FF0000
+        );
+        assert!(Mixer::parse_label_color(&serde_json::json!("red")).is_err());
+        assert!(Mixer::parse_label_color(&serde_json::json!(42)).is_err());
+        assert!(Mixer::parse_label_position("middle").is_err());
 
-        gst::Element::link_many(&[
-            &vsrc,
-            &vqueue,
-            &self.video_mixer,
-            &vcapsfilter,
-            self.video_producer.appsink().upcast_ref(),
-        ])?;
+        if let Some(overlay) = Mixer::build_label_overlay() {
+            assert_eq!(Mixer::label_position(&overlay), "top-left");
 
-        let base_plate_pad = self.video_mixer.static_pad("sink_0").unwrap();
+            for position in &["top-right", "bottom-left", "bottom-right", "center"] {
+                let (valign, halign) = Mixer::parse_label_position(position).unwrap();
+                overlay.set_property_from_str("valign", valign);
+                overlay.set_property_from_str("halign", halign);
 
-        base_plate_pad.set_property("alpha", &0.0f64).unwrap();
-        base_plate_pad.set_property("width", &width).unwrap();
-        base_plate_pad.set_property("height", &height).unwrap();
-        base_plate_pad.set_property_from_str("sizing-policy", "keep-aspect-ratio");
+                assert_eq!(&Mixer::label_position(&overlay), position);
+            }
+        }
+    }
 
-        gst::Element::link_many(&[
-            &asrc,
-            &asrccapsfilter,
-            &aqueue,
-            &self.audio_mixer,
-            &acapsfilter,
-            &level,
-            &aresample,
-            &aresamplecapsfilter,
-            self.audio_producer.appsink().upcast_ref(),
-        ])?;
+    #[test]
+    fn test_slideshow_images() {
+        let dir = tempfile::tempdir().unwrap();
 
-        for (id, slot) in self.consumer_slots.iter_mut() {
-            Mixer::connect_slot(
-                &self.pipeline,
-                slot,
-                &self.id,
-                id,
-                width,
-                height,
-                sample_rate,
-            )?;
+        for name in &["b.png", "a.png", "c.jpg"] {
+            std::fs::write(dir.path().join(name), b"").unwrap();
         }
+        std::fs::create_dir(dir.path().join("d.png")).unwrap();
 
-        let video_mixing_state = self.video_mixing_state.clone();
-        video_mixing_state.lock().unwrap().capsfilter = Some(vcapsfilter);
-        let id = self.id.clone();
-        let timeout =
-            self.setting("fallback-timeout").unwrap().as_i32().unwrap() as u64 * gst::MSECOND;
+        let names = |location: &std::path::Path| {
+            Mixer::slideshow_images(location.to_str().unwrap())
+                .unwrap()
+                .iter()
+                .map(|path| path.file_name().unwrap().to_str().unwrap().to_string())
+                .collect::<Vec<String>>()
+        };
 
-        self.video_mixer
-            .set_property("emit-signals", &true)
-            .unwrap();
-        self.video_mixer
-            .downcast_ref::<gst_base::Aggregator>()
-            .unwrap()
-            .connect_samples_selected(
-                move |agg: &gst_base::Aggregator, _segment, pts, _dts, _duration, _info| {
-                    let mut mixing_state = video_mixing_state.lock().unwrap();
-                    Mixer::update_video_mixing_state(agg, &id, pts, &mut *mixing_state, timeout);
-                },
-            );
+        assert_eq!(names(dir.path()), vec!["a.png", "b.png", "c.jpg"]);
+        assert_eq!(names(&dir.path().join("*.png")), vec!["a.png", "b.png"]);
+        assert_eq!(names(&dir.path().join("?.jpg")), vec!["c.jpg"]);
+        assert!(Mixer::slideshow_images(dir.path().join("*.gif").to_str().unwrap()).is_err());
+    }
 
-        let audio_mixing_state = self.audio_mixing_state.clone();
-        let id = self.id.clone();
+    #[test]
+    fn test_stream_metadata() {
+        gst::init().unwrap();
 
-        self.audio_mixer
-            .set_property("emit-signals", &true)
+        let mut mixer = Mixer::new("test-mixer", None).unwrap();
+
+        assert!(mixer
+            .set_stream_metadata("".into(), "value".into())
+            .is_err());
+        // Not a string tag
+        assert!(mixer
+            .set_stream_metadata("bitrate".into(), "1000".into())
+            .is_err());
+        assert!(mixer.stream_metadata.is_empty());
+
+        mixer
+            .set_stream_metadata("title".into(), "Program".into())
+            .unwrap();
+        mixer
+            .set_stream_metadata("service".into(), "auteur".into())
             .unwrap();
-        self.audio_mixer
-            .downcast_ref::<gst_base::Aggregator>()
-            .unwrap()
-            .connect_samples_selected(
-                move |agg: &gst_base::Aggregator, _segment, pts, _dts, _duration, _info| {
-                    let mut mixing_state = audio_mixing_state.lock().unwrap();
-                    Mixer::update_audio_mixing_state(agg, &id, pts, &mut *mixing_state);
-                },
-            );
 
-        let addr = ctx.address();
-        let id = self.id.clone();
-        self.pipeline.call_async(move |pipeline| {
-            if let Err(err) = pipeline.set_state(gst::State::Playing) {
-                let _ = addr.do_send(ErrorMessage(format!(
-                    "Failed to start mixer {}: {}",
-                    id, err
-                )));
-            }
-        });
+        let tags = mixer.stream_tags().unwrap();
 
-        self.video_producer.forward();
-        self.audio_producer.forward();
+        assert_eq!(
+            tags.get::<gst::tags::Title>().unwrap().get(),
+            Some("Program")
+        );
+        assert_eq!(
+            tags.get::<gst::tags::ExtendedComment>().unwrap().get(),
+            Some("service=auteur")
+        );
+    }
 
-        Ok(StateChangeResult::Success)
+    #[actix_rt::test]
+    #[test]
+    async fn test_connect_invalid_config_releases_pads() {
+        gst::init().unwrap();
+
+        let mixer = Mixer::new("test-mixer", None).unwrap();
+        let video_mixer = mixer.video_mixer.clone();
+        let audio_mixer = mixer.audio_mixer.clone();
+        let addr = mixer.start();
+
+        let mut config = HashMap::new();
+        config.insert("video::nonexistent".to_string(), serde_json::json!(1));
+
+        let res = addr
+            .send(ConsumerMessage::Connect {
+                link_id: "test-link".to_string(),
+                video_producer: Some(make_producer("test-video-producer")),
+                audio_producer: Some(make_producer("test-audio-producer")),
+                config: Some(config),
+                replace: false,
+            })
+            .await
+            .unwrap();
+
+        assert!(res.is_err());
+        assert!(video_mixer.sink_pads().is_empty());
+        assert!(audio_mixer.sink_pads().is_empty());
+
+        // The link id is free to be used again
+        let res = addr
+            .send(ConsumerMessage::Connect {
+                link_id: "test-link".to_string(),
+                video_producer: Some(make_producer("test-video-producer-2")),
+                audio_producer: None,
+                config: None,
+                replace: false,
+            })
+            .await
+            .unwrap();
+
+        assert!(res.is_ok());
+        assert_eq!(video_mixer.sink_pads().len(), 1);
+        assert!(audio_mixer.sink_pads().is_empty());
     }
 
-    /// Implement Connect command
-    #[instrument(level = "debug", name = "connecting", skip(self, video_producer, audio_producer), fields(id = %self.id))]
-    fn connect(
-        &mut self,
-        link_id: &str,
-        video_producer: &StreamProducer,
-        audio_producer: &StreamProducer,
-        config: Option<HashMap<String, serde_json::Value>>,
-    ) -> Result<(), Error> {
-        if self.consumer_slots.contains_key(link_id) {
-            return Err(anyhow!("mixer {} already has link {}", self.id, link_id));
-        }
+    #[actix_rt::test]
+    #[test]
+    async fn test_connect_disconnect_while_flowing() {
+        gst::init().unwrap();
 
-        let video_pad = self.video_mixer.request_pad_simple("sink_%u").unwrap();
-        let audio_pad = self.audio_mixer.request_pad_simple("sink_%u").unwrap();
+        create_source("test-source", &asset_uri("ball.mp4"))
+            .await
+            .unwrap();
+        create_mixer("test-mixer", None).await.unwrap();
 
-        if let Some(config) = config {
-            for (key, value) in config {
-                let (is_video, property) = Mixer::parse_slot_config_key(&key)?;
+        let listener_addr = register_listener(
+            "test-mixer",
+            "test-listener",
+            VecDeque::from(vec![State::Starting, State::Started]),
+        )
+        .await;
 
-                let pad = if is_video { &video_pad } else { &audio_pad };
+        start_node("test-mixer", None, None).await.unwrap();
+        start_node("test-source", None, None).await.unwrap();
 
-                PropertyController::validate_value(property, pad.upcast_ref(), &value)?;
+        let progression_result = listener_addr.send(WaitForProgressionMessage).await.unwrap();
 
-                debug!("Setting initial slot config {} {}", property, value);
+        assert!(progression_result.progressed_as_expected);
 
-                PropertyController::set_property_from_value(pad.upcast_ref(), property, &value);
-            }
+        for _ in 0..20 {
+            connect("test-link", "test-source", "test-mixer")
+                .await
+                .unwrap();
+
+            tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+
+            disconnect("test-link").await.unwrap();
         }
+    }
 
-        let video_appsrc = gst::ElementFactory::make(
-            "appsrc",
-            Some(&format!("mixer-slot-video-appsrc-{}", link_id)),
-        )
-        .unwrap()
-        .downcast::<gst_app::AppSrc>()
-        .unwrap();
-        let audio_appsrc = gst::ElementFactory::make(
-            "appsrc",
-            Some(&format!("mixer-slot-audio-appsrc-{}", link_id)),
-        )
+    #[actix_rt::test]
+    #[test]
+    async fn test_same_producer_two_slots() {
+        gst::init().unwrap();
+
+        let addr = Mixer::new("test-mixer", None).unwrap().start();
+        let producer = make_producer("camera-video-producer");
+
+        for (link_id, width) in &[("background", 1920), ("inset", 480)] {
+            let mut config = HashMap::new();
+            config.insert("video::width".to_string(), serde_json::json!(width));
+
+            addr.send(ConsumerMessage::Connect {
+                link_id: link_id.to_string(),
+                video_producer: Some(producer.clone()),
+                audio_producer: None,
+                config: Some(config),
+                replace: false,
+            })
+            .await
+            .unwrap()
+            .unwrap();
+        }
+
+        addr.send(ConsumerMessage::AddControlPoint {
+            slot_id: "inset".to_string(),
+            property: "video::xpos".to_string(),
+            control_point: ControlPoint {
+                id: "slide-in".to_string(),
+                time: get_now() + chrono::Duration::seconds(60),
+                value: 1440.into(),
+                mode: ControlMode::Interpolate,
+            },
+        })
+        .await
         .unwrap()
-        .downcast::<gst_app::AppSrc>()
         .unwrap();
 
-        for appsrc in &[&video_appsrc, &audio_appsrc] {
-            appsrc.set_format(gst::Format::Time);
-            appsrc.set_is_live(true);
-            appsrc.set_handle_segment_change(true);
-        }
-
-        let mut slot = ConsumerSlot {
-            video_producer: video_producer.clone(),
-            audio_producer: audio_producer.clone(),
-            video_appsrc,
-            audio_appsrc,
-            audio_bin: None,
-            video_bin: None,
-            volume: 1.0,
-            video_pad,
-            audio_pad,
+        let info = match addr.send(GetNodeInfoMessage).await.unwrap().unwrap() {
+            NodeInfo::Mixer(info) => info,
+            _ => unreachable!(),
         };
 
-        if self.state_machine.state == State::Started {
-            let width = self.setting("width").unwrap().as_i32().unwrap();
-            let height = self.setting("height").unwrap().as_i32().unwrap();
-            let sample_rate = self.setting("sample-rate").unwrap().as_i32().unwrap();
+        assert_eq!(info.slots.len(), 2);
+        assert_eq!(
+            info.slot_settings["background"]["video::width"],
+            serde_json::json!(1920)
+        );
+        assert_eq!(
+            info.slot_settings["inset"]["video::width"],
+            serde_json::json!(480)
+        );
 
-            if let Err(err) = Mixer::connect_slot(
-                &self.pipeline,
-                &mut slot,
-                &self.id,
-                link_id,
-                width,
-                height,
-                sample_rate,
-            ) {
-                return Err(err);
-            }
+        for (slot_id, n_control_points) in &[("background", 0), ("inset", 1)] {
+            let control_points = addr
+                .send(GetSlotControlPointsMessage {
+                    slot_id: slot_id.to_string(),
+                })
+                .await
+                .unwrap()
+                .unwrap();
+
+            assert_eq!(control_points.len(), *n_control_points);
         }
 
-        self.consumer_slots.insert(link_id.to_string(), slot);
+        // Disconnecting one slot leaves the other one in place
+        addr.send(ConsumerMessage::Disconnect {
+            slot_id: "background".to_string(),
+        })
+        .await
+        .unwrap()
+        .unwrap();
 
-        Ok(())
+        let info = match addr.send(GetNodeInfoMessage).await.unwrap().unwrap() {
+            NodeInfo::Mixer(info) => info,
+            _ => unreachable!(),
+        };
+
+        assert_eq!(info.slots.len(), 1);
+        assert!(info.slots.contains_key("inset"));
     }
 
-    /// Implement Disconnect command
-    #[instrument(level = "debug", name = "disconnecting", skip(self), fields(id = %self.id))]
-    fn disconnect(&mut self, slot_id: &str) -> Result<(), Error> {
-        if let Some(slot) = self.consumer_slots.remove(slot_id) {
-            slot.video_producer.remove_consumer(slot_id);
-            slot.audio_producer.remove_consumer(slot_id);
-            if let Some(video_bin) = slot.video_bin {
-                let mixer_pad = video_bin.static_pad("src").unwrap().peer().unwrap();
+    #[actix_rt::test]
+    #[test]
+    async fn test_same_source_two_slots_while_flowing() {
+        gst::init().unwrap();
 
-                video_bin.set_locked_state(true);
-                video_bin.set_state(gst::State::Null).unwrap();
-                self.pipeline.remove(&video_bin).unwrap();
+        create_source("test-source", &asset_uri("ball.mp4"))
+            .await
+            .unwrap();
+        create_mixer("test-mixer", None).await.unwrap();
 
-                self.video_mixer.release_request_pad(&mixer_pad);
-            }
-            if let Some(audio_bin) = slot.audio_bin {
-                let mixer_pad = audio_bin.static_pad("src").unwrap().peer().unwrap();
+        let listener_addr = register_listener(
+            "test-mixer",
+            "test-listener",
+            VecDeque::from(vec![State::Starting, State::Started]),
+        )
+        .await;
 
-                audio_bin.set_locked_state(true);
-                audio_bin.set_state(gst::State::Null).unwrap();
-                self.pipeline.remove(&audio_bin).unwrap();
+        start_node("test-mixer", None, None).await.unwrap();
+        start_node("test-source", None, None).await.unwrap();
 
-                self.audio_mixer.release_request_pad(&mixer_pad);
-            }
+        let progression_result = listener_addr.send(WaitForProgressionMessage).await.unwrap();
 
-            Ok(())
-        } else {
-            Err(anyhow!("mixer {} has no slot with id {}", self.id, slot_id))
-        }
-    }
+        assert!(progression_result.progressed_as_expected);
 
-    /// Implement AddControlPoint command for slots
-    #[instrument(level = "debug", name = "controlling-slot", skip(self), fields(id = %self.id))]
-    fn add_slot_control_point(
-        &mut self,
-        slot_id: &str,
-        property: &str,
-        point: ControlPoint,
-    ) -> Result<(), Error> {
-        if let Some(slot) = self.consumer_slots.get(slot_id) {
-            let (is_video, property) = Mixer::parse_slot_config_key(property)?;
+        connect("background", "test-source", "test-mixer")
+            .await
+            .unwrap();
+        connect("inset", "test-source", "test-mixer").await.unwrap();
 
-            let pad = if is_video {
-                slot.video_pad.clone()
-            } else {
-                slot.audio_pad.clone()
-            };
+        // The source fans out to both slots
+        let mut consumer_slot_ids = match node_info_unchecked("test-source").await {
+            NodeInfo::Source(info) => info.consumer_slot_ids,
+            _ => unreachable!(),
+        };
+        consumer_slot_ids.sort();
+        assert_eq!(
+            consumer_slot_ids,
+            vec!["background".to_string(), "inset".to_string()]
+        );
 
-            debug!(slot_id = %slot_id, pad_name = %pad.name(), property = %property, "Upserting controller");
+        tokio::time::sleep(std::time::Duration::from_millis(200)).await;
 
-            PropertyController::validate_control_point(property, pad.upcast_ref(), &point)?;
+        disconnect("background").await.unwrap();
 
-            let id = slot_id.to_owned() + property;
+        let consumer_slot_ids = match node_info_unchecked("test-source").await {
+            NodeInfo::Source(info) => info.consumer_slot_ids,
+            _ => unreachable!(),
+        };
+        assert_eq!(consumer_slot_ids, vec!["inset".to_string()]);
+    }
 
-            if is_video {
-                let mut mixing_state = self.video_mixing_state.lock().unwrap();
+    #[actix_rt::test]
+    #[test]
+    async fn test_subscribe_audio_level() {
+        gst::init().unwrap();
 
-                mixing_state
-                    .slot_controllers
-                    .as_mut()
-                    .unwrap()
-                    .entry(id)
-                    .or_insert_with(|| PropertyController::new(slot_id, pad.upcast(), property))
-                    .push_control_point(point);
-            } else {
-                let mut mixing_state = self.audio_mixing_state.lock().unwrap();
+        create_mixer("test-mixer", None).await.unwrap();
 
-                mixing_state
-                    .slot_controllers
-                    .as_mut()
-                    .unwrap()
-                    .entry(id)
-                    .or_insert_with(|| PropertyController::new(slot_id, pad.upcast(), property))
-                    .push_control_point(point);
-            }
+        let manager = NodeManager::from_registry();
 
-            Ok(())
-        } else {
-            Err(anyhow!("mixer {} has no slot with id {}", self.id, slot_id))
-        }
-    }
+        let mut receiver = manager
+            .send(SubscribeMixerAudioLevelMessage {
+                id: "test-mixer".to_string(),
+            })
+            .await
+            .unwrap()
+            .unwrap();
 
-    /// Implement RemoveControlPoint command for slots
-    #[instrument(level = "debug", name = "removing control point", skip(self), fields(id = %self.id))]
-    fn remove_slot_control_point(&mut self, controller_id: &str, slot_id: &str, property: &str) {
-        let split: Vec<&str> = property.splitn(2, "::").collect();
+        assert!(receiver.borrow().is_none());
 
-        let (is_video, property) = match split.len() {
-            2 => match split[0] {
-                "video" => (true, split[1]),
-                "audio" => (false, split[1]),
-                _ => {
-                    return;
-                }
-            },
-            _ => {
-                return;
-            }
-        };
+        assert!(manager
+            .send(SubscribeMixerAudioLevelMessage {
+                id: "no-such-mixer".to_string(),
+            })
+            .await
+            .unwrap()
+            .is_err());
 
-        let id = slot_id.to_owned() + property;
+        start_node("test-mixer", None, None).await.unwrap();
 
-        if is_video {
-            let mut mixing_state = self.video_mixing_state.lock().unwrap();
+        // The level element posts a measurement every 100 milliseconds
+        tokio::time::timeout(std::time::Duration::from_secs(5), receiver.changed())
+            .await
+            .unwrap()
+            .unwrap();
+
+        let info = receiver.borrow().clone().unwrap();
+        assert!(!info.rms_db.is_empty());
+        assert_eq!(info.rms_db.len(), info.peak_db.len());
+    }
+
+    #[actix_rt::test]
+    #[test]
+    async fn test_connect_audio_monitor() {
+        gst::init().unwrap();
+        let uri = asset_uri("ball.mp4");
+
+        let mut config = HashMap::new();
+        config.insert("enable-audio-monitor".to_string(), serde_json::json!(true));
+
+        create_mixer("test-monitored", Some(config)).await.unwrap();
+        create_mixer("test-unmonitored", None).await.unwrap();
+        create_mixer("test-mixer", None).await.unwrap();
+        create_source("test-source", &uri).await.unwrap();
+
+        let err = connect_media(
+            "monitor",
+            "test-unmonitored",
+            "test-mixer",
+            ConnectMedia::AudioMonitor,
+        )
+        .await
+        .unwrap_err();
+        assert!(err.to_string().contains("not enabled"));
+
+        let err = connect_media(
+            "monitor",
+            "test-source",
+            "test-mixer",
+            ConnectMedia::AudioMonitor,
+        )
+        .await
+        .unwrap_err();
+        assert!(err.to_string().contains("not a mixer"));
+
+        connect_media(
+            "monitor",
+            "test-monitored",
+            "test-mixer",
+            ConnectMedia::AudioMonitor,
+        )
+        .await
+        .unwrap();
 
-            if let Some(controller) = mixing_state.slot_controllers.as_mut().unwrap().get_mut(&id) {
-                controller.remove_control_point(controller_id);
+        start_node("test-monitored", None, None).await.unwrap();
+        start_node("test-mixer", None, None).await.unwrap();
+
+        let slot_info = || async {
+            match node_info_unchecked("test-mixer").await {
+                NodeInfo::Mixer(mut info) => info.slots.remove("monitor").unwrap(),
+                _ => panic!("Wrong info type"),
             }
-        } else {
-            let mut mixing_state = self.audio_mixing_state.lock().unwrap();
+        };
 
-            if let Some(controller) = mixing_state.slot_controllers.as_mut().unwrap().get_mut(&id) {
-                controller.remove_control_point(controller_id);
+        let mut info = slot_info().await;
+        for _ in 0..50 {
+            if info.audio_input.n_buffers > 0 {
+                break;
             }
+
+            tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+            info = slot_info().await;
+        }
+
+        // Only the monitor feed flows to the slot
+        assert!(info.audio_input.n_buffers > 0);
+        assert_eq!(info.video_input.n_buffers, 0);
+
+        match node_info_unchecked("test-monitored").await {
+            NodeInfo::Mixer(info) => assert!(info.consumer_slot_ids.is_empty()),
+            _ => panic!("Wrong info type"),
         }
     }
 
-    fn slot_control_points(&self) -> HashMap<String, HashMap<String, Vec<ControlPoint>>> {
-        let mut ret = HashMap::new();
+    #[actix_rt::test]
+    #[test]
+    async fn test_preview_mixer_output() {
+        gst::init().unwrap();
 
-        let mixing_state = self.video_mixing_state.lock().unwrap();
+        create_mixer("test-mixer", None).await.unwrap();
 
-        for controller in mixing_state.slot_controllers.as_ref().unwrap().values() {
-            ret.entry(controller.controllee_id.clone())
-                .or_insert_with(HashMap::new)
-                .insert(
-                    "video::".to_owned() + &controller.propname,
-                    controller.control_points(),
-                );
-        }
+        let manager = NodeManager::from_registry();
 
-        let mixing_state = self.audio_mixing_state.lock().unwrap();
+        let preview = |id: &str| {
+            manager.send(PreviewMixerOutputMessage {
+                id: id.to_string(),
+                width: 64,
+                height: 36,
+            })
+        };
 
-        for controller in mixing_state.slot_controllers.as_ref().unwrap().values() {
-            ret.entry(controller.controllee_id.clone())
-                .or_insert_with(HashMap::new)
-                .insert(
-                    "audio::".to_owned() + &controller.propname,
-                    controller.control_points(),
-                );
-        }
+        let err = preview("no-such-mixer").await.unwrap().unwrap_err();
+        assert!(err.to_string().contains("No node with id"));
 
-        ret
+        let err = preview("test-mixer").await.unwrap().unwrap_err();
+        assert!(err.to_string().contains("must be started"));
+
+        start_node("test-mixer", None, None).await.unwrap();
+
+        let jpeg = preview("test-mixer").await.unwrap().unwrap();
+        assert_eq!(&jpeg[..2], &[0xff, 0xd8]);
     }
 
-    fn slot_settings(&self) -> HashMap<String, HashMap<String, serde_json::Value>> {
-        let mut ret = HashMap::new();
+    #[actix_rt::test]
+    #[test]
+    async fn test_capture_frame_command() {
+        gst::init().unwrap();
 
-        for (id, slot) in &self.consumer_slots {
-            let mut properties =
-                PropertyController::properties(slot.video_pad.upcast_ref(), "video::");
-            properties.extend(PropertyController::properties(
-                slot.audio_pad.upcast_ref(),
-                "audio::",
-            ));
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("frame.png");
 
-            ret.insert(id.clone(), properties);
-        }
+        let capture = || Command::CaptureFrame {
+            id: "test-mixer".to_string(),
+            path: path.clone(),
+            format: ImageFileFormat::Png,
+        };
 
-        ret
-    }
+        create_mixer("test-mixer", None).await.unwrap();
 
-    /// Implement AddControlPoint command for the mixer
-    #[instrument(level = "debug", name = "controlling", skip(self), fields(id = %self.id))]
-    fn add_control_point(&mut self, property: String, point: ControlPoint) -> Result<(), Error> {
-        if let Some(setting) = self.settings.get(&property) {
-            SettingController::validate_control_point(&setting.lock().unwrap(), &point)?;
-            let mut mixing_state = self.video_mixing_state.lock().unwrap();
+        let err = run_command(capture()).await.unwrap_err();
+        assert!(err.to_string().contains("must be started"));
 
-            mixing_state
-                .mixer_controllers
-                .as_mut()
-                .unwrap()
-                .entry(property)
-                .or_insert_with(|| SettingController::new(&self.id, setting.clone()))
-                .push_control_point(point);
+        start_node("test-mixer", None, None).await.unwrap();
 
-            Ok(())
-        } else {
-            Err(anyhow!(
-                "mixer {} has no setting with name {}",
-                self.id,
-                property
-            ))
-        }
+        run_command(capture()).await.unwrap();
+
+        let png = std::fs::read(&path).unwrap();
+        assert_eq!(&png[..4], b"\x89PNG");
     }
 
-    /// Implement RemoveControlPoint command for the mixer
-    #[instrument(level = "debug", name = "removing control point", skip(self), fields(id = %self.id))]
-    fn remove_control_point(&mut self, controller_id: &str, property: &str) {
-        let mut mixing_state = self.video_mixing_state.lock().unwrap();
+    #[actix_rt::test]
+    #[test]
+    async fn test_scaled_output_commands() {
+        gst::init().unwrap();
 
-        if let Some(controller) = mixing_state
-            .slot_controllers
-            .as_mut()
-            .unwrap()
-            .get_mut(property)
-        {
-            controller.remove_control_point(controller_id);
-        }
-    }
+        create_mixer("test-mixer", None).await.unwrap();
+        create_mixer("test-consumer", None).await.unwrap();
 
-    #[instrument(level = "debug", skip(self, ctx), fields(id = %self.id))]
-    fn stop(&mut self, ctx: &mut Context<Self>) {
-        self.stop_schedule(ctx);
-        ctx.stop();
-    }
-}
+        run_command(Command::AddOutputScale {
+            id: "test-mixer".to_string(),
+            output_id: "small".to_string(),
+            width: 64,
+            height: 36,
+            framerate: "15/1".to_string(),
+        })
+        .await
+        .unwrap();
 
-impl Schedulable<Self> for Mixer {
-    fn state_machine(&self) -> &StateMachine {
-        &self.state_machine
-    }
+        let connect = |media: ConnectMedia| {
+            run_command(Command::Connect {
+                link_id: "link-0".to_string(),
+                src_id: "test-mixer".to_string(),
+                sink_id: "test-consumer".to_string(),
+                config: None,
+                media,
+                on_duplicate: DuplicateLinkPolicy::Reject,
+                output_id: Some("small".to_string()),
+            })
+        };
 
-    fn state_machine_mut(&mut self) -> &mut StateMachine {
-        &mut self.state_machine
-    }
+        assert!(connect(ConnectMedia::Audio).await.is_err());
+        connect(ConnectMedia::Both).await.unwrap();
 
-    fn node_id(&self) -> &str {
-        &self.id
-    }
+        start_node("test-mixer", None, None).await.unwrap();
+        start_node("test-consumer", None, None).await.unwrap();
 
-    #[instrument(level = "debug", skip(self, ctx), fields(id = %self.id))]
-    fn transition(
-        &mut self,
-        ctx: &mut Context<Self>,
-        target: State,
-    ) -> Result<StateChangeResult, Error> {
-        match target {
-            State::Initial => Ok(StateChangeResult::Skip),
-            State::Starting => self.start_pipeline(ctx),
-            State::Started => Ok(StateChangeResult::Success),
-            State::Stopping => Ok(StateChangeResult::Skip),
-            State::Stopped => {
-                self.stop(ctx);
-                Ok(StateChangeResult::Success)
+        let consumer_info = || async {
+            match node_info_unchecked("test-consumer").await {
+                NodeInfo::Mixer(info) => info,
+                _ => unreachable!(),
+            }
+        };
+
+        // The scaled output is consumed
+        let mut n_buffers = 0;
+        for _ in 0..50 {
+            n_buffers = consumer_info().await.slots["link-0"].video_input.n_buffers;
+
+            if n_buffers > 0 {
+                break;
             }
+
+            tokio::time::sleep(std::time::Duration::from_millis(100)).await;
         }
+        assert!(n_buffers > 0);
+
+        let remove_output = || {
+            run_command(Command::RemoveOutput {
+                id: "test-mixer".to_string(),
+                output_id: "small".to_string(),
+            })
+        };
+
+        // The link fed by the output is disconnected along with it
+        remove_output().await.unwrap();
+        assert!(consumer_info().await.slots.is_empty());
+        assert!(remove_output().await.is_err());
     }
-}
 
-impl Handler<ConsumerMessage> for Mixer {
-    type Result = MessageResult<ConsumerMessage>;
+    #[actix_rt::test]
+    #[test]
+    async fn test_update_setting_command() {
+        gst::init().unwrap();
+
+        let update_setting = |name: &str, value: serde_json::Value| {
+            run_command(Command::UpdateMixerSetting {
+                id: "test-mixer".to_string(),
+                name: name.to_string(),
+                value,
+            })
+        };
 
-    fn handle(&mut self, msg: ConsumerMessage, _ctx: &mut Context<Self>) -> Self::Result {
-        match msg {
-            ConsumerMessage::Connect {
-                link_id,
-                video_producer,
-                audio_producer,
-                config,
-            } => MessageResult(self.connect(&link_id, &video_producer, &audio_producer, config)),
-            ConsumerMessage::Disconnect { slot_id } => MessageResult(self.disconnect(&slot_id)),
-            ConsumerMessage::AddControlPoint {
-                slot_id,
-                property,
-                control_point,
-            } => MessageResult(self.add_slot_control_point(&slot_id, &property, control_point)),
-            ConsumerMessage::RemoveControlPoint {
-                controller_id,
-                slot_id,
-                property,
-            } => {
-                self.remove_slot_control_point(&controller_id, &slot_id, &property);
-                MessageResult(Ok(()))
+        // The equalizer is only inserted when a gain is set at start
+        let mut config = HashMap::new();
+        config.insert("audio-eq-low-gain-db".to_string(), serde_json::json!(-3.0));
+
+        create_mixer("test-mixer", Some(config)).await.unwrap();
+        start_node("test-mixer", None, None).await.unwrap();
+
+        update_setting("audio-eq-low-gain-db", serde_json::json!(6.0))
+            .await
+            .unwrap();
+
+        let mut gain = serde_json::json!(-3.0);
+        for _ in 0..50 {
+            gain = match node_info_unchecked("test-mixer").await {
+                NodeInfo::Mixer(info) => info.settings["audio-eq-low-gain-db"].clone(),
+                _ => unreachable!(),
+            };
+
+            if gain == serde_json::json!(6.0) {
+                break;
             }
+
+            tokio::time::sleep(std::time::Duration::from_millis(100)).await;
         }
+        assert_eq!(gain, serde_json::json!(6.0));
+
+        assert!(
+            update_setting("audio-eq-low-gain-db", serde_json::json!(48.0))
+                .await
+                .is_err()
+        );
+        assert!(update_setting("no-such-setting", serde_json::json!(0.0))
+            .await
+            .is_err());
     }
-}
 
-impl Handler<StartMessage> for Mixer {
-    type Result = MessageResult<StartMessage>;
+    #[actix_rt::test]
+    #[test]
+    async fn test_set_compositor_background_command() {
+        gst::init().unwrap();
 
-    fn handle(&mut self, msg: StartMessage, ctx: &mut Context<Self>) -> Self::Result {
-        MessageResult(self.start_schedule(ctx, msg.cue_time, msg.end_time))
-    }
-}
+        let set_background = |background: &str| {
+            run_command(Command::SetCompositorBackground {
+                id: "test-mixer".to_string(),
+                background: background.to_string(),
+            })
+        };
 
-impl Handler<ErrorMessage> for Mixer {
-    type Result = ();
+        create_mixer("test-mixer", None).await.unwrap();
+        start_node("test-mixer", None, None).await.unwrap();
 
-    fn handle(&mut self, msg: ErrorMessage, ctx: &mut Context<Self>) -> Self::Result {
-        error!("Got error message '{}' on destination {}", msg.0, self.id,);
+        set_background("white").await.unwrap();
+        assert!(set_background("purple").await.is_err());
 
-        NodeManager::from_registry().do_send(NodeStatusMessage::Error {
-            id: self.id.clone(),
-            message: msg.0,
-        });
+        let info = match node_info_unchecked("test-mixer").await {
+            NodeInfo::Mixer(info) => info,
+            _ => unreachable!(),
+        };
 
-        gst::debug_bin_to_dot_file_with_ts(
-            &self.pipeline,
-            gst::DebugGraphDetails::all(),
-            format!("error-mixer-{}", self.id),
+        assert_eq!(
+            info.settings["compositor-background"],
+            serde_json::json!("white")
         );
-
-        ctx.stop();
     }
-}
 
-impl Handler<GetProducerMessage> for Mixer {
-    type Result = MessageResult<GetProducerMessage>;
+    #[test]
+    fn test_snapshot_samples() {
+        gst::init().unwrap();
 
-    fn handle(&mut self, _msg: GetProducerMessage, _ctx: &mut Context<Self>) -> Self::Result {
-        MessageResult(Ok((
-            self.video_producer.clone(),
-            self.audio_producer.clone(),
-        )))
-    }
-}
+        assert_eq!(
+            Mixer::samples_to_f32("S16LE", &[0x00, 0x40, 0x00, 0x80]).unwrap(),
+            vec![0.5, -1.0]
+        );
+        assert_eq!(
+            Mixer::samples_to_f32(
+                "S24_32LE",
+                &[0x00, 0x00, 0xc0, 0x00, 0xff, 0xff, 0x7f, 0x00]
+            )
+            .unwrap(),
+            vec![-0.5, 8388607.0 / 8388608.0]
+        );
+        assert_eq!(
+            Mixer::samples_to_f32("F32LE", &0.25f32.to_le_bytes()).unwrap(),
+            vec![0.25]
+        );
+        assert!(Mixer::samples_to_f32("U8", &[0]).is_err());
 
-impl Handler<ScheduleMessage> for Mixer {
-    type Result = Result<(), Error>;
+        let sample = |pts: u64, caps: gst::Caps| {
+            let mut buffer = gst::Buffer::new();
+            buffer.get_mut().unwrap().set_pts(pts * gst::MSECOND);
 
-    fn handle(&mut self, msg: ScheduleMessage, ctx: &mut Context<Self>) -> Self::Result {
-        self.reschedule(ctx, msg.cue_time, msg.end_time)
-    }
-}
+            gst::Sample::builder().buffer(&buffer).caps(&caps).build()
+        };
 
-impl Handler<StopMessage> for Mixer {
-    type Result = Result<(), Error>;
+        let video_caps = gst::Caps::builder("video/x-raw")
+            .field("framerate", &gst::Fraction::new(25, 1))
+            .build();
+        let audio_caps = gst::Caps::builder("audio/x-raw").build();
 
-    fn handle(&mut self, _msg: StopMessage, ctx: &mut Context<Self>) -> Self::Result {
-        ctx.stop();
-        Ok(())
+        // Frames last 40 milliseconds at 25 fps
+        assert!(Mixer::samples_synchronized(
+            &sample(1000, video_caps.clone()),
+            &sample(1030, audio_caps.clone())
+        ));
+        assert!(!Mixer::samples_synchronized(
+            &sample(1000, video_caps.clone()),
+            &sample(1040, audio_caps.clone())
+        ));
+        assert!(!Mixer::samples_synchronized(
+            &sample(1000, video_caps),
+            &sample(950, audio_caps)
+        ));
     }
-}
 
-impl Handler<GetNodeInfoMessage> for Mixer {
-    type Result = Result<NodeInfo, Error>;
+    #[actix_rt::test]
+    #[test]
+    async fn test_output_caps() {
+        gst::init().unwrap();
 
-    fn handle(&mut self, _msg: GetNodeInfoMessage, _ctx: &mut Context<Self>) -> Self::Result {
-        Ok(NodeInfo::Mixer(MixerInfo {
-            slots: self
-                .consumer_slots
-                .iter()
-                .map(|(id, slot)| {
-                    (
-                        id.clone(),
-                        MixerSlotInfo {
-                            volume: slot.volume,
-                        },
-                    )
-                })
-                .collect(),
-            consumer_slot_ids: self.video_producer.get_consumer_ids(),
-            cue_time: self.state_machine.cue_time,
-            end_time: self.state_machine.end_time,
-            state: self.state_machine.state,
-            settings: self.settings(),
-            control_points: self.control_points(),
-            slot_settings: self.slot_settings(),
-            slot_control_points: self.slot_control_points(),
-        }))
-    }
-}
+        let mut config = HashMap::new();
+        config.insert("width".to_string(), serde_json::json!(320));
+        config.insert("height".to_string(), serde_json::json!(240));
 
-impl Handler<AddControlPointMessage> for Mixer {
-    type Result = Result<(), Error>;
+        create_mixer("test-mixer", Some(config)).await.unwrap();
 
-    fn handle(&mut self, msg: AddControlPointMessage, _ctx: &mut Context<Self>) -> Self::Result {
-        self.add_control_point(msg.property, msg.control_point)
+        let caps = || async {
+            match node_info_unchecked("test-mixer").await {
+                NodeInfo::Mixer(info) => (info.video_caps, info.audio_caps),
+                _ => panic!("Wrong info type"),
+            }
+        };
+
+        assert_eq!(caps().await, (None, None));
+
+        let listener_addr = register_listener(
+            "test-mixer",
+            "test-listener",
+            VecDeque::from(vec![State::Starting, State::Started]),
+        )
+        .await;
+
+        start_node("test-mixer", None, None).await.unwrap();
+
+        let progression_result = listener_addr.send(WaitForProgressionMessage).await.unwrap();
+        assert!(progression_result.progressed_as_expected);
+
+        // Caps get negotiated as the first buffers flow
+        let mut output_caps = caps().await;
+        for _ in 0..50 {
+            if output_caps.0.is_some() && output_caps.1.is_some() {
+                break;
+            }
+
+            tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+            output_caps = caps().await;
+        }
+
+        let (video_caps, audio_caps) = output_caps;
+
+        let video_caps = video_caps.unwrap();
+        assert!(video_caps.starts_with("video/x-raw"));
+        assert!(video_caps.contains("width=(int)320"));
+        assert!(video_caps.contains("height=(int)240"));
+        assert!(audio_caps.unwrap().starts_with("audio/x-raw"));
     }
-}
 
-impl Handler<RemoveControlPointMessage> for Mixer {
-    type Result = ();
+    #[actix_rt::test]
+    #[test]
+    async fn test_output_audio_bits_negotiated() {
+        gst::init().unwrap();
 
-    fn handle(&mut self, msg: RemoveControlPointMessage, _ctx: &mut Context<Self>) -> Self::Result {
-        self.remove_control_point(&msg.controller_id, &msg.property)
+        let mut config = HashMap::new();
+        config.insert("output-audio-bits".to_string(), serde_json::json!("24"));
+
+        create_mixer("test-mixer", Some(config)).await.unwrap();
+
+        let listener_addr = register_listener(
+            "test-mixer",
+            "test-listener",
+            VecDeque::from(vec![State::Starting, State::Started]),
+        )
+        .await;
+
+        start_node("test-mixer", None, None).await.unwrap();
+
+        let progression_result = listener_addr.send(WaitForProgressionMessage).await.unwrap();
+        assert!(progression_result.progressed_as_expected);
+
+        // Caps get negotiated as the first buffers flow
+        let mut audio_caps = None;
+        for _ in 0..50 {
+            if let NodeInfo::Mixer(info) = node_info_unchecked("test-mixer").await {
+                audio_caps = info.audio_caps;
+            }
+
+            if audio_caps.is_some() {
+                break;
+            }
+
+            tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+        }
+
+        assert!(audio_caps.unwrap().contains("format=(string)S24_32LE"));
     }
 }
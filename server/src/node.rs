@@ -7,20 +7,35 @@
 //! another through [`StreamProducer`](crate::utils::StreamProducer)
 
 use crate::destination::Destination;
-use crate::mixer::Mixer;
-use crate::source::Source;
-use crate::utils::StreamProducer;
+use crate::mixer::{
+    AddOutputScaleMessage, ApplyLayoutMessage, ApplyPresetMessage, CaptureFrameToFileMessage,
+    ClearSlotControlPointsMessage, DumpPipelineMessage, GetAudioMonitorProducerMessage,
+    GetAudioPipelineDescriptionMessage, GetOutputProducerMessage, GetSlotControlPointsMessage,
+    GetVideoPipelineDescriptionMessage, KillMessage, Mixer, PreviewOutputMessage,
+    RemoveOutputMessage, ResizeOutputPreservingSlotLayoutMessage, SetAudiomixerLatencyMessage,
+    SetCompositorBackgroundMessage, SetCompositorLatencyMessage, SetIgnoreInactivePadsMessage,
+    SetSlotLabelMessage, SetStreamMetadataMessage, SubscribeAudioLevelMessage, TakeSnapshotMessage,
+    UpdateSettingMessage, ValidateConfigMessage,
+};
+use crate::source::{ScreenCaptureSource, Source};
+use crate::utils::{
+    parse_fraction, AudioLevelInfo, LogFilter, MetricType, MetricsEncoder, PipelineLatency,
+    ProducerNotification, Schedulable, StreamProducer, StreamProducerStats,
+};
 use actix::prelude::*;
 use actix::WeakRecipient;
 use anyhow::{anyhow, Error};
 use auteur_controlling::controller::{
-    Command, CommandResult, ControlPoint, DestinationFamily, Info, NodeInfo, State,
+    Command, CommandResult, ConnectMedia, ControlPoint, DestinationFamily, DuplicateLinkPolicy,
+    ElementInfo, ForwardMode, Info, NodeInfo, NodeType, State,
 };
 use chrono::{DateTime, Utc};
 use futures::channel::oneshot;
 use futures::prelude::*;
-use std::collections::HashMap;
-use tracing::{debug, info, instrument, trace};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::time::Duration;
+use tokio::sync::watch;
+use tracing::{debug, info, instrument, trace, warn};
 use tracing_actix::ActorInstrument;
 use tracing_futures::Instrument;
 
@@ -51,8 +66,18 @@ use tracing_futures::Instrument;
 pub struct NodeManager {
     /// All nodes by id
     nodes: HashMap<String, Node>,
+    /// The ids of all nodes, by type
+    nodes_by_type: HashMap<NodeType, Vec<String>>,
     /// All links, link_id -> consumer recipient
     links: HashMap<String, Recipient<ConsumerMessage>>,
+    /// The nodes at both ends of all links, link_id -> (producer id, consumer id)
+    link_nodes: HashMap<String, (String, String)>,
+    /// The ids of the nodes that opted out of cascading stops
+    cascade_stop_opt_outs: HashSet<String>,
+    /// Notified once a node has stopped, by node id
+    stopped_waiters: HashMap<String, Vec<oneshot::Sender<()>>>,
+    /// Groups of nodes started and stopped together, group id -> node ids
+    groups: HashMap<String, Vec<String>>,
     /// All consumers by id
     consumers: HashMap<String, Recipient<ConsumerMessage>>,
     /// All producers by id
@@ -63,6 +88,12 @@ pub struct NodeManager {
     /// Listeners for unit tests and potential user interfaces.
     /// Listener id -> recipient
     listeners: HashMap<String, WeakRecipient<NodeStatusMessage>>,
+    /// For overriding the log level of individual nodes, unset in
+    /// unit tests
+    log_filter: Option<LogFilter>,
+    /// The interval at which the nodes we create emit heartbeats, zero
+    /// if disabled
+    heartbeat_interval: Duration,
 }
 
 /// Sent from [`controllers`](crate::controller::Controller), this is our
@@ -98,12 +129,15 @@ pub enum ConsumerMessage {
     Connect {
         /// The id of the slot
         link_id: String,
-        /// The video producer to connect to
-        video_producer: StreamProducer,
-        /// The audio producer to connect to
-        audio_producer: StreamProducer,
+        /// The video producer to connect to, if any
+        video_producer: Option<StreamProducer>,
+        /// The audio producer to connect to, if any
+        audio_producer: Option<StreamProducer>,
         /// Initial configuration of the consumer slot
         config: Option<HashMap<String, serde_json::Value>>,
+        /// Whether the consumer should replace its existing slot with
+        /// the same id, rather than reject the connection
+        replace: bool,
     },
     /// Lets the consumer disconnect a slot from its associated
     /// producers
@@ -129,6 +163,26 @@ pub enum ConsumerMessage {
         /// The name of the controlled property
         property: String,
     },
+    /// Instructs the consumer to flush the data queued for a slot, and
+    /// request a keyframe from its producer
+    FlushSlot {
+        /// The id of the slot to flush
+        slot_id: String,
+    },
+    /// Instructs the consumer to hold the video of a slot on its current
+    /// frame, or to resume it
+    FreezeSlot {
+        /// The id of the slot to freeze
+        slot_id: String,
+        /// Whether to freeze or resume the slot
+        frozen: bool,
+    },
+    /// Instructs the consumer to stack its slots in the given order,
+    /// bottom to top
+    SetSlotOrder {
+        /// The ids of the slots, bottom to top
+        order: Vec<String>,
+    },
 }
 
 impl Message for ConsumerMessage {
@@ -208,6 +262,25 @@ impl Message for StopMessage {
     type Result = Result<(), Error>;
 }
 
+/// Stop a node, then all the nodes downstream of it, sent to [`NodeManager`]
+///
+/// Nodes are stopped level by level in topological order, a level is only
+/// stopped once all the nodes of the previous one have. Nodes that opted
+/// out with their `cascade-stop` setting are left running, as are the
+/// nodes only reachable through them.
+#[derive(Debug)]
+pub struct CascadeStopMessage {
+    /// The id of the node to stop first
+    pub root_node_id: String,
+    /// How long the nodes of each level are given to stop, before the
+    /// next level is stopped regardless
+    pub level_timeout: Duration,
+}
+
+impl Message for CascadeStopMessage {
+    type Result = Result<(), Error>;
+}
+
 /// A node has stopped, sent from any node to [`NodeManager`]
 #[derive(Debug)]
 pub struct StoppedMessage {
@@ -232,6 +305,182 @@ impl Message for GetNodeInfoMessage {
     type Result = Result<NodeInfo, Error>;
 }
 
+/// Retrieves the current state of a node, cheap enough to check that
+/// the node is still processing messages. Sent from [`NodeManager`] to
+/// any [`Node`].
+#[derive(Debug)]
+pub struct GetStateMessage;
+
+impl Message for GetStateMessage {
+    type Result = State;
+}
+
+/// Retrieves the latency of the pipeline of a node. Sent from
+/// [`NodeManager`] to any [`Node`], reported in the metrics.
+#[derive(Debug)]
+pub struct GetLatencyMessage;
+
+impl Message for GetLatencyMessage {
+    type Result = Result<PipelineLatency, Error>;
+}
+
+/// How long the nodes of each level of a cascading stop are given to
+/// stop, before the next level is stopped regardless
+const STOP_LEVEL_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How long each node is given to reply to a [`GetAllNodesInfoMessage`]
+const NODE_INFO_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Retrieves the info of all nodes, with each node given
+/// [`NODE_INFO_TIMEOUT`] to reply. Sent to [`NodeManager`].
+///
+/// This is an O(N) operation, sending [`GetNodeInfoMessage`] to every
+/// node in the system, prefer getting the info of individual nodes
+/// in hot paths.
+#[derive(Debug)]
+pub struct GetAllNodesInfoMessage {
+    /// Only gather the info of these nodes, unknown ids are ignored
+    pub ids: Option<Vec<String>>,
+}
+
+impl Message for GetAllNodesInfoMessage {
+    type Result = AllNodesInfo;
+}
+
+/// The outcome of querying the info of a node
+#[derive(Debug)]
+pub enum NodeInfoResult {
+    /// The node replied with its info
+    Info { id: String, info: NodeInfo },
+    /// The node did not reply in time
+    Timeout { id: String },
+    /// The node replied with an error
+    Error { id: String, error: String },
+}
+
+/// The info of all nodes, in reply to [`GetAllNodesInfoMessage`]
+#[derive(Debug)]
+pub struct AllNodesInfo {
+    pub nodes: Vec<NodeInfoResult>,
+}
+
+/// How long a node is given to reply to a [`PingNodeMessage`]
+pub const PING_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// Checks that a node is still responding, replies with the time the
+/// node took to do so. Sent to [`NodeManager`].
+#[derive(Debug)]
+pub struct PingNodeMessage {
+    /// The id of the node to ping
+    pub node_id: String,
+}
+
+impl Message for PingNodeMessage {
+    type Result = Result<Duration, NodeError>;
+}
+
+/// Pings all nodes concurrently, replies with the outcome for each
+/// node, node id -> latency. Sent to [`NodeManager`].
+#[derive(Debug)]
+pub struct PingAllNodesMessage {
+    /// How long each node is given to reply
+    pub timeout: Duration,
+}
+
+impl Message for PingAllNodesMessage {
+    type Result = HashMap<String, Result<Duration, NodeError>>;
+}
+
+/// Why a node could not be pinged
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NodeError {
+    /// No node exists with that id
+    NotFound,
+    /// The node did not reply in time
+    Timeout,
+    /// The node could not be reached
+    Internal(String),
+}
+
+impl std::fmt::Display for NodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            NodeError::NotFound => write!(f, "No such node"),
+            NodeError::Timeout => write!(f, "Node did not reply in time"),
+            NodeError::Internal(err) => write!(f, "Internal server error {}", err),
+        }
+    }
+}
+
+impl std::error::Error for NodeError {}
+
+/// Retrieves the ids of all nodes of a given type. Sent to [`NodeManager`].
+#[derive(Debug)]
+pub struct GetNodesByTypeMessage {
+    /// The type of the nodes to list
+    pub node_type: NodeType,
+}
+
+impl Message for GetNodesByTypeMessage {
+    type Result = Vec<String>;
+}
+
+/// Retrieves the metrics of all nodes, encoded in the Prometheus text
+/// format. Sent from the HTTP service to [`NodeManager`].
+#[derive(Debug)]
+pub struct GetMetricsMessage;
+
+impl Message for GetMetricsMessage {
+    type Result = String;
+}
+
+/// Subscribes to the output audio level measurements of a mixer. Sent
+/// from the HTTP service to [`NodeManager`].
+#[derive(Debug)]
+pub struct SubscribeMixerAudioLevelMessage {
+    /// The id of the mixer
+    pub id: String,
+}
+
+impl Message for SubscribeMixerAudioLevelMessage {
+    type Result = Result<watch::Receiver<Option<AudioLevelInfo>>, Error>;
+}
+
+/// Generates a JPEG thumbnail of the video output of a mixer. Sent
+/// from the HTTP service to [`NodeManager`].
+#[derive(Debug)]
+pub struct PreviewMixerOutputMessage {
+    /// The id of the mixer
+    pub id: String,
+    /// Width of the thumbnail
+    pub width: u16,
+    /// Height of the thumbnail
+    pub height: u16,
+}
+
+impl Message for PreviewMixerOutputMessage {
+    type Result = Result<Vec<u8>, Error>;
+}
+
+/// Hands our global log filter over to [`NodeManager`], for
+/// [`Command::SetNodeLogLevel`]. Sent from the HTTP service upon startup.
+#[derive(Debug)]
+pub struct SetLogFilterMessage(pub LogFilter);
+
+impl Message for SetLogFilterMessage {
+    type Result = ();
+}
+
+/// Sets the interval at which the nodes created from now on emit
+/// [`NodeStatusMessage::Heartbeat`], zero disables them. Sent from the
+/// HTTP service upon startup.
+#[derive(Debug)]
+pub struct SetHeartbeatIntervalMessage(pub Duration);
+
+impl Message for SetHeartbeatIntervalMessage {
+    type Result = ();
+}
+
 /// Sent from [`Node`] to [`NodeManager`] so that it can inform listeners
 /// of nodes' status
 #[derive(Debug, Clone)]
@@ -240,12 +489,70 @@ pub enum NodeStatusMessage {
     State { id: String, state: State },
     /// Node encountered an error
     Error { id: String, message: String },
+    /// Node encountered a warning, or has information to share
+    Warning { id: String, message: String },
+    /// Node is alive and processing messages, emitted periodically
+    Heartbeat {
+        id: String,
+        state: State,
+        /// Time elapsed since the node was created
+        uptime: std::time::Duration,
+    },
 }
 
 impl Message for NodeStatusMessage {
     type Result = ();
 }
 
+/// Sent from [`stream producers`](crate::utils::StreamProducer) to
+/// [`NodeManager`] when they removed a consumer on their own, so that
+/// the consumer node releases the slot of the link too
+#[derive(Debug)]
+pub struct ConsumerRemovedMessage {
+    /// The id of the link the consumer was added with
+    pub link_id: String,
+}
+
+impl Message for ConsumerRemovedMessage {
+    type Result = ();
+}
+
+/// Have the notifications of a producer of node `id` sent to
+/// [`NodeManager`]: failing consumers are reported to listeners on
+/// behalf of the node, and the links of removed consumers are
+/// disconnected
+pub fn forward_producer_notifications(producer: &StreamProducer, id: &str) {
+    // Nodes may also be built outside of a running system, eg in tests
+    if System::try_current().is_none() {
+        return;
+    }
+
+    // Streaming threads can't look up the registry themselves
+    let node_manager = NodeManager::from_registry();
+    let id = id.to_string();
+
+    producer.set_notify(move |notification| match notification {
+        ProducerNotification::PushFailing { message, .. } => {
+            node_manager.do_send(NodeStatusMessage::Warning {
+                id: id.clone(),
+                message,
+            });
+        }
+        ProducerNotification::ConsumerRemoved {
+            consumer_id,
+            message,
+        } => {
+            node_manager.do_send(NodeStatusMessage::Warning {
+                id: id.clone(),
+                message,
+            });
+            node_manager.do_send(ConsumerRemovedMessage {
+                link_id: consumer_id,
+            });
+        }
+    });
+}
+
 /// Sent from any [`NodeStatusMessage`] recipient to [`NodeManager`] to register
 /// a state listener
 #[derive(Debug)]
@@ -263,6 +570,8 @@ impl Message for RegisterListenerMessage {
 enum Node {
     /// A source node is a producer
     Source(Addr<Source>),
+    /// A screen capture is a source node as well
+    ScreenCapture(Addr<ScreenCaptureSource>),
     /// A destination node is a consumer
     Destination(Addr<Destination>),
     /// A mixer node is both a consumer and a producer
@@ -270,10 +579,21 @@ enum Node {
 }
 
 impl Node {
+    /// The type of the node
+    fn node_type(&self) -> NodeType {
+        match self {
+            Node::Source(_) => NodeType::Source,
+            Node::ScreenCapture(_) => NodeType::Source,
+            Node::Destination(_) => NodeType::Destination,
+            Node::Mixer(_) => NodeType::Mixer,
+        }
+    }
+
     /// Start the node
     fn start(&mut self, msg: StartMessage) -> ResponseFuture<Result<(), Error>> {
         let recipient: Recipient<StartMessage> = match self {
             Node::Source(addr) => addr.clone().recipient(),
+            Node::ScreenCapture(addr) => addr.clone().recipient(),
             Node::Destination(addr) => addr.clone().recipient(),
             Node::Mixer(addr) => addr.clone().recipient(),
         };
@@ -289,6 +609,7 @@ impl Node {
     fn schedule(&mut self, msg: ScheduleMessage) -> ResponseFuture<Result<(), Error>> {
         let recipient: Recipient<ScheduleMessage> = match self {
             Node::Source(addr) => addr.clone().recipient(),
+            Node::ScreenCapture(addr) => addr.clone().recipient(),
             Node::Destination(addr) => addr.clone().recipient(),
             Node::Mixer(addr) => addr.clone().recipient(),
         };
@@ -304,16 +625,29 @@ impl Node {
     fn stop(&mut self) {
         let recipient: Recipient<StopMessage> = match self {
             Node::Source(addr) => addr.clone().recipient(),
+            Node::ScreenCapture(addr) => addr.clone().recipient(),
             Node::Destination(addr) => addr.clone().recipient(),
             Node::Mixer(addr) => addr.clone().recipient(),
         };
         let _ = recipient.do_send(StopMessage);
     }
 
+    /// Stop the node immediately, only mixers drain their pipeline
+    /// before stopping otherwise
+    fn kill(&mut self) {
+        match self {
+            Node::Mixer(addr) => {
+                let _ = addr.do_send(KillMessage);
+            }
+            _ => self.stop(),
+        }
+    }
+
     /// Get node-specific info
     fn get_info(&mut self) -> ResponseFuture<Result<NodeInfo, Error>> {
         let recipient: Recipient<GetNodeInfoMessage> = match self {
             Node::Source(addr) => addr.clone().recipient(),
+            Node::ScreenCapture(addr) => addr.clone().recipient(),
             Node::Destination(addr) => addr.clone().recipient(),
             Node::Mixer(addr) => addr.clone().recipient(),
         };
@@ -325,6 +659,69 @@ impl Node {
         })
     }
 
+    /// Get node-specific info, giving up after `timeout`
+    fn get_info_with_timeout(
+        &mut self,
+        id: String,
+        timeout: Duration,
+    ) -> ResponseFuture<NodeInfoResult> {
+        let recipient: Recipient<GetNodeInfoMessage> = match self {
+            Node::Source(addr) => addr.clone().recipient(),
+            Node::ScreenCapture(addr) => addr.clone().recipient(),
+            Node::Destination(addr) => addr.clone().recipient(),
+            Node::Mixer(addr) => addr.clone().recipient(),
+        };
+        Box::pin(async move {
+            match recipient.send(GetNodeInfoMessage).timeout(timeout).await {
+                Ok(Ok(info)) => NodeInfoResult::Info { id, info },
+                Ok(Err(err)) => NodeInfoResult::Error {
+                    id,
+                    error: err.to_string(),
+                },
+                Err(MailboxError::Timeout) => NodeInfoResult::Timeout { id },
+                Err(err) => NodeInfoResult::Error {
+                    id,
+                    error: format!("Internal server error {}", err),
+                },
+            }
+        })
+    }
+
+    /// Check that the node replies within `timeout`
+    fn ping(&mut self, timeout: Duration) -> ResponseFuture<Result<Duration, NodeError>> {
+        let recipient: Recipient<GetStateMessage> = match self {
+            Node::Source(addr) => addr.clone().recipient(),
+            Node::ScreenCapture(addr) => addr.clone().recipient(),
+            Node::Destination(addr) => addr.clone().recipient(),
+            Node::Mixer(addr) => addr.clone().recipient(),
+        };
+        Box::pin(async move {
+            let start = std::time::Instant::now();
+
+            match recipient.send(GetStateMessage).timeout(timeout).await {
+                Ok(_) => Ok(start.elapsed()),
+                Err(MailboxError::Timeout) => Err(NodeError::Timeout),
+                Err(err) => Err(NodeError::Internal(err.to_string())),
+            }
+        })
+    }
+
+    /// Get the latency of the pipeline of the node
+    fn get_latency(&mut self) -> ResponseFuture<Result<PipelineLatency, Error>> {
+        let recipient: Recipient<GetLatencyMessage> = match self {
+            Node::Source(addr) => addr.clone().recipient(),
+            Node::ScreenCapture(addr) => addr.clone().recipient(),
+            Node::Destination(addr) => addr.clone().recipient(),
+            Node::Mixer(addr) => addr.clone().recipient(),
+        };
+        Box::pin(async move {
+            match recipient.send(GetLatencyMessage).await {
+                Ok(res) => res,
+                Err(err) => Err(anyhow!("Internal server error {}", err)),
+            }
+        })
+    }
+
     /// Control a property of the node
     fn add_control_point(
         &mut self,
@@ -332,6 +729,7 @@ impl Node {
     ) -> ResponseFuture<Result<(), Error>> {
         let recipient: Recipient<AddControlPointMessage> = match self {
             Node::Source(addr) => addr.clone().recipient(),
+            Node::ScreenCapture(addr) => addr.clone().recipient(),
             Node::Destination(addr) => addr.clone().recipient(),
             Node::Mixer(addr) => addr.clone().recipient(),
         };
@@ -348,6 +746,7 @@ impl Node {
     fn remove_control_point(&self, msg: RemoveControlPointMessage) {
         let recipient: Recipient<RemoveControlPointMessage> = match self {
             Node::Source(addr) => addr.clone().recipient(),
+            Node::ScreenCapture(addr) => addr.clone().recipient(),
             Node::Destination(addr) => addr.clone().recipient(),
             Node::Mixer(addr) => addr.clone().recipient(),
         };
@@ -359,11 +758,18 @@ impl Default for NodeManager {
     fn default() -> Self {
         Self {
             nodes: HashMap::new(),
+            nodes_by_type: HashMap::new(),
             links: HashMap::new(),
+            link_nodes: HashMap::new(),
+            cascade_stop_opt_outs: HashSet::new(),
+            stopped_waiters: HashMap::new(),
+            groups: HashMap::new(),
             consumers: HashMap::new(),
             producers: HashMap::new(),
             listeners: HashMap::new(),
             no_more_modes_sender: None,
+            log_filter: None,
+            heartbeat_interval: Duration::from_secs(0),
         }
     }
 }
@@ -387,11 +793,11 @@ impl NodeManager {
             return CommandResult::Error(format!("A node already exists with id {}", id));
         }
 
-        let source = Source::new(id, uri);
+        let mut source = Source::new(id, uri);
+        source.set_heartbeat_interval(self.heartbeat_interval);
         let source_addr = source.start();
 
-        self.nodes
-            .insert(id.to_string(), Node::Source(source_addr.clone()));
+        self.add_node(id, Node::Source(source_addr.clone()));
 
         self.producers
             .insert(id.to_string(), source_addr.recipient());
@@ -401,20 +807,69 @@ impl NodeManager {
         CommandResult::Success
     }
 
+    /// Create a [`ScreenCaptureSource`] and store it as a producer
+    fn create_screen_capture(
+        &mut self,
+        id: &str,
+        config: Option<HashMap<String, serde_json::Value>>,
+    ) -> CommandResult {
+        if self.nodes.contains_key(id) {
+            return CommandResult::Error(format!("A node already exists with id {}", id));
+        }
+
+        let mut source = match ScreenCaptureSource::new(id, config) {
+            Ok(source) => source,
+            Err(err) => {
+                return CommandResult::Error(format!("Failed to create screen capture: {}", err));
+            }
+        };
+
+        source.set_heartbeat_interval(self.heartbeat_interval);
+        let addr = source.start();
+
+        self.add_node(id, Node::ScreenCapture(addr.clone()));
+        self.producers.insert(id.to_string(), addr.recipient());
+
+        trace!("Created screen capture {}", id);
+
+        CommandResult::Success
+    }
+
     /// Create a [`Destination`] and store it as a consumer
-    fn create_destination(&mut self, id: &str, family: &DestinationFamily) -> CommandResult {
+    fn create_destination(
+        &mut self,
+        id: &str,
+        family: &DestinationFamily,
+        mut config: Option<HashMap<String, serde_json::Value>>,
+    ) -> CommandResult {
         if self.nodes.contains_key(id) {
             return CommandResult::Error(format!("A node already exists with id {}", id));
         }
 
-        let dest = Destination::new(id, family);
+        let cascade_stop = match NodeManager::take_cascade_stop(&mut config) {
+            Ok(cascade_stop) => cascade_stop,
+            Err(err) => {
+                return CommandResult::Error(format!("Failed to create destination: {}", err));
+            }
+        };
+
+        let mut dest = match Destination::new(id, family, config) {
+            Ok(dest) => dest,
+            Err(err) => {
+                return CommandResult::Error(format!("Failed to create destination: {}", err));
+            }
+        };
 
+        dest.set_heartbeat_interval(self.heartbeat_interval);
         let addr = dest.start();
 
-        self.nodes
-            .insert(id.to_string(), Node::Destination(addr.clone()));
+        self.add_node(id, Node::Destination(addr.clone()));
         self.consumers.insert(id.to_string(), addr.recipient());
 
+        if !cascade_stop {
+            self.cascade_stop_opt_outs.insert(id.to_string());
+        }
+
         trace!("Created destination {}", id);
 
         CommandResult::Success
@@ -424,34 +879,82 @@ impl NodeManager {
     fn create_mixer(
         &mut self,
         id: &str,
-        config: Option<HashMap<String, serde_json::Value>>,
+        mut config: Option<HashMap<String, serde_json::Value>>,
     ) -> CommandResult {
         if self.nodes.contains_key(id) {
             return CommandResult::Error(format!("A node already exists with id {}", id));
         }
 
-        let mixer = match Mixer::new(id, config) {
+        let cascade_stop = match NodeManager::take_cascade_stop(&mut config) {
+            Ok(cascade_stop) => cascade_stop,
+            Err(err) => {
+                return CommandResult::Error(format!("Failed to create mixer: {}", err));
+            }
+        };
+
+        let mut mixer = match Mixer::new(id, config) {
             Ok(mixer) => mixer,
             Err(err) => {
                 return CommandResult::Error(format!("Failed to create mixer: {}", err));
             }
         };
 
+        mixer.set_heartbeat_interval(self.heartbeat_interval);
         let addr = mixer.start();
 
-        self.nodes.insert(id.to_string(), Node::Mixer(addr.clone()));
+        self.add_node(id, Node::Mixer(addr.clone()));
         self.producers
             .insert(id.to_string(), addr.clone().recipient());
         self.consumers.insert(id.to_string(), addr.recipient());
 
+        if !cascade_stop {
+            self.cascade_stop_opt_outs.insert(id.to_string());
+        }
+
         trace!("Created mixer {}", id);
 
         CommandResult::Success
     }
 
+    /// Extract the `cascade-stop` setting from the config of a consumer,
+    /// it is handled by us rather than by the node itself
+    fn take_cascade_stop(
+        config: &mut Option<HashMap<String, serde_json::Value>>,
+    ) -> Result<bool, Error> {
+        match config
+            .as_mut()
+            .and_then(|config| config.remove("cascade-stop"))
+        {
+            Some(value) => value
+                .as_bool()
+                .ok_or_else(|| anyhow!("cascade-stop must be a boolean")),
+            None => Ok(true),
+        }
+    }
+
+    /// Store a node in our collections
+    fn add_node(&mut self, id: &str, node: Node) {
+        self.nodes_by_type
+            .entry(node.node_type())
+            .or_default()
+            .push(id.to_string());
+        self.nodes.insert(id.to_string(), node);
+    }
+
     /// Remove a node from our collections by id
     fn remove_node(&mut self, id: &str) {
-        let _ = self.nodes.remove(id);
+        if let Some(node) = self.nodes.remove(id) {
+            if let Some(ids) = self.nodes_by_type.get_mut(&node.node_type()) {
+                ids.retain(|node_id| node_id != id);
+            }
+        }
+        for node_ids in self.groups.values_mut() {
+            node_ids.retain(|node_id| node_id != id);
+        }
+        self.groups.retain(|_, node_ids| !node_ids.is_empty());
+        if let Some(ref mut log_filter) = self.log_filter {
+            let _ = log_filter.set_node_level(id, None);
+        }
         if self.nodes.is_empty() {
             if let Some(sender) = self.no_more_modes_sender.take() {
                 let _ = sender.send(());
@@ -460,79 +963,419 @@ impl NodeManager {
     }
 
     /// Tell a node to stop, by id
-    fn stop_node(&mut self, id: &str) -> CommandResult {
+    fn stop_node(&mut self, id: &str, kill: bool) -> CommandResult {
         if let Some(node) = self.nodes.get_mut(id) {
-            node.stop();
+            if kill {
+                node.kill();
+            } else {
+                node.stop();
+            }
             CommandResult::Success
         } else {
             CommandResult::Error(format!("No node with id {}", id))
         }
     }
 
-    /// Tell a node to disconnect one of its consumer slots
-    fn disconnect_consumer(&self, consumer: &mut Recipient<ConsumerMessage>, slot_id: String) {
-        let _ = consumer.do_send(ConsumerMessage::Disconnect { slot_id });
-    }
+    /// Create a group of existing nodes
+    fn create_group(&mut self, id: &str, node_ids: Vec<String>) -> CommandResult {
+        if self.groups.contains_key(id) {
+            return CommandResult::Error(format!("A group already exists with id {}", id));
+        }
 
-    /// Look up all consumers for a given producer and tell them to disconnect
-    /// the associated slots
-    fn disconnect_consumers(
-        &mut self,
-        video_producer: Option<StreamProducer>,
-        audio_producer: Option<StreamProducer>,
-    ) {
-        debug!("Disconnecting consumers");
+        if node_ids.is_empty() {
+            return CommandResult::Error(format!("Group {} has no nodes", id));
+        }
 
-        if let Some(video_producer) = video_producer {
-            for slot_id in video_producer.get_consumer_ids() {
-                if let Some(mut consumer) = self.links.remove(&slot_id) {
-                    self.disconnect_consumer(&mut consumer, slot_id);
-                }
-            }
+        if let Some(node_id) = node_ids
+            .iter()
+            .find(|node_id| !self.nodes.contains_key(*node_id))
+        {
+            return CommandResult::Error(format!("No node with id {}", node_id));
         }
 
-        if let Some(audio_producer) = audio_producer {
-            for slot_id in audio_producer.get_consumer_ids() {
-                if let Some(mut consumer) = self.links.remove(&slot_id) {
-                    self.disconnect_consumer(&mut consumer, slot_id);
-                }
-            }
+        let mut unique_ids = HashSet::new();
+
+        if let Some(node_id) = node_ids
+            .iter()
+            .find(|node_id| !unique_ids.insert(node_id.as_str()))
+        {
+            return CommandResult::Error(format!(
+                "Node {} is listed more than once in group {}",
+                node_id, id
+            ));
         }
+
+        self.groups.insert(id.to_string(), node_ids);
+
+        CommandResult::Success
     }
 
-    /// Start a [`Node`]
-    #[instrument(level = "trace", name = "start-command", skip(self))]
-    fn send_start_command_future(
+    /// Start all the nodes of a group at once
+    ///
+    /// The start messages are all sent before any of them is awaited,
+    /// the command fails if any node fails to start.
+    #[instrument(level = "trace", name = "start-group-command", skip(self))]
+    fn start_group_future(
         &mut self,
         id: &str,
         cue_time: Option<DateTime<Utc>>,
         end_time: Option<DateTime<Utc>>,
     ) -> ResponseActFuture<Self, CommandResult> {
-        if let Some(node) = self.nodes.get(id) {
-            let mut node = node.clone();
-            Box::pin(
-                {
-                    async move { node.start(StartMessage { cue_time, end_time }).await }
-                        .into_actor(self)
-                        .then(move |res, _slf, _ctx| {
-                            actix::fut::ready(match res {
-                                Ok(_) => CommandResult::Success,
-                                Err(err) => CommandResult::Error(format!("{}", err)),
-                            })
-                        })
-                }
-                .in_current_actor_span(),
-            )
-        } else {
-            Box::pin(actix::fut::ready(CommandResult::Error(format!(
-                "No node with id {}",
-                id
-            ))))
-        }
-    }
+        let node_ids = match self.groups.get(id) {
+            Some(node_ids) => node_ids.clone(),
+            None => {
+                return Box::pin(actix::fut::ready(CommandResult::Error(format!(
+                    "No group with id {}",
+                    id
+                ))))
+            }
+        };
 
-    /// Reschedule a [`Node`]
-    #[instrument(level = "trace", name = "schedule-command", skip(self))]
+        let futs: Vec<_> = node_ids
+            .iter()
+            .map(|node_id| {
+                let mut node = self.nodes[node_id].clone();
+                let node_id = node_id.clone();
+                let fut = node.start(StartMessage { cue_time, end_time });
+
+                async move { fut.await.map_err(|err| format!("{}: {}", node_id, err)) }
+            })
+            .collect();
+
+        Box::pin(
+            {
+                async move { futures::future::join_all(futs).await }
+                    .into_actor(self)
+                    .then(move |res, _slf, _ctx| {
+                        let errors: Vec<String> = res.into_iter().filter_map(Result::err).collect();
+
+                        actix::fut::ready(if errors.is_empty() {
+                            CommandResult::Success
+                        } else {
+                            CommandResult::Error(format!(
+                                "Failed to start nodes of group: {}",
+                                errors.join(", ")
+                            ))
+                        })
+                    })
+            }
+            .in_current_actor_span(),
+        )
+    }
+
+    /// Override the log level of a node, or reset it to the global level
+    fn set_node_log_level(&mut self, id: &str, level: Option<String>) -> CommandResult {
+        if !self.nodes.contains_key(id) {
+            return CommandResult::Error(format!("No node with id {}", id));
+        }
+
+        let log_filter = match self.log_filter {
+            Some(ref mut log_filter) => log_filter,
+            None => return CommandResult::Error("Log levels cannot be changed".to_string()),
+        };
+
+        match log_filter.set_node_level(id, level.as_deref()) {
+            Ok(()) => CommandResult::Success,
+            Err(err) => CommandResult::Error(format!("{}", err)),
+        }
+    }
+
+    /// Set the forward mode of the video and audio producers of a node
+    fn set_forward_mode_future(
+        &mut self,
+        id: &str,
+        mode: ForwardMode,
+    ) -> ResponseActFuture<Self, CommandResult> {
+        let producer = match self.producers.get(id) {
+            Some(producer) => producer.clone(),
+            None => {
+                return Box::pin(actix::fut::ready(CommandResult::Error(format!(
+                    "No producer with id {}",
+                    id
+                ))))
+            }
+        };
+
+        Box::pin(
+            async move {
+                match producer.send(GetProducerMessage).await {
+                    Ok(Ok((video_producer, audio_producer))) => {
+                        video_producer.set_forward_mode(mode);
+                        audio_producer.set_forward_mode(mode);
+                        CommandResult::Success
+                    }
+                    Ok(Err(err)) => {
+                        CommandResult::Error(format!("Failed to get producer: {}", err))
+                    }
+                    Err(err) => CommandResult::Error(format!("Internal server error {}", err)),
+                }
+            }
+            .into_actor(self)
+            .in_current_actor_span(),
+        )
+    }
+
+    /// Tell all the nodes of a group to stop
+    ///
+    /// All nodes are told to stop, the command fails if any of them
+    /// couldn't be, eg because it was already removed. As with
+    /// [`Command::Remove`], nodes are removed once stopped, and the group
+    /// along with its last node.
+    fn stop_group(&mut self, id: &str) -> CommandResult {
+        let node_ids = match self.groups.get(id) {
+            Some(node_ids) => node_ids.clone(),
+            None => return CommandResult::Error(format!("No group with id {}", id)),
+        };
+
+        let errors: Vec<String> = node_ids
+            .iter()
+            .filter_map(|node_id| match self.stop_node(node_id, false) {
+                CommandResult::Error(err) => Some(format!("{}: {}", node_id, err)),
+                _ => None,
+            })
+            .collect();
+
+        if errors.is_empty() {
+            CommandResult::Success
+        } else {
+            CommandResult::Error(format!(
+                "Failed to stop nodes of group: {}",
+                errors.join(", ")
+            ))
+        }
+    }
+
+    /// Compute the levels of a cascading stop from `root_id`, in
+    /// topological order
+    fn cascade_stop_levels(&self, root_id: &str) -> VecDeque<Vec<String>> {
+        let mut downstream: HashMap<&str, Vec<&str>> = HashMap::new();
+
+        for (src_id, sink_id) in self.link_nodes.values() {
+            if self.nodes.contains_key(sink_id) && !self.cascade_stop_opt_outs.contains(sink_id) {
+                downstream
+                    .entry(src_id.as_str())
+                    .or_default()
+                    .push(sink_id.as_str());
+            }
+        }
+
+        let mut reachable = HashSet::new();
+        let mut to_visit = vec![root_id];
+
+        while let Some(id) = to_visit.pop() {
+            if reachable.insert(id) {
+                to_visit.extend(downstream.get(id).into_iter().flatten());
+            }
+        }
+
+        let mut n_upstream: HashMap<&str, usize> = reachable.iter().map(|id| (*id, 0)).collect();
+
+        for id in &reachable {
+            for sink_id in downstream.get(id).into_iter().flatten() {
+                *n_upstream.get_mut(sink_id).unwrap() += 1;
+            }
+        }
+
+        let mut levels = VecDeque::new();
+        let mut level = vec![root_id];
+        n_upstream.remove(root_id);
+
+        while !level.is_empty() {
+            let mut next_level = vec![];
+
+            for id in &level {
+                for sink_id in downstream.get(id).into_iter().flatten() {
+                    if let Some(n) = n_upstream.get_mut(sink_id) {
+                        *n -= 1;
+                        if *n == 0 {
+                            n_upstream.remove(sink_id);
+                            next_level.push(*sink_id);
+                        }
+                    }
+                }
+            }
+
+            levels.push_back(level.iter().map(|id| id.to_string()).collect());
+            level = next_level;
+        }
+
+        // Cycles can't be ordered, stop their nodes last
+        if !n_upstream.is_empty() {
+            levels.push_back(n_upstream.keys().map(|id| id.to_string()).collect());
+        }
+
+        levels
+    }
+
+    /// Stop the nodes of a level, then those of the next levels once
+    /// they have all stopped, or after `level_timeout`
+    ///
+    /// Resolves to the ids of the nodes that did not stop in time.
+    fn stop_levels(
+        &mut self,
+        mut levels: VecDeque<Vec<String>>,
+        level_timeout: Duration,
+        mut not_stopped: Vec<String>,
+    ) -> ResponseActFuture<Self, Vec<String>> {
+        let level = match levels.pop_front() {
+            Some(level) => level,
+            None => return Box::pin(actix::fut::ready(not_stopped)),
+        };
+
+        let mut stopped_receivers = vec![];
+
+        for id in level {
+            if let Some(node) = self.nodes.get_mut(&id) {
+                let (stopped_sender, stopped_receiver) = oneshot::channel::<()>();
+
+                debug!(id = %id, "Stopping node in cascade");
+
+                node.stop();
+                self.stopped_waiters
+                    .entry(id.clone())
+                    .or_default()
+                    .push(stopped_sender);
+                stopped_receivers.push(async move {
+                    match tokio::time::timeout(level_timeout, stopped_receiver).await {
+                        Ok(_) => None,
+                        Err(_) => {
+                            warn!(id = %id, "Node did not stop in time");
+                            Some(id)
+                        }
+                    }
+                });
+            }
+        }
+
+        Box::pin(
+            futures::future::join_all(stopped_receivers)
+                .into_actor(self)
+                .then(move |timed_out, slf, _ctx| {
+                    not_stopped.extend(timed_out.into_iter().flatten());
+                    slf.stop_levels(levels, level_timeout, not_stopped)
+                }),
+        )
+    }
+
+    /// Stop a node, and the nodes downstream of it
+    #[instrument(level = "trace", name = "cascade-stop", skip(self))]
+    fn cascade_stop_future(
+        &mut self,
+        root_id: &str,
+        level_timeout: Duration,
+    ) -> ResponseActFuture<Self, Result<(), Error>> {
+        if !self.nodes.contains_key(root_id) {
+            return Box::pin(actix::fut::ready(Err(anyhow!(
+                "No node with id {}",
+                root_id
+            ))));
+        }
+
+        let levels = self.cascade_stop_levels(root_id);
+
+        Box::pin(self.stop_levels(levels, level_timeout, vec![]).map(
+            move |not_stopped, _slf, _ctx| {
+                if not_stopped.is_empty() {
+                    Ok(())
+                } else {
+                    Err(anyhow!(
+                        "Nodes {} did not stop within {:?}",
+                        not_stopped.join(", "),
+                        level_timeout
+                    ))
+                }
+            },
+        ))
+    }
+
+    /// Stop a node and the nodes downstream of it in the background
+    fn cascade_stop(&mut self, ctx: &mut Context<Self>, root_id: &str) -> CommandResult {
+        if !self.nodes.contains_key(root_id) {
+            return CommandResult::Error(format!("No node with id {}", root_id));
+        }
+
+        let root_id = root_id.to_string();
+
+        ctx.spawn(self.cascade_stop_future(&root_id, STOP_LEVEL_TIMEOUT).map(
+            move |res, slf, _ctx| {
+                if let Err(err) = res {
+                    slf.notify_listeners(NodeStatusMessage::Warning {
+                        id: root_id,
+                        message: err.to_string(),
+                    });
+                }
+            },
+        ));
+
+        CommandResult::Success
+    }
+
+    /// Tell a node to disconnect one of its consumer slots
+    fn disconnect_consumer(&self, consumer: &mut Recipient<ConsumerMessage>, slot_id: String) {
+        let _ = consumer.do_send(ConsumerMessage::Disconnect { slot_id });
+    }
+
+    /// Look up all consumers for a given producer and tell them to disconnect
+    /// the associated slots
+    fn disconnect_consumers(
+        &mut self,
+        video_producer: Option<StreamProducer>,
+        audio_producer: Option<StreamProducer>,
+    ) {
+        debug!("Disconnecting consumers");
+
+        if let Some(video_producer) = video_producer {
+            for slot_id in video_producer.get_consumer_ids() {
+                if let Some(mut consumer) = self.links.remove(&slot_id) {
+                    self.link_nodes.remove(&slot_id);
+                    self.disconnect_consumer(&mut consumer, slot_id);
+                }
+            }
+        }
+
+        if let Some(audio_producer) = audio_producer {
+            for slot_id in audio_producer.get_consumer_ids() {
+                if let Some(mut consumer) = self.links.remove(&slot_id) {
+                    self.link_nodes.remove(&slot_id);
+                    self.disconnect_consumer(&mut consumer, slot_id);
+                }
+            }
+        }
+    }
+
+    /// Start a [`Node`]
+    #[instrument(level = "trace", name = "start-command", skip(self))]
+    fn send_start_command_future(
+        &mut self,
+        id: &str,
+        cue_time: Option<DateTime<Utc>>,
+        end_time: Option<DateTime<Utc>>,
+    ) -> ResponseActFuture<Self, CommandResult> {
+        if let Some(node) = self.nodes.get(id) {
+            let mut node = node.clone();
+            Box::pin(
+                {
+                    async move { node.start(StartMessage { cue_time, end_time }).await }
+                        .into_actor(self)
+                        .then(move |res, _slf, _ctx| {
+                            actix::fut::ready(match res {
+                                Ok(_) => CommandResult::Success,
+                                Err(err) => CommandResult::Error(format!("{}", err)),
+                            })
+                        })
+                }
+                .in_current_actor_span(),
+            )
+        } else {
+            Box::pin(actix::fut::ready(CommandResult::Error(format!(
+                "No node with id {}",
+                id
+            ))))
+        }
+    }
+
+    /// Reschedule a [`Node`]
+    #[instrument(level = "trace", name = "schedule-command", skip(self))]
     fn send_schedule_command_future(
         &mut self,
         id: &str,
@@ -562,7 +1405,205 @@ impl NodeManager {
         }
     }
 
+    /// Validate a configuration against a mixer, without applying it
+    #[instrument(
+        level = "trace",
+        name = "validate-mixer-config",
+        skip(self, settings, slot_config)
+    )]
+    fn validate_mixer_config_future(
+        &mut self,
+        id: &str,
+        settings: HashMap<String, serde_json::Value>,
+        slot_config: HashMap<String, serde_json::Value>,
+    ) -> ResponseActFuture<Self, CommandResult> {
+        let addr = match self.nodes.get(id) {
+            Some(Node::Mixer(addr)) => addr.clone(),
+            Some(_) => {
+                return Box::pin(actix::fut::ready(CommandResult::Error(format!(
+                    "Node with id {} is not a mixer",
+                    id
+                ))))
+            }
+            None => {
+                return Box::pin(actix::fut::ready(CommandResult::Error(format!(
+                    "No node with id {}",
+                    id
+                ))))
+            }
+        };
+
+        Box::pin(
+            {
+                async move {
+                    addr.send(ValidateConfigMessage {
+                        settings,
+                        slot_config,
+                    })
+                    .await
+                }
+                .into_actor(self)
+                .then(move |res, _slf, _ctx| {
+                    actix::fut::ready(match res {
+                        Ok(errors) if errors.is_empty() => CommandResult::Success,
+                        Ok(errors) => CommandResult::Error(errors.join("\n")),
+                        Err(err) => CommandResult::Error(format!("Internal server error {}", err)),
+                    })
+                })
+            }
+            .in_current_actor_span(),
+        )
+    }
+
+    /// Send a mixer-specific message to a [`Mixer`], for requests that
+    /// return data rather than a [`CommandResult`]
+    fn mixer_request_future<M, T>(&self, id: &str, msg: M) -> ResponseFuture<Result<T, Error>>
+    where
+        M: Message<Result = Result<T, Error>> + Send + 'static,
+        T: Send + 'static,
+        Mixer: Handler<M>,
+    {
+        match self.nodes.get(id) {
+            Some(Node::Mixer(addr)) => {
+                let addr = addr.clone();
+                Box::pin(async move {
+                    addr.send(msg)
+                        .await
+                        .map_err(|err| anyhow!("Internal server error {}", err))?
+                })
+            }
+            Some(_) => Box::pin(future::err(anyhow!("Node with id {} is not a mixer", id))),
+            None => Box::pin(future::err(anyhow!("No node with id {}", id))),
+        }
+    }
+
+    /// Remove a scaled output of a [`Mixer`], and disconnect the links
+    /// it fed
+    #[instrument(level = "debug", name = "removing-output", skip(self))]
+    fn remove_output_future(
+        &mut self,
+        id: &str,
+        output_id: String,
+    ) -> ResponseActFuture<Self, CommandResult> {
+        Box::pin(
+            self.mixer_request_future(id, RemoveOutputMessage { output_id })
+                .into_actor(self)
+                .then(|res, slf, _ctx| {
+                    actix::fut::ready(match res {
+                        Ok(link_ids) => {
+                            for link_id in link_ids {
+                                if let Some(mut consumer) = slf.links.remove(&link_id) {
+                                    slf.link_nodes.remove(&link_id);
+                                    slf.disconnect_consumer(&mut consumer, link_id);
+                                }
+                            }
+
+                            CommandResult::Success
+                        }
+                        Err(err) => CommandResult::Error(format!("{}", err)),
+                    })
+                })
+                .in_current_actor_span(),
+        )
+    }
+
+    /// Retrieve the control points of a slot of a [`Mixer`]
+    fn get_slot_control_points_future(
+        &mut self,
+        id: &str,
+        slot_id: String,
+    ) -> ResponseActFuture<Self, CommandResult> {
+        Box::pin(
+            self.mixer_request_future(id, GetSlotControlPointsMessage { slot_id })
+                .into_actor(self)
+                .map(|res, _slf, _ctx| match res {
+                    Ok(control_points) => CommandResult::ControlPoints(control_points),
+                    Err(err) => CommandResult::Error(format!("{}", err)),
+                }),
+        )
+    }
+
+    /// Describe the elements of a path of the pipeline of a [`Mixer`]
+    fn pipeline_description_future<M>(
+        &mut self,
+        id: &str,
+        msg: M,
+    ) -> ResponseActFuture<Self, CommandResult>
+    where
+        M: Message<Result = Result<Vec<ElementInfo>, Error>> + Send + 'static,
+        Mixer: Handler<M>,
+    {
+        Box::pin(self.mixer_request_future(id, msg).into_actor(self).map(
+            |res, _slf, _ctx| match res {
+                Ok(elements) => CommandResult::PipelineDescription(elements),
+                Err(err) => CommandResult::Error(format!("{}", err)),
+            },
+        ))
+    }
+
+    /// Take a snapshot of the output of a [`Mixer`]
+    fn take_snapshot_future(&mut self, id: &str) -> ResponseActFuture<Self, CommandResult> {
+        Box::pin(
+            self.mixer_request_future(id, TakeSnapshotMessage)
+                .into_actor(self)
+                .map(|res, _slf, _ctx| match res {
+                    Ok(snapshot) => CommandResult::Snapshot(snapshot),
+                    Err(err) => CommandResult::Error(format!("{}", err)),
+                }),
+        )
+    }
+
+    /// Send a mixer-specific message to a [`Mixer`], the value it
+    /// replies with on success is discarded
+    #[instrument(level = "trace", name = "mixer-command", skip(self, msg))]
+    fn send_mixer_command_future<M, T>(
+        &mut self,
+        id: &str,
+        msg: M,
+    ) -> ResponseActFuture<Self, CommandResult>
+    where
+        M: Message<Result = Result<T, Error>> + Send + 'static,
+        T: Send + 'static,
+        Mixer: Handler<M>,
+    {
+        match self.nodes.get(id) {
+            Some(Node::Mixer(addr)) => {
+                let addr = addr.clone();
+                Box::pin(
+                    {
+                        async move { addr.send(msg).await }.into_actor(self).then(
+                            move |res, _slf, _ctx| {
+                                actix::fut::ready(match res {
+                                    Ok(Ok(_)) => CommandResult::Success,
+                                    Ok(Err(err)) => CommandResult::Error(format!("{}", err)),
+                                    Err(err) => CommandResult::Error(format!(
+                                        "Internal server error {}",
+                                        err
+                                    )),
+                                })
+                            },
+                        )
+                    }
+                    .in_current_actor_span(),
+                )
+            }
+            Some(_) => Box::pin(actix::fut::ready(CommandResult::Error(format!(
+                "Node with id {} is not a mixer",
+                id
+            )))),
+            None => Box::pin(actix::fut::ready(CommandResult::Error(format!(
+                "No node with id {}",
+                id
+            )))),
+        }
+    }
+
     /// Connect a producer and a consumer
+    ///
+    /// When a link with the same id exists, the connection is refused,
+    /// or the consumer replaces it according to `on_duplicate`. A failed
+    /// replacement leaves no link behind: the consumer may already have
+    /// disconnected the old one, so it is disconnected and forgotten.
     #[instrument(level = "trace", name = "connect-command", skip(self))]
     fn connect_future(
         &mut self,
@@ -570,7 +1611,39 @@ impl NodeManager {
         src: &str,
         sink: &str,
         config: Option<HashMap<String, serde_json::Value>>,
+        media: ConnectMedia,
+        on_duplicate: DuplicateLinkPolicy,
+        output_id: Option<String>,
     ) -> ResponseActFuture<Self, CommandResult> {
+        let replace = match self.link_nodes.get(link_id) {
+            Some((_, existing_sink)) => match on_duplicate {
+                DuplicateLinkPolicy::Replace if existing_sink == sink => true,
+                DuplicateLinkPolicy::Replace => {
+                    return Box::pin(actix::fut::ready(CommandResult::Error(format!(
+                        "Link {} connects to {}, it can't be replaced with a link to {}",
+                        link_id, existing_sink, sink
+                    ))));
+                }
+                DuplicateLinkPolicy::Reject => {
+                    return Box::pin(actix::fut::ready(CommandResult::Error(format!(
+                        "A link already exists with id {}",
+                        link_id
+                    ))));
+                }
+                DuplicateLinkPolicy::Warn => {
+                    let message = format!("A link already exists with id {}", link_id);
+
+                    self.notify_listeners(NodeStatusMessage::Warning {
+                        id: sink.to_string(),
+                        message: message.clone(),
+                    });
+
+                    return Box::pin(actix::fut::ready(CommandResult::Error(message)));
+                }
+            },
+            None => false,
+        };
+
         let producer = match self.producers.get(src) {
             Some(producer) => producer.clone(),
             None => {
@@ -580,6 +1653,46 @@ impl NodeManager {
             }
         };
 
+        // Screen captures never output audio, links from them only
+        // connect their video
+        let media = match (media, self.nodes.get(src)) {
+            (ConnectMedia::Both, Some(Node::ScreenCapture(_))) => ConnectMedia::Video,
+            (ConnectMedia::Audio, Some(Node::ScreenCapture(_))) => {
+                return Box::pin(actix::fut::ready(CommandResult::Error(format!(
+                    "Screen capture with id {} has no audio",
+                    src
+                ))));
+            }
+            (media, _) => media,
+        };
+
+        let monitored_mixer = match (media, self.nodes.get(src)) {
+            (ConnectMedia::AudioMonitor, Some(Node::Mixer(addr))) => Some(addr.clone()),
+            (ConnectMedia::AudioMonitor, _) => {
+                return Box::pin(actix::fut::ready(CommandResult::Error(format!(
+                    "Node with id {} is not a mixer, it has no audio monitor",
+                    src
+                ))));
+            }
+            _ => None,
+        };
+
+        let scaled_output = match (output_id, self.nodes.get(src)) {
+            (None, _) => None,
+            (Some(_), _) if media == ConnectMedia::Audio || media == ConnectMedia::AudioMonitor => {
+                return Box::pin(actix::fut::ready(CommandResult::Error(
+                    "Scaled outputs only have video".to_string(),
+                )));
+            }
+            (Some(output_id), Some(Node::Mixer(addr))) => Some((addr.clone(), output_id)),
+            (Some(_), _) => {
+                return Box::pin(actix::fut::ready(CommandResult::Error(format!(
+                    "Node with id {} is not a mixer, it has no scaled outputs",
+                    src
+                ))));
+            }
+        };
+
         let consumer = match self.consumers.get(sink) {
             Some(consumer) => consumer.clone(),
             None => {
@@ -596,39 +1709,104 @@ impl NodeManager {
 
         let consumer_clone = consumer.clone();
         let link_id_clone = link_id.clone();
+        let link_nodes = (src.to_string(), sink.to_string());
 
         Box::pin(
             {
-                async move { producer.send(GetProducerMessage {}).in_current_span().await }
-                    .then(move |res| async move {
-                        let res = res.unwrap();
-
-                        let (video_producer, audio_producer) = match res {
-                            Ok(res) => res,
-                            Err(err) => {
-                                return Ok(Err(anyhow!("Failed to get producer: {:?}", err)));
-                            }
-                        };
-
-                        consumer
-                            .send(ConsumerMessage::Connect {
-                                link_id,
-                                video_producer,
-                                audio_producer,
-                                config,
-                            })
+                async move {
+                    let producers = match (monitored_mixer, scaled_output) {
+                        (Some(mixer), _) => mixer
+                            .send(GetAudioMonitorProducerMessage)
+                            .in_current_span()
+                            .await
+                            .unwrap()
+                            .map(|audio_producer| (None, Some(audio_producer))),
+                        (None, Some((mixer, output_id))) => mixer
+                            .send(GetOutputProducerMessage { output_id })
+                            .in_current_span()
+                            .await
+                            .unwrap()
+                            .map(|video_producer| (Some(video_producer), None)),
+                        (None, None) => producer
+                            .send(GetProducerMessage {})
                             .in_current_span()
                             .await
+                            .unwrap()
+                            .map(|(video_producer, audio_producer)| {
+                                (
+                                    Some(video_producer).filter(|_| media != ConnectMedia::Audio),
+                                    Some(audio_producer).filter(|_| media != ConnectMedia::Video),
+                                )
+                            }),
+                    };
+
+                    let (video_producer, audio_producer) = match producers {
+                        Ok(res) => res,
+                        Err(err) => {
+                            return Ok(Err(anyhow!("Failed to get producer: {:?}", err)));
+                        }
+                    };
+
+                    consumer
+                        .send(ConsumerMessage::Connect {
+                            link_id,
+                            video_producer,
+                            audio_producer,
+                            config,
+                            replace,
+                        })
+                        .in_current_span()
+                        .await
+                }
+                .into_actor(self)
+                .then(move |res, slf, _ctx| {
+                    actix::fut::ready(match res {
+                        Ok(res) => match res {
+                            Ok(_) => {
+                                debug!("Link established");
+                                slf.links.insert(link_id_clone.clone(), consumer_clone);
+                                slf.link_nodes.insert(link_id_clone, link_nodes);
+                                CommandResult::Success
+                            }
+                            Err(err) => {
+                                if replace {
+                                    slf.forget_replaced_link(&link_id_clone, consumer_clone);
+                                }
+                                CommandResult::Error(format!("{}", err))
+                            }
+                        },
+                        Err(err) => CommandResult::Error(format!("Internal server error {}", err)),
                     })
+                })
+            }
+            .in_current_actor_span(),
+        )
+    }
+
+    /// Drop a link whose replacement failed, whether or not its consumer
+    /// had already disconnected it
+    fn forget_replaced_link(&mut self, link_id: &str, mut consumer: Recipient<ConsumerMessage>) {
+        debug!(link_id = %link_id, "forgetting link after failed replacement");
+
+        self.links.remove(link_id);
+        self.link_nodes.remove(link_id);
+        self.disconnect_consumer(&mut consumer, link_id.to_string());
+    }
+
+    /// Send a message to a consumer, and report its result
+    fn send_consumer_message_future(
+        &mut self,
+        consumer: Recipient<ConsumerMessage>,
+        msg: ConsumerMessage,
+    ) -> ResponseActFuture<Self, CommandResult> {
+        Box::pin(
+            {
+                async move { consumer.send(msg).in_current_span().await }
                     .into_actor(self)
-                    .then(move |res, slf, _ctx| {
+                    .then(move |res, _slf, _ctx| {
                         actix::fut::ready(match res {
                             Ok(res) => match res {
-                                Ok(_) => {
-                                    debug!("Link established");
-                                    slf.links.insert(link_id_clone, consumer_clone);
-                                    CommandResult::Success
-                                }
+                                Ok(_) => CommandResult::Success,
                                 Err(err) => CommandResult::Error(format!("{}", err)),
                             },
                             Err(err) => {
@@ -641,61 +1819,368 @@ impl NodeManager {
         )
     }
 
-    /// Disconnect a consumer by id
-    #[instrument(level = "trace", name = "disconnect-command", skip(self))]
-    fn disconnect(&mut self, link_id: &str) -> CommandResult {
-        if let Some(mut consumer) = self.links.remove(link_id) {
-            self.disconnect_consumer(&mut consumer, link_id.to_string());
-            CommandResult::Success
-        } else {
-            CommandResult::Error(format!("no link with id {}", link_id))
-        }
-    }
+    /// Send a message about one of its slots to the consumer of a link
+    #[instrument(level = "trace", name = "slot-command", skip(self))]
+    fn slot_message_future(
+        &mut self,
+        link_id: String,
+        msg: ConsumerMessage,
+    ) -> ResponseActFuture<Self, CommandResult> {
+        if let Some(consumer) = self.links.get(&link_id) {
+            let consumer = consumer.clone();
+            self.send_consumer_message_future(consumer, msg)
+        } else {
+            Box::pin(actix::fut::ready(CommandResult::Error(format!(
+                "No link with id {}",
+                link_id
+            ))))
+        }
+    }
+
+    /// Send a message about its slots to a consumer node
+    #[instrument(level = "trace", name = "consumer-command", skip(self, msg))]
+    fn consumer_message_future(
+        &mut self,
+        id: String,
+        msg: ConsumerMessage,
+    ) -> ResponseActFuture<Self, CommandResult> {
+        if let Some(consumer) = self.consumers.get(&id) {
+            let consumer = consumer.clone();
+            self.send_consumer_message_future(consumer, msg)
+        } else {
+            Box::pin(actix::fut::ready(CommandResult::Error(format!(
+                "No consumer with id {}",
+                id
+            ))))
+        }
+    }
+
+    /// Disconnect a consumer by id
+    #[instrument(level = "trace", name = "disconnect-command", skip(self))]
+    fn disconnect(&mut self, link_id: &str) -> CommandResult {
+        if let Some(mut consumer) = self.links.remove(link_id) {
+            self.link_nodes.remove(link_id);
+            self.disconnect_consumer(&mut consumer, link_id.to_string());
+            CommandResult::Success
+        } else {
+            CommandResult::Error(format!("no link with id {}", link_id))
+        }
+    }
+
+    /// Get the info either of a specific [`Node`], or of all nodes
+    ///
+    /// The info of all nodes is gathered with [`GetAllNodesInfoMessage`],
+    /// the nodes that failed to report it are listed as errors. When
+    /// `node_type` is set, the nodes are first selected with
+    /// [`GetNodesByTypeMessage`].
+    #[instrument(level = "trace", name = "get-info-command", skip(self, ctx))]
+    fn get_info_future(
+        &mut self,
+        ctx: &mut Context<Self>,
+        id: Option<&String>,
+        node_type: Option<NodeType>,
+    ) -> ResponseActFuture<Self, CommandResult> {
+        let id = match id {
+            Some(id) => id,
+            None => {
+                let addr = ctx.address();
+
+                return Box::pin(
+                    async move {
+                        let ids = match node_type {
+                            Some(node_type) => {
+                                match addr.send(GetNodesByTypeMessage { node_type }).await {
+                                    Ok(ids) => Some(ids),
+                                    Err(err) => {
+                                        return CommandResult::Error(format!(
+                                            "Internal server error {}",
+                                            err
+                                        ))
+                                    }
+                                }
+                            }
+                            None => None,
+                        };
+
+                        match addr.send(GetAllNodesInfoMessage { ids }).await {
+                            Ok(all_nodes_info) => {
+                                CommandResult::Info(NodeManager::collect_info(all_nodes_info))
+                            }
+                            Err(err) => {
+                                CommandResult::Error(format!("Internal server error {}", err))
+                            }
+                        }
+                    }
+                    .into_actor(self)
+                    .in_current_actor_span(),
+                );
+            }
+        };
+
+        let mut node = match self.nodes.get(id) {
+            Some(node) => node.clone(),
+            None => {
+                return Box::pin(actix::fut::ready(CommandResult::Error(format!(
+                    "No node with id {}",
+                    id
+                ))));
+            }
+        };
+
+        let id = id.clone();
+
+        Box::pin(
+            async move {
+                match node.get_info().await {
+                    Ok(info) => CommandResult::Info(Info {
+                        nodes: std::iter::once((id, info)).collect(),
+                        errors: HashMap::new(),
+                    }),
+                    Err(err) => CommandResult::Error(format!("Internal server error {}", err)),
+                }
+            }
+            .into_actor(self)
+            .in_current_actor_span(),
+        )
+    }
+
+    /// Sort the outcomes of [`GetAllNodesInfoMessage`] into an [`Info`]
+    fn collect_info(all_nodes_info: AllNodesInfo) -> Info {
+        let mut info = Info {
+            nodes: HashMap::new(),
+            errors: HashMap::new(),
+        };
 
-    /// Get the info either of a specific [`Node`], or of all nodes
-    #[instrument(level = "trace", name = "get-info-command", skip(self))]
-    fn get_info_future(&mut self, id: Option<&String>) -> ResponseActFuture<Self, CommandResult> {
-        let mut nodes: Vec<(String, Node)> = match id {
-            Some(id) => {
-                if let Some(node) = self.nodes.get(id) {
-                    vec![(id.clone(), node.clone())]
-                } else {
-                    return Box::pin(actix::fut::ready(CommandResult::Error(format!(
-                        "No node with id {}",
-                        id
-                    ))));
+        for result in all_nodes_info.nodes {
+            match result {
+                NodeInfoResult::Info {
+                    id,
+                    info: node_info,
+                } => {
+                    info.nodes.insert(id, node_info);
+                }
+                NodeInfoResult::Timeout { id } => {
+                    warn!(id = %id, "node did not report its info in time");
+                    info.errors
+                        .insert(id, "Timed out getting the node info".to_string());
+                }
+                NodeInfoResult::Error { id, error } => {
+                    info.errors.insert(id, error);
                 }
             }
-            None => self
-                .nodes
-                .iter()
-                .map(|(id, node)| (id.clone(), node.clone()))
-                .collect(),
-        };
+        }
+
+        info
+    }
+
+    /// Gather the state of all nodes and the statistics of their producers
+    #[instrument(level = "trace", name = "get-metrics-command", skip(self))]
+    fn get_metrics_future(&mut self) -> ResponseActFuture<Self, String> {
+        let mut nodes: Vec<(String, Node, Option<Recipient<GetProducerMessage>>)> = self
+            .nodes
+            .iter()
+            .map(|(id, node)| (id.clone(), node.clone(), self.producers.get(id).cloned()))
+            .collect();
 
         Box::pin(
             {
                 async move {
-                    let all_futures = nodes.drain(..).map(|(node_id, mut node)| async move {
-                        node.get_info().await.map(|res| (node_id, res))
-                    });
-                    futures::future::join_all(all_futures).await
+                    let all_futures =
+                        nodes
+                            .drain(..)
+                            .map(|(node_id, mut node, producer)| async move {
+                                let info = node.get_info().await.ok();
+                                let latency = node.get_latency().await.ok();
+                                let producers = match producer {
+                                    Some(producer) => producer
+                                        .send(GetProducerMessage)
+                                        .await
+                                        .ok()
+                                        .and_then(|res| res.ok()),
+                                    None => None,
+                                };
+
+                                (node_id, info, producers, latency)
+                            });
+
+                    NodeManager::encode_metrics(&futures::future::join_all(all_futures).await)
                 }
                 .into_actor(self)
-                .then(move |mut res, _slf, _ctx| {
-                    actix::fut::ready(CommandResult::Info(Info {
-                        nodes: res
-                            .drain(..)
-                            .filter(|res| res.is_ok())
-                            .map(|res| res.unwrap())
-                            .collect(),
-                    }))
-                })
             }
             .in_current_actor_span(),
         )
     }
 
+    /// Encode the metrics of all nodes in the Prometheus text format
+    fn encode_metrics(
+        nodes: &[(
+            String,
+            Option<NodeInfo>,
+            Option<(StreamProducer, StreamProducer)>,
+            Option<PipelineLatency>,
+        )],
+    ) -> String {
+        let mut encoder = MetricsEncoder::new();
+
+        encoder.family(
+            "auteur_node_state",
+            MetricType::Gauge,
+            "The state of a node, 1 for the current state and 0 otherwise",
+        );
+
+        for (id, info, _, _) in nodes {
+            let (node_type, state) = match info {
+                Some(NodeInfo::Source(info)) => ("source", info.state),
+                Some(NodeInfo::Destination(info)) => ("destination", info.state),
+                Some(NodeInfo::Mixer(info)) => ("mixer", info.state),
+                None => continue,
+            };
+
+            for candidate in &[
+                State::Initial,
+                State::Starting,
+                State::Started,
+                State::Stopping,
+                State::Stopped,
+            ] {
+                encoder.sample(
+                    "auteur_node_state",
+                    &[
+                        ("node", id),
+                        ("type", node_type),
+                        ("state", &format!("{:?}", candidate).to_lowercase()),
+                    ],
+                    if *candidate == state { 1.0 } else { 0.0 },
+                );
+            }
+        }
+
+        encoder.family(
+            "auteur_mixer_base_plate_active",
+            MetricType::Gauge,
+            "Whether a mixer is currently displaying its base plate",
+        );
+
+        for (id, info, _, _) in nodes {
+            if let Some(NodeInfo::Mixer(info)) = info {
+                encoder.sample(
+                    "auteur_mixer_base_plate_active",
+                    &[("node", id)],
+                    if info.base_plate_active { 1.0 } else { 0.0 },
+                );
+            }
+        }
+
+        encoder.family(
+            "auteur_pipeline_latency_seconds",
+            MetricType::Gauge,
+            "The minimum latency of the pipeline of a node",
+        );
+
+        for (id, _, _, latency) in nodes {
+            if let Some(min_latency) = latency.and_then(|latency| latency.min_latency.nseconds()) {
+                encoder.sample(
+                    "auteur_pipeline_latency_seconds",
+                    &[("node", id)],
+                    min_latency as f64 / 1_000_000_000.0,
+                );
+            }
+        }
+
+        encoder.family(
+            "auteur_pipeline_live",
+            MetricType::Gauge,
+            "Whether the pipeline of a node is live",
+        );
+
+        for (id, _, _, latency) in nodes {
+            if let Some(latency) = latency {
+                encoder.sample(
+                    "auteur_pipeline_live",
+                    &[("node", id)],
+                    if latency.live { 1.0 } else { 0.0 },
+                );
+            }
+        }
+
+        let stats: Vec<(&str, &str, StreamProducerStats)> = nodes
+            .iter()
+            .filter_map(|(id, _, producers, _)| {
+                producers.as_ref().map(|(video_producer, audio_producer)| {
+                    vec![
+                        (id.as_str(), "video", video_producer.stats()),
+                        (id.as_str(), "audio", audio_producer.stats()),
+                    ]
+                })
+            })
+            .flatten()
+            .collect();
+
+        encoder.family(
+            "auteur_producer_forwarded_samples_total",
+            MetricType::Counter,
+            "The number of samples a producer forwarded to its consumers",
+        );
+
+        for (id, stream, stats) in &stats {
+            encoder.sample(
+                "auteur_producer_forwarded_samples_total",
+                &[("node", id), ("stream", stream)],
+                stats.n_forwarded_samples as f64,
+            );
+        }
+
+        encoder.family(
+            "auteur_producer_latency_seconds",
+            MetricType::Gauge,
+            "The latency currently observed by a producer",
+        );
+
+        for (id, stream, stats) in &stats {
+            if let Some(latency) = stats.current_latency.and_then(|latency| latency.nseconds()) {
+                encoder.sample(
+                    "auteur_producer_latency_seconds",
+                    &[("node", id), ("stream", stream)],
+                    latency as f64 / 1_000_000_000.0,
+                );
+            }
+        }
+
+        encoder.family(
+            "auteur_consumer_dropped_samples_total",
+            MetricType::Counter,
+            "The number of samples dropped for a consumer of a producer",
+        );
+
+        for (id, stream, stats) in &stats {
+            for (consumer_id, n_dropped_samples) in &stats.n_dropped_samples {
+                encoder.sample(
+                    "auteur_consumer_dropped_samples_total",
+                    &[("node", id), ("stream", stream), ("consumer", consumer_id)],
+                    *n_dropped_samples as f64,
+                );
+            }
+        }
+
+        encoder.family(
+            "auteur_consumer_enough_data_total",
+            MetricType::Counter,
+            "The number of times the queue of a consumer of a producer filled up",
+        );
+
+        for (id, stream, stats) in &stats {
+            for (consumer_id, n_enough_data) in &stats.n_enough_data {
+                encoder.sample(
+                    "auteur_consumer_enough_data_total",
+                    &[("node", id), ("stream", stream), ("consumer", consumer_id)],
+                    *n_enough_data as f64,
+                );
+            }
+        }
+
+        encoder.finish()
+    }
+
     /// Add a control point for a property and keep track of the controllee
     #[instrument(level = "trace", name = "control-property-command", skip(self))]
     fn control_property_future(
@@ -821,24 +2306,49 @@ impl NodeManager {
 impl Handler<CommandMessage> for NodeManager {
     type Result = ResponseActFuture<Self, CommandResult>;
 
-    #[instrument(level = "trace", name = "command", skip(self, _ctx))]
-    fn handle(&mut self, msg: CommandMessage, _ctx: &mut Context<Self>) -> Self::Result {
+    #[instrument(level = "trace", name = "command", skip(self, ctx))]
+    fn handle(&mut self, msg: CommandMessage, ctx: &mut Context<Self>) -> Self::Result {
         match msg.command {
             Command::Connect {
                 link_id,
                 src_id,
                 sink_id,
                 config,
-            } => self.connect_future(&link_id, &src_id, &sink_id, config),
+                media,
+                on_duplicate,
+                output_id,
+            } => self.connect_future(
+                &link_id,
+                &src_id,
+                &sink_id,
+                config,
+                media,
+                on_duplicate,
+                output_id,
+            ),
             Command::Disconnect { link_id } => {
                 Box::pin(actix::fut::ready(self.disconnect(&link_id)))
             }
+            Command::FlushSlot { link_id } => self.slot_message_future(
+                link_id.clone(),
+                ConsumerMessage::FlushSlot { slot_id: link_id },
+            ),
+            Command::FreezeSlot { link_id, frozen } => self.slot_message_future(
+                link_id.clone(),
+                ConsumerMessage::FreezeSlot {
+                    slot_id: link_id,
+                    frozen,
+                },
+            ),
             Command::CreateSource { id, uri } => {
                 Box::pin(actix::fut::ready(self.create_source(&id, &uri)))
             }
-            Command::CreateDestination { id, family } => {
-                Box::pin(actix::fut::ready(self.create_destination(&id, &family)))
+            Command::CreateScreenCapture { id, config } => {
+                Box::pin(actix::fut::ready(self.create_screen_capture(&id, config)))
             }
+            Command::CreateDestination { id, family, config } => Box::pin(actix::fut::ready(
+                self.create_destination(&id, &family, config),
+            )),
             Command::CreateMixer { id, config } => {
                 Box::pin(actix::fut::ready(self.create_mixer(&id, config)))
             }
@@ -852,8 +2362,34 @@ impl Handler<CommandMessage> for NodeManager {
                 cue_time,
                 end_time,
             } => self.send_schedule_command_future(&id, cue_time, end_time),
-            Command::Remove { id } => Box::pin(actix::fut::ready(self.stop_node(&id))),
-            Command::GetInfo { id } => self.get_info_future(id.as_ref()),
+            Command::CreateGroup { id, node_ids } => {
+                Box::pin(actix::fut::ready(self.create_group(&id, node_ids)))
+            }
+            Command::StartGroup {
+                id,
+                cue_time,
+                end_time,
+            } => self.start_group_future(&id, cue_time, end_time),
+            Command::StopGroup { id } => Box::pin(actix::fut::ready(self.stop_group(&id))),
+            Command::SetNodeLogLevel { id, level } => {
+                Box::pin(actix::fut::ready(self.set_node_log_level(&id, level)))
+            }
+            Command::SetForwardMode { id, mode } => self.set_forward_mode_future(&id, mode),
+            Command::Remove {
+                id,
+                kill: true,
+                cascade: true,
+            } => Box::pin(actix::fut::ready(CommandResult::Error(format!(
+                "Node {} can't be both killed and stopped in cascade",
+                id
+            )))),
+            Command::Remove {
+                id,
+                kill,
+                cascade: false,
+            } => Box::pin(actix::fut::ready(self.stop_node(&id, kill))),
+            Command::Remove { id, .. } => Box::pin(actix::fut::ready(self.cascade_stop(ctx, &id))),
+            Command::GetInfo { id, node_type } => self.get_info_future(ctx, id.as_ref(), node_type),
             Command::AddControlPoint {
                 controllee_id,
                 property,
@@ -868,6 +2404,110 @@ impl Handler<CommandMessage> for NodeManager {
                 &controllee_id,
                 &property,
             ))),
+            Command::ApplyPreset {
+                id,
+                preset,
+                cue_time,
+            } => self.send_mixer_command_future(&id, ApplyPresetMessage { preset, cue_time }),
+            Command::ApplyLayout {
+                id,
+                layout,
+                cue_time,
+                transition_ms,
+            } => self.send_mixer_command_future(
+                &id,
+                ApplyLayoutMessage {
+                    layout,
+                    cue_time,
+                    transition_ms,
+                },
+            ),
+            Command::CaptureFrame { id, path, format } => {
+                self.send_mixer_command_future(&id, CaptureFrameToFileMessage { path, format })
+            }
+            Command::SetCompositorBackground { id, background } => {
+                self.send_mixer_command_future(&id, SetCompositorBackgroundMessage { background })
+            }
+            Command::AddOutputScale {
+                id,
+                output_id,
+                width,
+                height,
+                framerate,
+            } => match parse_fraction(&framerate) {
+                Some(framerate) => self.send_mixer_command_future(
+                    &id,
+                    AddOutputScaleMessage {
+                        output_id,
+                        width,
+                        height,
+                        framerate,
+                    },
+                ),
+                None => Box::pin(actix::fut::ready(CommandResult::Error(format!(
+                    "Invalid framerate {}, expected a strictly positive fraction",
+                    framerate
+                )))),
+            },
+            Command::RemoveOutput { id, output_id } => self.remove_output_future(&id, output_id),
+            Command::UpdateMixerSetting { id, name, value } => {
+                self.send_mixer_command_future(&id, UpdateSettingMessage { name, value })
+            }
+            Command::GetSlotControlPoints { id, slot_id } => {
+                self.get_slot_control_points_future(&id, slot_id)
+            }
+            Command::ClearSlotControlPoints {
+                id,
+                slot_id,
+                property,
+            } => self.send_mixer_command_future(
+                &id,
+                ClearSlotControlPointsMessage { slot_id, property },
+            ),
+            Command::SetCompositorLatency { id, latency_ns } => {
+                self.send_mixer_command_future(&id, SetCompositorLatencyMessage { latency_ns })
+            }
+            Command::SetAudiomixerLatency { id, latency_ns } => {
+                self.send_mixer_command_future(&id, SetAudiomixerLatencyMessage { latency_ns })
+            }
+            Command::GetAudioPipelineDescription { id } => {
+                self.pipeline_description_future(&id, GetAudioPipelineDescriptionMessage)
+            }
+            Command::GetVideoPipelineDescription { id } => {
+                self.pipeline_description_future(&id, GetVideoPipelineDescriptionMessage)
+            }
+            Command::ResizeMixerOutput { id, width, height } => self.send_mixer_command_future(
+                &id,
+                ResizeOutputPreservingSlotLayoutMessage {
+                    new_width: width,
+                    new_height: height,
+                },
+            ),
+            Command::TakeMixerSnapshot { id } => self.take_snapshot_future(&id),
+            Command::SetMixerIgnoreInactivePads { id, video, audio } => {
+                self.send_mixer_command_future(&id, SetIgnoreInactivePadsMessage { video, audio })
+            }
+            Command::SetSlotLabel { id, slot_id, text } => {
+                self.send_mixer_command_future(&id, SetSlotLabelMessage { slot_id, text })
+            }
+            Command::SetSlotOrder { id, order } => {
+                self.consumer_message_future(id, ConsumerMessage::SetSlotOrder { order })
+            }
+            Command::ValidateMixerConfig {
+                id,
+                config,
+                slot_config,
+            } => self.validate_mixer_config_future(
+                &id,
+                config.unwrap_or_default(),
+                slot_config.unwrap_or_default(),
+            ),
+            Command::SetStreamMetadata { id, key, value } => {
+                self.send_mixer_command_future(&id, SetStreamMetadataMessage { key, value })
+            }
+            Command::DumpPipeline { id } => {
+                self.send_mixer_command_future(&id, DumpPipelineMessage)
+            }
         }
     }
 }
@@ -880,15 +2520,43 @@ impl Handler<StoppedMessage> for NodeManager {
         self.remove_node(&msg.id);
         let _ = self.consumers.remove(&msg.id);
         let _ = self.producers.remove(&msg.id);
+        self.cascade_stop_opt_outs.remove(&msg.id);
+
+        for stopped_sender in self.stopped_waiters.remove(&msg.id).unwrap_or_default() {
+            let _ = stopped_sender.send(());
+        }
 
         self.disconnect_consumers(msg.video_producer, msg.audio_producer);
 
+        // Links to other producers of the node, eg the audio monitor of a mixer
+        let link_ids: Vec<String> = self
+            .link_nodes
+            .iter()
+            .filter(|(_, (src_id, _))| src_id == &msg.id)
+            .map(|(link_id, _)| link_id.clone())
+            .collect();
+
+        for link_id in link_ids {
+            if let Some(mut consumer) = self.links.remove(&link_id) {
+                self.link_nodes.remove(&link_id);
+                self.disconnect_consumer(&mut consumer, link_id);
+            }
+        }
+
         debug!("mixer {} removed from NodeManager", msg.id);
 
         MessageResult(())
     }
 }
 
+impl Handler<CascadeStopMessage> for NodeManager {
+    type Result = ResponseActFuture<Self, Result<(), Error>>;
+
+    fn handle(&mut self, msg: CascadeStopMessage, _ctx: &mut Context<Self>) -> Self::Result {
+        self.cascade_stop_future(&msg.root_node_id, msg.level_timeout)
+    }
+}
+
 impl Handler<StopMessage> for NodeManager {
     type Result = ResponseFuture<Result<(), Error>>;
 
@@ -933,6 +2601,151 @@ impl Handler<RegisterListenerMessage> for NodeManager {
     }
 }
 
+impl Handler<GetAllNodesInfoMessage> for NodeManager {
+    type Result = ResponseFuture<AllNodesInfo>;
+
+    #[instrument(
+        level = "trace",
+        name = "getting all nodes info",
+        skip(self, _ctx, msg)
+    )]
+    fn handle(&mut self, msg: GetAllNodesInfoMessage, _ctx: &mut Context<Self>) -> Self::Result {
+        let all_futures = self
+            .nodes
+            .iter()
+            .filter(|(id, _)| msg.ids.as_ref().map_or(true, |ids| ids.contains(id)))
+            .map(|(id, node)| {
+                node.clone()
+                    .get_info_with_timeout(id.clone(), NODE_INFO_TIMEOUT)
+            })
+            .collect::<Vec<_>>();
+
+        Box::pin(async move {
+            AllNodesInfo {
+                nodes: futures::future::join_all(all_futures).await,
+            }
+        })
+    }
+}
+
+impl Handler<PingNodeMessage> for NodeManager {
+    type Result = ResponseFuture<Result<Duration, NodeError>>;
+
+    #[instrument(level = "trace", name = "pinging node", skip(self, _ctx))]
+    fn handle(&mut self, msg: PingNodeMessage, _ctx: &mut Context<Self>) -> Self::Result {
+        match self.nodes.get_mut(&msg.node_id) {
+            Some(node) => node.ping(PING_TIMEOUT),
+            None => Box::pin(future::err(NodeError::NotFound)),
+        }
+    }
+}
+
+impl Handler<PingAllNodesMessage> for NodeManager {
+    type Result = ResponseFuture<HashMap<String, Result<Duration, NodeError>>>;
+
+    #[instrument(level = "trace", name = "pinging all nodes", skip(self, _ctx))]
+    fn handle(&mut self, msg: PingAllNodesMessage, _ctx: &mut Context<Self>) -> Self::Result {
+        let all_futures = self
+            .nodes
+            .iter()
+            .map(|(id, node)| {
+                let id = id.clone();
+                node.clone().ping(msg.timeout).map(move |res| (id, res))
+            })
+            .collect::<Vec<_>>();
+
+        Box::pin(async move {
+            futures::future::join_all(all_futures)
+                .await
+                .into_iter()
+                .collect()
+        })
+    }
+}
+
+impl Handler<GetNodesByTypeMessage> for NodeManager {
+    type Result = MessageResult<GetNodesByTypeMessage>;
+
+    #[instrument(level = "trace", name = "getting nodes by type", skip(self, _ctx))]
+    fn handle(&mut self, msg: GetNodesByTypeMessage, _ctx: &mut Context<Self>) -> Self::Result {
+        MessageResult(
+            self.nodes_by_type
+                .get(&msg.node_type)
+                .cloned()
+                .unwrap_or_default(),
+        )
+    }
+}
+
+impl Handler<SetLogFilterMessage> for NodeManager {
+    type Result = ();
+
+    fn handle(&mut self, msg: SetLogFilterMessage, _ctx: &mut Context<Self>) -> Self::Result {
+        self.log_filter = Some(msg.0);
+    }
+}
+
+impl Handler<SetHeartbeatIntervalMessage> for NodeManager {
+    type Result = ();
+
+    fn handle(
+        &mut self,
+        msg: SetHeartbeatIntervalMessage,
+        _ctx: &mut Context<Self>,
+    ) -> Self::Result {
+        self.heartbeat_interval = msg.0;
+    }
+}
+
+impl Handler<GetMetricsMessage> for NodeManager {
+    type Result = ResponseActFuture<Self, String>;
+
+    #[instrument(level = "trace", name = "getting metrics", skip(self, _ctx, _msg))]
+    fn handle(&mut self, _msg: GetMetricsMessage, _ctx: &mut Context<Self>) -> Self::Result {
+        self.get_metrics_future()
+    }
+}
+
+impl Handler<SubscribeMixerAudioLevelMessage> for NodeManager {
+    type Result = ResponseFuture<Result<watch::Receiver<Option<AudioLevelInfo>>, Error>>;
+
+    fn handle(
+        &mut self,
+        msg: SubscribeMixerAudioLevelMessage,
+        _ctx: &mut Context<Self>,
+    ) -> Self::Result {
+        match self.nodes.get(&msg.id) {
+            Some(Node::Mixer(addr)) => {
+                let addr = addr.clone();
+                Box::pin(async move {
+                    addr.send(SubscribeAudioLevelMessage)
+                        .await
+                        .map_err(|err| anyhow!("Internal server error {}", err))
+                })
+            }
+            Some(_) => Box::pin(future::err(anyhow!(
+                "Node with id {} is not a mixer",
+                msg.id
+            ))),
+            None => Box::pin(future::err(anyhow!("No node with id {}", msg.id))),
+        }
+    }
+}
+
+impl Handler<PreviewMixerOutputMessage> for NodeManager {
+    type Result = ResponseFuture<Result<Vec<u8>, Error>>;
+
+    fn handle(&mut self, msg: PreviewMixerOutputMessage, _ctx: &mut Context<Self>) -> Self::Result {
+        self.mixer_request_future(
+            &msg.id,
+            PreviewOutputMessage {
+                width: msg.width,
+                height: msg.height,
+            },
+        )
+    }
+}
+
 impl Handler<NodeStatusMessage> for NodeManager {
     type Result = ();
 
@@ -941,3 +2754,396 @@ impl Handler<NodeStatusMessage> for NodeManager {
         self.notify_listeners(msg)
     }
 }
+
+impl Handler<ConsumerRemovedMessage> for NodeManager {
+    type Result = ();
+
+    #[instrument(level = "debug", name = "consumer-removed", skip(self, _ctx))]
+    fn handle(&mut self, msg: ConsumerRemovedMessage, _ctx: &mut Context<Self>) -> Self::Result {
+        // The link may have been disconnected in the meantime
+        if let Some(mut consumer) = self.links.remove(&msg.link_id) {
+            self.link_nodes.remove(&msg.link_id);
+            self.disconnect_consumer(&mut consumer, msg.link_id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::tests::*;
+    use test_env_log::test;
+
+    #[actix_rt::test]
+    #[test]
+    async fn test_get_all_nodes_info() {
+        gst::init().unwrap();
+
+        create_source("test-source", &asset_uri("ball.mp4"))
+            .await
+            .unwrap();
+        create_mixer("test-mixer", None).await.unwrap();
+
+        let manager = NodeManager::from_registry();
+
+        match manager
+            .send(CommandMessage {
+                command: Command::GetInfo {
+                    id: None,
+                    node_type: None,
+                },
+            })
+            .await
+            .unwrap()
+        {
+            CommandResult::Info(info) => {
+                assert!(info.nodes.contains_key("test-source"));
+                assert!(info.nodes.contains_key("test-mixer"));
+                assert!(info.errors.is_empty());
+            }
+            res => panic!("Unexpected result {:?}", res),
+        }
+    }
+
+    #[actix_rt::test]
+    #[test]
+    async fn test_nodes_by_type() {
+        gst::init().unwrap();
+
+        create_source("test-source", &asset_uri("ball.mp4"))
+            .await
+            .unwrap();
+        create_mixer("test-mixer", None).await.unwrap();
+
+        let manager = NodeManager::from_registry();
+
+        let sources = manager
+            .send(GetNodesByTypeMessage {
+                node_type: NodeType::Source,
+            })
+            .await
+            .unwrap();
+        assert_eq!(sources, vec!["test-source".to_string()]);
+
+        let destinations = manager
+            .send(GetNodesByTypeMessage {
+                node_type: NodeType::Destination,
+            })
+            .await
+            .unwrap();
+        assert!(destinations.is_empty());
+
+        match manager
+            .send(CommandMessage {
+                command: Command::GetInfo {
+                    id: None,
+                    node_type: Some(NodeType::Mixer),
+                },
+            })
+            .await
+            .unwrap()
+        {
+            CommandResult::Info(info) => {
+                assert_eq!(info.nodes.len(), 1);
+                assert!(matches!(info.nodes["test-mixer"], NodeInfo::Mixer(_)));
+            }
+            res => panic!("Unexpected result {:?}", res),
+        }
+    }
+
+    #[actix_rt::test]
+    #[test]
+    async fn test_get_info_timeout() {
+        gst::init().unwrap();
+
+        let uri = asset_uri("ball.mp4");
+        let arbiter = Arbiter::new();
+        let addr = Source::start_in_arbiter(&arbiter.handle(), move |_| {
+            Source::new("test-slow-source", &uri)
+        });
+
+        // Keep the arbiter of the source busy so that it can't reply
+        arbiter.spawn_fn(|| std::thread::sleep(Duration::from_secs(2)));
+
+        let res = Node::Source(addr)
+            .get_info_with_timeout("test-slow-source".to_string(), Duration::from_millis(100))
+            .await;
+
+        match res {
+            NodeInfoResult::Timeout { id } => assert_eq!(id, "test-slow-source"),
+            res => panic!("Unexpected result {:?}", res),
+        }
+
+        arbiter.stop();
+    }
+
+    #[actix_rt::test]
+    #[test]
+    async fn test_cascade_stop() {
+        gst::init().unwrap();
+
+        let mut opt_out = HashMap::new();
+        opt_out.insert("cascade-stop".to_string(), serde_json::json!(false));
+
+        create_source("test-source", &asset_uri("ball.mp4"))
+            .await
+            .unwrap();
+        create_mixer("test-mixer-a", None).await.unwrap();
+        create_mixer("test-mixer-b", None).await.unwrap();
+        create_mixer("test-mixer-c", Some(opt_out)).await.unwrap();
+        create_mixer("test-mixer-d", None).await.unwrap();
+
+        connect("link-0", "test-source", "test-mixer-a")
+            .await
+            .unwrap();
+        connect("link-1", "test-mixer-a", "test-mixer-b")
+            .await
+            .unwrap();
+        connect("link-2", "test-mixer-a", "test-mixer-c")
+            .await
+            .unwrap();
+        // Only reachable through a node that opted out
+        connect("link-3", "test-mixer-c", "test-mixer-d")
+            .await
+            .unwrap();
+
+        let manager = NodeManager::from_registry();
+
+        manager
+            .send(CascadeStopMessage {
+                root_node_id: "test-source".to_string(),
+                level_timeout: STOP_LEVEL_TIMEOUT,
+            })
+            .await
+            .unwrap()
+            .unwrap();
+
+        let sources = manager
+            .send(GetNodesByTypeMessage {
+                node_type: NodeType::Source,
+            })
+            .await
+            .unwrap();
+        assert!(sources.is_empty());
+
+        let mut mixers = manager
+            .send(GetNodesByTypeMessage {
+                node_type: NodeType::Mixer,
+            })
+            .await
+            .unwrap();
+        mixers.sort();
+        assert_eq!(
+            mixers,
+            vec!["test-mixer-c".to_string(), "test-mixer-d".to_string()]
+        );
+
+        assert!(manager
+            .send(CascadeStopMessage {
+                root_node_id: "test-source".to_string(),
+                level_timeout: STOP_LEVEL_TIMEOUT,
+            })
+            .await
+            .unwrap()
+            .is_err());
+    }
+
+    /// Add a node started outside of [`NodeManager`]
+    struct AddNodeMessage {
+        id: String,
+        node: Node,
+        producer: Option<Recipient<GetProducerMessage>>,
+    }
+
+    impl Message for AddNodeMessage {
+        type Result = ();
+    }
+
+    impl Handler<AddNodeMessage> for NodeManager {
+        type Result = ();
+
+        fn handle(&mut self, msg: AddNodeMessage, _ctx: &mut Context<Self>) {
+            self.add_node(&msg.id, msg.node);
+            if let Some(producer) = msg.producer {
+                self.producers.insert(msg.id, producer);
+            }
+        }
+    }
+
+    #[actix_rt::test]
+    #[test]
+    async fn test_cascade_stop_level_timeout() {
+        gst::init().unwrap();
+
+        let uri = asset_uri("ball.mp4");
+        let arbiter = Arbiter::new();
+        let addr = Source::start_in_arbiter(&arbiter.handle(), move |_| {
+            Source::new("test-slow-source", &uri)
+        });
+
+        let manager = NodeManager::from_registry();
+
+        manager
+            .send(AddNodeMessage {
+                id: "test-slow-source".to_string(),
+                node: Node::Source(addr),
+                producer: None,
+            })
+            .await
+            .unwrap();
+
+        // Keep the arbiter of the source busy so that it can't stop
+        arbiter.spawn_fn(|| std::thread::sleep(Duration::from_secs(1)));
+
+        let err = manager
+            .send(CascadeStopMessage {
+                root_node_id: "test-slow-source".to_string(),
+                level_timeout: Duration::from_millis(100),
+            })
+            .await
+            .unwrap()
+            .unwrap_err();
+        assert!(err.to_string().contains("test-slow-source"));
+
+        arbiter.stop();
+    }
+
+    #[actix_rt::test]
+    #[test]
+    async fn test_node_groups() {
+        gst::init().unwrap();
+
+        create_mixer("test-mixer-a", None).await.unwrap();
+        create_mixer("test-mixer-b", None).await.unwrap();
+
+        let create_group = |id: &str, node_ids: &[&str]| {
+            run_command(Command::CreateGroup {
+                id: id.to_string(),
+                node_ids: node_ids.iter().map(|id| id.to_string()).collect(),
+            })
+        };
+
+        assert!(create_group("test-group", &[]).await.is_err());
+        assert!(
+            create_group("test-group", &["test-mixer-a", "test-mixer-c"])
+                .await
+                .is_err()
+        );
+        assert!(
+            create_group("test-group", &["test-mixer-a", "test-mixer-a"])
+                .await
+                .is_err()
+        );
+        create_group("test-group", &["test-mixer-a", "test-mixer-b"])
+            .await
+            .unwrap();
+        assert!(create_group("test-group", &["test-mixer-a"]).await.is_err());
+
+        run_command(Command::StartGroup {
+            id: "test-group".to_string(),
+            cue_time: None,
+            end_time: None,
+        })
+        .await
+        .unwrap();
+
+        for id in &["test-mixer-a", "test-mixer-b"] {
+            let mut state = State::Initial;
+            for _ in 0..50 {
+                state = match node_info_unchecked(id).await {
+                    NodeInfo::Mixer(info) => info.state,
+                    _ => unreachable!(),
+                };
+
+                if state == State::Started {
+                    break;
+                }
+
+                tokio::time::sleep(Duration::from_millis(100)).await;
+            }
+            assert_eq!(state, State::Started);
+        }
+
+        let stop_group = || {
+            run_command(Command::StopGroup {
+                id: "test-group".to_string(),
+            })
+        };
+
+        stop_group().await.unwrap();
+
+        // The nodes are removed once stopped, and the group with them
+        let manager = NodeManager::from_registry();
+        let mut mixers = vec![];
+        for _ in 0..50 {
+            mixers = manager
+                .send(GetNodesByTypeMessage {
+                    node_type: NodeType::Mixer,
+                })
+                .await
+                .unwrap();
+
+            if mixers.is_empty() {
+                break;
+            }
+
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+        assert!(mixers.is_empty());
+        assert!(stop_group().await.is_err());
+    }
+
+    #[actix_rt::test]
+    #[test]
+    async fn test_set_node_log_level_unknown_node() {
+        gst::init().unwrap();
+
+        let err = run_command(Command::SetNodeLogLevel {
+            id: "test-mixer".to_string(),
+            level: Some("debug".to_string()),
+        })
+        .await
+        .unwrap_err();
+        assert!(err.to_string().contains("No node with id test-mixer"));
+    }
+
+    #[actix_rt::test]
+    #[test]
+    async fn test_set_keyframe_forward_mode() {
+        gst::init().unwrap();
+
+        let manager = NodeManager::from_registry();
+
+        let source = Source::new("test-source", &asset_uri("ball.mp4")).start();
+        manager
+            .send(AddNodeMessage {
+                id: "test-source".to_string(),
+                node: Node::Source(source.clone()),
+                producer: Some(source.clone().recipient()),
+            })
+            .await
+            .unwrap();
+
+        let mixer = Mixer::new("test-mixer", None).unwrap().start();
+        manager
+            .send(AddNodeMessage {
+                id: "test-mixer".to_string(),
+                node: Node::Mixer(mixer.clone()),
+                producer: Some(mixer.clone().recipient()),
+            })
+            .await
+            .unwrap();
+
+        let producers: Vec<Recipient<GetProducerMessage>> =
+            vec![source.recipient(), mixer.recipient()];
+
+        for (id, producer) in ["test-source", "test-mixer"].iter().zip(producers) {
+            set_forward_mode(id, ForwardMode::Keyframe).await.unwrap();
+
+            let (video_producer, audio_producer) =
+                producer.send(GetProducerMessage).await.unwrap().unwrap();
+            assert_eq!(video_producer.forward_mode(), ForwardMode::Keyframe);
+            assert_eq!(audio_producer.forward_mode(), ForwardMode::Keyframe);
+        }
+    }
+}
@@ -0,0 +1,142 @@
+//! Checks for the GStreamer elements we depend on
+//!
+//! Nodes create their elements as needed, a missing element would
+//! otherwise only be noticed once a node using it is created or started.
+
+use tracing::debug;
+
+/// Elements no node can work without, or that nodes create
+/// unconditionally
+const REQUIRED_ELEMENTS: &[&str] = &[
+    "compositor",
+    "audiomixer",
+    "appsink",
+    "appsrc",
+    "videotestsrc",
+    "audiotestsrc",
+    "videoconvert",
+    "videoscale",
+    "videorate",
+    "audioconvert",
+    "audioresample",
+    "queue",
+    "capsfilter",
+    "tee",
+    "level",
+    "imagefreeze",
+    "filesrc",
+    "decodebin3",
+    "fallbacksrc",
+    "deinterlace",
+    "h264parse",
+    "x264enc",
+    "faac",
+];
+
+/// Elements only some features depend on, feature -> elements
+const OPTIONAL_ELEMENTS: &[(&str, &[&str])] = &[
+    (
+        "RTMP",
+        &["rtmp2sink", "flvmux", "timecodestamper", "timeoverlay"],
+    ),
+    ("Local file", &["splitmuxsink", "qtmux", "multiqueue"]),
+    ("Local playback", &["autovideosink", "autoaudiosink"]),
+    ("PCM", &["wavenc", "tcpclientsink", "filesink"]),
+    ("JACK", &["jackaudiosink"]),
+    ("DASH", &["dashsink", "avenc_aac"]),
+    (
+        "Previews and frame captures",
+        &["jpegenc", "pngenc", "filesink"],
+    ),
+    ("Slot denoising", &["hqdn3d"]),
+    ("Slot labels", &["textoverlay"]),
+    ("Slot compression", &["audiodynamic"]),
+    ("Slot rounded corners", &["roundedcorners"]),
+    #[cfg(target_os = "linux")]
+    (
+        "Screen capture",
+        &["ximagesrc", "pipewiresrc", "videocrop", "videorate"],
+    ),
+    #[cfg(target_os = "macos")]
+    ("Screen capture", &["avfvideosrc", "videocrop", "videorate"]),
+];
+
+fn is_available(element: &str) -> bool {
+    let available = gst::ElementFactory::find(element).is_some();
+
+    debug!(element = %element, available = %available, "checked element");
+
+    available
+}
+
+/// Check that the elements we depend on are available.
+///
+/// On success, returns the features that are unavailable because of
+/// missing optional elements, eg `"JACK (jackaudiosink)"`. Otherwise, returns
+/// the missing required elements.
+pub fn check_required_plugins() -> Result<Vec<String>, Vec<String>> {
+    check_elements(is_available)
+}
+
+/// Check our elements against `is_available`, see [`check_required_plugins`]
+fn check_elements<F>(is_available: F) -> Result<Vec<String>, Vec<String>>
+where
+    F: Fn(&str) -> bool,
+{
+    let missing: Vec<String> = REQUIRED_ELEMENTS
+        .iter()
+        .filter(|element| !is_available(element))
+        .map(|element| element.to_string())
+        .collect();
+
+    if !missing.is_empty() {
+        return Err(missing);
+    }
+
+    Ok(OPTIONAL_ELEMENTS
+        .iter()
+        .filter_map(|(feature, elements)| {
+            let missing: Vec<&str> = elements
+                .iter()
+                .copied()
+                .filter(|element| !is_available(element))
+                .collect();
+
+            if missing.is_empty() {
+                None
+            } else {
+                Some(format!("{} ({})", feature, missing.join(", ")))
+            }
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_required_plugins() {
+        assert_eq!(check_elements(|_| true), Ok(vec![]));
+
+        assert_eq!(
+            check_elements(|element| element != "compositor" && element != "jackaudiosink"),
+            Err(vec!["compositor".to_string()])
+        );
+
+        assert_eq!(
+            check_elements(|element| element != "jackaudiosink"),
+            Ok(vec!["JACK (jackaudiosink)".to_string()])
+        );
+
+        // Features sharing a missing element are all reported
+        assert_eq!(
+            check_elements(|element| element != "filesink" && element != "qtmux"),
+            Ok(vec![
+                "Local file (qtmux)".to_string(),
+                "PCM (filesink)".to_string(),
+                "Previews and frame captures (filesink)".to_string(),
+            ])
+        );
+    }
+}
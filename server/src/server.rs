@@ -2,11 +2,19 @@
 
 use crate::config::Config;
 use crate::controller::Controller;
-use crate::node::{NodeManager, StopMessage};
+use crate::node::{
+    GetMetricsMessage, NodeManager, PingAllNodesMessage, PreviewMixerOutputMessage,
+    SetHeartbeatIntervalMessage, SetLogFilterMessage, StopMessage, SubscribeMixerAudioLevelMessage,
+    PING_TIMEOUT,
+};
+use crate::utils::LogFilter;
+use crate::ws::AudioLevelStream;
 
 use actix::SystemService;
 use actix_web::{web, App, HttpRequest, HttpResponse, HttpServer};
 use actix_web_actors::ws;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 
 use tracing::error;
 
@@ -29,13 +37,138 @@ async fn ws(
     }
 }
 
+/// Create a WebSocket actor streaming the audio level of a mixer.
+async fn audio_level_ws(
+    path: web::Path<String>,
+    req: HttpRequest,
+    stream: web::Payload,
+) -> Result<HttpResponse, actix_web::Error> {
+    let node_id = path.into_inner();
+
+    let receiver = NodeManager::from_registry()
+        .send(SubscribeMixerAudioLevelMessage {
+            id: node_id.clone(),
+        })
+        .await
+        .map_err(|err| {
+            error!("Failed to subscribe to audio levels: {}", err);
+            HttpResponse::InternalServerError()
+        })?;
+
+    match receiver {
+        Ok(receiver) => ws::start(AudioLevelStream::new(&node_id, receiver), &req, stream),
+        Err(err) => Ok(HttpResponse::NotFound().body(err.to_string())),
+    }
+}
+
+/// Expose the metrics of all nodes in the Prometheus text format
+async fn metrics() -> Result<HttpResponse, actix_web::Error> {
+    let metrics = NodeManager::from_registry()
+        .send(GetMetricsMessage)
+        .await
+        .map_err(|err| {
+            error!("Failed to gather metrics: {}", err);
+            HttpResponse::InternalServerError()
+        })?;
+
+    Ok(HttpResponse::Ok()
+        .content_type("text/plain; version=0.0.4")
+        .body(metrics))
+}
+
+/// Outcome of pinging a node, as served by [`health`]
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum NodeHealth {
+    /// The node replied, after that many milliseconds
+    Healthy { latency_ms: u64 },
+    /// The node could not be pinged
+    Unhealthy { error: String },
+}
+
+/// Ping all nodes, reply with the outcome for each node id. The status
+/// is 503 when any of the nodes did not reply.
+async fn health() -> Result<HttpResponse, actix_web::Error> {
+    let pings = NodeManager::from_registry()
+        .send(PingAllNodesMessage {
+            timeout: PING_TIMEOUT,
+        })
+        .await
+        .map_err(|err| {
+            error!("Failed to ping nodes: {}", err);
+            HttpResponse::InternalServerError()
+        })?;
+
+    let all_healthy = pings.values().all(|res| res.is_ok());
+
+    let nodes = pings
+        .into_iter()
+        .map(|(id, res)| {
+            let health = match res {
+                Ok(latency) => NodeHealth::Healthy {
+                    latency_ms: latency.as_millis() as u64,
+                },
+                Err(err) => NodeHealth::Unhealthy {
+                    error: err.to_string(),
+                },
+            };
+            (id, health)
+        })
+        .collect::<BTreeMap<_, _>>();
+
+    if all_healthy {
+        Ok(HttpResponse::Ok().json(nodes))
+    } else {
+        Ok(HttpResponse::ServiceUnavailable().json(nodes))
+    }
+}
+
+/// Dimensions of the thumbnails served by [`preview`]
+#[derive(Debug, Deserialize)]
+struct PreviewQuery {
+    width: u16,
+    height: u16,
+}
+
+/// Serve a JPEG thumbnail of the current video output of a mixer
+async fn preview(
+    path: web::Path<String>,
+    query: web::Query<PreviewQuery>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let jpeg = NodeManager::from_registry()
+        .send(PreviewMixerOutputMessage {
+            id: path.into_inner(),
+            width: query.width,
+            height: query.height,
+        })
+        .await
+        .map_err(|err| {
+            error!("Failed to preview output: {}", err);
+            HttpResponse::InternalServerError()
+        })?;
+
+    match jpeg {
+        Ok(jpeg) => Ok(HttpResponse::Ok().content_type("image/jpeg").body(jpeg)),
+        Err(err) => Ok(HttpResponse::BadRequest().body(err.to_string())),
+    }
+}
+
 /// Start the server based on the passed `Config`.
-pub async fn run(cfg: Config) -> Result<(), anyhow::Error> {
+pub async fn run(cfg: Config, log_filter: LogFilter) -> Result<(), anyhow::Error> {
+    NodeManager::from_registry().do_send(SetLogFilterMessage(log_filter));
+    NodeManager::from_registry().do_send(SetHeartbeatIntervalMessage(
+        std::time::Duration::from_secs(cfg.heartbeat_interval),
+    ));
+
     let server = HttpServer::new(move || {
         App::new()
             .wrap(actix_web::middleware::Logger::default())
             .wrap(tracing_actix_web::TracingLogger::default())
             .route("/ws/{mode:(control)}", web::get().to(ws))
+            .route("/ws/audio/{node_id}", web::get().to(audio_level_ws))
+            .route("/metrics", web::get().to(metrics))
+            .route("/health", web::get().to(health))
+            .route("/preview/{node_id}", web::get().to(preview))
     });
 
     let server = if cfg.use_tls {
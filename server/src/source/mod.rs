@@ -1,7 +1,8 @@
 //! A source processing node.
 //!
-//! The only supported source type is created with a URI. In the future
-//! generators could also be supported, for example to display a countdown.
+//! Sources are created with a URI, or capture a display with a
+//! [`ScreenCaptureSource`]. In the future generators could also be
+//! supported, for example to display a countdown.
 //!
 //! The main complexity for this node is the [`starting`](State::Starting)
 //! feature: when the node is scheduled to play in the future, we spin it
@@ -18,13 +19,18 @@
 //! pipeline and moved to a new one, this is safe but needs to be kept
 //! in mind.
 
+mod screen_capture;
+
+pub use screen_capture::ScreenCaptureSource;
+
 use crate::node::{
-    AddControlPointMessage, GetNodeInfoMessage, GetProducerMessage, NodeManager, NodeStatusMessage,
-    RemoveControlPointMessage, ScheduleMessage, StartMessage, StopMessage, StoppedMessage,
+    forward_producer_notifications, AddControlPointMessage, GetLatencyMessage, GetNodeInfoMessage,
+    GetProducerMessage, GetStateMessage, NodeManager, NodeStatusMessage, RemoveControlPointMessage,
+    ScheduleMessage, StartMessage, StopMessage, StoppedMessage,
 };
 use crate::utils::{
-    make_element, ErrorMessage, PipelineManager, Schedulable, StateChangeResult, StateMachine,
-    StopManagerMessage, StreamProducer,
+    make_element, query_pipeline_latency, ErrorMessage, PipelineLatency, PipelineManager,
+    Schedulable, StateChangeResult, StateMachine, StopManagerMessage, StreamProducer,
 };
 use actix::prelude::*;
 use anyhow::{anyhow, Error};
@@ -90,11 +96,18 @@ impl Source {
                 .downcast::<gst_app::AppSink>()
                 .unwrap();
 
+        let audio_producer = StreamProducer::from(&audio_appsink);
+        let video_producer = StreamProducer::from(&video_appsink);
+
+        for producer in &[&audio_producer, &video_producer] {
+            forward_producer_notifications(producer, id);
+        }
+
         Self {
             id: id.to_string(),
             uri: uri.to_string(),
-            audio_producer: StreamProducer::from(&audio_appsink),
-            video_producer: StreamProducer::from(&video_appsink),
+            audio_producer,
+            video_producer,
             media: None,
             state_handle: None,
             monitor_handle: None,
@@ -397,8 +410,10 @@ impl Source {
 impl Actor for Source {
     type Context = Context<Self>;
 
-    #[instrument(level = "debug", name = "starting", skip(self, _ctx), fields(id = %self.id))]
-    fn started(&mut self, _ctx: &mut Self::Context) {}
+    #[instrument(level = "debug", name = "starting", skip(self, ctx), fields(id = %self.id))]
+    fn started(&mut self, ctx: &mut Self::Context) {
+        self.start_heartbeat(ctx);
+    }
 
     #[instrument(level = "debug", name = "stopping", skip(self, _ctx), fields(id = %self.id))]
     fn stopped(&mut self, _ctx: &mut Self::Context) {
@@ -590,6 +605,27 @@ impl Handler<StopMessage> for Source {
     }
 }
 
+impl Handler<GetLatencyMessage> for Source {
+    type Result = ResponseFuture<Result<PipelineLatency, Error>>;
+
+    fn handle(&mut self, _msg: GetLatencyMessage, _ctx: &mut Context<Self>) -> Self::Result {
+        query_pipeline_latency(
+            self.media
+                .as_ref()
+                .map(|media| media.pipeline_manager.clone()),
+            &self.id,
+        )
+    }
+}
+
+impl Handler<GetStateMessage> for Source {
+    type Result = MessageResult<GetStateMessage>;
+
+    fn handle(&mut self, _msg: GetStateMessage, _ctx: &mut Context<Self>) -> Self::Result {
+        MessageResult(self.state_machine.state)
+    }
+}
+
 impl Handler<GetNodeInfoMessage> for Source {
     type Result = Result<NodeInfo, Error>;
 
@@ -600,6 +636,8 @@ impl Handler<GetNodeInfoMessage> for Source {
             cue_time: self.state_machine.cue_time,
             end_time: self.state_machine.end_time,
             state: self.state_machine.state,
+            video_forwarding: self.video_producer.is_forwarding(),
+            audio_forwarding: self.audio_producer.is_forwarding(),
         }))
     }
 }
@@ -607,8 +645,12 @@ impl Handler<GetNodeInfoMessage> for Source {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::node::{
+        NodeError, PingAllNodesMessage, PingNodeMessage, SetHeartbeatIntervalMessage,
+    };
     use crate::utils::get_now;
     use crate::utils::tests::*;
+    use auteur_controlling::controller::ForwardMode;
     use std::collections::VecDeque;
     use test_env_log::test;
 
@@ -629,11 +671,39 @@ mod tests {
             assert!(sinfo.cue_time.is_none());
             assert!(sinfo.end_time.is_none());
             assert_eq!(sinfo.state, State::Initial);
+            assert!(!sinfo.video_forwarding);
+            assert!(!sinfo.audio_forwarding);
         } else {
             panic!("Wrong info type");
         }
     }
 
+    #[actix_rt::test]
+    #[test]
+    async fn test_set_forward_mode() {
+        gst::init().unwrap();
+        let uri = asset_uri("ball.mp4");
+
+        create_source("test-source", &uri).await.unwrap();
+        create_local_destination("test-destination", "/tmp/test-forward-mode", None)
+            .await
+            .unwrap();
+
+        set_forward_mode("test-source", ForwardMode::Auto)
+            .await
+            .unwrap();
+
+        let err = set_forward_mode("test-destination", ForwardMode::Auto)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("No producer with id"));
+
+        let err = set_forward_mode("no-such-node", ForwardMode::Auto)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("No producer with id"));
+    }
+
     #[actix_rt::test]
     #[test]
     async fn test_start_immediate() {
@@ -718,6 +788,86 @@ mod tests {
 
         assert!(progression_result.progressed_as_expected);
     }
+
+    #[actix_rt::test]
+    #[test]
+    async fn test_ping() {
+        gst::init().unwrap();
+        let uri = asset_uri("ball.mp4");
+
+        create_source("test-source", &uri).await.unwrap();
+
+        let manager = NodeManager::from_registry();
+
+        let latency = manager
+            .send(PingNodeMessage {
+                node_id: "test-source".to_string(),
+            })
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(latency < std::time::Duration::from_millis(500));
+
+        assert_eq!(
+            manager
+                .send(PingNodeMessage {
+                    node_id: "no-source".to_string(),
+                })
+                .await
+                .unwrap(),
+            Err(NodeError::NotFound)
+        );
+
+        let pings = manager
+            .send(PingAllNodesMessage {
+                timeout: std::time::Duration::from_secs(1),
+            })
+            .await
+            .unwrap();
+        assert_eq!(pings.len(), 1);
+        assert!(pings["test-source"].is_ok());
+    }
+
+    #[actix_rt::test]
+    #[test]
+    async fn test_heartbeat() {
+        gst::init().unwrap();
+        let uri = asset_uri("ball.mp4");
+
+        NodeManager::from_registry()
+            .send(SetHeartbeatIntervalMessage(std::time::Duration::from_secs(
+                1,
+            )))
+            .await
+            .unwrap();
+
+        let listener_addr = register_listener(
+            "test-source",
+            "test-listener",
+            VecDeque::from(vec![State::Starting, State::Started]),
+        )
+        .await;
+
+        create_source("test-source", &uri).await.unwrap();
+
+        start_node("test-source", None, None).await.unwrap();
+
+        let progression_result = listener_addr.send(WaitForProgressionMessage).await.unwrap();
+
+        assert!(progression_result.progressed_as_expected);
+
+        // Let a few heartbeats go by
+        tokio::time::pause();
+        tokio::time::sleep(std::time::Duration::from_millis(3500)).await;
+
+        let heartbeats = listener_addr.send(GetHeartbeatsMessage).await.unwrap();
+
+        assert!(heartbeats.len() >= 3);
+        assert_eq!(heartbeats.last().unwrap().0, State::Started);
+        assert!(heartbeats
+            .windows(2)
+            .all(|window| window[0].1 < window[1].1));
+    }
 }
 
 impl Handler<AddControlPointMessage> for Source {
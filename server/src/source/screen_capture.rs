@@ -0,0 +1,777 @@
+//! A source node capturing a display.
+//!
+//! The capture element depends on the platform: `ximagesrc` on Linux,
+//! or `pipewiresrc` when running under Wayland, and `avfvideosrc` on
+//! macOS. Screen captures are live and only produce video, the audio
+//! producer of the node never outputs any sample and is not connected
+//! to consumers.
+
+use crate::node::{
+    forward_producer_notifications, AddControlPointMessage, GetLatencyMessage, GetNodeInfoMessage,
+    GetProducerMessage, GetStateMessage, NodeManager, NodeStatusMessage, RemoveControlPointMessage,
+    ScheduleMessage, StartMessage, StopMessage, StoppedMessage,
+};
+use crate::utils::{
+    make_element, query_pipeline_latency, ErrorMessage, PipelineLatency, PipelineManager,
+    Schedulable, Setting, SettingController, SettingSpec, StateChangeResult, StateMachine,
+    StopManagerMessage, StreamProducer,
+};
+use actix::prelude::*;
+use anyhow::{anyhow, Error};
+use auteur_controlling::controller::{ForwardMode, NodeInfo, SourceInfo, State};
+use gst::prelude::*;
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use tracing::{debug, error, instrument};
+
+/// The part of a display to capture, in display pixels
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CaptureRegion {
+    /// Horizontal offset of the region
+    pub x: u32,
+    /// Vertical offset of the region
+    pub y: u32,
+    /// Width of the region
+    pub width: u32,
+    /// Height of the region
+    pub height: u32,
+}
+
+impl CaptureRegion {
+    /// Parse a region from an object with `x`, `y`, `width` and
+    /// `height` members
+    fn from_value(value: &serde_json::Value) -> Result<Self, Error> {
+        let member = |name: &str| -> Result<u32, Error> {
+            value
+                .get(name)
+                .and_then(|v| v.as_u64())
+                .and_then(|v| u32::try_from(v).ok())
+                .ok_or_else(|| anyhow!("region {} must be a positive 32-bit integer", name))
+        };
+
+        let region = Self {
+            x: member("x")?,
+            y: member("y")?,
+            width: member("width")?,
+            height: member("height")?,
+        };
+
+        if region.width == 0 || region.height == 0 {
+            return Err(anyhow!("region can't be empty"));
+        }
+
+        if region.x.checked_add(region.width).is_none()
+            || region.y.checked_add(region.height).is_none()
+        {
+            return Err(anyhow!("region is out of bounds"));
+        }
+
+        Ok(region)
+    }
+}
+
+/// The pipeline wrapped by the node when capturing
+#[derive(Debug)]
+struct Media {
+    /// The wrapped pipeline
+    pipeline: gst::Pipeline,
+    /// A helper for managing the pipeline
+    pipeline_manager: Addr<PipelineManager>,
+}
+
+/// The ScreenCaptureSource actor
+#[derive(Debug)]
+pub struct ScreenCaptureSource {
+    /// Unique identifier
+    id: String,
+    /// The display to capture, the default one of the platform if None
+    display_id: Option<u32>,
+    /// The part of the display to capture, all of it if None
+    region: Option<CaptureRegion>,
+    /// The framerate of the output
+    framerate: gst::Fraction,
+    /// When our video producer starts forwarding
+    forward_mode: ForwardMode,
+    /// Output audio producer, never fed
+    audio_producer: StreamProducer,
+    /// Output video producer
+    video_producer: StreamProducer,
+    /// GStreamer elements when capturing
+    media: Option<Media>,
+    /// Our state machine
+    state_machine: StateMachine,
+}
+
+impl ScreenCaptureSource {
+    fn create_settings() -> HashMap<String, Setting> {
+        let mut settings = HashMap::new();
+
+        settings.insert(
+            "display-id".to_string(),
+            Setting {
+                name: "display-id".to_string(),
+                spec: SettingSpec::I64 {
+                    min: 0,
+                    max: u32::MAX as i64,
+                    current: 0,
+                },
+                controllable: false,
+            },
+        );
+
+        settings.insert(
+            "framerate".to_string(),
+            Setting {
+                name: "framerate".to_string(),
+                spec: SettingSpec::Fraction {
+                    current: gst::Fraction::new(30, 1),
+                },
+                controllable: false,
+            },
+        );
+
+        settings.insert(
+            "forward-mode".to_string(),
+            Setting {
+                name: "forward-mode".to_string(),
+                spec: SettingSpec::Enum {
+                    current: "manual".to_string(),
+                    choices: &["manual", "auto", "keyframe"],
+                },
+                controllable: false,
+            },
+        );
+
+        settings
+    }
+
+    /// Create a screen capture source
+    #[instrument(level = "debug", name = "creating")]
+    pub fn new(
+        id: &str,
+        mut config: Option<HashMap<String, serde_json::Value>>,
+    ) -> Result<Self, Error> {
+        let region = match config.as_mut().and_then(|config| config.remove("region")) {
+            Some(serde_json::Value::Null) | None => None,
+            Some(value) => Some(CaptureRegion::from_value(&value)?),
+        };
+
+        let mut settings = ScreenCaptureSource::create_settings();
+
+        let display_id_set = config
+            .as_ref()
+            .map_or(false, |config| config.contains_key("display-id"));
+
+        if let Some(config) = config {
+            for (key, value) in config {
+                if let Some(setting) = settings.get_mut(&key) {
+                    SettingController::validate_value(setting, &value)?;
+                    SettingController::set_from_value(setting, &value);
+                } else {
+                    return Err(anyhow!("No setting with name {} on screen captures", key));
+                }
+            }
+        }
+
+        let display_id =
+            Some(settings["display-id"].as_i64().unwrap() as u32).filter(|_| display_id_set);
+
+        #[cfg(target_os = "linux")]
+        if std::env::var_os("WAYLAND_DISPLAY").is_some() {
+            ScreenCaptureSource::pipewire_node_id(display_id)?;
+        }
+
+        let audio_appsink =
+            gst::ElementFactory::make("appsink", Some(&format!("src-audio-appsink-{}", id)))
+                .unwrap()
+                .downcast::<gst_app::AppSink>()
+                .unwrap();
+
+        let video_appsink =
+            gst::ElementFactory::make("appsink", Some(&format!("src-video-appsink-{}", id)))
+                .unwrap()
+                .downcast::<gst_app::AppSink>()
+                .unwrap();
+
+        let forward_mode = match settings["forward-mode"].as_str().unwrap() {
+            "auto" => ForwardMode::Auto,
+            "keyframe" => ForwardMode::Keyframe,
+            _ => ForwardMode::Manual,
+        };
+
+        let audio_producer = StreamProducer::from(&audio_appsink);
+        let video_producer = StreamProducer::from(&video_appsink);
+        video_producer.set_forward_mode(forward_mode);
+
+        for producer in &[&audio_producer, &video_producer] {
+            forward_producer_notifications(producer, id);
+        }
+
+        Ok(Self {
+            id: id.to_string(),
+            display_id,
+            region,
+            framerate: settings["framerate"].as_fraction().unwrap(),
+            forward_mode,
+            audio_producer,
+            video_producer,
+            media: None,
+            state_machine: StateMachine::default(),
+        })
+    }
+
+    /// The PipeWire node capturing the display under Wayland. Unlike X
+    /// screens, node ids are allocated dynamically, there is no sensible
+    /// default
+    #[cfg(target_os = "linux")]
+    fn pipewire_node_id(display_id: Option<u32>) -> Result<u32, Error> {
+        display_id.ok_or_else(|| {
+            anyhow!("display-id must be set to the id of a PipeWire node under Wayland")
+        })
+    }
+
+    /// Create the element capturing our display, and whether it
+    /// restricts the capture to our region itself
+    #[cfg(target_os = "linux")]
+    fn make_capture_element(&self) -> Result<(gst::Element, bool), Error> {
+        if std::env::var_os("WAYLAND_DISPLAY").is_some() {
+            let node_id = ScreenCaptureSource::pipewire_node_id(self.display_id)?;
+            let src = make_element("pipewiresrc", None)?;
+            src.set_property("path", &node_id.to_string()).unwrap();
+            src.set_property("do-timestamp", &true).unwrap();
+
+            return Ok((src, false));
+        }
+
+        let src = make_element("ximagesrc", None)?;
+        src.set_property("show-pointer", &true).unwrap();
+        src.set_property("use-damage", &false).unwrap();
+        src.set_property("screen-num", &self.display_id.unwrap_or(0))
+            .unwrap();
+
+        if let Some(region) = self.region {
+            src.set_property("startx", &region.x).unwrap();
+            src.set_property("starty", &region.y).unwrap();
+            src.set_property("endx", &(region.x + region.width - 1))
+                .unwrap();
+            src.set_property("endy", &(region.y + region.height - 1))
+                .unwrap();
+        }
+
+        Ok((src, true))
+    }
+
+    /// Create the element capturing our display, and whether it
+    /// restricts the capture to our region itself
+    #[cfg(target_os = "macos")]
+    fn make_capture_element(&self) -> Result<(gst::Element, bool), Error> {
+        let src = make_element("avfvideosrc", None)?;
+        src.set_property("capture-screen", &true).unwrap();
+        src.set_property("capture-screen-cursor", &true).unwrap();
+        src.set_property("device-index", &(self.display_id.unwrap_or(0) as i32))
+            .unwrap();
+
+        Ok((src, false))
+    }
+
+    /// Create the element capturing our display, and whether it
+    /// restricts the capture to our region itself
+    #[cfg(not(any(target_os = "linux", target_os = "macos")))]
+    fn make_capture_element(&self) -> Result<(gst::Element, bool), Error> {
+        Err(anyhow!("Screen capture is not supported on this platform"))
+    }
+
+    /// Create a `videocrop` restricting its input to `region`, for the
+    /// capture elements that always capture the whole display
+    fn make_cropper(region: CaptureRegion) -> Result<gst::Element, Error> {
+        let crop = make_element("videocrop", None)?;
+
+        crop.set_property("left", &(region.x as i32)).unwrap();
+        crop.set_property("top", &(region.y as i32)).unwrap();
+
+        // What to crop on the right and bottom depends on the size of
+        // the display, only known once caps are received
+        let crop_weak = crop.downgrade();
+        crop.static_pad("sink").unwrap().add_probe(
+            gst::PadProbeType::EVENT_DOWNSTREAM,
+            move |_pad, info| {
+                if let Some(gst::PadProbeData::Event(ref ev)) = info.data {
+                    if let gst::EventView::Caps(caps) = ev.view() {
+                        let size = caps.caps().structure(0).and_then(|s| {
+                            Some((s.get::<i32>("width").ok()?, s.get::<i32>("height").ok()?))
+                        });
+
+                        if let (Some(crop), Some((width, height))) = (crop_weak.upgrade(), size) {
+                            let right = width as i64 - region.x as i64 - region.width as i64;
+                            let bottom = height as i64 - region.y as i64 - region.height as i64;
+
+                            crop.set_property("right", &(right.max(0) as i32)).unwrap();
+                            crop.set_property("bottom", &(bottom.max(0) as i32))
+                                .unwrap();
+                        }
+                    }
+                }
+
+                gst::PadProbeReturn::Ok
+            },
+        );
+
+        Ok(crop)
+    }
+
+    /// Build and start our pipeline, its output is discarded by our
+    /// video producer until we [`unblock`](Self::unblock)
+    #[instrument(level = "debug", name = "capturing", skip(self, ctx), fields(id = %self.id))]
+    fn start_capture(&mut self, ctx: &mut Context<Self>) -> Result<StateChangeResult, Error> {
+        let pipeline = gst::Pipeline::new(Some(&self.id.to_string()));
+
+        let (src, crops) = self.make_capture_element()?;
+        let vconv = make_element("videoconvert", None)?;
+        let vrate = make_element("videorate", None)?;
+        let capsfilter = make_element("capsfilter", None)?;
+        let appsink: &gst::Element = self.video_producer.appsink().upcast_ref();
+
+        capsfilter
+            .set_property(
+                "caps",
+                &gst::Caps::builder("video/x-raw")
+                    .field("framerate", &self.framerate)
+                    .build(),
+            )
+            .unwrap();
+
+        pipeline.add_many(&[&src, &vconv, &vrate, &capsfilter, appsink])?;
+
+        match self.region {
+            Some(region) if !crops => {
+                let crop = ScreenCaptureSource::make_cropper(region)?;
+                pipeline.add(&crop)?;
+                gst::Element::link_many(&[&src, &crop, &vconv])?;
+            }
+            _ => src.link(&vconv)?,
+        }
+
+        gst::Element::link_many(&[&vconv, &vrate, &capsfilter, appsink])?;
+
+        self.media = Some(Media {
+            pipeline: pipeline.clone(),
+            pipeline_manager: PipelineManager::new(
+                pipeline.clone(),
+                ctx.address().downgrade().recipient(),
+                &self.id,
+            )
+            .start(),
+        });
+
+        debug!("now capturing");
+
+        pipeline.set_state(gst::State::Playing)?;
+
+        Ok(StateChangeResult::Success)
+    }
+
+    /// Start forwarding our captured frames
+    #[instrument(level = "debug", name = "unblocking", skip(self), fields(id = %self.id))]
+    fn unblock(&mut self) -> Result<StateChangeResult, Error> {
+        // Otherwise the producer decides by itself when to forward
+        if self.forward_mode == ForwardMode::Manual {
+            self.video_producer.forward();
+        }
+        self.audio_producer.forward();
+
+        debug!("unblocked, now playing");
+
+        Ok(StateChangeResult::Success)
+    }
+
+    #[instrument(level = "debug", skip(self), fields(id = %self.id))]
+    fn reinitialize(&mut self) -> Result<StateChangeResult, Error> {
+        if let Some(media) = self.media.take() {
+            debug!("tearing down previous pipeline");
+            let _ = media.pipeline.set_state(gst::State::Null);
+
+            let appsink: &gst::Element = self.video_producer.appsink().upcast_ref();
+            media.pipeline.remove(appsink).unwrap();
+
+            let _ = media.pipeline_manager.do_send(StopManagerMessage);
+        }
+
+        Ok(StateChangeResult::Success)
+    }
+
+    #[instrument(level = "debug", skip(self, ctx), fields(id = %self.id))]
+    fn stop(&mut self, ctx: &mut Context<Self>) {
+        self.stop_schedule(ctx);
+        ctx.stop();
+    }
+}
+
+impl Actor for ScreenCaptureSource {
+    type Context = Context<Self>;
+
+    #[instrument(level = "debug", name = "starting", skip(self, ctx), fields(id = %self.id))]
+    fn started(&mut self, ctx: &mut Self::Context) {
+        self.start_heartbeat(ctx);
+    }
+
+    #[instrument(level = "debug", name = "stopping", skip(self, _ctx), fields(id = %self.id))]
+    fn stopped(&mut self, _ctx: &mut Self::Context) {
+        if let Some(media) = self.media.take() {
+            let _ = media.pipeline.set_state(gst::State::Null);
+            let _ = media.pipeline_manager.do_send(StopManagerMessage);
+        }
+
+        NodeManager::from_registry().do_send(StoppedMessage {
+            id: self.id.clone(),
+            video_producer: Some(self.video_producer.clone()),
+            audio_producer: Some(self.audio_producer.clone()),
+        });
+    }
+}
+
+impl Schedulable<Self> for ScreenCaptureSource {
+    fn state_machine(&self) -> &StateMachine {
+        &self.state_machine
+    }
+
+    fn state_machine_mut(&mut self) -> &mut StateMachine {
+        &mut self.state_machine
+    }
+
+    fn node_id(&self) -> &str {
+        &self.id
+    }
+
+    #[instrument(level = "debug", skip(self, ctx), fields(id = %self.id))]
+    fn transition(
+        &mut self,
+        ctx: &mut Context<Self>,
+        target: State,
+    ) -> Result<StateChangeResult, Error> {
+        match target {
+            State::Initial => self.reinitialize(),
+            State::Starting => self.start_capture(ctx),
+            State::Started => self.unblock(),
+            State::Stopping => Ok(StateChangeResult::Skip),
+            State::Stopped => {
+                self.stop(ctx);
+                Ok(StateChangeResult::Success)
+            }
+        }
+    }
+}
+
+impl Handler<StartMessage> for ScreenCaptureSource {
+    type Result = MessageResult<StartMessage>;
+
+    fn handle(&mut self, msg: StartMessage, ctx: &mut Context<Self>) -> Self::Result {
+        MessageResult(self.start_schedule(ctx, msg.cue_time, msg.end_time))
+    }
+}
+
+impl Handler<GetProducerMessage> for ScreenCaptureSource {
+    type Result = MessageResult<GetProducerMessage>;
+
+    fn handle(&mut self, _msg: GetProducerMessage, _ctx: &mut Context<Self>) -> Self::Result {
+        MessageResult(Ok((
+            self.video_producer.clone(),
+            self.audio_producer.clone(),
+        )))
+    }
+}
+
+impl Handler<ErrorMessage> for ScreenCaptureSource {
+    type Result = ();
+
+    fn handle(&mut self, msg: ErrorMessage, ctx: &mut Context<Self>) -> Self::Result {
+        error!(
+            "Got error message '{}' on screen capture {}",
+            msg.0, self.id,
+        );
+
+        NodeManager::from_registry().do_send(NodeStatusMessage::Error {
+            id: self.id.clone(),
+            message: msg.0,
+        });
+
+        if let Some(media) = &self.media {
+            gst::debug_bin_to_dot_file_with_ts(
+                &media.pipeline,
+                gst::DebugGraphDetails::all(),
+                format!("error-screen-capture-{}", self.id),
+            );
+        }
+
+        self.stop(ctx);
+    }
+}
+
+impl Handler<ScheduleMessage> for ScreenCaptureSource {
+    type Result = Result<(), Error>;
+
+    fn handle(&mut self, msg: ScheduleMessage, ctx: &mut Context<Self>) -> Self::Result {
+        self.reschedule(ctx, msg.cue_time, msg.end_time)
+    }
+}
+
+impl Handler<StopMessage> for ScreenCaptureSource {
+    type Result = Result<(), Error>;
+
+    fn handle(&mut self, _msg: StopMessage, ctx: &mut Context<Self>) -> Self::Result {
+        self.stop(ctx);
+        Ok(())
+    }
+}
+
+impl Handler<GetLatencyMessage> for ScreenCaptureSource {
+    type Result = ResponseFuture<Result<PipelineLatency, Error>>;
+
+    fn handle(&mut self, _msg: GetLatencyMessage, _ctx: &mut Context<Self>) -> Self::Result {
+        query_pipeline_latency(
+            self.media
+                .as_ref()
+                .map(|media| media.pipeline_manager.clone()),
+            &self.id,
+        )
+    }
+}
+
+impl Handler<GetStateMessage> for ScreenCaptureSource {
+    type Result = MessageResult<GetStateMessage>;
+
+    fn handle(&mut self, _msg: GetStateMessage, _ctx: &mut Context<Self>) -> Self::Result {
+        MessageResult(self.state_machine.state)
+    }
+}
+
+impl Handler<GetNodeInfoMessage> for ScreenCaptureSource {
+    type Result = Result<NodeInfo, Error>;
+
+    fn handle(&mut self, _msg: GetNodeInfoMessage, _ctx: &mut Context<Self>) -> Self::Result {
+        Ok(NodeInfo::Source(SourceInfo {
+            uri: format!("screen://{}", self.display_id.unwrap_or(0)),
+            consumer_slot_ids: self.video_producer.get_consumer_ids(),
+            cue_time: self.state_machine.cue_time,
+            end_time: self.state_machine.end_time,
+            state: self.state_machine.state,
+            video_forwarding: self.video_producer.is_forwarding(),
+            audio_forwarding: self.audio_producer.is_forwarding(),
+        }))
+    }
+}
+
+impl Handler<AddControlPointMessage> for ScreenCaptureSource {
+    type Result = Result<(), Error>;
+
+    fn handle(&mut self, _msg: AddControlPointMessage, _ctx: &mut Context<Self>) -> Self::Result {
+        Err(anyhow!("Screen capture has no property to control"))
+    }
+}
+
+impl Handler<RemoveControlPointMessage> for ScreenCaptureSource {
+    type Result = ();
+
+    fn handle(
+        &mut self,
+        _msg: RemoveControlPointMessage,
+        _ctx: &mut Context<Self>,
+    ) -> Self::Result {
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::tests::*;
+    use auteur_controlling::controller::{Command, ConnectMedia};
+    use test_env_log::test;
+
+    #[test]
+    fn test_screen_capture_config() {
+        gst::init().unwrap();
+
+        let source = ScreenCaptureSource::new(
+            "test-capture",
+            Some(
+                vec![
+                    ("display-id".to_string(), 1.into()),
+                    ("framerate".to_string(), "15/1".into()),
+                    ("forward-mode".to_string(), "auto".into()),
+                    (
+                        "region".to_string(),
+                        serde_json::json!({"x": 10, "y": 20, "width": 640, "height": 360}),
+                    ),
+                ]
+                .into_iter()
+                .collect(),
+            ),
+        )
+        .unwrap();
+
+        assert_eq!(source.display_id, Some(1));
+        assert_eq!(source.framerate, gst::Fraction::new(15, 1));
+        assert_eq!(source.forward_mode, ForwardMode::Auto);
+        assert_eq!(
+            source.region,
+            Some(CaptureRegion {
+                x: 10,
+                y: 20,
+                width: 640,
+                height: 360
+            })
+        );
+
+        let source = ScreenCaptureSource::new("test-capture-2", None);
+
+        if std::env::var_os("WAYLAND_DISPLAY").is_some() && cfg!(target_os = "linux") {
+            // There is no default PipeWire node
+            assert!(source.is_err());
+        } else {
+            let source = source.unwrap();
+
+            assert_eq!(source.display_id, None);
+            assert_eq!(source.framerate, gst::Fraction::new(30, 1));
+            assert_eq!(source.forward_mode, ForwardMode::Manual);
+            assert!(source.region.is_none());
+        }
+
+        let source = ScreenCaptureSource::new(
+            "test-capture-keyframe",
+            Some(
+                vec![
+                    ("display-id".to_string(), 0.into()),
+                    ("forward-mode".to_string(), "keyframe".into()),
+                ]
+                .into_iter()
+                .collect(),
+            ),
+        )
+        .unwrap();
+
+        assert_eq!(source.forward_mode, ForwardMode::Keyframe);
+
+        // Empty regions and unknown settings are refused
+        assert!(ScreenCaptureSource::new(
+            "test-capture-3",
+            Some(
+                vec![(
+                    "region".to_string(),
+                    serde_json::json!({"x": 0, "y": 0, "width": 0, "height": 360}),
+                )]
+                .into_iter()
+                .collect(),
+            ),
+        )
+        .is_err());
+
+        assert!(ScreenCaptureSource::new(
+            "test-capture-4",
+            Some(
+                vec![("uri".to_string(), "screen://0".into())]
+                    .into_iter()
+                    .collect(),
+            ),
+        )
+        .is_err());
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_pipewire_node_id() {
+        assert_eq!(ScreenCaptureSource::pipewire_node_id(Some(42)).unwrap(), 42);
+        assert!(ScreenCaptureSource::pipewire_node_id(None).is_err());
+    }
+
+    #[test]
+    fn test_cropper() {
+        gst::init().unwrap();
+
+        let pipeline = gst::Pipeline::new(None);
+        let src = make_element("videotestsrc", None).unwrap();
+        src.set_property("num-buffers", &1i32).unwrap();
+        let capsfilter = make_element("capsfilter", None).unwrap();
+        capsfilter
+            .set_property(
+                "caps",
+                &gst::Caps::builder("video/x-raw")
+                    .field("width", &320i32)
+                    .field("height", &240i32)
+                    .build(),
+            )
+            .unwrap();
+        let crop = ScreenCaptureSource::make_cropper(CaptureRegion {
+            x: 10,
+            y: 20,
+            width: 100,
+            height: 50,
+        })
+        .unwrap();
+        let sink = make_element("fakesink", None).unwrap();
+
+        pipeline
+            .add_many(&[&src, &capsfilter, &crop, &sink])
+            .unwrap();
+        gst::Element::link_many(&[&src, &capsfilter, &crop, &sink]).unwrap();
+
+        pipeline.set_state(gst::State::Playing).unwrap();
+        let msg = pipeline
+            .bus()
+            .unwrap()
+            .timed_pop_filtered(
+                5 * gst::SECOND,
+                &[gst::MessageType::Eos, gst::MessageType::Error],
+            )
+            .unwrap();
+        assert_eq!(msg.type_(), gst::MessageType::Eos);
+
+        let property = |name: &str| crop.property(name).unwrap().get::<i32>().unwrap();
+
+        // Cropped down to the region, out of the 320x240 display
+        assert_eq!(property("left"), 10);
+        assert_eq!(property("top"), 20);
+        assert_eq!(property("right"), 210);
+        assert_eq!(property("bottom"), 170);
+
+        let caps = sink.static_pad("sink").unwrap().current_caps().unwrap();
+        let s = caps.structure(0).unwrap();
+        assert_eq!(s.get::<i32>("width").unwrap(), 100);
+        assert_eq!(s.get::<i32>("height").unwrap(), 50);
+
+        pipeline.set_state(gst::State::Null).unwrap();
+    }
+
+    #[actix_rt::test]
+    #[test]
+    async fn test_connect_video_only() {
+        gst::init().unwrap();
+
+        let mut config = HashMap::new();
+        config.insert("display-id".to_string(), serde_json::json!(0));
+        run_command(Command::CreateScreenCapture {
+            id: "test-capture".to_string(),
+            config: Some(config),
+        })
+        .await
+        .unwrap();
+        create_mixer("test-mixer", None).await.unwrap();
+
+        // Only the video of screen captures is connected
+        connect("link-0", "test-capture", "test-mixer")
+            .await
+            .unwrap();
+
+        let info = match node_info_unchecked("test-mixer").await {
+            NodeInfo::Mixer(info) => info,
+            _ => unreachable!(),
+        };
+
+        let slot_settings = &info.slot_settings["link-0"];
+        assert!(slot_settings.contains_key("video::zorder"));
+        assert!(!slot_settings.contains_key("audio::volume"));
+
+        let err = connect_media("link-1", "test-capture", "test-mixer", ConnectMedia::Audio)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("has no audio"));
+    }
+}
@@ -0,0 +1,165 @@
+//! Easing curves for interpolated control points
+
+use anyhow::{anyhow, Error};
+use auteur_controlling::controller::{ControlMode, ControlPoint};
+use chrono::{DateTime, Utc};
+
+/// Number of bisection steps when solving a curve for a time fraction,
+/// more than enough for nanosecond precision over hours
+const BISECTION_STEPS: u32 = 48;
+
+/// Evaluate one dimension of a cubic Bézier curve with end points
+/// 0 and 1 at parameter `s`, using de Casteljau's algorithm
+fn de_casteljau(p1: f64, p2: f64, s: f64) -> f64 {
+    let lerp = |a: f64, b: f64| a + (b - a) * s;
+
+    let (a, b, c) = (lerp(0., p1), lerp(p1, p2), lerp(p2, 1.));
+    let (d, e) = (lerp(a, b), lerp(b, c));
+
+    lerp(d, e)
+}
+
+/// Map a time fraction to a value fraction along a cubic Bézier
+/// easing curve, as with CSS' `cubic-bezier()`
+///
+/// The time coordinates of the control points must be in [0, 1], so
+/// that the curve is a function of time.
+pub fn bezier_cubic(cp1: (f64, f64), cp2: (f64, f64), t: f64) -> f64 {
+    if t <= 0. {
+        return 0.;
+    } else if t >= 1. {
+        return 1.;
+    }
+
+    let (mut low, mut high) = (0., 1.);
+
+    for _ in 0..BISECTION_STEPS {
+        let mid = (low + high) / 2.;
+
+        if de_casteljau(cp1.0, cp2.0, mid) < t {
+            low = mid;
+        } else {
+            high = mid;
+        }
+    }
+
+    de_casteljau(cp1.1, cp2.1, (low + high) / 2.)
+}
+
+/// Validate the control points of an easing curve, if any
+pub fn validate_mode(mode: &ControlMode) -> Result<(), Error> {
+    if let ControlMode::BezierCubic {
+        cp1_time_frac,
+        cp1_value_frac,
+        cp2_time_frac,
+        cp2_value_frac,
+    } = *mode
+    {
+        for frac in &[cp1_time_frac, cp1_value_frac, cp2_time_frac, cp2_value_frac] {
+            if !(0. ..=1.).contains(frac) {
+                return Err(anyhow!(
+                    "Bezier control point coordinates must be in [0, 1], got {}",
+                    frac
+                ));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Compute the duration to step an interpolation by, so that values
+/// follow the easing curve of `point` rather than a straight line
+///
+/// Interpolation is incremental: each step covers `duration` out of
+/// the period remaining until `point` is reached. Since `start`, when
+/// interpolation towards `point` began, the value followed the curve,
+/// the remainder of the curve can be expressed as such a step.
+pub fn eased_duration(
+    start: DateTime<Utc>,
+    now: DateTime<Utc>,
+    duration: u64,
+    point: &ControlPoint,
+) -> u64 {
+    let (cp1, cp2) = match point.mode {
+        ControlMode::BezierCubic {
+            cp1_time_frac,
+            cp1_value_frac,
+            cp2_time_frac,
+            cp2_value_frac,
+        } => (
+            (cp1_time_frac, cp1_value_frac),
+            (cp2_time_frac, cp2_value_frac),
+        ),
+        _ => return duration,
+    };
+
+    if point.time < now {
+        return duration;
+    }
+
+    let period = (point.time - now).num_nanoseconds().unwrap() as u64 + duration;
+    let total = (point.time - start).num_nanoseconds().unwrap_or(0) as f64;
+
+    if total <= 0. {
+        return period;
+    }
+
+    let elapsed = (now - start).num_nanoseconds().unwrap_or(0) as f64;
+    let t0 = ((elapsed - duration as f64) / total).max(0.);
+    let t1 = elapsed / total;
+
+    let e0 = bezier_cubic(cp1, cp2, t0);
+    let e1 = bezier_cubic(cp1, cp2, t1);
+
+    if e0 >= 1. {
+        return period;
+    }
+
+    ((e1 - e0) / (1. - e0) * period as f64).round() as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bezier_cubic_linear() {
+        for i in 0..=10 {
+            let t = i as f64 / 10.;
+            let value = bezier_cubic((1. / 3., 1. / 3.), (2. / 3., 2. / 3.), t);
+
+            assert!((value - t).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_bezier_cubic_ease_in_out() {
+        let ease = |t| bezier_cubic((0.42, 0.), (0.58, 1.), t);
+
+        assert_eq!(ease(0.), 0.);
+        assert_eq!(ease(1.), 1.);
+        assert!((ease(0.5) - 0.5).abs() < 1e-9);
+        assert!(ease(0.1) < 0.1);
+        assert!(ease(0.9) > 0.9);
+    }
+
+    #[test]
+    fn test_validate_mode() {
+        assert!(validate_mode(&ControlMode::Interpolate).is_ok());
+        assert!(validate_mode(&ControlMode::BezierCubic {
+            cp1_time_frac: 0.25,
+            cp1_value_frac: 0.1,
+            cp2_time_frac: 0.25,
+            cp2_value_frac: 1.,
+        })
+        .is_ok());
+        assert!(validate_mode(&ControlMode::BezierCubic {
+            cp1_time_frac: 1.5,
+            cp1_value_frac: 0.1,
+            cp2_time_frac: 0.25,
+            cp2_value_frac: 1.,
+        })
+        .is_err());
+    }
+}
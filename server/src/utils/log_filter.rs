@@ -0,0 +1,148 @@
+//! Runtime control over the traces we emit
+
+use anyhow::{anyhow, Error};
+use std::collections::BTreeMap;
+use tracing_subscriber::filter::{EnvFilter, LevelFilter};
+use tracing_subscriber::{reload, Registry};
+
+/// Our global filter, which can be overridden per node at runtime.
+///
+/// Per-node levels apply to the events emitted within the spans that
+/// record the node `id`, as our nodes' instrumented methods do. Other
+/// events remain filtered according to the initial directives.
+pub struct LogFilter {
+    /// The directives the filter was initially built from
+    base_directives: String,
+    /// Per-node levels, node id -> level
+    node_levels: BTreeMap<String, String>,
+    /// For updating the filter of our subscriber
+    handle: reload::Handle<EnvFilter, Registry>,
+}
+
+impl LogFilter {
+    /// Build the filter layer of our subscriber from `directives`, and a
+    /// handle for overriding it per node
+    pub fn new(directives: &str) -> Result<(Self, reload::Layer<EnvFilter, Registry>), Error> {
+        let filter = EnvFilter::try_new(directives)
+            .map_err(|err| anyhow!("Invalid log directives {}: {}", directives, err))?;
+        let (layer, handle) = reload::Layer::new(filter);
+
+        Ok((
+            Self {
+                base_directives: directives.to_string(),
+                node_levels: BTreeMap::new(),
+                handle,
+            },
+            layer,
+        ))
+    }
+
+    /// The directives of our filter, the initial ones followed by one
+    /// span field directive per node
+    fn directives(&self) -> String {
+        let mut directives = self.base_directives.clone();
+
+        for (id, level) in &self.node_levels {
+            directives += &format!(",[{{id={}}}]={}", id, level);
+        }
+
+        directives
+    }
+
+    /// Set the level of the traces of the node with id `id`, or reset it
+    /// to the global level when `level` is None
+    ///
+    /// The id is pasted in a directive, where it is matched as a regular
+    /// expression: only ids made of ASCII alphanumerics, `-` and `_` are
+    /// accepted.
+    pub fn set_node_level(&mut self, id: &str, level: Option<&str>) -> Result<(), Error> {
+        if id.is_empty()
+            || !id
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        {
+            return Err(anyhow!(
+                "Log levels can only be set for ids made of ASCII alphanumerics, - and _, not {}",
+                id
+            ));
+        }
+
+        if level.is_none() && !self.node_levels.contains_key(id) {
+            return Ok(());
+        }
+
+        let previous = match level {
+            Some(level) => {
+                let level = level
+                    .parse::<LevelFilter>()
+                    .map_err(|err| anyhow!("Invalid log level {}: {}", level, err))?;
+                self.node_levels
+                    .insert(id.to_string(), level.to_string().to_lowercase())
+            }
+            None => self.node_levels.remove(id),
+        };
+
+        let handle = &self.handle;
+        let res = EnvFilter::try_new(self.directives())
+            .map_err(|err| anyhow!("Invalid log directives: {}", err))
+            .and_then(|filter| {
+                handle
+                    .reload(filter)
+                    .map_err(|err| anyhow!("Failed to update log filter: {}", err))
+            });
+
+        // Keep our levels in sync with the filter actually in use
+        if res.is_err() {
+            match previous {
+                Some(previous) => self.node_levels.insert(id.to_string(), previous),
+                None => self.node_levels.remove(id),
+            };
+        }
+
+        res
+    }
+}
+
+impl std::fmt::Debug for LogFilter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LogFilter")
+            .field("directives", &self.directives())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_node_levels() {
+        let (mut filter, _layer) = LogFilter::new("warn").unwrap();
+
+        filter.set_node_level("mixer-1", Some("trace")).unwrap();
+        filter.set_node_level("source-1", Some("debug")).unwrap();
+        assert!(filter.set_node_level("source-2", Some("loud")).is_err());
+
+        assert_eq!(
+            filter.directives(),
+            "warn,[{id=mixer-1}]=trace,[{id=source-1}]=debug"
+        );
+
+        filter.set_node_level("mixer-1", None).unwrap();
+
+        assert_eq!(filter.directives(), "warn,[{id=source-1}]=debug");
+
+        filter.set_node_level("source-1", Some("INFO")).unwrap();
+
+        assert_eq!(filter.directives(), "warn,[{id=source-1}]=info");
+
+        for id in &["", "mixer,1", "mixer]1", "mixer.*"] {
+            assert!(filter.set_node_level(id, Some("debug")).is_err());
+        }
+
+        // Resetting a node without a level is a no-op
+        filter.set_node_level("source-2", None).unwrap();
+
+        assert_eq!(filter.directives(), "warn,[{id=source-1}]=info");
+    }
+}
@@ -0,0 +1,107 @@
+//! Encoding of metrics in the Prometheus text exposition format
+
+use std::fmt::Write;
+
+/// The type of a metric family
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MetricType {
+    /// A monotonically increasing value
+    Counter,
+    /// A value that can go up and down
+    Gauge,
+}
+
+impl MetricType {
+    fn as_str(&self) -> &'static str {
+        match self {
+            MetricType::Counter => "counter",
+            MetricType::Gauge => "gauge",
+        }
+    }
+}
+
+/// Accumulates metric families and their samples, and encodes
+/// them in the Prometheus text format.
+///
+/// All the samples of a given family must be added right after
+/// the family was declared.
+#[derive(Debug, Default)]
+pub struct MetricsEncoder {
+    /// The encoded output
+    output: String,
+}
+
+impl MetricsEncoder {
+    /// Create an empty encoder
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Declare a new metric family
+    pub fn family(&mut self, name: &str, type_: MetricType, help: &str) {
+        let _ = writeln!(self.output, "# HELP {} {}", name, escape_help(help));
+        let _ = writeln!(self.output, "# TYPE {} {}", name, type_.as_str());
+    }
+
+    /// Add a sample to the last declared family
+    pub fn sample(&mut self, name: &str, labels: &[(&str, &str)], value: f64) {
+        self.output.push_str(name);
+
+        if !labels.is_empty() {
+            let labels = labels
+                .iter()
+                .map(|(name, value)| format!("{}=\"{}\"", name, escape_label_value(value)))
+                .collect::<Vec<String>>()
+                .join(",");
+
+            let _ = write!(self.output, "{{{}}}", labels);
+        }
+
+        let _ = writeln!(self.output, " {}", value);
+    }
+
+    /// Retrieve the encoded metrics
+    pub fn finish(self) -> String {
+        self.output
+    }
+}
+
+/// Escape backslashes and line feeds in a help text
+fn escape_help(help: &str) -> String {
+    help.replace('\\', "\\\\").replace('\n', "\\n")
+}
+
+/// Escape backslashes, double quotes and line feeds in a label value
+fn escape_label_value(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    fn test_metrics_encoder() {
+        let mut encoder = MetricsEncoder::new();
+
+        encoder.family("auteur_test_total", MetricType::Counter, "A test counter");
+        encoder.sample("auteur_test_total", &[("node", "source")], 42.0);
+        encoder.sample("auteur_test_total", &[("node", "quoted \"node\"\n")], 0.5);
+        encoder.family("auteur_test", MetricType::Gauge, "A test gauge");
+        encoder.sample("auteur_test", &[], 1.0);
+
+        assert_eq!(
+            encoder.finish(),
+            "# HELP auteur_test_total A test counter\n\
+             # TYPE auteur_test_total counter\n\
+             auteur_test_total{node=\"source\"} 42\n\
+             auteur_test_total{node=\"quoted \\\"node\\\"\\n\"} 0.5\n\
+             # HELP auteur_test A test gauge\n\
+             # TYPE auteur_test gauge\n\
+             auteur_test 1\n"
+        );
+    }
+}
@@ -4,7 +4,13 @@ use actix::prelude::*;
 use anyhow::{anyhow, Error};
 
 use chrono::{DateTime, Utc};
+#[cfg(test)]
+use std::cell::Cell;
+use std::sync::Mutex;
 
+pub mod easing;
+pub mod log_filter;
+pub mod metrics;
 pub mod pipeline_manager;
 pub mod property_controller;
 pub mod schedulable;
@@ -13,11 +19,16 @@ pub mod stream_producer;
 #[cfg(test)]
 pub mod tests;
 
-pub use pipeline_manager::{PipelineManager, StopManagerMessage, WaitForEosMessage};
+pub use log_filter::LogFilter;
+pub use metrics::{MetricType, MetricsEncoder};
+pub use pipeline_manager::{
+    query_pipeline_latency, AudioLevelInfo, FragmentClosedMessage, PipelineLatency,
+    PipelineManager, StopManagerMessage, WaitForEosMessage,
+};
 pub use property_controller::PropertyController;
 pub use schedulable::{Schedulable, StateChangeResult, StateMachine};
-pub use setting_controller::{Setting, SettingController, SettingSpec};
-pub use stream_producer::StreamProducer;
+pub use setting_controller::{parse_fraction, Setting, SettingController, SettingSpec};
+pub use stream_producer::{ProducerNotification, StreamProducer, StreamProducerStats};
 
 /// Wrapper around `gst::ElementFactory::make` with a better error
 /// message
@@ -34,57 +45,109 @@ impl Message for ErrorMessage {
     type Result = ();
 }
 
-#[cfg(not(test))]
-/// In normal operation, now is the actual system time
-pub fn get_now() -> DateTime<Utc> {
-    Utc::now()
+/// The offset of the PTP timescale (TAI) to UTC, in seconds, as of the
+/// last leap second in 2017
+const TAI_UTC_OFFSET_SECS: i64 = 37;
+
+/// The current time according to a pipeline clock, when it was overridden
+/// with a network or PTP clock, so that control points are synchronized
+/// across machines following that clock. [`get_now`] otherwise.
+///
+/// Network clocks are expected to follow a realtime clock, counting
+/// from the Unix epoch.
+pub fn get_clock_now(clock: Option<&gst::Clock>) -> DateTime<Utc> {
+    use chrono::TimeZone;
+    use gst::prelude::*;
+
+    let nseconds = match clock.and_then(|clock| clock.time().nseconds()) {
+        Some(nseconds) => nseconds as i64,
+        None => return get_now(),
+    };
+
+    let now = Utc.timestamp_nanos(nseconds);
+
+    if clock.unwrap().is::<gst_net::PtpClock>() {
+        now - chrono::Duration::seconds(TAI_UTC_OFFSET_SECS)
+    } else {
+        now
+    }
 }
 
-#[cfg(test)]
-/// When testing, we sample a base system time, then base all future
-/// observations of the system time on the actix time, which progresses
-/// immediately on tokio sleep (eg actix run_later)
+/// The system time [`get_now`] counts from, and the monotonic instant
+/// it was sampled at
+static NOW_BASE: Mutex<Option<(DateTime<Utc>, actix::clock::Instant)>> = Mutex::new(None);
+
+/// The current time, against which control points are synchronized
+///
+/// The system time is only sampled once, later observations add the
+/// time elapsed on a monotonic clock to it, so that control points aren't
+/// thrown off when the system time gets stepped (eg by NTP), and progress
+/// consistently with the monotonic system clock our pipelines run on.
 ///
-/// FIXME: does unsafe matter in tests? If so, how can we do this safely?
+/// That clock is the actix one: when testing with a paused clock, time
+/// progresses immediately on tokio sleep (eg actix run_later). Tests can
+/// also pin the time with [`FakeNow`].
 pub fn get_now() -> DateTime<Utc> {
-    static mut UTC_BASE: Option<DateTime<Utc>> = None;
-    static mut ACTIX_BASE: Option<actix::clock::Instant> = None;
-
-    let ubase = unsafe {
-        match UTC_BASE {
-            Some(ubase) => ubase,
-            None => {
-                let ubase = Utc::now();
-                UTC_BASE = Some(ubase);
-                ubase
-            }
-        }
-    };
+    if let Some(now) = fake_now() {
+        return now;
+    }
 
-    let abase = unsafe {
-        match ACTIX_BASE {
-            Some(abase) => abase,
-            None => {
-                let abase = actix::clock::Instant::now();
-                ACTIX_BASE = Some(abase);
-                abase
-            }
-        }
-    };
+    let (ubase, abase) = *NOW_BASE
+        .lock()
+        .unwrap()
+        .get_or_insert_with(|| (Utc::now(), actix::clock::Instant::now()));
 
     // This is really quite unfortunate, but Instant::now()
     // is actually *not* monotonic on certain systems.
     //
     // https://github.com/rust-lang/rust/issues/56612 though
     // closed seems related.
-    let anow = actix::clock::Instant::now();
-
-    let delta = if anow > abase {
-        anow - abase
-    } else {
-        // FIXME: use std::time::Duration::ZERO when stable
-        std::time::Duration::new(0, 0)
-    };
+    let delta = actix::clock::Instant::now().saturating_duration_since(abase);
 
     ubase + chrono::Duration::from_std(delta).unwrap()
 }
+
+#[cfg(test)]
+thread_local! {
+    /// The time pinned with [`FakeNow`] on this thread
+    static FAKE_NOW: Cell<Option<DateTime<Utc>>> = Cell::new(None);
+}
+
+#[cfg(not(test))]
+fn fake_now() -> Option<DateTime<Utc>> {
+    None
+}
+
+#[cfg(test)]
+fn fake_now() -> Option<DateTime<Utc>> {
+    FAKE_NOW.with(|now| now.get())
+}
+
+/// Pins the time returned by [`get_now`] on the current thread until
+/// dropped, so that tests can drive control points at precise times
+/// without sleeping
+#[cfg(test)]
+#[derive(Debug)]
+pub struct FakeNow;
+
+#[cfg(test)]
+impl FakeNow {
+    /// Pin the time to `now`
+    pub fn new(now: DateTime<Utc>) -> Self {
+        FAKE_NOW.with(|fake_now| fake_now.set(Some(now)));
+
+        Self
+    }
+
+    /// Move the pinned time forward by `delta`
+    pub fn advance(&self, delta: chrono::Duration) {
+        FAKE_NOW.with(|fake_now| fake_now.set(fake_now.get().map(|now| now + delta)));
+    }
+}
+
+#[cfg(test)]
+impl Drop for FakeNow {
+    fn drop(&mut self) {
+        FAKE_NOW.with(|fake_now| fake_now.set(None));
+    }
+}
@@ -1,12 +1,15 @@
 //! Helper gst::Pipeline wrapper
 
+use crate::node::{NodeManager, NodeStatusMessage};
 use crate::utils::ErrorMessage;
 use actix::prelude::*;
 use actix::WeakRecipient;
+use anyhow::{anyhow, Error};
 use futures::channel::oneshot;
 use futures::prelude::*;
 use gst::prelude::*;
-use tracing::{debug, instrument, trace};
+use tokio::sync::watch;
+use tracing::{debug, info, instrument, trace, warn};
 
 /// Maps GStreamer messages for consumption by a [`PipelineManager`]
 /// actor
@@ -17,6 +20,58 @@ impl Message for BusMessage {
     type Result = ();
 }
 
+/// The measurements last posted by a `level` element, one value
+/// per channel
+#[derive(Debug, Clone)]
+pub struct AudioLevelInfo {
+    /// The RMS level in dB
+    pub rms_db: Vec<f64>,
+    /// The peak level in dB
+    pub peak_db: Vec<f64>,
+    /// The decaying peak level in dB
+    pub decay_db: Vec<f64>,
+    /// The timestamp of the measured buffer
+    pub timestamp: gst::ClockTime,
+}
+
+impl AudioLevelInfo {
+    /// Parse a `level` element message
+    fn from_structure(s: &gst::StructureRef) -> Option<Self> {
+        let values = |field: &str| -> Option<Vec<f64>> {
+            let array = s.get::<gst::glib::ValueArray>(field).ok()?;
+
+            Some(
+                array
+                    .iter()
+                    .filter_map(|value| value.get::<f64>().ok())
+                    .collect(),
+            )
+        };
+
+        Some(Self {
+            rms_db: values("rms")?,
+            peak_db: values("peak")?,
+            decay_db: values("decay")?,
+            timestamp: gst::ClockTime::from_nseconds(s.get::<u64>("timestamp").ok()?),
+        })
+    }
+}
+
+/// Sent from [`PipelineManager`] to nodes when a `splitmuxsink`, or
+/// a sink wrapping one such as `dashsink`, has closed a fragment
+#[derive(Debug)]
+pub struct FragmentClosedMessage {
+    /// The path of the fragment
+    pub location: String,
+    /// Whether the fragment holds video, `dashsink` closes a fragment
+    /// per stream
+    pub is_video: bool,
+}
+
+impl Message for FragmentClosedMessage {
+    type Result = ();
+}
+
 /// Sent from nodes to [`PipelineManager`] to wait for EOS to have
 /// been processed by the pipeline
 #[derive(Debug)]
@@ -40,6 +95,81 @@ impl Handler<WaitForEosMessage> for PipelineManager {
     }
 }
 
+/// The latency of a pipeline, as reported by a latency query
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PipelineLatency {
+    /// Whether the pipeline is live
+    pub live: bool,
+    /// The minimum latency of the pipeline
+    pub min_latency: gst::ClockTime,
+    /// The maximum latency of the pipeline, None if unlimited
+    pub max_latency: Option<gst::ClockTime>,
+}
+
+/// Sent from nodes to [`PipelineManager`] to query the latency of
+/// the pipeline
+#[derive(Debug)]
+pub struct GetPipelineLatencyMessage;
+
+impl Message for GetPipelineLatencyMessage {
+    type Result = Result<PipelineLatency, Error>;
+}
+
+impl Handler<GetPipelineLatencyMessage> for PipelineManager {
+    type Result = Result<PipelineLatency, Error>;
+
+    fn handle(
+        &mut self,
+        _msg: GetPipelineLatencyMessage,
+        _ctx: &mut Context<Self>,
+    ) -> Self::Result {
+        PipelineManager::query_latency(&self.pipeline)
+            .ok_or_else(|| anyhow!("Latency query failed for pipeline {}", self.id))
+    }
+}
+
+/// Query the latency of the pipeline of node `node_id` through its
+/// manager, if it has a pipeline yet, as all nodes do in reply to
+/// [`GetLatencyMessage`](crate::node::GetLatencyMessage)
+pub fn query_pipeline_latency(
+    pipeline_manager: Option<Addr<PipelineManager>>,
+    node_id: &str,
+) -> ResponseFuture<Result<PipelineLatency, Error>> {
+    let node_id = node_id.to_string();
+
+    Box::pin(async move {
+        match pipeline_manager {
+            Some(pipeline_manager) => pipeline_manager
+                .send(GetPipelineLatencyMessage)
+                .await
+                .map_err(|err| anyhow!("Internal server error {}", err))?,
+            None => Err(anyhow!("{} has no pipeline yet", node_id)),
+        }
+    })
+}
+
+/// Sent from the streaming thread recalculating the latency of the
+/// pipeline to [`PipelineManager`]
+#[derive(Debug)]
+struct LatencyUpdatedMessage(Option<PipelineLatency>);
+
+impl Message for LatencyUpdatedMessage {
+    type Result = ();
+}
+
+impl Handler<LatencyUpdatedMessage> for PipelineManager {
+    type Result = ();
+
+    fn handle(&mut self, msg: LatencyUpdatedMessage, _ctx: &mut Context<Self>) {
+        if let Some(latency) = msg.0 {
+            if self.latency != Some(latency) {
+                debug!(id = %self.id, live = %latency.live, min = %latency.min_latency, max = ?latency.max_latency, "Pipeline latency changed");
+                self.latency = Some(latency);
+            }
+        }
+    }
+}
+
 /// Sent from nodes to [`PipelineManager`] to tear it down
 #[derive(Debug)]
 pub struct StopManagerMessage;
@@ -71,6 +201,18 @@ pub struct PipelineManager {
     eos_sender: Option<oneshot::Sender<()>>,
     /// To wait for EOS to be processed
     eos_receiver: Option<oneshot::Receiver<()>>,
+    /// The last warning forwarded to [`NodeManager`], identical consecutive
+    /// warnings are only forwarded once
+    last_warning: Option<String>,
+    /// The last info message forwarded to [`NodeManager`], deduplicated
+    /// separately from warnings
+    last_info: Option<String>,
+    /// The name of a `level` element, and where to publish its measurements
+    audio_level: Option<(String, watch::Sender<Option<AudioLevelInfo>>)>,
+    /// Notified of the fragments closed by our sinks
+    fragment_recipient: Option<WeakRecipient<FragmentClosedMessage>>,
+    /// The last latency reported for the pipeline
+    latency: Option<PipelineLatency>,
 }
 
 impl Actor for PipelineManager {
@@ -91,7 +233,7 @@ impl Actor for PipelineManager {
 
 impl StreamHandler<BusMessage> for PipelineManager {
     #[instrument(name = "Handling GStreamer bus message", level = "trace", skip(self, msg, _ctx), fields(id = %self.id, source = msg.0.src().as_ref().map(|src| src.path_string()).as_deref().unwrap_or("UNKNOWN")))]
-    fn handle(&mut self, msg: BusMessage, _ctx: &mut Context<Self>) {
+    fn handle(&mut self, msg: BusMessage, ctx: &mut Context<Self>) {
         use gst::MessageView;
 
         match msg.0.view() {
@@ -99,6 +241,29 @@ impl StreamHandler<BusMessage> for PipelineManager {
                 if let Some(s) = m.structure() {
                     if s.name() == "level" {
                         trace!("audio output level: {}", s);
+
+                        if let Some((level_name, audio_level)) = &self.audio_level {
+                            if m.src().map(|src| src.name().as_str() == level_name) == Some(true) {
+                                if let Some(info) = AudioLevelInfo::from_structure(s) {
+                                    let _ = audio_level.send(Some(info));
+                                }
+                            }
+                        }
+                    } else if s.name() == "splitmuxsink-fragment-closed" {
+                        if let (Some(recipient), Ok(location)) = (
+                            self.fragment_recipient
+                                .as_ref()
+                                .and_then(|recipient| recipient.upgrade()),
+                            s.get::<String>("location"),
+                        ) {
+                            // splitmuxsink names its video pad "video"
+                            let is_video = m
+                                .src()
+                                .and_then(|src| src.downcast::<gst::Element>().ok())
+                                .map_or(false, |src| src.static_pad("video").is_some());
+
+                            let _ = recipient.do_send(FragmentClosedMessage { location, is_video });
+                        }
                     }
                 }
             }
@@ -108,8 +273,12 @@ impl StreamHandler<BusMessage> for PipelineManager {
                         || src.has_as_ancestor(&self.pipeline)
                     {
                         trace!("Pipeline for node {} updated latency", self.id);
-                        self.pipeline.call_async(|pipeline| {
+                        let addr = ctx.address();
+                        self.pipeline.call_async(move |pipeline| {
                             let _ = pipeline.recalculate_latency();
+                            addr.do_send(LatencyUpdatedMessage(PipelineManager::query_latency(
+                                pipeline,
+                            )));
                         });
                     }
                 }
@@ -158,6 +327,36 @@ impl StreamHandler<BusMessage> for PipelineManager {
                     let _ = eos_sender.send(());
                 }
             }
+            MessageView::Warning(warning) => {
+                self.forward_warning(format!(
+                    "Got warning from {}: {}{}",
+                    warning
+                        .src()
+                        .as_ref()
+                        .map(|src| src.path_string())
+                        .as_deref()
+                        .unwrap_or("UNKNOWN"),
+                    warning.error(),
+                    warning
+                        .debug()
+                        .map(|dbg| format!(" ({})", dbg))
+                        .unwrap_or_default()
+                ));
+            }
+            MessageView::Info(info) => {
+                self.forward_info(format!(
+                    "Got info from {}: {}{}",
+                    info.src()
+                        .as_ref()
+                        .map(|src| src.path_string())
+                        .as_deref()
+                        .unwrap_or("UNKNOWN"),
+                    info.error(),
+                    info.debug()
+                        .map(|dbg| format!(" ({})", dbg))
+                        .unwrap_or_default()
+                ));
+            }
             _ => (),
         }
     }
@@ -178,6 +377,91 @@ impl PipelineManager {
             id: id.to_string(),
             eos_sender: Some(eos_sender),
             eos_receiver: Some(eos_receiver),
+            last_warning: None,
+            last_info: None,
+            audio_level: None,
+            fragment_recipient: None,
+            latency: None,
+        }
+    }
+
+    /// Query the latency of a pipeline
+    fn query_latency(pipeline: &gst::Pipeline) -> Option<PipelineLatency> {
+        let mut query = gst::query::Latency::new();
+
+        if !pipeline.query(&mut query) {
+            return None;
         }
+
+        let (live, min_latency, max_latency) = query.result();
+
+        Some(PipelineLatency {
+            live,
+            min_latency,
+            max_latency: if max_latency.is_none() {
+                None
+            } else {
+                Some(max_latency)
+            },
+        })
+    }
+
+    /// Publish the measurements of the `level` element with the given name
+    /// when they get posted
+    pub fn track_audio_level(
+        mut self,
+        level_name: &str,
+        audio_level: watch::Sender<Option<AudioLevelInfo>>,
+    ) -> Self {
+        self.audio_level = Some((level_name.to_string(), audio_level));
+        self
+    }
+
+    /// Notify `recipient` of the fragments closed by our sinks
+    pub fn forward_closed_fragments(
+        mut self,
+        recipient: WeakRecipient<FragmentClosedMessage>,
+    ) -> Self {
+        self.fragment_recipient = Some(recipient);
+        self
+    }
+
+    /// Notify listeners of a warning, unless it is identical to the
+    /// previous one
+    fn forward_warning(&mut self, message: String) {
+        if self.last_warning.as_ref() == Some(&message) {
+            trace!(id = %self.id, "Not forwarding repeated warning: {}", message);
+            return;
+        }
+
+        warn!(id = %self.id, "{}", message);
+
+        self.last_warning = Some(message.clone());
+
+        NodeManager::from_registry().do_send(NodeStatusMessage::Warning {
+            id: self.id.clone(),
+            message,
+        });
+    }
+
+    /// Notify listeners of an info message, unless it is identical to
+    /// the previous one
+    ///
+    /// Listeners receive it as a warning as well, but it is only logged
+    /// as info, and doesn't affect the deduplication of warnings.
+    fn forward_info(&mut self, message: String) {
+        if self.last_info.as_ref() == Some(&message) {
+            trace!(id = %self.id, "Not forwarding repeated info: {}", message);
+            return;
+        }
+
+        info!(id = %self.id, "{}", message);
+
+        self.last_info = Some(message.clone());
+
+        NodeManager::from_registry().do_send(NodeStatusMessage::Warning {
+            id: self.id.clone(),
+            message,
+        });
     }
 }
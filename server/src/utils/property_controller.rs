@@ -3,14 +3,43 @@
 use gst::glib::types::Type;
 use gst::prelude::*;
 use priority_queue::PriorityQueue;
-use std::cmp::Reverse;
+use std::cmp::{Ordering, Reverse};
 use std::collections::HashMap;
 
 use anyhow::{anyhow, Error};
-use auteur_controlling::controller::{ControlMode, ControlPoint};
+use auteur_controlling::controller::{ControlMode, ControlPoint, ControlPointSummary};
 use chrono::{DateTime, Utc};
 use tracing::{instrument, trace};
 
+use super::easing;
+
+/// A control point in a schedule, ordered by time
+///
+/// Control points can't be totally ordered themselves, as their mode may
+/// hold floating point coordinates.
+#[derive(Debug, Clone)]
+pub(super) struct ScheduledControlPoint(pub ControlPoint);
+
+impl PartialEq for ScheduledControlPoint {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.time == other.0.time
+    }
+}
+
+impl Eq for ScheduledControlPoint {}
+
+impl Ord for ScheduledControlPoint {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.time.cmp(&other.0.time)
+    }
+}
+
+impl PartialOrd for ScheduledControlPoint {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
 /// Represents a controller for a property
 #[derive(Debug)]
 pub struct PropertyController {
@@ -21,7 +50,12 @@ pub struct PropertyController {
     /// The controlled property
     pub propname: String,
     /// The future control points
-    control_points: Option<PriorityQueue<String, Reverse<ControlPoint>>>,
+    control_points: Option<PriorityQueue<String, Reverse<ScheduledControlPoint>>>,
+    /// The control point currently interpolated towards, and when
+    /// interpolation started, for easing
+    interpolation_start: Option<(String, DateTime<Utc>)>,
+    /// The value of the last control point reached
+    last_value: Option<serde_json::Value>,
 }
 
 impl PropertyController {
@@ -32,16 +66,22 @@ impl PropertyController {
             obj,
             propname: propname.to_string(),
             control_points: Some(PriorityQueue::new()),
+            interpolation_start: None,
+            last_value: None,
         }
     }
 
     /// Schedule a control point
+    ///
+    /// A [`ControlMode::Set`] point with a null value holds the property at
+    /// its current value until the time of the point, an interpolation
+    /// scheduled after it thus starts from the value the property has then.
     #[instrument(level = "debug", name = "pushing control point", skip(self), fields(controllee_id = %self.controllee_id, propname = %self.propname))]
     pub fn push_control_point(&mut self, point: ControlPoint) {
         self.control_points
             .as_mut()
             .unwrap()
-            .push(point.id.to_string(), Reverse(point));
+            .push(point.id.to_string(), Reverse(ScheduledControlPoint(point)));
     }
 
     /// Remove a control point
@@ -50,18 +90,60 @@ impl PropertyController {
         self.control_points.as_mut().unwrap().remove(id);
     }
 
+    /// Remove all the control points scheduled at a given time
+    #[instrument(level = "debug", name = "removing control points", skip(self), fields(controllee_id = %self.controllee_id, propname = %self.propname))]
+    pub fn remove_control_points_at(&mut self, time: DateTime<Utc>) {
+        let control_points = self.control_points.as_mut().unwrap();
+
+        let ids: Vec<String> = control_points
+            .iter()
+            .filter(|(_, Reverse(ScheduledControlPoint(point)))| point.time == time)
+            .map(|(id, _)| id.clone())
+            .collect();
+
+        for id in ids {
+            control_points.remove(&id);
+        }
+    }
+
+    /// Remove all the pending control points, the property keeps its
+    /// current value, including when it was being interpolated
+    #[instrument(level = "debug", name = "clearing control points", skip(self), fields(controllee_id = %self.controllee_id, propname = %self.propname))]
+    pub fn clear_control_points(&mut self) {
+        self.control_points.as_mut().unwrap().clear();
+        self.interpolation_start = None;
+
+        let value = self.obj.property(self.propname.as_str()).unwrap();
+        self.obj
+            .set_property(self.propname.as_str(), &value)
+            .unwrap();
+    }
+
     /// Retrieves all control points
     #[instrument(level = "debug", name = "getting control points", skip(self), fields(controllee_id = %self.controllee_id, propname = %self.propname))]
     pub fn control_points(&self) -> Vec<ControlPoint> {
         let mut ret: Vec<ControlPoint> = vec![];
 
-        for (_, Reverse(point)) in self.control_points.as_ref().unwrap() {
+        for (_, Reverse(ScheduledControlPoint(point))) in self.control_points.as_ref().unwrap() {
             ret.push(point.clone());
         }
 
         ret
     }
 
+    /// Summarizes the pending control points
+    pub fn summary(&self) -> ControlPointSummary {
+        let control_points = self.control_points.as_ref().unwrap();
+
+        ControlPointSummary {
+            count: control_points.len(),
+            next_time: control_points
+                .peek()
+                .map(|(_, Reverse(ScheduledControlPoint(point)))| point.time),
+            last_value: self.last_value.clone(),
+        }
+    }
+
     /// Update the value for a controlled property, duration is the
     /// duration elapsed since the last call and will be used to
     /// perform interpolation.
@@ -71,19 +153,30 @@ impl PropertyController {
     pub fn synchronize(&mut self, now: DateTime<Utc>, duration: gst::ClockTime) -> bool {
         let mut control_points = self.control_points.take().unwrap();
 
-        if let Some((_id, Reverse(point))) = control_points.peek() {
+        if let Some((id, Reverse(ScheduledControlPoint(point)))) = control_points.peek() {
             let mut do_trace = false;
 
             let initial = self.obj.property(self.propname.as_str()).unwrap();
             if match point.mode {
-                ControlMode::Interpolate => match duration {
+                ControlMode::Interpolate | ControlMode::BezierCubic { .. } => match duration {
                     gst::CLOCK_TIME_NONE => false,
                     _ => {
                         do_trace = true;
+
+                        let duration = duration.nseconds().unwrap();
+                        let start = match self.interpolation_start {
+                            Some((ref start_id, start)) if start_id == id => start,
+                            _ => {
+                                let start = now - chrono::Duration::nanoseconds(duration as i64);
+                                self.interpolation_start = Some((id.clone(), start));
+                                start
+                            }
+                        };
+
                         PropertyController::interpolate_property(
                             &self.obj,
                             now,
-                            duration.nseconds().unwrap(),
+                            easing::eased_duration(start, now, duration, point),
                             &self.propname,
                             point,
                         )
@@ -94,7 +187,10 @@ impl PropertyController {
                 }
             } {
                 do_trace = true;
-                control_points.pop().unwrap();
+                let (_, Reverse(ScheduledControlPoint(point))) = control_points.pop().unwrap();
+                if !point.value.is_null() {
+                    self.last_value = Some(point.value);
+                }
             }
 
             if do_trace {
@@ -405,25 +501,27 @@ impl PropertyController {
             return Err(anyhow!("property {} is not writable", property));
         }
 
-        if point.mode == ControlMode::Interpolate
+        if point.mode != ControlMode::Set
             && !pspec.flags().contains(gst::glib::ParamFlags::READABLE)
         {
             return Err(anyhow!("property {} is not readable", property));
         }
 
+        easing::validate_mode(&point.mode)?;
+
         PropertyController::validate_value_against_pspec(&pspec, &point.value)?;
 
         // Additional verification for non-interpolatable types
         match pspec.value_type() {
             Type::STRING => match point.mode {
                 ControlMode::Set => Ok(()),
-                ControlMode::Interpolate => Err(anyhow!(
+                _ => Err(anyhow!(
                     "Control points for string values must use mode Set"
                 )),
             },
             Type::BOOL => match point.mode {
                 ControlMode::Set => Ok(()),
-                ControlMode::Interpolate => Err(anyhow!(
+                _ => Err(anyhow!(
                     "Control points for boolean values must use mode Set"
                 )),
             },
@@ -431,9 +529,7 @@ impl PropertyController {
                 if pspec.downcast_ref::<gst::glib::ParamSpecEnum>().is_some() {
                     match point.mode {
                         ControlMode::Set => Ok(()),
-                        ControlMode::Interpolate => {
-                            Err(anyhow!("Control points for enum values must use mode Set"))
-                        }
+                        _ => Err(anyhow!("Control points for enum values must use mode Set")),
                     }
                 } else {
                     Ok(())
@@ -645,11 +741,35 @@ impl PropertyController {
             return false;
         }
 
-        PropertyController::set_property_from_value(obj, property, &point.value);
+        // Holding the current value
+        if !point.value.is_null() {
+            PropertyController::set_property_from_value(obj, property, &point.value);
+        }
 
         true
     }
 
+    /// Get the current value of a property, if it can be interpolated
+    pub fn interpolatable_value(obj: &gst::Object, property: &str) -> Option<serde_json::Value> {
+        let pspec = obj.find_property(property)?;
+
+        if !pspec.flags().contains(gst::glib::ParamFlags::READABLE) {
+            return None;
+        }
+
+        let prop_value = obj.property(property).unwrap();
+
+        match pspec.value_type() {
+            Type::I32 => Some(prop_value.get::<i32>().unwrap().into()),
+            Type::U32 => Some(prop_value.get::<u32>().unwrap().into()),
+            Type::I_LONG | Type::I64 => Some(prop_value.get::<i64>().unwrap().into()),
+            Type::U_LONG | Type::U64 => Some(prop_value.get::<u64>().unwrap().into()),
+            Type::F32 => Some(prop_value.get::<f32>().unwrap().into()),
+            Type::F64 => Some(prop_value.get::<f64>().unwrap().into()),
+            _ => None,
+        }
+    }
+
     /// Lists all the current values for controllable properties of an object
     pub fn properties(obj: &gst::Object, prefix: &str) -> HashMap<String, serde_json::Value> {
         let mut ret = HashMap::new();
@@ -670,7 +790,7 @@ impl PropertyController {
             let prop_value = obj.property(pspec.name()).unwrap();
 
             let value = match pspec.value_type() {
-                Type::STRING => prop_value.get::<String>().unwrap().into(),
+                Type::STRING => prop_value.get::<Option<String>>().unwrap().into(),
                 Type::BOOL => prop_value.get::<bool>().unwrap().into(),
                 Type::I32 => prop_value.get::<i32>().unwrap().into(),
                 Type::U32 => prop_value.get::<u32>().unwrap().into(),
@@ -697,7 +817,7 @@ impl PropertyController {
 #[cfg(test)]
 pub mod tests {
     use super::*;
-    use crate::utils::{get_now, make_element};
+    use crate::utils::{get_now, make_element, FakeNow};
     use auteur_controlling::controller::{ControlMode, ControlPoint};
 
     #[test]
@@ -793,6 +913,41 @@ pub mod tests {
             .unwrap();
     }
 
+    #[test]
+    fn test_property_controller_summary() {
+        gst::init().unwrap();
+
+        let queue = make_element("queue", None).unwrap();
+        let now = get_now();
+
+        let mut controller =
+            PropertyController::new("slot-0", queue.clone().upcast(), "max-size-bytes");
+
+        for (id, offset, value) in &[("first", 1, 10u64), ("second", 2, 20u64)] {
+            controller.push_control_point(ControlPoint {
+                id: id.to_string(),
+                time: now + chrono::Duration::seconds(*offset),
+                value: (*value).into(),
+                mode: ControlMode::Set,
+            });
+        }
+
+        let summary = controller.summary();
+        assert_eq!(summary.count, 2);
+        assert_eq!(summary.next_time, Some(now + chrono::Duration::seconds(1)));
+        assert_eq!(summary.last_value, None);
+
+        assert_eq!(
+            controller.synchronize(now + chrono::Duration::seconds(1), gst::CLOCK_TIME_NONE),
+            false
+        );
+
+        let summary = controller.summary();
+        assert_eq!(summary.count, 1);
+        assert_eq!(summary.next_time, Some(now + chrono::Duration::seconds(2)));
+        assert_eq!(summary.last_value, Some(10u64.into()));
+    }
+
     #[test]
     fn test_property_controller_set() {
         gst::init().unwrap();
@@ -952,4 +1107,178 @@ pub mod tests {
             0
         );
     }
+
+    #[test]
+    fn test_property_controller_clear_control_points() {
+        gst::init().unwrap();
+
+        let queue = make_element("queue", None).unwrap();
+        let now = get_now();
+        let mut controller =
+            PropertyController::new("test-controllee", queue.clone().upcast(), "max-size-bytes");
+
+        controller.push_control_point(ControlPoint {
+            id: "first".to_string(),
+            time: now + chrono::Duration::seconds(2),
+            value: 1000u64.into(),
+            mode: ControlMode::Interpolate,
+        });
+        controller.push_control_point(ControlPoint {
+            id: "second".to_string(),
+            time: now + chrono::Duration::seconds(4),
+            value: 0u64.into(),
+            mode: ControlMode::Set,
+        });
+
+        // Halfway towards the first point
+        assert!(!controller.synchronize(now + chrono::Duration::seconds(1), gst::SECOND));
+
+        let value = queue
+            .property("max-size-bytes")
+            .unwrap()
+            .get::<u32>()
+            .unwrap();
+
+        controller.clear_control_points();
+
+        assert!(controller.control_points().is_empty());
+        assert!(controller.synchronize(now + chrono::Duration::seconds(4), gst::SECOND));
+        assert_eq!(
+            queue
+                .property("max-size-bytes")
+                .unwrap()
+                .get::<u32>()
+                .unwrap(),
+            value
+        );
+    }
+
+    #[test]
+    fn test_property_controller_remove_control_points_at() {
+        gst::init().unwrap();
+
+        let queue = make_element("queue", None).unwrap();
+        let now = get_now();
+        let mut controller =
+            PropertyController::new("test-controllee", queue.upcast(), "max-size-bytes");
+
+        for (id, offset) in &[("first", 1), ("second", 1), ("third", 2)] {
+            controller.push_control_point(ControlPoint {
+                id: id.to_string(),
+                time: now + chrono::Duration::nanoseconds(*offset),
+                value: 0u64.into(),
+                mode: ControlMode::Set,
+            });
+        }
+
+        controller.remove_control_points_at(now + chrono::Duration::nanoseconds(1));
+
+        let points = controller.control_points();
+
+        assert_eq!(points.len(), 1);
+        assert_eq!(points[0].id, "third");
+    }
+
+    #[test]
+    fn test_property_controller_hold() {
+        gst::init().unwrap();
+
+        let queue = make_element("queue", None).unwrap();
+        queue.set_property("max-size-bytes", &10u32).unwrap();
+        let now = get_now();
+        let mut controller =
+            PropertyController::new("slot-0", queue.clone().upcast(), "max-size-bytes");
+
+        controller.push_control_point(ControlPoint {
+            id: "hold".to_string(),
+            time: now + chrono::Duration::nanoseconds(2),
+            value: serde_json::Value::Null,
+            mode: ControlMode::Set,
+        });
+        controller.push_control_point(ControlPoint {
+            id: "end".to_string(),
+            time: now + chrono::Duration::nanoseconds(4),
+            value: 0u64.into(),
+            mode: ControlMode::Interpolate,
+        });
+
+        let max_size_bytes = || {
+            queue
+                .property("max-size-bytes")
+                .unwrap()
+                .get::<u32>()
+                .unwrap()
+        };
+
+        // Held until the first point, whatever happens meanwhile
+        controller.synchronize(
+            now + chrono::Duration::nanoseconds(1),
+            gst::ClockTime::from_nseconds(1),
+        );
+        assert_eq!(max_size_bytes(), 10);
+
+        queue.set_property("max-size-bytes", &20u32).unwrap();
+
+        controller.synchronize(
+            now + chrono::Duration::nanoseconds(2),
+            gst::ClockTime::from_nseconds(1),
+        );
+        assert_eq!(max_size_bytes(), 20);
+        assert!(controller.summary().last_value.is_none());
+
+        // The interpolation starts from the value at the time of the hold
+        controller.synchronize(
+            now + chrono::Duration::nanoseconds(3),
+            gst::ClockTime::from_nseconds(1),
+        );
+        assert_eq!(max_size_bytes(), 10);
+
+        assert!(controller.synchronize(
+            now + chrono::Duration::nanoseconds(4),
+            gst::ClockTime::from_nseconds(1),
+        ));
+        assert_eq!(max_size_bytes(), 0);
+    }
+
+    #[test]
+    fn test_property_controller_interpolate_fake_now() {
+        gst::init().unwrap();
+
+        let queue = make_element("queue", None).unwrap();
+        queue.set_property("max-size-bytes", &0u32).unwrap();
+
+        let start = get_now();
+        let now = FakeNow::new(start);
+        assert_eq!(get_now(), start);
+
+        let mut controller =
+            PropertyController::new("test-controllee", queue.clone().upcast(), "max-size-bytes");
+
+        controller.push_control_point(ControlPoint {
+            id: "first".to_string(),
+            time: start + chrono::Duration::seconds(1),
+            value: 1000u64.into(),
+            mode: ControlMode::Interpolate,
+        });
+
+        for expected in &[250u32, 500, 750, 1000] {
+            now.advance(chrono::Duration::milliseconds(250));
+
+            let done = controller.synchronize(get_now(), 250u64 * gst::MSECOND);
+
+            assert_eq!(done, *expected == 1000);
+            assert_eq!(
+                queue
+                    .property("max-size-bytes")
+                    .unwrap()
+                    .get::<u32>()
+                    .unwrap(),
+                *expected
+            );
+        }
+
+        drop(now);
+
+        assert!(get_now() >= start);
+    }
 }
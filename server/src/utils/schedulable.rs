@@ -4,6 +4,7 @@ use crate::utils::ErrorMessage;
 use actix::prelude::*;
 use anyhow::{anyhow, Error};
 use chrono::{DateTime, Utc};
+use std::time::Duration;
 use tracing::{instrument, trace};
 
 use auteur_controlling::controller::State;
@@ -23,6 +24,10 @@ pub struct StateMachine {
     pub end_time: Option<DateTime<Utc>>,
     /// Scheduling timer
     handle: Option<SpawnHandle>,
+    /// When the node was created, for reporting its uptime
+    created: actix::clock::Instant,
+    /// The interval between heartbeats, zero if disabled
+    heartbeat_interval: Duration,
 }
 
 impl Default for StateMachine {
@@ -32,6 +37,8 @@ impl Default for StateMachine {
             cue_time: None,
             end_time: None,
             handle: None,
+            created: actix::clock::Instant::now(),
+            heartbeat_interval: Duration::from_secs(0),
         }
     }
 }
@@ -43,6 +50,10 @@ pub enum StateChangeResult {
     Success,
     /// Proceed to the next state
     Skip,
+    /// The transition is still in progress, the node is expected to
+    /// resume scheduling with [`Schedulable::do_schedule`] once it has
+    /// completed
+    Pending,
 }
 
 /// (`Nodes`)[crate::node] should implement this trait in order to drive their
@@ -170,6 +181,35 @@ where
         });
     }
 
+    /// Set the interval at which the node emits heartbeats once started,
+    /// zero disables them
+    fn set_heartbeat_interval(&mut self, interval: Duration) {
+        self.state_machine_mut().heartbeat_interval = interval;
+    }
+
+    /// Emit a heartbeat at the configured interval for as long as the
+    /// node is alive, expected to be called from [`Actor::started`].
+    ///
+    /// Heartbeats are handled in turn with the other messages of the
+    /// node, their absence reveals a node that is wedged.
+    fn start_heartbeat(&mut self, ctx: &mut Context<A>) {
+        let interval = self.state_machine().heartbeat_interval;
+
+        if interval.is_zero() {
+            return;
+        }
+
+        ctx.run_interval(interval, |s, _ctx| {
+            let machine = s.state_machine();
+
+            let _ = NodeManager::from_registry().do_send(NodeStatusMessage::Heartbeat {
+                id: s.node_id().to_string(),
+                state: machine.state,
+                uptime: machine.created.elapsed(),
+            });
+        });
+    }
+
     /// Internal scheduling loop
     #[instrument(level = "trace", skip(self, ctx), fields(node_id = %self.node_id()))]
     fn do_schedule(&mut self, ctx: &mut Context<A>) {
@@ -219,6 +259,10 @@ where
                             self.do_schedule(ctx);
                             break;
                         }
+                        Ok(StateChangeResult::Pending) => {
+                            trace!("Transition to {:?} pending", target);
+                            break;
+                        }
                         Err(err) => {
                             ctx.notify(ErrorMessage(format!(
                                 "Failed to change node state to {:?}: {:?}",
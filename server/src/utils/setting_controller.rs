@@ -1,7 +1,7 @@
 //! Schedule "native" setting updates
 
 use anyhow::{anyhow, Error};
-use auteur_controlling::controller::{ControlMode, ControlPoint};
+use auteur_controlling::controller::{ControlMode, ControlPoint, ControlPointSummary};
 use chrono::{DateTime, Utc};
 use gst::prelude::*;
 use priority_queue::PriorityQueue;
@@ -9,13 +9,30 @@ use std::cmp::Reverse;
 use std::sync::{Arc, Mutex};
 use tracing::{instrument, trace};
 
+use super::easing;
+use super::property_controller::ScheduledControlPoint;
+
 /// Specifies a setting typology (type, valid range)
 #[derive(Debug)]
 pub enum SettingSpec {
     /// Integer specification
     I32 { current: i32, min: i32, max: i32 },
+    /// 64-bit integer specification
+    I64 { current: i64, min: i64, max: i64 },
+    /// Floating point specification
+    F64 { current: f64, min: f64, max: f64 },
     /// String specification
     Str { current: String },
+    /// Boolean specification
+    Bool { current: bool },
+    /// Strictly positive fraction specification, represented as a
+    /// `numerator/denominator` string
+    Fraction { current: gst::Fraction },
+    /// String specification, restricted to a set of choices
+    Enum {
+        current: String,
+        choices: &'static [&'static str],
+    },
 }
 
 /// Represents a (potentially controllable) "native" setting for
@@ -39,10 +56,43 @@ impl Setting {
         }
     }
 
+    /// The current 64-bit integer value of the setting
+    pub fn as_i64(&self) -> Option<i64> {
+        match self.spec {
+            SettingSpec::I64 { current, .. } => Some(current),
+            _ => None,
+        }
+    }
+
+    /// The current floating point value of the setting
+    pub fn as_f64(&self) -> Option<f64> {
+        match self.spec {
+            SettingSpec::F64 { current, .. } => Some(current),
+            _ => None,
+        }
+    }
+
     /// The current string value of the setting
     pub fn as_str(&self) -> Option<&str> {
         match self.spec {
             SettingSpec::Str { ref current, .. } => Some(current),
+            SettingSpec::Enum { ref current, .. } => Some(current),
+            _ => None,
+        }
+    }
+
+    /// The current boolean value of the setting
+    pub fn as_bool(&self) -> Option<bool> {
+        match self.spec {
+            SettingSpec::Bool { current } => Some(current),
+            _ => None,
+        }
+    }
+
+    /// The current fraction value of the setting
+    pub fn as_fraction(&self) -> Option<gst::Fraction> {
+        match self.spec {
+            SettingSpec::Fraction { current } => Some(current),
             _ => None,
         }
     }
@@ -50,7 +100,14 @@ impl Setting {
     pub fn as_value(&self) -> serde_json::Value {
         match self.spec {
             SettingSpec::I32 { current, .. } => current.into(),
+            SettingSpec::I64 { current, .. } => current.into(),
+            SettingSpec::F64 { current, .. } => current.into(),
             SettingSpec::Str { ref current, .. } => current.clone().into(),
+            SettingSpec::Bool { current } => current.into(),
+            SettingSpec::Fraction { current } => {
+                format!("{}/{}", current.numer(), current.denom()).into()
+            }
+            SettingSpec::Enum { ref current, .. } => current.clone().into(),
         }
     }
 }
@@ -63,7 +120,12 @@ pub struct SettingController {
     /// The controlled setting
     pub setting: Arc<Mutex<Setting>>,
     /// The future control points
-    control_points: Option<PriorityQueue<String, Reverse<ControlPoint>>>,
+    control_points: Option<PriorityQueue<String, Reverse<ScheduledControlPoint>>>,
+    /// The control point currently interpolated towards, and when
+    /// interpolation started, for easing
+    interpolation_start: Option<(String, DateTime<Utc>)>,
+    /// The value of the last control point reached
+    last_value: Option<serde_json::Value>,
 }
 
 impl SettingController {
@@ -73,6 +135,8 @@ impl SettingController {
             controllee_id: controllee_id.to_string(),
             setting,
             control_points: Some(PriorityQueue::new()),
+            interpolation_start: None,
+            last_value: None,
         }
     }
 
@@ -82,7 +146,7 @@ impl SettingController {
         self.control_points
             .as_mut()
             .unwrap()
-            .push(point.id.to_string(), Reverse(point));
+            .push(point.id.to_string(), Reverse(ScheduledControlPoint(point)));
     }
 
     /// Remove a control point
@@ -91,18 +155,47 @@ impl SettingController {
         self.control_points.as_mut().unwrap().remove(id);
     }
 
+    /// Remove all the control points scheduled at a given time
+    #[instrument(level = "debug", name = "removing control points", skip(self), fields(controllee_id = %self.controllee_id, setting = %self.setting.lock().unwrap().name))]
+    pub fn remove_control_points_at(&mut self, time: DateTime<Utc>) {
+        let control_points = self.control_points.as_mut().unwrap();
+
+        let ids: Vec<String> = control_points
+            .iter()
+            .filter(|(_, Reverse(ScheduledControlPoint(point)))| point.time == time)
+            .map(|(id, _)| id.clone())
+            .collect();
+
+        for id in ids {
+            control_points.remove(&id);
+        }
+    }
+
     /// Retrieves all control points
     #[instrument(level = "debug", name = "getting control points", skip(self), fields(controllee_id = %self.controllee_id, setting = %self.setting.lock().unwrap().name))]
     pub fn control_points(&self) -> Vec<ControlPoint> {
         let mut ret: Vec<ControlPoint> = vec![];
 
-        for (_, Reverse(point)) in self.control_points.as_ref().unwrap() {
+        for (_, Reverse(ScheduledControlPoint(point))) in self.control_points.as_ref().unwrap() {
             ret.push(point.clone());
         }
 
         ret
     }
 
+    /// Summarizes the pending control points
+    pub fn summary(&self) -> ControlPointSummary {
+        let control_points = self.control_points.as_ref().unwrap();
+
+        ControlPointSummary {
+            count: control_points.len(),
+            next_time: control_points
+                .peek()
+                .map(|(_, Reverse(ScheduledControlPoint(point)))| point.time),
+            last_value: self.last_value.clone(),
+        }
+    }
+
     /// Update the value for a controlled setting, duration is the
     /// duration elapsed since the last call and will be used to
     /// perform interpolation.
@@ -113,23 +206,31 @@ impl SettingController {
         let mut control_points = self.control_points.take().unwrap();
         let mut setting = self.setting.lock().unwrap();
 
-        if let Some((_id, Reverse(point))) = control_points.peek() {
+        if let Some((id, Reverse(ScheduledControlPoint(point)))) = control_points.peek() {
             let mut do_trace = false;
 
-            let initial: serde_json::Value = match setting.spec {
-                SettingSpec::I32 { current, .. } => current.into(),
-                SettingSpec::Str { ref current } => current.clone().into(),
-            };
+            let initial = setting.as_value();
 
             if match point.mode {
-                ControlMode::Interpolate => match duration {
+                ControlMode::Interpolate | ControlMode::BezierCubic { .. } => match duration {
                     gst::CLOCK_TIME_NONE => false,
                     _ => {
                         do_trace = true;
+
+                        let duration = duration.nseconds().unwrap();
+                        let start = match self.interpolation_start {
+                            Some((ref start_id, start)) if start_id == id => start,
+                            _ => {
+                                let start = now - chrono::Duration::nanoseconds(duration as i64);
+                                self.interpolation_start = Some((id.clone(), start));
+                                start
+                            }
+                        };
+
                         SettingController::interpolate(
                             &mut setting,
                             now,
-                            duration.nseconds().unwrap(),
+                            easing::eased_duration(start, now, duration, point),
                             point,
                         )
                     }
@@ -137,14 +238,12 @@ impl SettingController {
                 ControlMode::Set => SettingController::set(&mut setting, now, point),
             } {
                 do_trace = true;
-                control_points.pop().unwrap();
+                let (_, Reverse(ScheduledControlPoint(point))) = control_points.pop().unwrap();
+                self.last_value = Some(point.value);
             }
 
             if do_trace {
-                let new: serde_json::Value = match setting.spec {
-                    SettingSpec::I32 { current, .. } => current.into(),
-                    SettingSpec::Str { ref current } => current.clone().into(),
-                };
+                let new = setting.as_value();
 
                 trace!(setting = %setting.name, "Synchronized setting controller: {:?} -> {:?}", initial, new);
             }
@@ -190,6 +289,56 @@ impl SettingController {
                     Err(anyhow!("expected i32 value for property {}", setting.name))
                 }
             }
+            SettingSpec::I64 { min, max, .. } => {
+                if let Some(value) = value.as_i64() {
+                    if value > max {
+                        return Err(anyhow!(
+                            "Invalid value for setting {} ({} > {})",
+                            setting.name,
+                            value,
+                            max
+                        ));
+                    }
+
+                    if value < min {
+                        return Err(anyhow!(
+                            "Invalid value for setting {} ({} < {})",
+                            setting.name,
+                            value,
+                            min
+                        ));
+                    }
+
+                    Ok(())
+                } else {
+                    Err(anyhow!("expected i64 value for property {}", setting.name))
+                }
+            }
+            SettingSpec::F64 { min, max, .. } => {
+                if let Some(value) = value.as_f64() {
+                    if value > max {
+                        return Err(anyhow!(
+                            "Invalid value for setting {} ({} > {})",
+                            setting.name,
+                            value,
+                            max
+                        ));
+                    }
+
+                    if value < min {
+                        return Err(anyhow!(
+                            "Invalid value for setting {} ({} < {})",
+                            setting.name,
+                            value,
+                            min
+                        ));
+                    }
+
+                    Ok(())
+                } else {
+                    Err(anyhow!("expected f64 value for property {}", setting.name))
+                }
+            }
             SettingSpec::Str { .. } => {
                 if value.is_string() {
                     Ok(())
@@ -200,6 +349,34 @@ impl SettingController {
                     ))
                 }
             }
+            SettingSpec::Bool { .. } => {
+                if value.is_boolean() {
+                    Ok(())
+                } else {
+                    Err(anyhow!(
+                        "expected boolean value for property {}",
+                        setting.name
+                    ))
+                }
+            }
+            SettingSpec::Fraction { .. } => {
+                if value.as_str().and_then(parse_fraction).is_some() {
+                    Ok(())
+                } else {
+                    Err(anyhow!(
+                        "expected strictly positive fraction (eg \"16/15\") for property {}",
+                        setting.name
+                    ))
+                }
+            }
+            SettingSpec::Enum { choices, .. } => match value.as_str() {
+                Some(value) if choices.contains(&value) => Ok(()),
+                _ => Err(anyhow!(
+                    "expected one of {} for property {}",
+                    choices.join(", "),
+                    setting.name
+                )),
+            },
         }
     }
 
@@ -213,7 +390,23 @@ impl SettingController {
             return Err(anyhow!("setting {} is not controllable", setting.name));
         }
 
-        SettingController::validate_value_against_setting(setting, &point.value)
+        SettingController::validate_value_against_setting(setting, &point.value)?;
+        easing::validate_mode(&point.mode)?;
+
+        // Additional verification for non-interpolatable types
+        match setting.spec {
+            SettingSpec::I32 { .. } | SettingSpec::I64 { .. } | SettingSpec::F64 { .. } => Ok(()),
+            SettingSpec::Str { .. }
+            | SettingSpec::Bool { .. }
+            | SettingSpec::Fraction { .. }
+            | SettingSpec::Enum { .. } => match point.mode {
+                ControlMode::Set => Ok(()),
+                _ => Err(anyhow!(
+                    "Control points for setting {} must use mode Set",
+                    setting.name
+                )),
+            },
+        }
     }
 
     /// Validate a value for a setting
@@ -248,7 +441,48 @@ impl SettingController {
 
                 *current = (*current as i64 + step) as i32;
             }
-            SettingSpec::Str { .. } => unreachable!(),
+            SettingSpec::I64 {
+                ref mut current, ..
+            } => {
+                let target = point.value.as_i64().unwrap();
+
+                // Make sure we avoid over / underflow
+                if target >= *current {
+                    let step = (target - *current)
+                        .mul_div_round(duration as i64, period as i64)
+                        .unwrap();
+
+                    *current += step;
+                } else {
+                    let step = (*current - target)
+                        .mul_div_round(duration as i64, period as i64)
+                        .unwrap();
+
+                    *current -= step;
+                }
+            }
+            SettingSpec::F64 {
+                ref mut current, ..
+            } => {
+                let target = point.value.as_f64().unwrap();
+
+                let step = (target - *current) / period as f64 * duration as f64;
+
+                let new = *current + step;
+
+                // Make sure float arithmetics doesn't make us over / undershoot
+                *current = if (target >= *current && new > target)
+                    || (target <= *current && new < target)
+                {
+                    target
+                } else {
+                    new
+                };
+            }
+            SettingSpec::Str { .. }
+            | SettingSpec::Bool { .. }
+            | SettingSpec::Fraction { .. }
+            | SettingSpec::Enum { .. } => unreachable!(),
         }
 
         period <= duration
@@ -264,9 +498,30 @@ impl SettingController {
             } => {
                 *current = value.as_i64().unwrap() as i32;
             }
+            SettingSpec::I64 {
+                ref mut current, ..
+            } => {
+                *current = value.as_i64().unwrap();
+            }
+            SettingSpec::F64 {
+                ref mut current, ..
+            } => {
+                *current = value.as_f64().unwrap();
+            }
             SettingSpec::Str { ref mut current } => {
                 *current = value.as_str().unwrap().to_string();
             }
+            SettingSpec::Bool { ref mut current } => {
+                *current = value.as_bool().unwrap();
+            }
+            SettingSpec::Fraction { ref mut current } => {
+                *current = parse_fraction(value.as_str().unwrap()).unwrap();
+            }
+            SettingSpec::Enum {
+                ref mut current, ..
+            } => {
+                *current = value.as_str().unwrap().to_string();
+            }
         }
     }
 
@@ -281,3 +536,17 @@ impl SettingController {
         true
     }
 }
+
+/// Parse a strictly positive `numerator/denominator` fraction
+pub fn parse_fraction(value: &str) -> Option<gst::Fraction> {
+    let mut split = value.splitn(2, '/');
+
+    let numer = split.next()?.trim().parse::<i32>().ok()?;
+    let denom = split.next()?.trim().parse::<i32>().ok()?;
+
+    if numer > 0 && denom > 0 {
+        Some(gst::Fraction::new(numer, denom))
+    } else {
+        None
+    }
+}
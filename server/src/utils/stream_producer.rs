@@ -3,16 +3,30 @@
 use std::collections::HashMap;
 use std::mem;
 use std::sync::{atomic, Arc, Mutex};
+use std::time::{Duration, Instant};
 
+use anyhow::{anyhow, Error};
+use auteur_controlling::controller::ForwardMode;
+use futures::channel::oneshot;
+use futures::prelude::*;
 use gst::prelude::*;
 
 use tracing::{debug, error, trace, warn};
 
+/// How often repeated failures to push to a consumer are logged
+const PUSH_FAILURE_LOG_INTERVAL: Duration = Duration::from_secs(1);
+/// For how long pushing to a consumer must fail before listeners
+/// are notified
+const PUSH_FAILURE_NOTIFY_DELAY: Duration = Duration::from_secs(5);
+
 /// The interface for transporting media data from one node
 /// to another.
 ///
 /// A producer is essentially a GStreamer `appsink` whose output
 /// is sent to a set of consumers, who are essentially `appsrc` wrappers
+///
+/// Whether the producer discards its first samples is determined by
+/// its [`ForwardMode`]
 #[derive(Debug, Clone)]
 pub struct StreamProducer {
     /// The appsink to dispatch data for
@@ -31,11 +45,30 @@ impl Eq for StreamProducer {}
 
 impl StreamProducer {
     /// Add an appsrc to dispatch data to
-    pub fn add_consumer(&self, consumer: &gst_app::AppSrc, consumer_id: &str) {
+    ///
+    /// This fails if the producer already has as many consumers as
+    /// allowed by [`Self::set_max_consumers`]. Consumers added once the
+    /// producer has reached EOS immediately receive EOS, callers must thus
+    /// not add a consumer again in reaction to its EOS, as that would loop
+    /// for as long as the producer doesn't flow again.
+    ///
+    /// Consumers are identified by `consumer_id`, not by node: a node may
+    /// add several consumers, eg one per mixer slot fed by this producer.
+    /// Each consumer receives every sample, and new consumers requesting
+    /// a keyframe for their first buffer share a single request.
+    pub fn add_consumer(&self, consumer: &gst_app::AppSrc, consumer_id: &str) -> Result<(), Error> {
         let mut consumers = self.consumers.lock().unwrap();
         if consumers.consumers.get(consumer_id).is_some() {
             error!(appsink = %self.appsink.name(), appsrc = %consumer.name(), "Consumer already added");
-            return;
+            return Ok(());
+        }
+
+        if consumers.consumers.len() >= consumers.max_consumers {
+            return Err(anyhow!(
+                "producer {} reached its maximum of {} consumers",
+                self.appsink.name(),
+                consumers.max_consumers
+            ));
         }
 
         debug!(appsink = %self.appsink.name(), appsrc = %consumer.name(), "Adding consumer");
@@ -68,22 +101,178 @@ impl StreamProducer {
             consumer_id.to_string(),
             StreamConsumer::new(consumer, fku_probe_id, consumer_id),
         );
+
+        // The EOS callback sets the flag with our lock taken, the consumer
+        // thus either was part of its consumers or receives EOS here
+        if consumers.eos_received {
+            debug!(appsink = %self.appsink.name(), appsrc = %consumer.name(), "Producer already reached EOS");
+            let _ = consumer.end_of_stream();
+        }
+
+        Ok(())
+    }
+
+    /// Resolves once the producer outputs data again after having reached
+    /// EOS, eg after a flush, or right away if it hasn't reached EOS
+    ///
+    /// Consumers added while the producer is at EOS immediately receive
+    /// EOS, waiting for the next stream allows adding them back once
+    /// there is data to consume again.
+    pub fn next_stream(&self) -> impl Future<Output = ()> {
+        let (sender, receiver) = oneshot::channel();
+        let mut consumers = self.consumers.lock().unwrap();
+
+        if consumers.eos_received {
+            consumers.next_stream_waiters.push(sender);
+        } else {
+            let _ = sender.send(());
+        }
+
+        // Also resolves if the producer is dropped in the meantime
+        receiver.map(|_| ())
+    }
+
+    /// Limit the number of consumers, existing consumers are kept when
+    /// lowering the limit below their number. There is no limit by
+    /// default.
+    pub fn set_max_consumers(&self, max: usize) {
+        self.consumers.lock().unwrap().max_consumers = max;
     }
 
     /// Remove a consumer appsrc by id
+    ///
+    /// Samples are pushed outside of our lock, this returns once any
+    /// sample being pushed to the consumer was pushed. That push may
+    /// block if the appsrc is, so the consumer should flush it first.
     pub fn remove_consumer(&self, consumer_id: &str) {
-        if let Some(consumer) = self.consumers.lock().unwrap().consumers.remove(consumer_id) {
+        let consumer = self.consumers.lock().unwrap().consumers.remove(consumer_id);
+
+        if let Some(consumer) = consumer {
+            // Held by the streaming thread while pushing
+            drop(consumer.pushing.lock().unwrap());
             debug!(appsink = %self.appsink.name(), appsrc = %consumer.appsrc.name(), "Removed consumer");
         } else {
             debug!(appsink = %self.appsink.name(), consumer_id = %consumer_id, "Consumer not found");
         }
     }
 
+    /// Send a tag event to the consumers
+    ///
+    /// The event is sent to the `appsrc`s, which push it downstream in
+    /// order with the samples already queued: consumers added later do
+    /// not receive it.
+    pub fn send_tags(&self, tags: &gst::TagList) {
+        let consumers = self.consumers.lock().unwrap();
+
+        for consumer in consumers.consumers.values() {
+            trace!(appsink = %self.appsink.name(), appsrc = %consumer.appsrc.name(), tags = ?tags, "Sending tags");
+
+            consumer
+                .appsrc
+                .send_event(gst::event::Tag::new(tags.clone()));
+        }
+    }
+
     /// Stop discarding data samples and start forwarding them to the consumers.
     ///
-    /// This is useful for example for prerolling live sources.
+    /// This is useful for example for prerolling live sources. It is only
+    /// needed in [`ForwardMode::Manual`], and ignored in the other modes,
+    /// where the producer decides by itself when to start forwarding.
     pub fn forward(&self) {
-        self.consumers.lock().unwrap().discard = false;
+        let mut consumers = self.consumers.lock().unwrap();
+
+        if consumers.forward_mode == ForwardMode::Manual {
+            consumers.discard = false;
+        }
+    }
+
+    /// Whether samples are forwarded to the consumers, rather than
+    /// discarded
+    ///
+    /// A producer that never starts forwarding, eg because its node
+    /// didn't call [`Self::forward`], silently starves its consumers.
+    pub fn is_forwarding(&self) -> bool {
+        !self.consumers.lock().unwrap().discard
+    }
+
+    /// Set when the producer starts forwarding samples, see [`ForwardMode`]
+    ///
+    /// Switching to [`ForwardMode::Auto`] after samples were already
+    /// discarded will start forwarding on the next sample, and to
+    /// [`ForwardMode::Keyframe`] on the next keyframe.
+    pub fn set_forward_mode(&self, mode: ForwardMode) {
+        self.consumers.lock().unwrap().forward_mode = mode;
+    }
+
+    /// When the producer starts forwarding samples, see [`ForwardMode`]
+    pub fn forward_mode(&self) -> ForwardMode {
+        self.consumers.lock().unwrap().forward_mode
+    }
+
+    /// Request a keyframe, with all headers, from upstream of our `appsink`
+    ///
+    /// Unlike the keyframes requested when consumers receive their first
+    /// buffer, this is not tied to any consumer, eg for providing a clean
+    /// splice point in the output stream.
+    pub fn request_keyframe(&self) {
+        trace!(appsink = %self.appsink.name(), "Requesting keyframe");
+
+        self.appsink.send_event(
+            gst_video::UpstreamForceKeyUnitEvent::builder()
+                .all_headers(true)
+                .build(),
+        );
+    }
+
+    /// Override the latency configured on the `appsrc` of a consumer
+    ///
+    /// By default, all consumers are configured with the latency
+    /// reported downstream of our `appsink`, see
+    /// [`StreamProducerStats::current_latency`].
+    /// An override takes precedence over that latency for this consumer
+    /// only, and persists across latency updates until cleared with
+    /// `None`, at which point the reported latency applies again from
+    /// the next sample.
+    pub fn set_consumer_latency(&self, consumer_id: &str, latency: Option<gst::ClockTime>) {
+        let mut consumers = self.consumers.lock().unwrap();
+
+        if let Some(consumer) = consumers.consumers.get_mut(consumer_id) {
+            debug!(appsink = %self.appsink.name(), appsrc = %consumer.appsrc.name(), latency = ?latency, "Overriding consumer latency");
+
+            consumer.latency_override = latency;
+
+            match latency {
+                Some(latency) => consumer.appsrc.set_latency(latency, gst::CLOCK_TIME_NONE),
+                None => consumer
+                    .forwarded_latency
+                    .store(false, atomic::Ordering::SeqCst),
+            }
+        } else {
+            debug!(appsink = %self.appsink.name(), consumer_id = %consumer_id, "Consumer not found");
+        }
+    }
+
+    /// Remove consumers that failed to accept samples for at least
+    /// `timeout`, `None` to keep them forever, this is the default
+    ///
+    /// A consumer whose `appsrc` rejects every sample is usually part
+    /// of a dead pipeline. Listeners are notified of the removal with
+    /// a status message, and the consumer node is disconnected from the
+    /// link.
+    pub fn set_auto_remove_timeout(&self, timeout: Option<Duration>) {
+        self.consumers.lock().unwrap().auto_remove_timeout = timeout;
+    }
+
+    /// Set the callback notified about our consumers, eg when one is
+    /// removed
+    ///
+    /// The callback is called from the streaming thread of our
+    /// `appsink`. Until set, such events are only logged.
+    pub fn set_notify<F>(&self, notify: F)
+    where
+        F: Fn(ProducerNotification) + Send + Sync + 'static,
+    {
+        self.consumers.lock().unwrap().notify = Some(Notify(Arc::new(notify)));
     }
 
     /// Get the GStreamer `appsink` wrapped by this producer
@@ -91,6 +280,31 @@ impl StreamProducer {
         &self.appsink
     }
 
+    /// Get a snapshot of the statistics of this producer
+    pub fn stats(&self) -> StreamProducerStats {
+        let consumers = self.consumers.lock().unwrap();
+
+        StreamProducerStats {
+            n_forwarded_samples: consumers.n_forwarded_samples,
+            current_latency: consumers.current_latency,
+            n_dropped_samples: consumers
+                .consumers
+                .iter()
+                .map(|(id, c)| {
+                    (
+                        id.clone(),
+                        c.n_dropped_samples.load(atomic::Ordering::SeqCst),
+                    )
+                })
+                .collect(),
+            n_enough_data: consumers
+                .consumers
+                .iter()
+                .map(|(id, c)| (id.clone(), c.n_enough_data.load(atomic::Ordering::SeqCst)))
+                .collect(),
+        }
+    }
+
     /// Get the unique identifiers of all the consumers currently connected
     /// to this producer
     ///
@@ -114,6 +328,14 @@ impl<'a> From<&'a gst_app::AppSink> for StreamProducer {
             latency_updated: false,
             consumers: HashMap::new(),
             discard: true,
+            forward_mode: ForwardMode::default(),
+            requested_forward_keyframe: false,
+            n_forwarded_samples: 0,
+            auto_remove_timeout: None,
+            max_consumers: usize::MAX,
+            eos_received: false,
+            next_stream_waiters: vec![],
+            notify: None,
         }));
 
         let consumers_clone = consumers.clone();
@@ -131,8 +353,50 @@ impl<'a> From<&'a gst_app::AppSink> for StreamProducer {
                         }
                     };
 
+                    // Data flows again after a flush
+                    if consumers.eos_received {
+                        consumers.eos_received = false;
+
+                        for sender in consumers.next_stream_waiters.drain(..) {
+                            let _ = sender.send(());
+                        }
+                    }
+
                     if consumers.discard {
-                        return Ok(gst::FlowSuccess::Ok);
+                        match consumers.forward_mode {
+                            ForwardMode::Manual => return Ok(gst::FlowSuccess::Ok),
+                            ForwardMode::Auto => {
+                                debug!(appsink = %appsink.name(), "First sample, forwarding automatically");
+                            }
+                            ForwardMode::Keyframe => {
+                                let is_keyframe = sample.buffer().map_or(false, |buffer| {
+                                    !buffer.flags().contains(gst::BufferFlags::DELTA_UNIT)
+                                });
+
+                                if !is_keyframe {
+                                    if !consumers.requested_forward_keyframe {
+                                        trace!(appsink = %appsink.name(), "Requesting keyframe to start forwarding");
+                                        appsink.send_event(
+                                            gst_video::UpstreamForceKeyUnitEvent::builder()
+                                                .all_headers(true)
+                                                .build(),
+                                        );
+                                        consumers.requested_forward_keyframe = true;
+                                    }
+
+                                    return Ok(gst::FlowSuccess::Ok);
+                                }
+
+                                debug!(appsink = %appsink.name(), "First keyframe, forwarding automatically");
+
+                                // The consumers start with this keyframe
+                                for c in consumers.consumers.values() {
+                                    c.first_buffer.store(false, atomic::Ordering::SeqCst);
+                                }
+                            }
+                        }
+
+                        consumers.discard = false;
                     }
 
                     let span = tracing::trace_span!("New sample", appsink = %appsink.name());
@@ -140,13 +404,16 @@ impl<'a> From<&'a gst_app::AppSink> for StreamProducer {
 
                     let latency = consumers.current_latency;
                     let latency_updated = mem::replace(&mut consumers.latency_updated, false);
+                    let auto_remove_timeout = consumers.auto_remove_timeout;
+                    let notify = consumers.notify.clone();
                     let mut requested_keyframe = false;
 
                     let current_consumers = consumers
                         .consumers
                         .values()
                         .map(|c| {
-                            if let Some(latency) = latency {
+                            // Overrides were already applied upon being set
+                            if let (Some(latency), None) = (latency, c.latency_override) {
                                 if c.forwarded_latency
                                     .compare_exchange(
                                         false,
@@ -178,15 +445,114 @@ impl<'a> From<&'a gst_app::AppSink> for StreamProducer {
                                 requested_keyframe = true;
                             }
 
-                            c.appsrc.clone()
+                            (
+                                c.id.clone(),
+                                c.appsrc.clone(),
+                                c.first_buffer.clone(),
+                                c.n_dropped_samples.clone(),
+                                c.push_failures.clone(),
+                                c.pushing.clone(),
+                            )
                         })
                         .collect::<smallvec::SmallVec<[_; 16]>>();
                     drop(consumers);
 
+                    let mut dead_consumers = smallvec::SmallVec::<[_; 16]>::new();
+                    let mut forwarded = false;
+
                     //trace!("Appsink pushing sample {:?}, current running time: {}", sample, appsink.current_running_time());
-                    for consumer in current_consumers {
-                        if let Err(err) = consumer.push_sample(&sample) {
-                            warn!(appsrc = %consumer.name(), "Failed to push sample: {}", err);
+                    for (
+                        consumer_id,
+                        consumer,
+                        first_buffer,
+                        n_dropped_samples,
+                        push_failures,
+                        pushing,
+                    ) in current_consumers
+                    {
+                        let _pushing = pushing.lock().unwrap();
+
+                        let (flushed, res) =
+                            push_to_consumer(&consumer, &sample, |consumer, sample| {
+                                consumer.push_sample(sample)
+                            });
+
+                        if flushed {
+                            // Start afresh from a new key unit
+                            first_buffer.store(true, atomic::Ordering::SeqCst);
+                        }
+
+                        match res {
+                            Ok(_) => {
+                                push_failures.lock().unwrap().record_success(&consumer);
+                                forwarded = true;
+                            }
+                            Err(err) if flushed => {
+                                n_dropped_samples.fetch_add(1, atomic::Ordering::SeqCst);
+                                let reason = format!(
+                                    "Removed consumer {} after pushing failed again once flushed: {}",
+                                    consumer_id, err
+                                );
+                                dead_consumers.push((consumer_id, push_failures, reason));
+                            }
+                            Err(err) => {
+                                n_dropped_samples.fetch_add(1, atomic::Ordering::SeqCst);
+
+                                let mut failures = push_failures.lock().unwrap();
+                                let failing = failures.record_failure(&consumer_id, &consumer, err);
+                                let exceeded = failures.exceeded(auto_remove_timeout);
+                                drop(failures);
+
+                                if let (Some(message), Some(notify)) = (failing, notify.as_ref()) {
+                                    (notify.0)(ProducerNotification::PushFailing {
+                                        consumer_id: consumer_id.clone(),
+                                        message,
+                                    });
+                                }
+
+                                if exceeded {
+                                    let reason = format!(
+                                        "Removed consumer {} after it failed to accept samples for {} ms",
+                                        consumer_id,
+                                        auto_remove_timeout.unwrap().as_millis()
+                                    );
+                                    dead_consumers.push((consumer_id, push_failures, reason));
+                                }
+                            }
+                        }
+                    }
+
+                    if forwarded || !dead_consumers.is_empty() {
+                        let mut consumers = consumers_clone.lock().unwrap();
+
+                        // Only count samples that made it to a consumer
+                        if forwarded {
+                            consumers.n_forwarded_samples += 1;
+                        }
+
+                        for (consumer_id, push_failures, reason) in dead_consumers {
+                            // The consumer may have been removed and added again with the
+                            // same id since we released the lock, only remove the one
+                            // that actually failed
+                            let is_same = consumers
+                                .consumers
+                                .get(&consumer_id)
+                                .map_or(false, |c| Arc::ptr_eq(&c.push_failures, &push_failures));
+
+                            if !is_same {
+                                continue;
+                            }
+
+                            consumers.consumers.remove(&consumer_id);
+
+                            warn!(appsink = %appsink.name(), consumer_id = %consumer_id, "{}", reason);
+
+                            if let Some(ref notify) = notify {
+                                (notify.0)(ProducerNotification::ConsumerRemoved {
+                                    consumer_id,
+                                    message: reason,
+                                });
+                            }
                         }
                     }
 
@@ -196,13 +562,15 @@ impl<'a> From<&'a gst_app::AppSink> for StreamProducer {
                     let span = tracing::debug_span!("EOS", appsink = %appsink.name());
                     let _guard = span.enter();
 
-                    let current_consumers = consumers_clone2
-                        .lock()
-                        .unwrap()
+                    let mut consumers = consumers_clone2.lock().unwrap();
+                    consumers.eos_received = true;
+
+                    let current_consumers = consumers
                         .consumers
                         .values()
                         .map(|c| c.appsrc.clone())
                         .collect::<smallvec::SmallVec<[_; 16]>>();
+                    drop(consumers);
 
                     for consumer in current_consumers {
                         let _ = consumer.end_of_stream();
@@ -238,6 +606,20 @@ impl<'a> From<&'a gst_app::AppSink> for StreamProducer {
     }
 }
 
+/// Statistics about a producer, see [`StreamProducer::stats`]
+#[derive(Debug, Clone)]
+pub struct StreamProducerStats {
+    /// The number of samples pushed to at least one consumer
+    pub n_forwarded_samples: u64,
+    /// The currently-observed latency
+    pub current_latency: Option<gst::ClockTime>,
+    /// The number of samples that could not be pushed, by consumer id
+    pub n_dropped_samples: HashMap<String, u64>,
+    /// The number of times the queue of a consumer filled up, its oldest
+    /// samples then get dropped, by consumer id
+    pub n_enough_data: HashMap<String, u64>,
+}
+
 /// Wrapper around a HashMap of consumers, exists for thread safety
 /// and also protects some of the producer state
 #[derive(Debug)]
@@ -250,30 +632,203 @@ struct StreamConsumers {
     consumers: HashMap<String, StreamConsumer>,
     /// Whether appsrc samples should be forwarded to consumers yet
     discard: bool,
+    /// Whether discarding stops on the first sample, on the first
+    /// keyframe or on `forward()`
+    forward_mode: ForwardMode,
+    /// Whether a keyframe was requested in order to start forwarding, in
+    /// [`ForwardMode::Keyframe`]
+    requested_forward_keyframe: bool,
+    /// The number of samples pushed to at least one consumer
+    n_forwarded_samples: u64,
+    /// For how long a consumer may fail to accept samples before
+    /// getting removed, if at all
+    auto_remove_timeout: Option<Duration>,
+    /// How many consumers may be added
+    max_consumers: usize,
+    /// Whether the appsink has received EOS, since its last sample
+    eos_received: bool,
+    /// Notified once the appsink outputs a sample after EOS
+    next_stream_waiters: Vec<oneshot::Sender<()>>,
+    /// Notified about our consumers, see [`StreamProducer::set_notify`]
+    notify: Option<Notify>,
+}
+
+/// Notifications about the consumers of a producer, see
+/// [`StreamProducer::set_notify`]
+#[derive(Debug)]
+pub enum ProducerNotification {
+    /// Pushing samples to a consumer has been failing for a while
+    PushFailing {
+        consumer_id: String,
+        message: String,
+    },
+    /// A consumer was removed after failing to accept samples
+    ConsumerRemoved {
+        consumer_id: String,
+        message: String,
+    },
+}
+
+/// The callback set with [`StreamProducer::set_notify`]
+#[derive(Clone)]
+struct Notify(Arc<dyn Fn(ProducerNotification) + Send + Sync>);
+
+impl std::fmt::Debug for Notify {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("Notify")
+    }
+}
+
+/// Tracks consecutive failures to push samples to a consumer, in
+/// order to throttle their logging and detect dead consumers
+#[derive(Debug, Default)]
+struct PushFailures {
+    /// The number of consecutive failures
+    n_consecutive: u64,
+    /// When the current sequence of failures started
+    since: Option<Instant>,
+    /// When a failure was last logged
+    last_logged: Option<Instant>,
+    /// Whether listeners were notified of the current sequence of failures
+    notified: bool,
+}
+
+impl PushFailures {
+    /// Whether pushing has been failing for at least `timeout`
+    fn exceeded(&self, timeout: Option<Duration>) -> bool {
+        match (self.since, timeout) {
+            (Some(since), Some(timeout)) => since.elapsed() >= timeout,
+            _ => false,
+        }
+    }
+
+    /// Reset after a successful push
+    fn record_success(&mut self, appsrc: &gst_app::AppSrc) {
+        if self.n_consecutive > 0 {
+            debug!(appsrc = %appsrc.name(), "Pushing samples succeeded again after {} failures", self.n_consecutive);
+        }
+
+        *self = PushFailures::default();
+    }
+
+    /// Log the first failure immediately, then at most once per
+    /// [`PUSH_FAILURE_LOG_INTERVAL`]. Returns a message for notifying
+    /// listeners, once when failures persist for
+    /// [`PUSH_FAILURE_NOTIFY_DELAY`]
+    fn record_failure(
+        &mut self,
+        consumer_id: &str,
+        appsrc: &gst_app::AppSrc,
+        err: gst::FlowError,
+    ) -> Option<String> {
+        let now = Instant::now();
+        let since = *self.since.get_or_insert(now);
+
+        self.n_consecutive += 1;
+
+        let log = match self.last_logged {
+            Some(last_logged) => now.duration_since(last_logged) >= PUSH_FAILURE_LOG_INTERVAL,
+            None => true,
+        };
+
+        if log {
+            warn!(appsrc = %appsrc.name(), "Failed to push sample: {} ({} consecutive failures)", err, self.n_consecutive);
+            self.last_logged = Some(now);
+        }
+
+        if !self.notified && now.duration_since(since) >= PUSH_FAILURE_NOTIFY_DELAY {
+            self.notified = true;
+
+            Some(format!(
+                "Failed to push samples to consumer {} for {} seconds: {}",
+                consumer_id,
+                PUSH_FAILURE_NOTIFY_DELAY.as_secs(),
+                err
+            ))
+        } else {
+            None
+        }
+    }
+}
+
+/// Push a sample to a consumer with `push`. A [`gst::FlowError::Error`]
+/// may be transient: the consumer is then flushed and the sample pushed
+/// again once, before giving up on it.
+///
+/// Returns whether the consumer was flushed, and the result of the last push.
+/// No lock on the consumer's push failures may be held, as flushing is
+/// done on the calling thread.
+fn push_to_consumer<F>(
+    consumer: &gst_app::AppSrc,
+    sample: &gst::Sample,
+    mut push: F,
+) -> (bool, Result<gst::FlowSuccess, gst::FlowError>)
+where
+    F: FnMut(&gst_app::AppSrc, &gst::Sample) -> Result<gst::FlowSuccess, gst::FlowError>,
+{
+    match push(consumer, sample) {
+        Err(gst::FlowError::Error) => {
+            warn!(appsrc = %consumer.name(), "Failed to push sample, flushing consumer and retrying");
+
+            flush_consumer(consumer);
+
+            (true, push(consumer, sample))
+        }
+        res => (false, res),
+    }
+}
+
+/// Drop the data queued in a consumer's `appsrc`, and reset the
+/// elements downstream of it
+fn flush_consumer(appsrc: &gst_app::AppSrc) {
+    debug!(appsrc = %appsrc.name(), "Flushing consumer");
+
+    appsrc.send_event(gst::event::FlushStart::new());
+    // The running time of the consumer must stay in line with ours
+    appsrc.send_event(gst::event::FlushStop::new(false));
 }
 
 /// Wrapper around a consumer's `appsrc`
 #[derive(Debug)]
 struct StreamConsumer {
+    /// The identifier of the consumer
+    id: String,
     /// The GStreamer `appsrc` of the consumer
     appsrc: gst_app::AppSrc,
     /// The id of a pad probe that intercepts force-key-unit events
     fku_probe_id: Option<gst::PadProbeId>,
     /// Whether an initial latency was forwarded to the `appsrc`
     forwarded_latency: atomic::AtomicBool,
+    /// A latency to configure the `appsrc` with, instead of the latency
+    /// reported downstream of the producer
+    latency_override: Option<gst::ClockTime>,
     /// Whether a first buffer has made it through, used to determine
     /// whether a new key unit should be requested. Only useful for encoded
     /// streams.
-    first_buffer: atomic::AtomicBool,
+    first_buffer: Arc<atomic::AtomicBool>,
+    /// The number of samples that could not be pushed
+    n_dropped_samples: Arc<atomic::AtomicU64>,
+    /// The number of times the queue of the `appsrc` filled up, because
+    /// the consumer wasn't consuming fast enough
+    n_enough_data: Arc<atomic::AtomicU64>,
+    /// Consecutive failures to push samples
+    push_failures: Arc<Mutex<PushFailures>>,
+    /// Held by the streaming thread while pushing to the consumer, so
+    /// that removing it can wait for the push to complete
+    pushing: Arc<Mutex<()>>,
 }
 
 impl StreamConsumer {
     /// Create a new consumer
     fn new(appsrc: &gst_app::AppSrc, fku_probe_id: gst::PadProbeId, consumer_id: &str) -> Self {
+        let id = consumer_id.to_string();
         let consumer_id = consumer_id.to_string();
+        let n_enough_data = Arc::new(atomic::AtomicU64::new(0));
+        let n_enough_data_clone = n_enough_data.clone();
         appsrc.set_callbacks(
             gst_app::AppSrcCallbacks::builder()
                 .enough_data(move |_appsrc| {
+                    n_enough_data_clone.fetch_add(1, atomic::Ordering::SeqCst);
                     trace!(
                         "consumer {} is not consuming fast enough, old samples are getting dropped",
                         consumer_id
@@ -283,10 +838,16 @@ impl StreamConsumer {
         );
 
         StreamConsumer {
+            id,
             appsrc: appsrc.clone(),
             fku_probe_id: Some(fku_probe_id),
             forwarded_latency: atomic::AtomicBool::new(false),
-            first_buffer: atomic::AtomicBool::new(true),
+            latency_override: None,
+            first_buffer: Arc::new(atomic::AtomicBool::new(true)),
+            n_dropped_samples: Arc::new(atomic::AtomicU64::new(0)),
+            n_enough_data,
+            push_failures: Arc::new(Mutex::new(PushFailures::default())),
+            pushing: Arc::new(Mutex::new(())),
         }
     }
 }
@@ -319,3 +880,423 @@ impl std::borrow::Borrow<gst_app::AppSrc> for StreamConsumer {
         &self.appsrc
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_appsrc(name: &str) -> gst_app::AppSrc {
+        gst::ElementFactory::make("appsrc", Some(name))
+            .unwrap()
+            .downcast::<gst_app::AppSrc>()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_max_consumers() {
+        gst::init().unwrap();
+
+        let appsink = gst::ElementFactory::make("appsink", None)
+            .unwrap()
+            .downcast::<gst_app::AppSink>()
+            .unwrap();
+        let producer = StreamProducer::from(&appsink);
+
+        producer.set_max_consumers(1);
+
+        producer
+            .add_consumer(&make_appsrc("consumer-0"), "consumer-0")
+            .unwrap();
+        assert!(producer
+            .add_consumer(&make_appsrc("consumer-1"), "consumer-1")
+            .is_err());
+        assert_eq!(producer.get_consumer_ids(), vec!["consumer-0".to_string()]);
+
+        producer.remove_consumer("consumer-0");
+
+        producer
+            .add_consumer(&make_appsrc("consumer-1"), "consumer-1")
+            .unwrap();
+    }
+
+    #[test]
+    fn test_forward_on_keyframe() {
+        gst::init().unwrap();
+
+        let pipeline = gst::Pipeline::new(None);
+        let src = make_appsrc("producer-src");
+        let appsink = gst::ElementFactory::make("appsink", None)
+            .unwrap()
+            .downcast::<gst_app::AppSink>()
+            .unwrap();
+        appsink.set_sync(false);
+        pipeline
+            .add_many(&[src.upcast_ref(), appsink.upcast_ref()])
+            .unwrap();
+        src.link(&appsink).unwrap();
+
+        let producer = StreamProducer::from(&appsink);
+        producer.set_forward_mode(ForwardMode::Keyframe);
+
+        let consumer_pipeline = gst::Pipeline::new(None);
+        let consumer = make_appsrc("consumer-0");
+        let sink = gst::ElementFactory::make("fakesink", None).unwrap();
+        consumer_pipeline
+            .add_many(&[consumer.upcast_ref(), &sink])
+            .unwrap();
+        consumer.link(&sink).unwrap();
+        consumer_pipeline.set_state(gst::State::Playing).unwrap();
+        producer.add_consumer(&consumer, "consumer-0").unwrap();
+
+        pipeline.set_state(gst::State::Playing).unwrap();
+
+        for delta_unit in &[true, true, false, true] {
+            let mut buffer = gst::Buffer::with_size(1).unwrap();
+            if *delta_unit {
+                buffer
+                    .get_mut()
+                    .unwrap()
+                    .set_flags(gst::BufferFlags::DELTA_UNIT);
+            }
+            src.push_buffer(buffer).unwrap();
+        }
+
+        src.end_of_stream().unwrap();
+        wait_for_eos(&pipeline);
+
+        assert!(producer.is_forwarding());
+        assert_eq!(producer.stats().n_forwarded_samples, 2);
+
+        pipeline.set_state(gst::State::Null).unwrap();
+        consumer_pipeline.set_state(gst::State::Null).unwrap();
+    }
+
+    #[test]
+    fn test_notify_consumer_removed() {
+        gst::init().unwrap();
+
+        let pipeline = gst::Pipeline::new(None);
+        let src = make_appsrc("producer-src");
+        let appsink = gst::ElementFactory::make("appsink", None)
+            .unwrap()
+            .downcast::<gst_app::AppSink>()
+            .unwrap();
+        appsink.set_sync(false);
+        pipeline
+            .add_many(&[src.upcast_ref(), appsink.upcast_ref()])
+            .unwrap();
+        src.link(&appsink).unwrap();
+
+        let producer = StreamProducer::from(&appsink);
+        producer.set_forward_mode(ForwardMode::Auto);
+        producer.set_auto_remove_timeout(Some(Duration::from_secs(0)));
+
+        let notifications = Arc::new(Mutex::new(vec![]));
+        let notifications_clone = notifications.clone();
+        producer.set_notify(move |notification| {
+            notifications_clone.lock().unwrap().push(notification);
+        });
+
+        // Stopped, rejects every sample as flushing
+        let consumer = make_appsrc("consumer-0");
+        consumer.set_state(gst::State::Paused).unwrap();
+        consumer.set_state(gst::State::Null).unwrap();
+        producer.add_consumer(&consumer, "consumer-0").unwrap();
+
+        pipeline.set_state(gst::State::Playing).unwrap();
+
+        src.push_buffer(gst::Buffer::with_size(1).unwrap()).unwrap();
+        src.end_of_stream().unwrap();
+        wait_for_eos(&pipeline);
+
+        assert!(producer.get_consumer_ids().is_empty());
+
+        let notifications = notifications.lock().unwrap();
+        assert_eq!(notifications.len(), 1);
+        match notifications[0] {
+            ProducerNotification::ConsumerRemoved {
+                ref consumer_id, ..
+            } => assert_eq!(consumer_id, "consumer-0"),
+            ref notification => panic!("Unexpected notification {:?}", notification),
+        }
+
+        pipeline.set_state(gst::State::Null).unwrap();
+    }
+
+    #[test]
+    fn test_forwarded_samples_without_consumers() {
+        gst::init().unwrap();
+
+        let pipeline = gst::Pipeline::new(None);
+        let src = make_appsrc("producer-src");
+        let appsink = gst::ElementFactory::make("appsink", None)
+            .unwrap()
+            .downcast::<gst_app::AppSink>()
+            .unwrap();
+        appsink.set_sync(false);
+        pipeline
+            .add_many(&[src.upcast_ref(), appsink.upcast_ref()])
+            .unwrap();
+        src.link(&appsink).unwrap();
+
+        let producer = StreamProducer::from(&appsink);
+        producer.set_forward_mode(ForwardMode::Auto);
+
+        // Only the first sample starts forwarding
+        producer.forward();
+        assert!(!producer.is_forwarding());
+
+        pipeline.set_state(gst::State::Playing).unwrap();
+
+        for _ in 0..3 {
+            src.push_buffer(gst::Buffer::with_size(1).unwrap()).unwrap();
+        }
+
+        src.end_of_stream().unwrap();
+        wait_for_eos(&pipeline);
+
+        // Nothing was pushed anywhere
+        assert!(producer.is_forwarding());
+        assert_eq!(producer.stats().n_forwarded_samples, 0);
+
+        pipeline.set_state(gst::State::Null).unwrap();
+    }
+
+    #[test]
+    fn test_consumers_first_buffer() {
+        gst::init().unwrap();
+
+        let producer_pipeline = gst::Pipeline::new(None);
+        let src = make_appsrc("producer-src");
+        let appsink = gst::ElementFactory::make("appsink", None)
+            .unwrap()
+            .downcast::<gst_app::AppSink>()
+            .unwrap();
+        appsink.set_sync(false);
+        producer_pipeline
+            .add_many(&[src.upcast_ref(), appsink.upcast_ref()])
+            .unwrap();
+        src.link(&appsink).unwrap();
+
+        let n_keyframe_requests = Arc::new(atomic::AtomicU64::new(0));
+        let n_keyframe_requests_clone = n_keyframe_requests.clone();
+        src.static_pad("src")
+            .unwrap()
+            .add_probe(gst::PadProbeType::EVENT_UPSTREAM, move |_pad, info| {
+                if let Some(gst::PadProbeData::Event(ref ev)) = info.data {
+                    if gst_video::UpstreamForceKeyUnitEvent::parse(ev).is_ok() {
+                        n_keyframe_requests_clone.fetch_add(1, atomic::Ordering::SeqCst);
+                    }
+                }
+
+                gst::PadProbeReturn::Ok
+            })
+            .unwrap();
+
+        let producer = StreamProducer::from(&appsink);
+        producer.set_forward_mode(ForwardMode::Auto);
+
+        // Two consumers in a single pipeline, as with two mixer slots
+        let consumer_pipeline = gst::Pipeline::new(None);
+        let mut n_buffers = vec![];
+        for id in &["slot-0", "slot-1"] {
+            let consumer = make_appsrc(id);
+            let sink = gst::ElementFactory::make("fakesink", None).unwrap();
+            sink.set_property("sync", false).unwrap();
+            consumer_pipeline
+                .add_many(&[consumer.upcast_ref(), &sink])
+                .unwrap();
+            consumer.link(&sink).unwrap();
+
+            let n = Arc::new(atomic::AtomicU64::new(0));
+            let n_clone = n.clone();
+            sink.static_pad("sink")
+                .unwrap()
+                .add_probe(gst::PadProbeType::BUFFER, move |_pad, _info| {
+                    n_clone.fetch_add(1, atomic::Ordering::SeqCst);
+                    gst::PadProbeReturn::Ok
+                })
+                .unwrap();
+            n_buffers.push(n);
+
+            producer.add_consumer(&consumer, id).unwrap();
+        }
+
+        let mut ids = producer.get_consumer_ids();
+        ids.sort();
+        assert_eq!(ids, vec!["slot-0".to_string(), "slot-1".to_string()]);
+
+        consumer_pipeline.set_state(gst::State::Playing).unwrap();
+        producer_pipeline.set_state(gst::State::Playing).unwrap();
+
+        for _ in 0..3 {
+            src.push_buffer(gst::Buffer::with_size(1).unwrap()).unwrap();
+        }
+
+        src.end_of_stream().unwrap();
+        wait_for_eos(&producer_pipeline);
+        wait_for_eos(&consumer_pipeline);
+
+        for n in &n_buffers {
+            assert_eq!(n.load(atomic::Ordering::SeqCst), 3);
+        }
+        assert_eq!(producer.stats().n_forwarded_samples, 3);
+        assert_eq!(n_keyframe_requests.load(atomic::Ordering::SeqCst), 1);
+
+        producer_pipeline.set_state(gst::State::Null).unwrap();
+        consumer_pipeline.set_state(gst::State::Null).unwrap();
+    }
+
+    fn wait_for_eos(pipeline: &gst::Pipeline) {
+        let bus = pipeline.bus().unwrap();
+
+        let msg = bus
+            .timed_pop_filtered(
+                5 * gst::SECOND,
+                &[gst::MessageType::Eos, gst::MessageType::Error],
+            )
+            .expect("timed out waiting for EOS");
+
+        assert_eq!(msg.type_(), gst::MessageType::Eos);
+    }
+
+    #[test]
+    fn test_add_consumer_after_eos() {
+        gst::init().unwrap();
+
+        let producer_pipeline = gst::Pipeline::new(None);
+        let src = make_appsrc("producer-src");
+        let appsink = gst::ElementFactory::make("appsink", None)
+            .unwrap()
+            .downcast::<gst_app::AppSink>()
+            .unwrap();
+        producer_pipeline
+            .add_many(&[src.upcast_ref(), appsink.upcast_ref()])
+            .unwrap();
+        src.link(&appsink).unwrap();
+
+        let producer = StreamProducer::from(&appsink);
+
+        producer_pipeline.set_state(gst::State::Playing).unwrap();
+        src.end_of_stream().unwrap();
+        wait_for_eos(&producer_pipeline);
+
+        let consumer_pipeline = gst::Pipeline::new(None);
+        let consumer = make_appsrc("consumer-0");
+        let sink = gst::ElementFactory::make("fakesink", None).unwrap();
+        consumer_pipeline
+            .add_many(&[consumer.upcast_ref(), &sink])
+            .unwrap();
+        consumer.link(&sink).unwrap();
+        consumer_pipeline.set_state(gst::State::Playing).unwrap();
+
+        producer.add_consumer(&consumer, "consumer-0").unwrap();
+        wait_for_eos(&consumer_pipeline);
+
+        producer_pipeline.set_state(gst::State::Null).unwrap();
+        consumer_pipeline.set_state(gst::State::Null).unwrap();
+    }
+
+    #[test]
+    fn test_next_stream() {
+        gst::init().unwrap();
+
+        let pipeline = gst::Pipeline::new(None);
+        let src = make_appsrc("producer-src");
+        let appsink = gst::ElementFactory::make("appsink", None)
+            .unwrap()
+            .downcast::<gst_app::AppSink>()
+            .unwrap();
+        appsink.set_sync(false);
+        pipeline
+            .add_many(&[src.upcast_ref(), appsink.upcast_ref()])
+            .unwrap();
+        src.link(&appsink).unwrap();
+
+        let producer = StreamProducer::from(&appsink);
+
+        // Not at EOS yet
+        assert!(producer.next_stream().now_or_never().is_some());
+
+        pipeline.set_state(gst::State::Playing).unwrap();
+        src.end_of_stream().unwrap();
+        wait_for_eos(&pipeline);
+
+        let mut next_stream = producer.next_stream().boxed();
+        assert!((&mut next_stream).now_or_never().is_none());
+
+        // Data flows again after a flush
+        src.send_event(gst::event::FlushStart::new());
+        src.send_event(gst::event::FlushStop::new(true));
+        src.push_buffer(gst::Buffer::with_size(1).unwrap()).unwrap();
+
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while (&mut next_stream).now_or_never().is_none() {
+            assert!(
+                Instant::now() < deadline,
+                "timed out waiting for the next stream"
+            );
+            std::thread::sleep(Duration::from_millis(10));
+        }
+
+        pipeline.set_state(gst::State::Null).unwrap();
+    }
+
+    #[test]
+    fn test_flush_consumer_on_error() {
+        gst::init().unwrap();
+
+        let pipeline = gst::Pipeline::new(None);
+        let appsrc = make_appsrc("consumer");
+        let sink = gst::ElementFactory::make("fakesink", None).unwrap();
+        pipeline
+            .add_many(&[appsrc.upcast_ref::<gst::Element>(), &sink])
+            .unwrap();
+        appsrc.link(&sink).unwrap();
+
+        let n_flushes = Arc::new(atomic::AtomicU64::new(0));
+        let n_flushes_clone = n_flushes.clone();
+        sink.static_pad("sink")
+            .unwrap()
+            .add_probe(gst::PadProbeType::EVENT_FLUSH, move |_pad, info| {
+                if let Some(gst::PadProbeData::Event(ref ev)) = info.data {
+                    if let gst::EventView::FlushStart(_) = ev.view() {
+                        n_flushes_clone.fetch_add(1, atomic::Ordering::SeqCst);
+                    }
+                }
+                gst::PadProbeReturn::Ok
+            })
+            .unwrap();
+
+        pipeline.set_state(gst::State::Playing).unwrap();
+
+        let sample = gst::Sample::builder().buffer(&gst::Buffer::new()).build();
+
+        // A transient error is recovered from by flushing
+        let mut results = vec![Ok(gst::FlowSuccess::Ok), Err(gst::FlowError::Error)];
+        let (flushed, res) = push_to_consumer(&appsrc, &sample, |_, _| results.pop().unwrap());
+        assert!(flushed);
+        assert_eq!(res, Ok(gst::FlowSuccess::Ok));
+        assert_eq!(n_flushes.load(atomic::Ordering::SeqCst), 1);
+
+        // The sample is only pushed again once
+        let mut n_pushes = 0;
+        let (flushed, res) = push_to_consumer(&appsrc, &sample, |_, _| {
+            n_pushes += 1;
+            Err(gst::FlowError::Error)
+        });
+        assert!(flushed);
+        assert_eq!(res, Err(gst::FlowError::Error));
+        assert_eq!(n_pushes, 2);
+        assert_eq!(n_flushes.load(atomic::Ordering::SeqCst), 2);
+
+        // Other errors don't cause a flush
+        let (flushed, res) =
+            push_to_consumer(&appsrc, &sample, |_, _| Err(gst::FlowError::Flushing));
+        assert!(!flushed);
+        assert_eq!(res, Err(gst::FlowError::Flushing));
+        assert_eq!(n_flushes.load(atomic::Ordering::SeqCst), 2);
+
+        pipeline.set_state(gst::State::Null).unwrap();
+    }
+}
@@ -1,10 +1,13 @@
 use crate::node::{CommandMessage, NodeManager, NodeStatusMessage, RegisterListenerMessage};
 use actix::prelude::*;
 use anyhow::{anyhow, Error};
-use auteur_controlling::controller::{Command, CommandResult, DestinationFamily, NodeInfo, State};
+use auteur_controlling::controller::{
+    Command, CommandResult, ConnectMedia, DestinationFamily, DuplicateLinkPolicy, ForwardMode,
+    NodeInfo, State,
+};
 use chrono::{DateTime, Utc};
 use futures::channel::oneshot;
-use std::collections::VecDeque;
+use std::collections::{HashMap, VecDeque};
 use std::path::PathBuf;
 use tracing::error;
 
@@ -26,6 +29,15 @@ impl Message for WaitForProgressionMessage {
     type Result = StateProgressionResult;
 }
 
+/// Sent from tests to [`StateListener`] to retrieve the heartbeats
+/// the node emitted so far, as (state, uptime)
+#[derive(Debug)]
+pub struct GetHeartbeatsMessage;
+
+impl Message for GetHeartbeatsMessage {
+    type Result = Vec<(State, std::time::Duration)>;
+}
+
 /// Actor that registers itself with NodeManager to track the
 /// progression of a node through its states
 pub struct StateListener {
@@ -39,6 +51,8 @@ pub struct StateListener {
     progress_receiver: Option<oneshot::Receiver<StateProgressionResult>>,
     /// Track whether the node encountered an error
     errored_out: bool,
+    /// The heartbeats of the node, as (state, uptime)
+    heartbeats: Vec<(State, std::time::Duration)>,
 }
 
 impl Handler<WaitForProgressionMessage> for StateListener {
@@ -55,6 +69,14 @@ impl Handler<WaitForProgressionMessage> for StateListener {
     }
 }
 
+impl Handler<GetHeartbeatsMessage> for StateListener {
+    type Result = MessageResult<GetHeartbeatsMessage>;
+
+    fn handle(&mut self, _msg: GetHeartbeatsMessage, _ctx: &mut Context<Self>) -> Self::Result {
+        MessageResult(self.heartbeats.clone())
+    }
+}
+
 impl StateListener {
     fn new(node_id: &str, expected_progression: VecDeque<State>) -> Self {
         let (progress_sender, progress_receiver) = oneshot::channel::<StateProgressionResult>();
@@ -64,6 +86,7 @@ impl StateListener {
             progress_sender: Some(progress_sender),
             progress_receiver: Some(progress_receiver),
             errored_out: false,
+            heartbeats: vec![],
         }
     }
 }
@@ -115,6 +138,14 @@ impl Handler<NodeStatusMessage> for StateListener {
 
                 self.errored_out = true;
             }
+            NodeStatusMessage::Warning { .. } => (),
+            NodeStatusMessage::Heartbeat { id, state, uptime } => {
+                if id != self.id {
+                    return;
+                }
+
+                self.heartbeats.push((state, uptime));
+            }
         }
     }
 }
@@ -135,7 +166,7 @@ pub async fn create_source(id: &str, uri: &str) -> Result<(), Error> {
     {
         CommandResult::Success => Ok(()),
         CommandResult::Error(err) => Err(anyhow!(err)),
-        CommandResult::Info(_) => unreachable!(),
+        _ => unreachable!(),
     }
 }
 
@@ -155,6 +186,96 @@ pub async fn create_local_destination(
                     base_name: base_name.to_string(),
                     max_size_time,
                 },
+                config: None,
+            },
+        })
+        .await
+        .unwrap()
+    {
+        CommandResult::Success => Ok(()),
+        CommandResult::Error(err) => Err(anyhow!(err)),
+        _ => unreachable!(),
+    }
+}
+
+/// Create a mixer
+pub async fn create_mixer(
+    id: &str,
+    config: Option<HashMap<String, serde_json::Value>>,
+) -> Result<(), Error> {
+    let manager = NodeManager::from_registry();
+
+    match manager
+        .send(CommandMessage {
+            command: Command::CreateMixer {
+                id: id.to_string(),
+                config,
+            },
+        })
+        .await
+        .unwrap()
+    {
+        CommandResult::Success => Ok(()),
+        CommandResult::Error(err) => Err(anyhow!(err)),
+        _ => unreachable!(),
+    }
+}
+
+/// Connect a producer with a consumer
+pub async fn connect(link_id: &str, src_id: &str, sink_id: &str) -> Result<(), Error> {
+    connect_on_duplicate(link_id, src_id, sink_id, DuplicateLinkPolicy::Reject).await
+}
+
+/// Connect a producer with a consumer, handling an existing link with
+/// the same id according to `on_duplicate`
+pub async fn connect_on_duplicate(
+    link_id: &str,
+    src_id: &str,
+    sink_id: &str,
+    on_duplicate: DuplicateLinkPolicy,
+) -> Result<(), Error> {
+    let manager = NodeManager::from_registry();
+
+    match manager
+        .send(CommandMessage {
+            command: Command::Connect {
+                link_id: link_id.to_string(),
+                src_id: src_id.to_string(),
+                sink_id: sink_id.to_string(),
+                config: None,
+                media: ConnectMedia::Both,
+                on_duplicate,
+                output_id: None,
+            },
+        })
+        .await
+        .unwrap()
+    {
+        CommandResult::Success => Ok(()),
+        CommandResult::Error(err) => Err(anyhow!(err)),
+        _ => unreachable!(),
+    }
+}
+
+/// Connect some of the media of a producer with a consumer
+pub async fn connect_media(
+    link_id: &str,
+    src_id: &str,
+    sink_id: &str,
+    media: ConnectMedia,
+) -> Result<(), Error> {
+    let manager = NodeManager::from_registry();
+
+    match manager
+        .send(CommandMessage {
+            command: Command::Connect {
+                link_id: link_id.to_string(),
+                src_id: src_id.to_string(),
+                sink_id: sink_id.to_string(),
+                config: None,
+                media,
+                on_duplicate: DuplicateLinkPolicy::Reject,
+                output_id: None,
             },
         })
         .await
@@ -162,7 +283,26 @@ pub async fn create_local_destination(
     {
         CommandResult::Success => Ok(()),
         CommandResult::Error(err) => Err(anyhow!(err)),
-        CommandResult::Info(_) => unreachable!(),
+        _ => unreachable!(),
+    }
+}
+
+/// Remove a connection between two nodes
+pub async fn disconnect(link_id: &str) -> Result<(), Error> {
+    let manager = NodeManager::from_registry();
+
+    match manager
+        .send(CommandMessage {
+            command: Command::Disconnect {
+                link_id: link_id.to_string(),
+            },
+        })
+        .await
+        .unwrap()
+    {
+        CommandResult::Success => Ok(()),
+        CommandResult::Error(err) => Err(anyhow!(err)),
+        _ => unreachable!(),
     }
 }
 
@@ -187,7 +327,38 @@ pub async fn start_node(
     {
         CommandResult::Success => Ok(()),
         CommandResult::Error(err) => Err(anyhow!(err)),
-        CommandResult::Info(_) => unreachable!(),
+        _ => unreachable!(),
+    }
+}
+
+/// Run a command that replies with either success or an error
+pub async fn run_command(command: Command) -> Result<(), Error> {
+    let manager = NodeManager::from_registry();
+
+    match manager.send(CommandMessage { command }).await.unwrap() {
+        CommandResult::Success => Ok(()),
+        CommandResult::Error(err) => Err(anyhow!(err)),
+        _ => unreachable!(),
+    }
+}
+
+/// Set when a producer node starts forwarding its output
+pub async fn set_forward_mode(id: &str, mode: ForwardMode) -> Result<(), Error> {
+    let manager = NodeManager::from_registry();
+
+    match manager
+        .send(CommandMessage {
+            command: Command::SetForwardMode {
+                id: id.to_string(),
+                mode,
+            },
+        })
+        .await
+        .unwrap()
+    {
+        CommandResult::Success => Ok(()),
+        CommandResult::Error(err) => Err(anyhow!(err)),
+        _ => unreachable!(),
     }
 }
 
@@ -212,7 +383,7 @@ pub async fn reschedule_node(
     {
         CommandResult::Success => Ok(()),
         CommandResult::Error(err) => Err(anyhow!(err)),
-        CommandResult::Info(_) => unreachable!(),
+        _ => unreachable!(),
     }
 }
 
@@ -224,6 +395,7 @@ pub async fn node_info_unchecked(id: &str) -> NodeInfo {
         .send(CommandMessage {
             command: Command::GetInfo {
                 id: Some(id.to_string()),
+                node_type: None,
             },
         })
         .await
@@ -0,0 +1,125 @@
+//! Streams the output audio level of a mixer to remote clients
+//!
+//! Pushes JSON [`AudioLevelEvent`] messages through websockets, one
+//! per measurement of the mixer's `level` element, thus at the rate of
+//! its `interval` (100 milliseconds by default). Measurements are not
+//! queued up: a client that can't keep up only receives the latest one.
+
+use crate::utils::AudioLevelInfo;
+
+use actix::prelude::*;
+use actix_web_actors::ws;
+use futures::prelude::*;
+use tokio::sync::watch;
+
+use tracing::{debug, error, trace};
+
+use auteur_controlling::controller::AudioLevelEvent;
+
+/// Actor that streams the audio level measurements of a mixer to a
+/// remote client
+#[derive(Debug)]
+pub struct AudioLevelStream {
+    /// The id of the mixer
+    node_id: String,
+    /// Notified of new measurements, taken once we've started
+    receiver: Option<watch::Receiver<Option<AudioLevelInfo>>>,
+}
+
+impl AudioLevelStream {
+    /// Create a new `AudioLevelStream` actor.
+    pub fn new(node_id: &str, receiver: watch::Receiver<Option<AudioLevelInfo>>) -> Self {
+        Self {
+            node_id: node_id.to_string(),
+            receiver: Some(receiver),
+        }
+    }
+
+    /// The measurements of the mixer as they get published, ends once
+    /// the mixer is gone
+    fn measurements(
+        receiver: watch::Receiver<Option<AudioLevelInfo>>,
+    ) -> impl Stream<Item = AudioLevelInfo> {
+        futures::stream::unfold(receiver, |mut receiver| async move {
+            loop {
+                receiver.changed().await.ok()?;
+
+                let info = receiver.borrow().clone();
+
+                if let Some(info) = info {
+                    return Some((info, receiver));
+                }
+            }
+        })
+    }
+}
+
+impl Actor for AudioLevelStream {
+    type Context = ws::WebsocketContext<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        debug!(id = %self.node_id, "Streaming audio levels");
+
+        if let Some(receiver) = self.receiver.take() {
+            ctx.add_stream(AudioLevelStream::measurements(receiver));
+        }
+    }
+}
+
+impl StreamHandler<AudioLevelInfo> for AudioLevelStream {
+    fn handle(&mut self, info: AudioLevelInfo, ctx: &mut Self::Context) {
+        trace!(id = %self.node_id, "Pushing audio level {:?}", info);
+
+        ctx.text(
+            serde_json::to_string(&AudioLevelEvent {
+                node_id: self.node_id.clone(),
+                rms_db: info.rms_db,
+                peak_db: info.peak_db,
+                timestamp_ns: info.timestamp.nseconds().unwrap_or(0),
+            })
+            .expect("Failed to serialize audio level event"),
+        );
+    }
+
+    fn finished(&mut self, ctx: &mut Self::Context) {
+        debug!(id = %self.node_id, "Mixer is gone, closing audio level stream");
+
+        ctx.close(Some(ws::CloseReason {
+            code: ws::CloseCode::Away,
+            description: Some(format!("Mixer {} was removed", self.node_id)),
+        }));
+        ctx.stop();
+    }
+}
+
+impl StreamHandler<Result<ws::Message, ws::ProtocolError>> for AudioLevelStream {
+    fn handle(&mut self, msg: Result<ws::Message, ws::ProtocolError>, ctx: &mut Self::Context) {
+        match msg {
+            Ok(ws::Message::Ping(msg)) => ctx.pong(&msg),
+            Ok(ws::Message::Close(reason)) => {
+                debug!(
+                    id = %self.node_id,
+                    "Audio level websocket connection closed: {:?}", reason
+                );
+                ctx.stop();
+            }
+            Ok(ws::Message::Text(_)) | Ok(ws::Message::Binary(_)) => {
+                error!("Audio level streams are read-only, ignoring message");
+            }
+            Ok(ws::Message::Continuation(_)) => {
+                error!("Unsupported continuation message, ignoring");
+            }
+            Ok(ws::Message::Nop) | Ok(ws::Message::Pong(_)) => {
+                // Do nothing
+            }
+            Err(err) => {
+                error!(
+                    id = %self.node_id,
+                    "Audio level websocket connection error: {:?}", err
+                );
+                ctx.close(None);
+                ctx.stop();
+            }
+        }
+    }
+}
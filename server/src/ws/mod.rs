@@ -0,0 +1,5 @@
+//! WebSocket endpoints streaming data out of nodes
+
+pub mod audio_level;
+
+pub use audio_level::AudioLevelStream;